@@ -0,0 +1,103 @@
+//! compiles data/epsg.csv into the EPSG table of src/epsg.rs
+
+use std::env;
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::path::Path;
+
+struct Datum {
+    name:String,
+    a:f64,
+    rf:f64,
+    helmert:[f64; 7],
+    accuracy:f64,
+}
+
+enum Kind {
+    Geographic,
+    Tm([f64; 5]),
+}
+
+struct Entry {
+    code:u32,
+    name:String,
+    datum:usize,
+    kind:Kind,
+}
+
+fn num(line:usize, field:&str) -> f64 {
+    field.trim().parse().unwrap_or_else(|_| panic!("data/epsg.csv:{}: bad number {:?}", line, field))
+}
+
+fn main() {
+    let src = Path::new("data/epsg.csv");
+    println!("cargo:rerun-if-changed=data/epsg.csv");
+    let text = fs::read_to_string(src).expect("data/epsg.csv");
+    let mut datums:Vec<Datum> = Vec::new();
+    let mut entries:Vec<Entry> = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let line = i + 1;
+        let raw = raw.trim();
+        if raw.is_empty() || raw.starts_with('#') {
+            continue;
+        }
+        let f:Vec<&str> = raw.split(',').map(|s| s.trim()).collect();
+        let datum = |name:&str| datums.iter().position(|d| d.name == name)
+            .unwrap_or_else(|| panic!("data/epsg.csv:{}: unknown datum {:?}", line, name));
+        match f[0] {
+            "datum" if f.len() == 12 => {
+                let mut helmert = [0.0; 7];
+                for (k, h) in helmert.iter_mut().enumerate() {
+                    *h = num(line, f[4 + k]);
+                }
+                datums.push(Datum { name: f[1].to_string(), a: num(line, f[2]), rf: num(line, f[3]), helmert, accuracy: num(line, f[11]) });
+            }
+            "geographic" if f.len() == 4 => {
+                let d = datum(f[3]);
+                entries.push(Entry { code: num(line, f[1]) as u32, name: f[2].to_string(), datum: d, kind: Kind::Geographic });
+            }
+            "tm" if f.len() == 9 => {
+                let d = datum(f[3]);
+                let p = [num(line, f[4]), num(line, f[5]), num(line, f[6]), num(line, f[7]), num(line, f[8])];
+                entries.push(Entry { code: num(line, f[1]) as u32, name: f[2].to_string(), datum: d, kind: Kind::Tm(p) });
+            }
+            "series" if f.len() == 11 => {
+                let d = datum(f[4]);
+                let (first, last) = (num(line, f[1]) as u32, num(line, f[2]) as u32);
+                let (zone, lng, step) = (num(line, f[5]), num(line, f[6]), num(line, f[7]));
+                for (k, code) in (first..=last).enumerate() {
+                    let lng0 = lng + step * k as f64;
+                    let name = f[3].replace("{zone}", &(zone as u32 + k as u32).to_string()).replace("{lng}", &lng0.to_string());
+                    let p = [0.0, lng0, num(line, f[8]), num(line, f[9]), num(line, f[10])];
+                    entries.push(Entry { code, name, datum: d, kind: Kind::Tm(p) });
+                }
+            }
+            _ => panic!("data/epsg.csv:{}: unrecognized row {:?}", line, raw),
+        }
+    }
+    entries.sort_by_key(|e| e.code);
+    for w in entries.windows(2) {
+        assert!(w[0].code != w[1].code, "data/epsg.csv: EPSG:{} defined twice", w[0].code);
+    }
+
+    let mut out = String::new();
+    writeln!(out, "pub(crate) static DATUMS:[Datum; {}] = [", datums.len()).unwrap();
+    for d in &datums {
+        let h = &d.helmert;
+        writeln!(out, "    Datum {{ name: {:?}, ellipsoid: Ellipsoid {{ a: {:?}, b: {:?} }}, \
+            to_wgs84: Helmert {{ tx: {:?}, ty: {:?}, tz: {:?}, rx: {:?}, ry: {:?}, rz: {:?}, s: {:?} }}, accuracy: {:?} }},",
+            d.name, d.a, d.a * (1.0 - 1.0 / d.rf), h[0], h[1], h[2], h[3], h[4], h[5], h[6], d.accuracy).unwrap();
+    }
+    writeln!(out, "];\n").unwrap();
+    writeln!(out, "pub(crate) static ENTRIES:[Entry; {}] = [", entries.len()).unwrap();
+    for e in &entries {
+        let kind = match e.kind {
+            Kind::Geographic => "Kind::Geographic".to_string(),
+            Kind::Tm(p) => format!("Kind::Tm {{ lat0: {:?}, lng0: {:?}, k0: {:?}, false_easting: {:?}, false_northing: {:?} }}", p[0], p[1], p[2], p[3], p[4]),
+        };
+        writeln!(out, "    Entry {{ code: {}, name: {:?}, datum: {}, kind: {} }},", e.code, e.name, e.datum, kind).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+    let dst = Path::new(&env::var("OUT_DIR").unwrap()).join("epsg_table.rs");
+    fs::write(dst, out).unwrap();
+}
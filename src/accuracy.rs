@@ -0,0 +1,51 @@
+//! rough accuracy estimates of transform steps, in meters
+//!
+//! the numbers are typical one sigma horizontal values used to propagate
+//! uncertainty, not guarantees: a datum's published accuracy takes precedence
+//! where one is known.
+
+use helmert::Helmert;
+
+/// the transverse mercator series inside its zone
+pub const TM_SERIES:f64 = 0.001;
+/// the 4th order Krüger transverse mercator
+pub const TM_FAST:f64 = 1e-5;
+/// the exact (Krüger) transverse mercator
+pub const TM_EXACT:f64 = 1e-8;
+/// a 3-parameter (translation only) datum shift
+pub const THREE_PARAMETER:f64 = 5.0;
+/// a 7-parameter datum shift
+pub const SEVEN_PARAMETER:f64 = 1.0;
+/// a correction grid, limited by the grid's own fit
+pub const GRID:f64 = 0.05;
+
+/// estimated accuracy of a Helmert shift from the number of parameters it uses
+/// # Examples
+///
+/// ```
+/// use mercator::accuracy::{helmert_accuracy, THREE_PARAMETER};
+/// use mercator::helmert::Helmert;
+/// assert_eq!(helmert_accuracy(&Helmert::default()), 0.0);
+/// assert_eq!(helmert_accuracy(&Helmert::translation(-752.0, -358.0, -179.0)), THREE_PARAMETER);
+/// ```
+pub fn helmert_accuracy(h:&Helmert) -> f64 {
+    if *h == Helmert::default() {
+        0.0
+    } else if h.s == 0.0 && h.rx == 0.0 && h.ry == 0.0 && h.rz == 0.0 {
+        THREE_PARAMETER
+    } else {
+        SEVEN_PARAMETER
+    }
+}
+
+/// combine independent errors as a root sum of squares
+/// # Examples
+///
+/// ```
+/// use mercator::accuracy::combine;
+/// assert_eq!(combine(&[3.0, 4.0]), 5.0);
+/// assert_eq!(combine(&[]), 0.0);
+/// ```
+pub fn combine(errors:&[f64]) -> f64 {
+    errors.iter().map(|e| e * e).sum::<f64>().sqrt()
+}
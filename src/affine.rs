@@ -0,0 +1,147 @@
+//! 2D affine transforms on plane coordinates
+//!
+//! transforms are built from translations, rotations, scalings and shears
+//! chained with [`Affine2D::then`], and go before or after a projection either
+//! as a [`Pipeline`](::pipeline::Pipeline) step or wrapped with a projection
+//! in an [`AffineGrid`], e.g. a construction grid rotated off TWD97 or the
+//! pixel grid of a scanned map.
+
+use std::f64::consts::PI;
+
+use error::{Error, Result};
+use projection::Projection;
+
+/// x' = a x + b y + c, y' = d x + e y + f
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Affine2D {
+    pub a:f64,
+    pub b:f64,
+    pub c:f64,
+    pub d:f64,
+    pub e:f64,
+    pub f:f64,
+}
+
+impl Affine2D {
+    pub fn new(a:f64, b:f64, c:f64, d:f64, e:f64, f:f64) -> Affine2D {
+        Affine2D { a, b, c, d, e, f }
+    }
+
+    pub fn identity() -> Affine2D {
+        Affine2D::new(1.0, 0.0, 0.0, 0.0, 1.0, 0.0)
+    }
+
+    /// a shift by `(dx, dy)`
+    pub fn translation(dx:f64, dy:f64) -> Affine2D {
+        Affine2D::new(1.0, 0.0, dx, 0.0, 1.0, dy)
+    }
+
+    /// a scaling of x by `sx` and y by `sy` about the origin
+    pub fn scaling(sx:f64, sy:f64) -> Affine2D {
+        Affine2D::new(sx, 0.0, 0.0, 0.0, sy, 0.0)
+    }
+
+    /// a rotation by `degrees` counter-clockwise about the origin
+    pub fn rotating(degrees:f64) -> Affine2D {
+        let (s, c) = (degrees * PI / 180.0).sin_cos();
+        Affine2D::new(c, -s, 0.0, s, c, 0.0)
+    }
+
+    /// a shear moving x by `kx · y` and y by `ky · x`
+    pub fn shearing(kx:f64, ky:f64) -> Affine2D {
+        Affine2D::new(1.0, kx, 0.0, ky, 1.0, 0.0)
+    }
+
+    /// this transform followed by `next`
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::affine::Affine2D;
+    /// // a site grid: 30° off grid north, origin at a TWD97 control point
+    /// let site = Affine2D::rotating(30.0).then(&Affine2D::translation(250000.0, 2650000.0));
+    /// let (x, y) = site.apply(100.0, 0.0);
+    /// assert!((x - 250086.6025).abs() < 1e-4 && (y - 2650050.0).abs() < 1e-6);
+    /// assert!((site.rotation() - 30.0).abs() < 1e-12);
+    /// let (u, v) = site.inverse().unwrap().apply(x, y);
+    /// assert!((u - 100.0).abs() < 1e-9 && v.abs() < 1e-9);
+    /// ```
+    pub fn then(&self, next:&Affine2D) -> Affine2D {
+        Affine2D::new(
+            next.a * self.a + next.b * self.d, next.a * self.b + next.b * self.e, next.a * self.c + next.b * self.f + next.c,
+            next.d * self.a + next.e * self.d, next.d * self.b + next.e * self.e, next.d * self.c + next.e * self.f + next.f)
+    }
+
+    pub fn apply(&self, x:f64, y:f64) -> (f64, f64) {
+        (self.a * x + self.b * y + self.c, self.d * x + self.e * y + self.f)
+    }
+
+    /// the inverse transform, `None` when the matrix is singular
+    pub fn inverse(&self) -> Option<Affine2D> {
+        let det = self.a * self.e - self.b * self.d;
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let (a, b, d, e) = (self.e / det, -self.b / det, -self.d / det, self.a / det);
+        Some(Affine2D::new(a, b, -(a * self.c + b * self.f), d, e, -(d * self.c + e * self.f)))
+    }
+
+    /// mean scale sqrt(|det|) of the linear part
+    pub fn scale(&self) -> f64 {
+        (self.a * self.e - self.b * self.d).abs().sqrt()
+    }
+
+    /// rotation angle of the x axis in degrees, counter-clockwise
+    pub fn rotation(&self) -> f64 {
+        self.d.atan2(self.a).to_degrees()
+    }
+}
+
+/// a projection with an affine transform applied to its grid coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct AffineGrid<P> {
+    pub projection:P,
+    pub affine:Affine2D,
+    inverse:Affine2D,
+}
+
+impl<P:Projection> AffineGrid<P> {
+    /// fails with [`Error::InvalidInput`] when the affine transform cannot be inverted
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::affine::{Affine2D, AffineGrid};
+    /// use mercator::crs::{Crs, Transformer};
+    /// use mercator::zone::Zone;
+    /// // site coordinates with the origin at TWD97 (250000, 2650000), axes turned 30°
+    /// let to_twd97 = Affine2D::rotating(30.0).then(&Affine2D::translation(250000.0, 2650000.0));
+    /// let site = AffineGrid::new(Zone::twd97(), to_twd97.inverse().unwrap()).unwrap();
+    /// let t = Transformer::new(Crs::wgs84(), Crs::custom(site));
+    /// let (lng, lat) = Zone::twd97().inverse(250086.6025, 2650050.0);
+    /// let (x, y) = t.transform(lng, lat);
+    /// assert!((x - 100.0).abs() < 1e-3 && y.abs() < 1e-3);
+    /// let (lng2, lat2) = t.inverse().transform(x, y);
+    /// assert!((lng2 - lng).abs() < 1e-9 && (lat2 - lat).abs() < 1e-9);
+    ///
+    /// assert!(AffineGrid::new(Zone::twd97(), Affine2D::scaling(0.0, 1.0)).is_err());
+    /// ```
+    pub fn new(projection:P, affine:Affine2D) -> Result<AffineGrid<P>> {
+        let inverse = affine.inverse().ok_or_else(|| Error::InvalidInput("affine transform is singular".to_string()))?;
+        Ok(AffineGrid { projection, affine, inverse })
+    }
+}
+
+impl<P:Projection> Projection for AffineGrid<P> {
+    fn name(&self) -> &str {
+        self.projection.name()
+    }
+
+    fn forward(&self, lng:f64, lat:f64) -> (f64, f64) {
+        let (x, y) = self.projection.forward(lng, lat);
+        self.affine.apply(x, y)
+    }
+
+    fn inverse(&self, x:f64, y:f64) -> (f64, f64) {
+        let (x, y) = self.inverse.apply(x, y);
+        self.projection.inverse(x, y)
+    }
+}
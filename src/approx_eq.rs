@@ -0,0 +1,77 @@
+//! `approx` comparisons for the coordinate types
+//!
+//! projected and ECEF coordinates compare every component against one
+//! tolerance in meters, 0.1 mm by default. geographic coordinates mix degrees
+//! and meters, so their tolerance is itself a [`LngLat`]: 1e-9° (about 0.1 mm)
+//! for lng/lat and 0.1 mm for the height. longitudes compare across ±180°.
+
+use approx::{AbsDiffEq, RelativeEq};
+
+use coord::{Ecef, LngLat, Projected};
+use longitude::lng_diff;
+
+/// default tolerance in meters
+const METERS:f64 = 1e-4;
+/// default tolerance in degrees
+const DEGREES:f64 = 1e-9;
+
+impl AbsDiffEq for LngLat {
+    type Epsilon = LngLat;
+
+    fn default_epsilon() -> LngLat {
+        LngLat { lng: DEGREES, lat: DEGREES, h: METERS }
+    }
+
+    fn abs_diff_eq(&self, other:&LngLat, epsilon:LngLat) -> bool {
+        lng_diff(self.lng, other.lng).abs() <= epsilon.lng
+            && f64::abs_diff_eq(&self.lat, &other.lat, epsilon.lat)
+            && f64::abs_diff_eq(&self.h, &other.h, epsilon.h)
+    }
+}
+
+impl RelativeEq for LngLat {
+    fn default_max_relative() -> LngLat {
+        let r = f64::default_max_relative();
+        LngLat { lng: r, lat: r, h: r }
+    }
+
+    fn relative_eq(&self, other:&LngLat, epsilon:LngLat, max_relative:LngLat) -> bool {
+        (lng_diff(self.lng, other.lng).abs() <= epsilon.lng
+            || f64::relative_eq(&self.lng, &other.lng, epsilon.lng, max_relative.lng))
+            && f64::relative_eq(&self.lat, &other.lat, epsilon.lat, max_relative.lat)
+            && f64::relative_eq(&self.h, &other.h, epsilon.h, max_relative.h)
+    }
+}
+
+macro_rules! impl_meters {
+    ($t:ty, $a:ident, $b:ident, $c:ident) => {
+        impl AbsDiffEq for $t {
+            type Epsilon = f64;
+
+            fn default_epsilon() -> f64 {
+                METERS
+            }
+
+            fn abs_diff_eq(&self, other:&$t, epsilon:f64) -> bool {
+                f64::abs_diff_eq(&self.$a, &other.$a, epsilon)
+                    && f64::abs_diff_eq(&self.$b, &other.$b, epsilon)
+                    && f64::abs_diff_eq(&self.$c, &other.$c, epsilon)
+            }
+        }
+
+        impl RelativeEq for $t {
+            fn default_max_relative() -> f64 {
+                f64::default_max_relative()
+            }
+
+            fn relative_eq(&self, other:&$t, epsilon:f64, max_relative:f64) -> bool {
+                f64::relative_eq(&self.$a, &other.$a, epsilon, max_relative)
+                    && f64::relative_eq(&self.$b, &other.$b, epsilon, max_relative)
+                    && f64::relative_eq(&self.$c, &other.$c, epsilon, max_relative)
+            }
+        }
+    };
+}
+
+impl_meters!(Projected, x, y, h);
+impl_meters!(Ecef, x, y, z);
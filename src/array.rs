@@ -0,0 +1,84 @@
+//! ndarray integration (feature `ndarray`)
+
+use ndarray::{Array, Array2, ArrayView2, Axis, Dimension, Zip};
+
+use affine::Affine2D;
+use error::{Error, Result};
+
+/// transform an N × 2 array of `(x, y)` rows, returning an array of the same shape
+/// # Examples
+///
+/// ```
+/// # extern crate ndarray;
+/// # extern crate mercator;
+/// use ndarray::array;
+/// use mercator::array::transform_rows;
+/// use mercator::wgs84_to_twd97;
+/// let pts = array![[121.5654, 25.0330], [120.6736, 24.1477]];
+/// let out = transform_rows(pts.view(), wgs84_to_twd97).unwrap();
+/// assert_eq!(out.dim(), (2, 2));
+/// assert!(out[[0, 0]] > 300000.0);
+/// ```
+pub fn transform_rows<F>(points:ArrayView2<f64>, f:F) -> Result<Array2<f64>>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    if points.ncols() != 2 {
+        return Err(Error::InvalidInput(format!("expected 2 columns, got {}", points.ncols())));
+    }
+    let mut out = points.to_owned();
+    for mut row in out.axis_iter_mut(Axis(0)) {
+        let (x, y) = f(row[0], row[1]);
+        row[0] = x;
+        row[1] = y;
+    }
+    Ok(out)
+}
+
+/// transform separate x and y arrays of any (matching) shape
+pub fn transform_arrays<F, D>(xs:&Array<f64, D>, ys:&Array<f64, D>, f:F) -> Result<(Array<f64, D>, Array<f64, D>)>
+    where F: Fn(f64, f64) -> (f64, f64), D: Dimension
+{
+    if xs.shape() != ys.shape() {
+        return Err(Error::InvalidInput(format!("x shape {:?} differs from y shape {:?}", xs.shape(), ys.shape())));
+    }
+    let mut ox = xs.clone();
+    let mut oy = ys.clone();
+    Zip::from(&mut ox).and(&mut oy).for_each(|x, y| {
+        let (nx, ny) = f(*x, *y);
+        *x = nx;
+        *y = ny;
+    });
+    Ok((ox, oy))
+}
+
+/// coordinates of every cell center of a `height` × `width` raster, mapped through `f`
+///
+/// `transform` places cell centers (col, row) in the raster's CRS, `f` maps
+/// them to the wanted CRS. the result is a pair of `height × width` arrays.
+/// # Examples
+///
+/// ```
+/// # extern crate mercator;
+/// use mercator::affine::Affine2D;
+/// use mercator::array::raster_coords;
+/// use mercator::f2degree_zone_to_wgs84;
+/// let t = Affine2D::new(20.0, 0.0, 300000.0, 0.0, -20.0, 2770000.0);
+/// let (lng, lat) = raster_coords(&t, 3, 4, |x, y| f2degree_zone_to_wgs84(x, y, 121.0));
+/// assert_eq!(lng.dim(), (4, 3));
+/// assert!(lat[[0, 0]] > lat[[3, 0]]);
+/// ```
+pub fn raster_coords<F>(transform:&Affine2D, width:usize, height:usize, f:F) -> (Array2<f64>, Array2<f64>)
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let mut xs = Array2::zeros((height, width));
+    let mut ys = Array2::zeros((height, width));
+    for row in 0..height {
+        for col in 0..width {
+            let (x, y) = transform.apply(col as f64, row as f64);
+            let (x, y) = f(x, y);
+            xs[[row, col]] = x;
+            ys[[row, col]] = y;
+        }
+    }
+    (xs, ys)
+}
@@ -0,0 +1,157 @@
+//! Arrow and GeoArrow columnar transforms (feature `arrow`)
+//!
+//! coordinates are read straight from the Arrow value buffers and written to
+//! new buffers; offsets, validity and any z / m values are carried over
+//! untouched.
+
+use std::sync::Arc;
+
+use arrow_array::{Array, ArrayRef, FixedSizeListArray, Float64Array, GenericListArray, OffsetSizeTrait, StructArray};
+use arrow_array::cast::AsArray;
+use arrow_buffer::NullBuffer;
+use arrow_schema::DataType;
+
+use error::{Error, Result};
+
+fn arrow_err(e:arrow_schema::ArrowError) -> Error {
+    Error::InvalidInput(format!("arrow: {}", e))
+}
+
+fn xy_values<F>(x:&[f64], y:&[f64], f:&F) -> (Vec<f64>, Vec<f64>)
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let mut ox = Vec::with_capacity(x.len());
+    let mut oy = Vec::with_capacity(y.len());
+    for (&x, &y) in x.iter().zip(y) {
+        let (nx, ny) = f(x, y);
+        ox.push(nx);
+        oy.push(ny);
+    }
+    (ox, oy)
+}
+
+/// transform a pair of x / y columns, a row is null in the output when it is null in either input
+/// # Examples
+///
+/// ```
+/// # extern crate arrow_array;
+/// # extern crate mercator;
+/// use arrow_array::{Array, Float64Array};
+/// use mercator::arrow::transform_columns;
+/// use mercator::wgs84_to_twd97;
+/// let lng = Float64Array::from(vec![Some(121.5654), None]);
+/// let lat = Float64Array::from(vec![25.0330, 24.1477]);
+/// let (x, y) = transform_columns(&lng, &lat, wgs84_to_twd97).unwrap();
+/// assert!(x.value(0) > 300000.0 && y.value(0) > 2700000.0);
+/// assert!(x.is_null(1) && y.is_null(1));
+/// ```
+pub fn transform_columns<F>(x:&Float64Array, y:&Float64Array, f:F) -> Result<(Float64Array, Float64Array)>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    if x.len() != y.len() {
+        return Err(Error::InvalidInput(format!("x has {} rows but y has {}", x.len(), y.len())));
+    }
+    let nulls = NullBuffer::union(x.nulls(), y.nulls());
+    let (ox, oy) = xy_values(x.values(), y.values(), &f);
+    Ok((Float64Array::new(ox.into(), nulls.clone()), Float64Array::new(oy.into(), nulls)))
+}
+
+fn float_child(array:&ArrayRef) -> Result<&Float64Array> {
+    array.as_primitive_opt().ok_or_else(|| Error::InvalidInput(format!("expected Float64 coordinates, got {}", array.data_type())))
+}
+
+fn transform_struct<F>(points:&StructArray, f:&F) -> Result<ArrayRef>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    if points.num_columns() < 2 {
+        return Err(Error::InvalidInput("separated points need x and y fields".to_string()));
+    }
+    let (x, y) = (float_child(points.column(0))?, float_child(points.column(1))?);
+    let (ox, oy) = xy_values(x.values(), y.values(), f);
+    let mut columns = points.columns().to_vec();
+    columns[0] = Arc::new(Float64Array::new(ox.into(), x.nulls().cloned()));
+    columns[1] = Arc::new(Float64Array::new(oy.into(), y.nulls().cloned()));
+    let out = StructArray::try_new(points.fields().clone(), columns, points.nulls().cloned()).map_err(arrow_err)?;
+    Ok(Arc::new(out))
+}
+
+fn transform_interleaved<F>(points:&FixedSizeListArray, f:&F) -> Result<ArrayRef>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let dim = points.value_length() as usize;
+    if dim < 2 {
+        return Err(Error::InvalidInput("interleaved points need at least 2 dimensions".to_string()));
+    }
+    let values = float_child(points.values())?;
+    let mut out = values.values().to_vec();
+    for c in out.chunks_mut(dim) {
+        let (x, y) = f(c[0], c[1]);
+        c[0] = x;
+        c[1] = y;
+    }
+    let field = match points.data_type() {
+        DataType::FixedSizeList(field, _) => field.clone(),
+        _ => unreachable!(),
+    };
+    let values:ArrayRef = Arc::new(Float64Array::new(out.into(), values.nulls().cloned()));
+    let out = FixedSizeListArray::try_new(field, dim as i32, values, points.nulls().cloned()).map_err(arrow_err)?;
+    Ok(Arc::new(out))
+}
+
+fn transform_list<O: OffsetSizeTrait, F>(lists:&GenericListArray<O>, f:&F) -> Result<ArrayRef>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let field = match lists.data_type() {
+        DataType::List(field) | DataType::LargeList(field) => field.clone(),
+        _ => unreachable!(),
+    };
+    let values = transform_geometry(lists.values().as_ref(), f)?;
+    let out = GenericListArray::<O>::try_new(field, lists.offsets().clone(), values, lists.nulls().cloned()).map_err(arrow_err)?;
+    Ok(Arc::new(out))
+}
+
+fn transform_geometry<F>(array:&dyn Array, f:&F) -> Result<ArrayRef>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    match array.data_type() {
+        DataType::Struct(_) => transform_struct(array.as_struct(), f),
+        DataType::FixedSizeList(_, _) => transform_interleaved(array.as_fixed_size_list(), f),
+        DataType::List(_) => transform_list(array.as_list::<i32>(), f),
+        DataType::LargeList(_) => transform_list(array.as_list::<i64>(), f),
+        t => Err(Error::InvalidInput(format!("not a GeoArrow native geometry array: {}", t))),
+    }
+}
+
+/// transform a GeoArrow native geometry array (point, linestring, polygon and multi variants)
+///
+/// coordinates may be separated (a struct of x, y[, z]) or interleaved (a
+/// fixed size list of 2 or more values); the nested list offsets are reused.
+/// # Examples
+///
+/// ```
+/// # extern crate arrow_array;
+/// # extern crate arrow_buffer;
+/// # extern crate arrow_schema;
+/// # extern crate mercator;
+/// use std::sync::Arc;
+/// use arrow_array::{Array, ArrayRef, FixedSizeListArray, Float64Array, ListArray};
+/// use arrow_array::cast::AsArray;
+/// use arrow_array::types::Float64Type;
+/// use arrow_schema::{DataType, Field};
+/// use mercator::arrow::transform_geoarrow;
+/// use mercator::wgs84_to_twd97;
+///
+/// let coords = Float64Array::from(vec![121.0, 24.0, 121.1, 24.1, 121.2, 24.2]);
+/// let field = Arc::new(Field::new("xy", DataType::Float64, false));
+/// let points = FixedSizeListArray::new(field, 2, Arc::new(coords), None);
+/// let lines = ListArray::new(Arc::new(Field::new("vertices", points.data_type().clone(), false)),
+///     arrow_buffer::OffsetBuffer::from_lengths([3]), Arc::new(points), None);
+/// let out:ArrayRef = transform_geoarrow(&lines, wgs84_to_twd97).unwrap();
+/// let xy = out.as_list::<i32>().values().as_fixed_size_list().values().as_primitive::<Float64Type>().clone();
+/// assert!((xy.value(0) - 250000.0).abs() < 1e-6);
+/// ```
+pub fn transform_geoarrow<F>(array:&dyn Array, f:F) -> Result<ArrayRef>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    transform_geometry(array, &f)
+}
@@ -0,0 +1,347 @@
+//! async (tokio) reprojection of CSV and GeoJSON text sequences
+//!
+//! a service reprojecting uploads reads a chunk of at most [`CHUNK_ROWS`]
+//! records, transforms it, writes it and waits for the write before reading
+//! the next one. memory stays bounded whatever the upload size, a slow client
+//! on the output side throttles the reading, and the task yields to the
+//! executor between chunks so other requests keep being served. the futures
+//! run on any executor; tokio's traits are only used for the IO.
+//! - https://www.rfc-editor.org/rfc/rfc8142 (GeoJSON text sequences)
+
+use std::future::Future;
+use std::io;
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncWrite};
+
+use csv::{join_fields, split_fields};
+use error::{Error, Result};
+
+/// records read, transformed and written together
+pub const CHUNK_ROWS:usize = 4096;
+
+/// the default longest line, line ending included, see [`Reprojection::with_max_line`]
+pub const MAX_LINE:usize = 16 << 20;
+
+/// the record separator starting every GeoJSON text sequence record
+const RS:char = '\u{1e}';
+
+fn format_err(msg:String) -> Error {
+    Error::Format(msg)
+}
+
+/// turns one input line, without its line ending, into output text and the number of records it held
+type Handler<'a> = Box<dyn FnMut(&str, &mut String) -> Result<usize> + Send + 'a>;
+
+enum State {
+    Reading,
+    Writing { eof:bool },
+    Flushing,
+    Done,
+}
+
+/// a running line by line reprojection, resolving to the number of records transformed
+///
+/// it is `Send` when the reader and writer are, so it can be spawned.
+pub struct Reprojection<'a, R, W> {
+    reader:R,
+    writer:W,
+    handler:Handler<'a>,
+    state:State,
+    /// bytes of a line not complete yet
+    partial:Vec<u8>,
+    max_line:usize,
+    out:String,
+    written:usize,
+    lines:usize,
+    records:usize,
+}
+
+impl<'a, R, W> Reprojection<'a, R, W> {
+    fn new(reader:R, writer:W, handler:Handler<'a>) -> Reprojection<'a, R, W> {
+        Reprojection {
+            reader, writer, handler,
+            state: State::Reading,
+            partial: Vec::new(),
+            max_line: MAX_LINE,
+            out: String::new(),
+            written: 0,
+            lines: 0,
+            records: 0,
+        }
+    }
+
+    /// fail on a line longer than `bytes`, line ending included, instead of
+    /// buffering it; [`MAX_LINE`] by default
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate futures;
+    /// # extern crate mercator;
+    /// use futures::executor::block_on;
+    /// use mercator::async_io::reproject_csv;
+    /// let input = format!("x,y\n{},1\n", "1".repeat(100));
+    /// let mut output = Vec::new();
+    /// let task = reproject_csv(input.as_bytes(), &mut output, "x", "y", |x, y| (x, y)).with_max_line(64);
+    /// assert!(block_on(task).is_err());
+    /// ```
+    pub fn with_max_line(mut self, bytes:usize) -> Reprojection<'a, R, W> {
+        self.max_line = bytes;
+        self
+    }
+
+    fn line(&mut self) -> Result<()> {
+        let bytes = mem::take(&mut self.partial);
+        let mut line = String::from_utf8(bytes).map_err(|_| format_err("input is not UTF-8".to_string()))?;
+        let len = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(len);
+        self.records += (self.handler)(&line, &mut self.out)?;
+        self.lines += 1;
+        Ok(())
+    }
+}
+
+impl<'a, R, W> Future for Reprojection<'a, R, W> where R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin {
+    type Output = Result<usize>;
+
+    fn poll(self:Pin<&mut Self>, cx:&mut Context) -> Poll<Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            let step = match this.state {
+                State::Reading => this.poll_read(cx),
+                State::Writing { eof } => this.poll_write(cx, eof),
+                State::Flushing => match Pin::new(&mut this.writer).poll_flush(cx) {
+                    Poll::Ready(r) => {
+                        this.state = State::Done;
+                        Poll::Ready(r.map_err(Error::from).map(|_| true))
+                    }
+                    Poll::Pending => Poll::Pending,
+                },
+                State::Done => return Poll::Ready(Ok(this.records)),
+            };
+            match step {
+                Poll::Ready(Ok(true)) => continue,
+                Poll::Ready(Ok(false)) => {
+                    // a chunk went out: let other tasks run before reading the next one
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                Poll::Ready(Err(e)) => {
+                    this.state = State::Done;
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<'a, R, W> Reprojection<'a, R, W> where R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin {
+    /// read lines until a chunk is full or the input ends
+    fn poll_read(&mut self, cx:&mut Context) -> Poll<Result<bool>> {
+        loop {
+            let buf = match Pin::new(&mut self.reader).poll_fill_buf(cx) {
+                Poll::Ready(Ok(buf)) => buf,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e.into())),
+                Poll::Pending => return Poll::Pending,
+            };
+            if buf.is_empty() {
+                if !self.partial.is_empty() {
+                    self.line()?;
+                }
+                self.state = State::Writing { eof: true };
+                return Poll::Ready(Ok(true));
+            }
+            let (used, complete) = match buf.iter().position(|&b| b == b'\n') {
+                Some(i) => (i + 1, true),
+                None => (buf.len(), false),
+            };
+            if self.partial.len() + used > self.max_line {
+                return Poll::Ready(Err(format_err(format!("line {} is longer than {} bytes", self.lines + 1, self.max_line))));
+            }
+            self.partial.extend_from_slice(&buf[..used]);
+            Pin::new(&mut self.reader).consume(used);
+            if complete {
+                self.line()?;
+                if self.lines.is_multiple_of(CHUNK_ROWS) {
+                    self.state = State::Writing { eof: false };
+                    return Poll::Ready(Ok(true));
+                }
+            }
+        }
+    }
+
+    /// write the transformed chunk; `Ok(false)` when done and more input follows
+    fn poll_write(&mut self, cx:&mut Context, eof:bool) -> Poll<Result<bool>> {
+        while self.written < self.out.len() {
+            match Pin::new(&mut self.writer).poll_write(cx, &self.out.as_bytes()[self.written..]) {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Err(io::Error::from(io::ErrorKind::WriteZero).into())),
+                Poll::Ready(Ok(n)) => self.written += n,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e.into())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        self.out.clear();
+        self.written = 0;
+        if eof {
+            self.state = State::Flushing;
+            Poll::Ready(Ok(true))
+        } else {
+            self.state = State::Reading;
+            Poll::Ready(Ok(false))
+        }
+    }
+}
+
+/// reproject the `x` / `y` columns of a CSV stream, copying every other field
+///
+/// the first line is the header naming the columns. rows with an empty x or y
+/// are copied as they are; a value that is not a number fails with its line
+/// number. resolves to the number of rows transformed.
+/// # Examples
+///
+/// ```
+/// # extern crate futures;
+/// # extern crate mercator;
+/// use futures::executor::block_on;
+/// use mercator::async_io::reproject_csv;
+/// use mercator::wgs84_to_twd97;
+/// let input = "id,lng,lat,name\n1,121,24,\"Hualien, east\"\n2,,,unknown\n";
+/// let mut output = Vec::new();
+/// let rows = block_on(reproject_csv(input.as_bytes(), &mut output, "lng", "lat", wgs84_to_twd97)).unwrap();
+/// assert_eq!(rows, 1);
+/// let text = String::from_utf8(output).unwrap();
+/// let lines:Vec<&str> = text.lines().collect();
+/// assert_eq!(lines[0], "id,lng,lat,name");
+/// assert!(lines[1].starts_with("1,250000") && lines[1].ends_with(",\"Hualien, east\""));
+/// assert_eq!(lines[2], "2,,,unknown");
+///
+/// // the future can go to a multi-threaded executor
+/// fn spawnable<T:Send>(_:&T) {}
+/// spawnable(&reproject_csv(input.as_bytes(), Vec::new(), "lng", "lat", wgs84_to_twd97));
+/// ```
+pub fn reproject_csv<'a, R, W, F>(reader:R, writer:W, x:&'a str, y:&'a str, f:F) -> Reprojection<'a, R, W>
+    where R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin, F: Fn(f64, f64) -> (f64, f64) + Send + 'a
+{
+    let mut columns:Option<(usize, usize)> = None;
+    let mut number = 0;
+    let handler = move |line:&str, out:&mut String| {
+        number += 1;
+        let mut fields = split_fields(line);
+        let (xi, yi) = match columns {
+            Some(c) => c,
+            None => {
+                let find = |name:&str| fields.iter().position(|h| h.trim() == name)
+                    .ok_or_else(|| format_err(format!("csv: no column {:?}", name)));
+                columns = Some((find(x)?, find(y)?));
+                out.push_str(line);
+                out.push('\n');
+                return Ok(0);
+            }
+        };
+        if fields.len() <= xi.max(yi) || fields[xi].trim().is_empty() || fields[yi].trim().is_empty() {
+            out.push_str(line);
+            out.push('\n');
+            return Ok(0);
+        }
+        let parse = |v:&str| v.trim().parse::<f64>()
+            .map_err(|_| format_err(format!("csv line {}: bad coordinate {:?}", number, v)));
+        let (px, py) = f(parse(&fields[xi])?, parse(&fields[yi])?);
+        fields[xi] = px.to_string();
+        fields[yi] = py.to_string();
+        out.push_str(&join_fields(&fields));
+        out.push('\n');
+        Ok(1)
+    };
+    Reprojection::new(reader, writer, Box::new(handler))
+}
+
+/// apply `f` to the positions of a GeoJSON object: a feature collection, a
+/// feature or any geometry; stale `bbox` members are removed
+/// # Examples
+///
+/// ```
+/// # extern crate serde_json;
+/// # extern crate mercator;
+/// use mercator::async_io::transform_geojson;
+/// let mut v:serde_json::Value = serde_json::from_str(
+///     r#"{"type":"Feature","bbox":[1,2,3,4],"geometry":{"type":"Point","coordinates":[1,2,10]}}"#).unwrap();
+/// transform_geojson(&mut v, &|x, y| (x * 2.0, y * 2.0));
+/// assert_eq!(v["geometry"]["coordinates"], serde_json::json!([2.0, 4.0, 10]));
+/// assert!(v.get("bbox").is_none());
+/// ```
+pub fn transform_geojson<F:Fn(f64, f64) -> (f64, f64)>(v:&mut Value, f:&F) {
+    fn positions<F:Fn(f64, f64) -> (f64, f64)>(v:&mut Value, f:&F) {
+        if let Value::Array(items) = v {
+            match (items.first().and_then(Value::as_f64), items.get(1).and_then(Value::as_f64)) {
+                (Some(x), Some(y)) => {
+                    let (x, y) = f(x, y);
+                    items[0] = Value::from(x);
+                    items[1] = Value::from(y);
+                }
+                _ => items.iter_mut().for_each(|item| positions(item, f)),
+            }
+        }
+    }
+    if let Some(obj) = v.as_object_mut() {
+        obj.remove("bbox");
+        if let Some(c) = obj.get_mut("coordinates") {
+            positions(c, f);
+        }
+        for key in ["geometry", "geometries", "features"] {
+            match obj.get_mut(key) {
+                Some(Value::Array(items)) => items.iter_mut().for_each(|item| transform_geojson(item, f)),
+                Some(item) => transform_geojson(item, f),
+                None => {}
+            }
+        }
+    }
+}
+
+/// reproject a GeoJSON text sequence (RFC 8142) or newline delimited GeoJSON
+///
+/// every record is a feature or geometry on its own line, the record separator
+/// framing is kept as found. resolves to the number of records written.
+/// # Examples
+///
+/// ```
+/// # extern crate futures;
+/// # extern crate mercator;
+/// # extern crate serde_json;
+/// use futures::executor::block_on;
+/// use mercator::async_io::reproject_geojson_seq;
+/// use mercator::wgs84_to_twd97;
+/// let input = "\u{1e}{\"type\":\"Feature\",\"properties\":{\"id\":1},\"geometry\":{\"type\":\"LineString\",\"coordinates\":[[121,24],[121.5,24.5]]}}\n";
+/// let mut output = Vec::new();
+/// assert_eq!(block_on(reproject_geojson_seq(input.as_bytes(), &mut output, wgs84_to_twd97)).unwrap(), 1);
+/// let text = String::from_utf8(output).unwrap();
+/// assert!(text.starts_with('\u{1e}') && text.ends_with('\n'));
+/// let v:serde_json::Value = serde_json::from_str(&text[1..]).unwrap();
+/// assert!((v["geometry"]["coordinates"][0][0].as_f64().unwrap() - 250000.0).abs() < 1e-6);
+/// ```
+pub fn reproject_geojson_seq<'a, R, W, F>(reader:R, writer:W, f:F) -> Reprojection<'a, R, W>
+    where R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin, F: Fn(f64, f64) -> (f64, f64) + Send + 'a
+{
+    let mut number = 0;
+    let handler = move |line:&str, out:&mut String| {
+        number += 1;
+        let framed = line.starts_with(RS);
+        let text = line.trim_start_matches(RS).trim();
+        if text.is_empty() {
+            return Ok(0);
+        }
+        let mut v:Value = serde_json::from_str(text)
+            .map_err(|e| format_err(format!("geojson line {}: {}", number, e)))?;
+        transform_geojson(&mut v, &f);
+        if framed {
+            out.push(RS);
+        }
+        out.push_str(&v.to_string());
+        out.push('\n');
+        Ok(1)
+    };
+    Reprojection::new(reader, writer, Box::new(handler))
+}
@@ -0,0 +1,68 @@
+//! axis order of coordinate tuples
+//!
+//! the crate works in `(lng, lat)` and `(east, north)` internally; an axis
+//! order only says how tuples are laid out at the edges, so data in EPSG
+//! authority order can be fed in and written out without manual swaps.
+
+/// the order of the two values of a coordinate tuple
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AxisOrder {
+    /// longitude then latitude, the GIS convention
+    #[default]
+    LngLat,
+    /// latitude then longitude, the EPSG authority order of 4326
+    LatLng,
+    /// easting then northing
+    EastNorth,
+    /// northing then easting
+    NorthEast,
+}
+
+impl AxisOrder {
+    /// true when the first value is the y axis (latitude or northing)
+    pub fn is_swapped(&self) -> bool {
+        match *self {
+            AxisOrder::LatLng | AxisOrder::NorthEast => true,
+            AxisOrder::LngLat | AxisOrder::EastNorth => false,
+        }
+    }
+
+    /// reorder a tuple in this order into x first order
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::axis::AxisOrder;
+    /// assert_eq!(AxisOrder::LatLng.to_xy(25.0, 121.5), (121.5, 25.0));
+    /// assert_eq!(AxisOrder::LngLat.to_xy(121.5, 25.0), (121.5, 25.0));
+    /// ```
+    pub fn to_xy(&self, a:f64, b:f64) -> (f64, f64) {
+        if self.is_swapped() { (b, a) } else { (a, b) }
+    }
+
+    /// reorder an x first tuple into this order
+    pub fn from_xy(&self, x:f64, y:f64) -> (f64, f64) {
+        self.to_xy(x, y)
+    }
+}
+
+/// adapt an x first transform to read and write tuples in the given orders
+///
+/// the result plugs into any of the batch or IO functions that take a closure.
+/// # Examples
+///
+/// ```
+/// use mercator::axis::{with_axes, AxisOrder};
+/// use mercator::wgs84_to_twd97;
+/// let mut f = with_axes(AxisOrder::LatLng, AxisOrder::NorthEast, wgs84_to_twd97);
+/// let (x, y) = wgs84_to_twd97(121.5, 25.0);
+/// assert_eq!(f(25.0, 121.5), (y, x));
+/// ```
+pub fn with_axes<F>(input:AxisOrder, output:AxisOrder, mut f:F) -> impl FnMut(f64, f64) -> (f64, f64)
+    where F: FnMut(f64, f64) -> (f64, f64)
+{
+    move |a, b| {
+        let (x, y) = input.to_xy(a, b);
+        let (x, y) = f(x, y);
+        output.from_xy(x, y)
+    }
+}
@@ -0,0 +1,524 @@
+//! batch transforms over slices of coordinates
+//!
+//! these are the kernels the array, dataframe and file integrations build on:
+//! any per-point function (a zone projection, a closure chaining several
+//! steps, ...) is applied in place. slices of your own records are
+//! transformed the same way once they implement [`HasCoords`]. with the
+//! `parallel` feature the `*_parallel` variants spread every chunk over the
+//! threads of the job and give the same points in the same order as the
+//! serial ones.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use error::{Error, Result};
+use extent::Extent;
+use job::{Job, CHUNK};
+use coord::{LngLat, Projected};
+use trace::{self, Activity};
+use zone::Zone;
+
+/// records carrying one `(x, y)` coordinate, which batch transforms read and write back
+/// # Examples
+///
+/// ```
+/// use mercator::batch::{transform_items, HasCoords};
+/// use mercator::wgs84_to_twd97;
+/// struct Vehicle { id:u32, lng:f64, lat:f64 }
+/// impl HasCoords for Vehicle {
+///     fn coords(&self) -> (f64, f64) {
+///         (self.lng, self.lat)
+///     }
+///     fn set_coords(&mut self, x:f64, y:f64) {
+///         self.lng = x;
+///         self.lat = y;
+///     }
+/// }
+/// let mut fleet = vec![Vehicle { id: 7, lng: 121.0, lat: 24.0 }, Vehicle { id: 9, lng: 121.5654, lat: 25.0330 }];
+/// transform_items(&mut fleet, wgs84_to_twd97);
+/// assert_eq!(fleet[0].id, 7);
+/// assert!((fleet[0].lng - 250000.0).abs() < 1e-6);
+/// assert!(fleet[1].lat > 2700000.0);
+/// ```
+pub trait HasCoords {
+    fn coords(&self) -> (f64, f64);
+    fn set_coords(&mut self, x:f64, y:f64);
+}
+
+impl HasCoords for (f64, f64) {
+    fn coords(&self) -> (f64, f64) {
+        *self
+    }
+
+    fn set_coords(&mut self, x:f64, y:f64) {
+        *self = (x, y);
+    }
+}
+
+impl HasCoords for LngLat {
+    fn coords(&self) -> (f64, f64) {
+        (self.lng, self.lat)
+    }
+
+    fn set_coords(&mut self, x:f64, y:f64) {
+        self.lng = x;
+        self.lat = y;
+    }
+}
+
+impl HasCoords for Projected {
+    fn coords(&self) -> (f64, f64) {
+        (self.x, self.y)
+    }
+
+    fn set_coords(&mut self, x:f64, y:f64) {
+        self.x = x;
+        self.y = y;
+    }
+}
+
+/// apply `f` to the coordinates of every item in place, leaving the rest of each item alone
+pub fn transform_items<T, F>(items:&mut [T], f:F)
+    where T: HasCoords, F: Fn(f64, f64) -> (f64, f64)
+{
+    for item in items.iter_mut() {
+        let (x, y) = item.coords();
+        let (nx, ny) = f(x, y);
+        item.set_coords(nx, ny);
+    }
+}
+
+/// apply `f` to every `(x, y)` pair in place
+/// # Examples
+///
+/// ```
+/// use mercator::batch::transform_points;
+/// use mercator::wgs84_to_twd97;
+/// let mut pts = vec![(121.5654, 25.0330), (120.6736, 24.1477)];
+/// transform_points(&mut pts, wgs84_to_twd97);
+/// assert!(pts.iter().all(|p| p.0 > 100000.0 && p.1 > 2000000.0));
+/// ```
+pub fn transform_points<F>(points:&mut [(f64, f64)], f:F)
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    for p in points.iter_mut() {
+        *p = f(p.0, p.1);
+    }
+}
+
+/// count, bounding box and centroid of transformed points, gathered as they are written
+/// # Examples
+///
+/// ```
+/// use mercator::batch::Summary;
+/// let mut s = Summary::new();
+/// assert_eq!(s.bbox(), None);
+/// s.add(1.0, 2.0);
+/// s.add(3.0, 6.0);
+/// assert_eq!(s.count, 2);
+/// assert_eq!(s.bbox(), Some((1.0, 2.0, 3.0, 6.0)));
+/// assert_eq!(s.centroid(), Some((2.0, 4.0)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    /// points added
+    pub count:usize,
+    /// points that failed to transform
+    pub failed:usize,
+    min:(f64, f64),
+    max:(f64, f64),
+    sum:(f64, f64),
+}
+
+impl Summary {
+    pub fn new() -> Summary {
+        Summary { count: 0, failed: 0, min: (f64::INFINITY, f64::INFINITY), max: (f64::NEG_INFINITY, f64::NEG_INFINITY), sum: (0.0, 0.0) }
+    }
+
+    /// add a transformed point
+    pub fn add(&mut self, x:f64, y:f64) {
+        self.count += 1;
+        self.min = (self.min.0.min(x), self.min.1.min(y));
+        self.max = (self.max.0.max(x), self.max.1.max(y));
+        self.sum = (self.sum.0 + x, self.sum.1 + y);
+    }
+
+    /// add the points and failures of another summary, e.g. of another chunk
+    pub fn merge(&mut self, other:&Summary) {
+        self.count += other.count;
+        self.failed += other.failed;
+        self.min = (self.min.0.min(other.min.0), self.min.1.min(other.min.1));
+        self.max = (self.max.0.max(other.max.0), self.max.1.max(other.max.1));
+        self.sum = (self.sum.0 + other.sum.0, self.sum.1 + other.sum.1);
+    }
+
+    /// `(min x, min y, max x, max y)`, `None` without points
+    pub fn bbox(&self) -> Option<(f64, f64, f64, f64)> {
+        if self.count == 0 {
+            return None;
+        }
+        Some((self.min.0, self.min.1, self.max.0, self.max.1))
+    }
+
+    /// the bounding box as an [`Extent`]
+    pub fn extent(&self) -> Option<Extent> {
+        self.bbox().map(|(min_x, min_y, max_x, max_y)| Extent::new(min_x, min_y, max_x, max_y))
+    }
+
+    /// mean of the points, `None` without points
+    pub fn centroid(&self) -> Option<(f64, f64)> {
+        if self.count == 0 {
+            return None;
+        }
+        Some((self.sum.0 / self.count as f64, self.sum.1 / self.count as f64))
+    }
+}
+
+impl Default for Summary {
+    fn default() -> Summary {
+        Summary::new()
+    }
+}
+
+/// [`transform_points`] also returning a [`Summary`] of the output
+/// # Examples
+///
+/// ```
+/// use mercator::batch::transform_points_summary;
+/// use mercator::wgs84_to_twd97;
+/// let mut pts = vec![(121.0, 24.0), (121.0, 24.5)];
+/// let s = transform_points_summary(&mut pts, wgs84_to_twd97);
+/// let (min_x, min_y, _, max_y) = s.bbox().unwrap();
+/// assert!((min_x - 250000.0).abs() < 1e-6);
+/// assert!(max_y - min_y > 55000.0);
+/// ```
+pub fn transform_points_summary<F>(points:&mut [(f64, f64)], f:F) -> Summary
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let mut summary = Summary::new();
+    for p in points.iter_mut() {
+        *p = f(p.0, p.1);
+        summary.add(p.0, p.1);
+    }
+    summary
+}
+
+/// [`transform_points`] as a [`Job`], returning how many points were transformed
+pub fn transform_points_with<F>(points:&mut [(f64, f64)], f:F, job:&mut Job) -> usize
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let total = points.len() as u64;
+    let activity = Activity::start("transform_points", total);
+    let mut done = 0;
+    for chunk in points.chunks_mut(CHUNK) {
+        if job.is_cancelled() {
+            break;
+        }
+        transform_points(chunk, &f);
+        done += chunk.len();
+        job.report(done as u64, total);
+    }
+    activity.finish(done as u64, 0);
+    done
+}
+
+/// points one rayon task transforms
+#[cfg(feature = "parallel")]
+const TASK:usize = 4096;
+
+/// [`transform_points_with`] with every chunk transformed in parallel on the pool of `job`
+///
+/// cancellation, progress and the points written are those of the serial
+/// function, bit for bit and in the same order.
+/// # Examples
+///
+/// ```
+/// use mercator::batch::{transform_points, transform_points_parallel};
+/// use mercator::job::Job;
+/// use mercator::wgs84_to_twd97;
+/// let input:Vec<(f64, f64)> = (0..100000).map(|i| (120.0 + (i % 300) as f64 * 0.01, 22.0 + (i / 300) as f64 * 0.01)).collect();
+/// let mut serial = input.clone();
+/// transform_points(&mut serial, wgs84_to_twd97);
+/// let mut parallel = input.clone();
+/// let done = transform_points_parallel(&mut parallel, wgs84_to_twd97, &mut Job::new().with_threads(4).unwrap());
+/// assert_eq!(done, 100000);
+/// assert!(serial.iter().zip(&parallel).all(|(a, b)| a.0.to_bits() == b.0.to_bits() && a.1.to_bits() == b.1.to_bits()));
+/// ```
+#[cfg(feature = "parallel")]
+pub fn transform_points_parallel<F>(points:&mut [(f64, f64)], f:F, job:&mut Job) -> usize
+    where F: Fn(f64, f64) -> (f64, f64) + Sync
+{
+    let total = points.len() as u64;
+    let activity = Activity::start("transform_points_parallel", total);
+    let mut done = 0;
+    for chunk in points.chunks_mut(CHUNK) {
+        if job.is_cancelled() {
+            break;
+        }
+        job.install(|| chunk.par_chunks_mut(TASK).for_each(|task| transform_points(task, &f)));
+        done += chunk.len();
+        job.report(done as u64, total);
+    }
+    activity.finish(done as u64, 0);
+    done
+}
+
+/// [`transform_points_summary`] in parallel on the pool of `job`, a [`CHUNK`] at a time
+///
+/// like [`transform_points_parallel`] it stops before the next chunk once the
+/// job is cancelled, and the summary covers the points transformed so far.
+/// the summary is that of the serial function unless the job allows
+/// otherwise with [`Job::with_bit_identical`]`(false)`, in which case the
+/// centroid may differ in the last bits.
+/// # Examples
+///
+/// ```
+/// use mercator::batch::{transform_points_summary, transform_points_summary_parallel};
+/// use mercator::job::Job;
+/// use mercator::wgs84_to_twd97;
+/// let input:Vec<(f64, f64)> = (0..50000).map(|i| (120.5 + (i % 200) as f64 * 0.005, 23.0 + (i / 200) as f64 * 0.005)).collect();
+/// let serial = transform_points_summary(&mut input.clone(), wgs84_to_twd97);
+/// let parallel = transform_points_summary_parallel(&mut input.clone(), wgs84_to_twd97, &mut Job::new().with_threads(3).unwrap());
+/// assert_eq!(serial, parallel);
+/// let merged = transform_points_summary_parallel(&mut input.clone(), wgs84_to_twd97, &mut Job::new().with_bit_identical(false));
+/// assert_eq!(merged.bbox(), serial.bbox());
+/// let (a, b) = (merged.centroid().unwrap(), serial.centroid().unwrap());
+/// assert!((a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6);
+/// ```
+///
+/// a cancelled job leaves the remaining points untouched:
+///
+/// ```
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use mercator::batch::transform_points_summary_parallel;
+/// use mercator::job::Job;
+/// use mercator::wgs84_to_twd97;
+/// let cancel = AtomicBool::new(false);
+/// let mut pts = vec![(121.0, 24.0); 100000];
+/// let mut job = Job::new().with_cancel(&cancel).with_progress(|_, _| cancel.store(true, Ordering::Relaxed));
+/// let summary = transform_points_summary_parallel(&mut pts, wgs84_to_twd97, &mut job);
+/// assert_eq!(summary.count, 65536);
+/// assert!((pts[65535].0 - 250000.0).abs() < 1e-6);
+/// assert_eq!(pts[65536], (121.0, 24.0));
+/// ```
+#[cfg(feature = "parallel")]
+pub fn transform_points_summary_parallel<F>(points:&mut [(f64, f64)], f:F, job:&mut Job) -> Summary
+    where F: Fn(f64, f64) -> (f64, f64) + Sync
+{
+    let total = points.len() as u64;
+    let activity = Activity::start("transform_points_summary_parallel", total);
+    let mut summary = Summary::new();
+    let mut done = 0;
+    for chunk in points.chunks_mut(CHUNK) {
+        if job.is_cancelled() {
+            break;
+        }
+        if job.is_bit_identical() {
+            job.install(|| chunk.par_chunks_mut(TASK).for_each(|task| transform_points(task, &f)));
+            for p in chunk.iter() {
+                summary.add(p.0, p.1);
+            }
+        } else {
+            // collected in task order, so the merge is the same for any number of threads
+            let parts:Vec<Summary> = job.install(|| chunk.par_chunks_mut(TASK).map(|task| transform_points_summary(task, &f)).collect());
+            for part in &parts {
+                summary.merge(part);
+            }
+        }
+        done += chunk.len();
+        job.report(done as u64, total);
+    }
+    activity.finish(done as u64, 0);
+    summary
+}
+
+/// apply `f` to separate x and y columns in place, the columns must have the same length
+pub fn transform_xy<F>(xs:&mut [f64], ys:&mut [f64], f:F)
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    assert_eq!(xs.len(), ys.len(), "x and y columns differ in length");
+    for (x, y) in xs.iter_mut().zip(ys.iter_mut()) {
+        let (nx, ny) = f(*x, *y);
+        *x = nx;
+        *y = ny;
+    }
+}
+
+/// [`transform_xy`] as a [`Job`], returning how many points were transformed
+pub fn transform_xy_with<F>(xs:&mut [f64], ys:&mut [f64], f:F, job:&mut Job) -> usize
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    assert_eq!(xs.len(), ys.len(), "x and y columns differ in length");
+    let total = xs.len() as u64;
+    let activity = Activity::start("transform_xy", total);
+    let mut done = 0;
+    for (x, y) in xs.chunks_mut(CHUNK).zip(ys.chunks_mut(CHUNK)) {
+        if job.is_cancelled() {
+            break;
+        }
+        transform_xy(x, y, &f);
+        done += x.len();
+        job.report(done as u64, total);
+    }
+    activity.finish(done as u64, 0);
+    done
+}
+
+/// apply `f` to an interleaved `[x0, y0, x1, y1, ...]` buffer in place
+pub fn transform_interleaved<F>(coords:&mut [f64], f:F)
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    assert!(coords.len().is_multiple_of(2), "interleaved buffer has an odd length");
+    for c in coords.chunks_mut(2) {
+        let (x, y) = f(c[0], c[1]);
+        c[0] = x;
+        c[1] = y;
+    }
+}
+
+/// [`transform_interleaved`] as a [`Job`], returning how many points were transformed
+pub fn transform_interleaved_with<F>(coords:&mut [f64], f:F, job:&mut Job) -> usize
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    assert!(coords.len().is_multiple_of(2), "interleaved buffer has an odd length");
+    let total = coords.len() as u64 / 2;
+    let activity = Activity::start("transform_interleaved", total);
+    let mut done = 0;
+    for chunk in coords.chunks_mut(2 * CHUNK) {
+        if job.is_cancelled() {
+            break;
+        }
+        transform_interleaved(chunk, &f);
+        done += chunk.len() / 2;
+        job.report(done as u64, total);
+    }
+    activity.finish(done as u64, 0);
+    done
+}
+
+/// what a fallible batch transform does with a point that fails
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailurePolicy {
+    /// leave the point as it was and carry on
+    Skip,
+    /// write this value in place of the point and carry on
+    Substitute(f64, f64),
+    /// stop at the first failure, later points are left as they were
+    Abort,
+}
+
+/// a point that could not be transformed
+#[derive(Debug)]
+pub struct Failure {
+    pub index:usize,
+    pub error:Error,
+}
+
+/// outcome of a fallible batch transform
+#[derive(Debug)]
+pub struct BatchReport {
+    /// points transformed successfully
+    pub transformed:usize,
+    /// failed points in index order
+    pub failures:Vec<Failure>,
+    /// true when the policy was `Abort` and a point failed
+    pub aborted:bool,
+    /// the transformed points and the failure count, substituted values are not part of it
+    pub summary:Summary,
+}
+
+impl BatchReport {
+    /// true when every point was transformed
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// indexes of the failed points
+    pub fn failed_indexes(&self) -> Vec<usize> {
+        self.failures.iter().map(|f| f.index).collect()
+    }
+}
+
+/// apply a fallible `f` to every point in place, reporting failures per point
+///
+/// a result with a non-finite coordinate counts as a failure too, so NaN
+/// never ends up in the output unnoticed.
+/// # Examples
+///
+/// ```
+/// use mercator::Error;
+/// use mercator::batch::{try_transform_points, FailurePolicy};
+/// use mercator::wgs84_to_twd97;
+/// let check = |lng:f64, lat:f64| if lat.abs() <= 90.0 {
+///     Ok(wgs84_to_twd97(lng, lat))
+/// } else {
+///     Err(Error::InvalidInput(format!("latitude {} out of range", lat)))
+/// };
+/// let mut pts = vec![(121.0, 24.0), (121.0, 95.0), (f64::NAN, 24.0)];
+/// let report = try_transform_points(&mut pts, check, FailurePolicy::Substitute(0.0, 0.0));
+/// assert_eq!(report.transformed, 1);
+/// assert_eq!(report.failed_indexes(), vec![1, 2]);
+/// assert_eq!(pts[1], (0.0, 0.0));
+/// assert_eq!((report.summary.count, report.summary.failed), (1, 2));
+/// ```
+pub fn try_transform_points<F>(points:&mut [(f64, f64)], f:F, policy:FailurePolicy) -> BatchReport
+    where F: Fn(f64, f64) -> Result<(f64, f64)>
+{
+    try_transform(points, f, policy, "try_transform_points")
+}
+
+/// [`try_transform_points`] over the coordinates of any [`HasCoords`] items
+pub fn try_transform_items<T, F>(items:&mut [T], f:F, policy:FailurePolicy) -> BatchReport
+    where T: HasCoords, F: Fn(f64, f64) -> Result<(f64, f64)>
+{
+    try_transform(items, f, policy, "try_transform_items")
+}
+
+fn try_transform<T, F>(items:&mut [T], f:F, policy:FailurePolicy, name:&'static str) -> BatchReport
+    where T: HasCoords, F: Fn(f64, f64) -> Result<(f64, f64)>
+{
+    let activity = Activity::start(name, items.len() as u64);
+    let mut report = BatchReport { transformed: 0, failures: Vec::new(), aborted: false, summary: Summary::new() };
+    for (index, item) in items.iter_mut().enumerate() {
+        let p = item.coords();
+        let result = match f(p.0, p.1) {
+            Ok((x, y)) if x.is_finite() && y.is_finite() => Ok((x, y)),
+            Ok((x, y)) => Err(Error::Transform(format!("({}, {}) gave non-finite ({}, {})", p.0, p.1, x, y))),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(q) => {
+                item.set_coords(q.0, q.1);
+                report.transformed += 1;
+                report.summary.add(q.0, q.1);
+            }
+            Err(error) => {
+                trace::point_failed(index, &error);
+                report.summary.failed += 1;
+                report.failures.push(Failure { index, error });
+                match policy {
+                    FailurePolicy::Skip => {}
+                    FailurePolicy::Substitute(x, y) => item.set_coords(x, y),
+                    FailurePolicy::Abort => {
+                        report.aborted = true;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    activity.finish(report.transformed as u64, report.failures.len() as u64);
+    report
+}
+
+impl Zone {
+    /// project wgs84 points into this zone in place
+    pub fn forward_batch(&self, points:&mut [(f64, f64)]) {
+        transform_points(points, |lng, lat| self.forward(lng, lat));
+    }
+
+    /// convert grid points of this zone to wgs84 in place
+    pub fn inverse_batch(&self, points:&mut [(f64, f64)]) {
+        transform_points(points, |x, y| self.inverse(x, y));
+    }
+}
@@ -0,0 +1,127 @@
+//! assignment of projected points to the cells of a regular grid
+//!
+//! the first step of heatmaps and statistics grids: project, then count per
+//! square cell of a given size in meters. cells are numbered from the grid
+//! origin, columns growing east and rows growing north, negative indices
+//! covering the points west or south of it.
+
+use std::collections::HashMap;
+
+use affine::Affine2D;
+use extent::Extent;
+use raster::Raster;
+
+/// the column and row of a cell
+pub type Cell = (i64, i64);
+
+/// a grid of square cells of `size` meters whose cell (0, 0) has its lower left corner at `origin`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellGrid {
+    pub origin:(f64, f64),
+    pub size:f64,
+}
+
+impl CellGrid {
+    pub fn new(origin_x:f64, origin_y:f64, size:f64) -> CellGrid {
+        CellGrid { origin: (origin_x, origin_y), size }
+    }
+
+    /// the cell holding a projected point, `None` for NaN or a point too far away to index
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::binning::CellGrid;
+    /// let grid = CellGrid::new(250000.0, 2655000.0, 1000.0);
+    /// assert_eq!(grid.cell(251500.0, 2654999.0), Some((1, -1)));
+    /// assert_eq!(grid.cell(f64::NAN, 0.0), None);
+    /// ```
+    pub fn cell(&self, x:f64, y:f64) -> Option<Cell> {
+        let col = ((x - self.origin.0) / self.size).floor();
+        let row = ((y - self.origin.1) / self.size).floor();
+        let range = -9.0e18..9.0e18;
+        if range.contains(&col) && range.contains(&row) {
+            Some((col as i64, row as i64))
+        } else {
+            None
+        }
+    }
+
+    /// the area covered by a cell
+    pub fn bounds(&self, (col, row):Cell) -> Extent {
+        let x = self.origin.0 + col as f64 * self.size;
+        let y = self.origin.1 + row as f64 * self.size;
+        Extent::new(x, y, x + self.size, y + self.size)
+    }
+
+    /// the center of a cell
+    pub fn center(&self, cell:Cell) -> (f64, f64) {
+        self.bounds(cell).center()
+    }
+}
+
+/// project every point with `f` and return its cell
+pub fn bin_points<F>(grid:&CellGrid, points:&[(f64, f64)], f:F) -> Vec<Option<Cell>>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    points.iter().map(|p| {
+        let (x, y) = f(p.0, p.1);
+        grid.cell(x, y)
+    }).collect()
+}
+
+/// project every point with `f` and count the points per occupied cell
+/// # Examples
+///
+/// ```
+/// use mercator::binning::{count_points, CellGrid};
+/// use mercator::wgs84_to_twd97;
+/// let grid = CellGrid::new(250000.0, 2655000.0, 1000.0);
+/// let counts = count_points(&grid, &[(121.0, 24.0), (121.001, 24.001), (121.1, 24.0)], wgs84_to_twd97);
+/// assert_eq!(counts[&(0, 0)], 2);
+/// assert_eq!(counts.len(), 2);
+/// ```
+pub fn count_points<F>(grid:&CellGrid, points:&[(f64, f64)], f:F) -> HashMap<Cell, u64>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let mut counts = HashMap::new();
+    for cell in bin_points(grid, points, f).into_iter().flatten() {
+        *counts.entry(cell).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// project every point with `f` and count the points of the `cols` x `rows`
+/// cells starting at cell (0, 0) into a raster
+///
+/// the raster has its top row at the north like any other raster of the
+/// crate, points outside the cells are not counted.
+/// # Examples
+///
+/// ```
+/// use mercator::binning::{count_raster, CellGrid};
+/// use mercator::wgs84_to_twd97;
+/// let grid = CellGrid::new(250000.0, 2655000.0, 1000.0);
+/// let r = count_raster(&grid, 3, 2, &[(121.0, 24.0), (121.001, 24.001), (121.0, 24.01), (120.0, 24.0)], wgs84_to_twd97);
+/// assert_eq!(r.get(0, 1), 2.0);
+/// assert_eq!(r.get(0, 0), 1.0);
+/// assert_eq!(r.data.iter().sum::<f64>(), 3.0);
+/// ```
+pub fn count_raster<F>(grid:&CellGrid, cols:usize, rows:usize, points:&[(f64, f64)], f:F) -> Raster
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let size = grid.size;
+    let top = grid.origin.1 + rows as f64 * size;
+    let transform = Affine2D::new(size, 0.0, grid.origin.0 + size / 2.0, 0.0, -size, top - size / 2.0);
+    let mut raster = Raster::new(cols, rows, transform);
+    for v in raster.data.iter_mut() {
+        *v = 0.0;
+    }
+    for (col, row) in bin_points(grid, points, f).into_iter().flatten() {
+        if col >= 0 && row >= 0 && (col as usize) < cols && (row as usize) < rows {
+            let r = rows - 1 - row as usize;
+            let v = raster.get(col as usize, r);
+            raster.set(col as usize, r, v + 1.0);
+        }
+    }
+    raster
+}
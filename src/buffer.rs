@@ -0,0 +1,247 @@
+//! buffers and circles at a ground distance around points and polylines
+//!
+//! the outlines are built on the ellipsoid from [`geodesic`](::geodesic)
+//! offsets, so a 500 m buffer is 500 m on the ground wherever it is, and only
+//! then optionally projected. lines get round caps and round joins on the
+//! outside of bends; inside a bend the two offsets meet at a mitre point,
+//! which assumes the segments around the bend are longer than the radius.
+//! rings are counterclockwise and closed, with longitudes in (-180, 180], so
+//! a buffer over the antimeridian still needs
+//! [`geometry`](::geometry) splitting before it is drawn.
+
+use crs::Transformer;
+use ellipsoid::Ellipsoid;
+use error::Result;
+use geodesic::Geodesic;
+use geometry::{LineString, Polygon};
+use longitude::normalize_lng;
+
+/// longest part of a segment offset as a whole, in meters
+const MAX_PIECE:f64 = 10_000.0;
+
+/// buffer polygons of a fixed radius
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Buffer {
+    geodesic:Geodesic,
+    radius:f64,
+    segments:usize,
+}
+
+impl Buffer {
+    /// buffers of `radius` meters on WGS84, with 64 vertices per full circle
+    pub fn new(radius:f64) -> Buffer {
+        Buffer { geodesic: Geodesic::wgs84(), radius, segments: 64 }
+    }
+
+    /// measure on another ellipsoid
+    pub fn with_ellipsoid(mut self, ellipsoid:&Ellipsoid) -> Buffer {
+        self.geodesic = Geodesic::new(ellipsoid);
+        self
+    }
+
+    /// vertices per full circle of the round parts, at least 4
+    pub fn with_segments(mut self, segments:usize) -> Buffer {
+        self.segments = segments.max(4);
+        self
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn offset(&self, p:(f64, f64), bearing:f64) -> (f64, f64) {
+        self.geodesic.direct(p, bearing, self.radius).point
+    }
+
+    /// points of the circle around `center` from `start` turning `sweep` degrees, ends included
+    fn arc(&self, center:(f64, f64), start:f64, sweep:f64) -> LineString {
+        let n = (sweep.abs() * self.segments as f64 / 360.0).ceil().max(1.0) as usize;
+        (0..=n).map(|k| self.offset(center, start + sweep * k as f64 / n as f64)).collect()
+    }
+
+    /// the stations of every segment, `(point, bearing)` from its start to its end
+    fn stations(&self, line:&[(f64, f64)]) -> Vec<Vec<((f64, f64), f64)>> {
+        line.windows(2).map(|w| {
+            let inv = self.geodesic.inverse(w[0], w[1]);
+            let pieces = (inv.distance / MAX_PIECE).ceil().max(1.0) as usize;
+            let path = self.geodesic.line(w[0], inv.initial_bearing);
+            let mut seg = vec![(w[0], inv.initial_bearing)];
+            for k in 1..pieces {
+                let d = path.position(inv.distance * k as f64 / pieces as f64);
+                seg.push((d.point, d.final_bearing));
+            }
+            seg.push((w[1], inv.final_bearing));
+            seg
+        }).collect()
+    }
+
+    /// the offset on the right of a path, with its joins
+    fn side(&self, line:&[(f64, f64)], out:&mut LineString) {
+        let segs = self.stations(line);
+        let (p, b) = segs[0][0];
+        out.push(self.offset(p, b + 90.0));
+        for (i, seg) in segs.iter().enumerate() {
+            for &(p, b) in &seg[1..seg.len() - 1] {
+                out.push(self.offset(p, b + 90.0));
+            }
+            let (v, b_in) = seg[seg.len() - 1];
+            let b_out = match segs.get(i + 1) {
+                Some(next) => next[0].1,
+                None => {
+                    out.push(self.offset(v, b_in + 90.0));
+                    continue;
+                }
+            };
+            let turn = normalize_lng(b_out - b_in);
+            let cos_half = (turn / 2.0).to_radians().cos();
+            if turn.abs() < 1e-9 {
+                out.push(self.offset(v, b_in + 90.0));
+            } else if turn < 0.0 {
+                // a left turn, the right side is the outside of the bend
+                out.extend(self.arc(v, b_in + 90.0, turn));
+            } else if cos_half > 0.2 {
+                let mitre = self.geodesic.direct(v, b_in + 90.0 + turn / 2.0, self.radius / cos_half);
+                out.push(mitre.point);
+            } else {
+                // nearly reversing, a mitre would spike far out
+                out.push(self.offset(v, b_in + 90.0));
+                out.push(self.offset(v, b_out + 90.0));
+            }
+        }
+    }
+
+    /// the buffer around a `(lng, lat)` point, a geodesic circle
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::buffer::Buffer;
+    /// use mercator::geodesic::Geodesic;
+    /// let circle = Buffer::new(1000.0).point((121.5, 25.0));
+    /// let ring = &circle[0];
+    /// assert_eq!(ring.len(), 65);
+    /// assert_eq!(ring[0], ring[64]);
+    /// let g = Geodesic::wgs84();
+    /// assert!(ring.iter().all(|p| (g.inverse((121.5, 25.0), *p).distance - 1000.0).abs() < 1e-6));
+    /// // the polygon inscribed in the circle
+    /// assert!((g.polygon_area(&circle) / (std::f64::consts::PI * 1e6) - 1.0).abs() < 2e-3);
+    /// ```
+    pub fn point(&self, center:(f64, f64)) -> Polygon {
+        vec![circle(&self.geodesic, center, self.radius, self.segments)]
+    }
+
+    /// the buffer around a polyline of `(lng, lat)` points, with round caps
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::buffer::Buffer;
+    /// use mercator::geodesic::Geodesic;
+    /// let g = Geodesic::wgs84();
+    /// let road = vec![(121.50, 25.00), (121.52, 25.00), (121.52, 25.02)];
+    /// let corridor = Buffer::new(50.0).line(&road);
+    /// // a 100 m wide band along the road, overlapping by 50 × 50 m inside the
+    /// // bend, plus half discs at the ends and a quarter disc outside the bend
+    /// let (r, pi) = (50.0, std::f64::consts::PI);
+    /// let expected = 2.0 * r * g.length(&road) - r * r + 1.25 * pi * r * r;
+    /// assert!((g.polygon_area(&corridor) / expected - 1.0).abs() < 1e-3);
+    /// ```
+    pub fn line(&self, line:&[(f64, f64)]) -> Polygon {
+        let mut points:LineString = Vec::with_capacity(line.len());
+        for &p in line {
+            if points.last() != Some(&p) {
+                points.push(p);
+            }
+        }
+        if points.len() < 2 {
+            return points.first().map(|&p| self.point(p)).unwrap_or_default();
+        }
+        let mut ring = Vec::new();
+        self.side(&points, &mut ring);
+        let end = points[points.len() - 1];
+        let b_end = self.geodesic.inverse(points[points.len() - 2], end).final_bearing;
+        let cap = self.arc(end, b_end + 90.0, -180.0);
+        ring.extend_from_slice(&cap[1..cap.len() - 1]);
+        points.reverse();
+        self.side(&points, &mut ring);
+        let start = points[points.len() - 1];
+        let b_start = self.geodesic.inverse(points[points.len() - 2], start).final_bearing;
+        let cap = self.arc(start, b_start + 90.0, -180.0);
+        ring.extend_from_slice(&cap[1..cap.len() - 1]);
+        let first = ring[0];
+        ring.push(first);
+        vec![ring]
+    }
+
+    /// [`Buffer::point`] projected with a transformer from WGS84 lng/lat
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::buffer::Buffer;
+    /// use mercator::crs::Transformer;
+    /// use mercator::extent::polygon_area;
+    /// let t = Transformer::from_epsg(4326, 3826).unwrap();
+    /// let circle = Buffer::new(1000.0).point_in((121.0, 24.0), &t).unwrap();
+    /// // grid area is ground area times the squared scale factor, 0.9999 on the central meridian
+    /// assert!((polygon_area(&circle) / (std::f64::consts::PI * 1e6 * 0.9998) - 1.0).abs() < 2e-3);
+    /// ```
+    pub fn point_in(&self, center:(f64, f64), transformer:&Transformer) -> Result<Polygon> {
+        project(self.point(center), transformer)
+    }
+
+    /// [`Buffer::line`] projected with a transformer from WGS84 lng/lat
+    pub fn line_in(&self, line:&[(f64, f64)], transformer:&Transformer) -> Result<Polygon> {
+        project(self.line(line), transformer)
+    }
+}
+
+/// a closed counterclockwise ring of `n` vertices `radius` meters from `center` along geodesics
+fn circle(geodesic:&Geodesic, center:(f64, f64), radius:f64, n:usize) -> LineString {
+    let mut ring:LineString = (0..n)
+        .map(|i| geodesic.direct(center, -360.0 * i as f64 / n as f64, radius).point)
+        .collect();
+    ring.push(ring[0]);
+    ring
+}
+
+/// a circle of `radius` meters around a WGS84 `(lng, lat)` center as a
+/// closed ring of `n` vertices (at least 3), the first due north
+///
+/// the vertices are on the circle, so the ring is the inscribed polygon.
+/// # Examples
+///
+/// ```
+/// use mercator::buffer::circle_polygon;
+/// use mercator::geodesic::Geodesic;
+/// let fence = circle_polygon((121.5654, 25.0330), 300.0, 36);
+/// assert_eq!(fence.len(), 37);
+/// assert_eq!(fence[0], fence[36]);
+/// let g = Geodesic::wgs84();
+/// assert!((g.inverse((121.5654, 25.0330), fence[9]).distance - 300.0).abs() < 1e-6);
+/// assert!(fence[9].0 < 121.5654 && (fence[9].1 - 25.0330).abs() < 1e-4);
+/// ```
+pub fn circle_polygon(center:(f64, f64), radius:f64, n:usize) -> LineString {
+    circle(&Geodesic::wgs84(), center, radius, n.max(3))
+}
+
+/// [`circle_polygon`] projected with a transformer from WGS84 lng/lat
+/// # Examples
+///
+/// ```
+/// use mercator::buffer::circle_polygon_in;
+/// use mercator::crs::{Crs, Transformer};
+/// use mercator::zone::Zone;
+/// let t = Transformer::new(Crs::wgs84(), Crs::projected(Zone::utm(51)));
+/// let ring = circle_polygon_in((121.5654, 25.0330), 300.0, 72, &t).unwrap();
+/// let (x, y) = t.transform(121.5654, 25.0330);
+/// // UTM scale near the zone edge stretches the ground radius a little
+/// assert!(ring.iter().all(|p| ((p.0 - x).hypot(p.1 - y) / 300.0 - 1.0).abs() < 2e-3));
+/// ```
+pub fn circle_polygon_in(center:(f64, f64), radius:f64, n:usize, transformer:&Transformer) -> Result<LineString> {
+    circle_polygon(center, radius, n).into_iter().map(|(x, y)| transformer.try_transform(x, y)).collect()
+}
+
+/// a polygon with every vertex transformed
+pub(crate) fn project(poly:Polygon, transformer:&Transformer) -> Result<Polygon> {
+    poly.into_iter()
+        .map(|ring| ring.into_iter().map(|(x, y)| transformer.try_transform(x, y)).collect())
+        .collect()
+}
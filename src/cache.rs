@@ -0,0 +1,145 @@
+//! cache of prepared transformers keyed by (source, target) CRS
+//!
+//! meant for servers answering many small requests: the transformer for a
+//! pair of CRSs is built once and shared afterwards. the least recently
+//! used entry is dropped when the cache is full. CRSs are looked up in the
+//! global [`registry`](::registry::registry), so custom definitions work
+//! the same as EPSG codes; the cache empties itself when the registry
+//! changes.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crs::Transformer;
+use error::Result;
+use registry::{normalize_key, registry};
+
+/// entries kept by the cache behind [`transform`]
+pub const DEFAULT_CAPACITY:usize = 64;
+
+struct Entries {
+    map:HashMap<(String, String), (Arc<Transformer>, u64)>,
+    clock:u64,
+    generation:u64,
+}
+
+/// a thread safe LRU cache of transformers
+pub struct TransformerCache {
+    capacity:usize,
+    entries:Mutex<Entries>,
+}
+
+impl TransformerCache {
+    /// a cache holding at most `capacity` transformers (at least one)
+    pub fn new(capacity:usize) -> TransformerCache {
+        TransformerCache {
+            capacity: capacity.max(1),
+            entries: Mutex::new(Entries { map: HashMap::new(), clock: 0, generation: registry().generation() }),
+        }
+    }
+
+    /// the transformer from `source` to `target` EPSG (or custom numeric) codes, built on first use
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use mercator::cache::TransformerCache;
+    /// let cache = TransformerCache::new(2);
+    /// let a = cache.get(4326, 3826).unwrap();
+    /// let b = cache.get(4326, 3826).unwrap();
+    /// assert!(Arc::ptr_eq(&a, &b));
+    /// cache.get(4326, 32651).unwrap();
+    /// cache.get(3826, 4326).unwrap();
+    /// // (4326, 3826) was the least recently used and has been dropped
+    /// assert_eq!(cache.len(), 2);
+    /// assert!(!Arc::ptr_eq(&a, &cache.get(4326, 3826).unwrap()));
+    /// ```
+    pub fn get(&self, source:u32, target:u32) -> Result<Arc<Transformer>> {
+        self.get_named(&source.to_string(), &target.to_string())
+    }
+
+    /// the transformer between two registry keys, e.g. `"EPSG:4326"` and a custom name
+    pub fn get_named(&self, source:&str, target:&str) -> Result<Arc<Transformer>> {
+        let key = (normalize_key(source), normalize_key(target));
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let generation = registry().generation();
+        if entries.generation != generation {
+            entries.map.clear();
+            entries.generation = generation;
+        }
+        entries.clock += 1;
+        let now = entries.clock;
+        if let Some(entry) = entries.map.get_mut(&key) {
+            entry.1 = now;
+            return Ok(entry.0.clone());
+        }
+        let t = Arc::new(registry().transformer(&key.0, &key.1)?);
+        if entries.map.len() >= self.capacity {
+            let oldest = entries.map.iter().min_by_key(|e| (e.1).1).map(|e| e.0.clone());
+            if let Some(oldest) = oldest {
+                entries.map.remove(&oldest);
+            }
+        }
+        entries.map.insert(key, (t.clone(), now));
+        Ok(t)
+    }
+
+    /// number of cached transformers
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// drop every cached transformer
+    pub fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).map.clear();
+    }
+}
+
+fn global() -> &'static TransformerCache {
+    static CACHE:OnceLock<TransformerCache> = OnceLock::new();
+    CACHE.get_or_init(|| TransformerCache::new(DEFAULT_CAPACITY))
+}
+
+/// the shared transformer from `source` to `target` codes
+pub fn transformer(source:u32, target:u32) -> Result<Arc<Transformer>> {
+    global().get(source, target)
+}
+
+/// the shared transformer between two registry keys
+pub fn transformer_named(source:&str, target:&str) -> Result<Arc<Transformer>> {
+    global().get_named(source, target)
+}
+
+/// transform one coordinate between EPSG codes through the shared cache
+/// # Examples
+///
+/// ```
+/// use mercator::cache::transform;
+/// use mercator::wgs84_to_twd97;
+/// let (x, y) = transform(4326, 3826, 121.5654, 25.0330).unwrap();
+/// assert_eq!((x, y), wgs84_to_twd97(121.5654, 25.0330));
+/// assert!(transform(4326, 1234, 121.0, 24.0).is_err());
+/// ```
+pub fn transform(source:u32, target:u32, x:f64, y:f64) -> Result<(f64, f64)> {
+    Ok(transformer(source, target)?.transform(x, y))
+}
+
+/// transform one coordinate between registry keys through the shared cache
+/// # Examples
+///
+/// ```
+/// use mercator::cache::transform_named;
+/// use mercator::crs::Crs;
+/// use mercator::registry::registry;
+/// use mercator::zone::{Zone, ZoneWidth};
+/// registry().register("site:grid", Crs::projected(Zone::new(ZoneWidth::Degree3, 120.0))).unwrap();
+/// let (x, _) = transform_named("EPSG:4326", "site:grid", 120.0, 24.0).unwrap();
+/// assert!((x - 350000.0).abs() < 1e-6);
+/// ```
+pub fn transform_named(source:&str, target:&str, x:f64, y:f64) -> Result<(f64, f64)> {
+    Ok(transformer_named(source, target)?.transform(x, y))
+}
@@ -0,0 +1,183 @@
+//! bounded memory conversions of point streams of any size
+//!
+//! a [`ChunkedTransformer`] pulls points from an iterator or a reader into a
+//! buffer of a fixed number of points, transforms the buffer, optionally in
+//! parallel, and hands it to a sink or a writer before pulling the next one.
+//! the memory used is the buffer, however many points go through.
+
+use std::io::{self, Read, Write};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use batch::transform_points;
+use error::{Error, Result};
+use job::{Job, CHUNK};
+use trace::Activity;
+
+/// bytes of one point of the binary format, `x, y` as little-endian f64
+const POINT_BYTES:usize = 16;
+
+fn sequential<F:Fn(f64, f64) -> (f64, f64)>(f:&F, points:&mut [(f64, f64)], _job:&Job) {
+    transform_points(points, f);
+}
+
+#[cfg(feature = "parallel")]
+fn in_parallel<F:Fn(f64, f64) -> (f64, f64) + Sync>(f:&F, points:&mut [(f64, f64)], job:&Job) {
+    job.install(|| points.par_chunks_mut(4096).for_each(|chunk| transform_points(chunk, f)));
+}
+
+/// a per-point function applied a chunk of points at a time
+/// # Examples
+///
+/// ```
+/// use mercator::chunked::ChunkedTransformer;
+/// use mercator::wgs84_to_twd97;
+/// let t = ChunkedTransformer::new(wgs84_to_twd97).with_chunk_size(1000);
+/// let mut sizes = Vec::new();
+/// let n = t.run((0..2500).map(|i| (121.0, 24.0 + i as f64 * 1e-4)), |chunk| {
+///     sizes.push(chunk.len());
+///     Ok(())
+/// }).unwrap();
+/// assert_eq!(n, 2500);
+/// assert_eq!(sizes, vec![1000, 1000, 500]);
+/// ```
+pub struct ChunkedTransformer<F> {
+    f:F,
+    chunk:usize,
+    apply:fn(&F, &mut [(f64, f64)], &Job),
+}
+
+impl<F> ChunkedTransformer<F> where F: Fn(f64, f64) -> (f64, f64) {
+    /// chunks of [`CHUNK`] points, transformed on the calling thread
+    pub fn new(f:F) -> ChunkedTransformer<F> {
+        ChunkedTransformer { f, chunk: CHUNK, apply: sequential::<F> }
+    }
+
+    /// points held and transformed together, at least 1
+    pub fn with_chunk_size(mut self, points:usize) -> ChunkedTransformer<F> {
+        self.chunk = points.max(1);
+        self
+    }
+
+    /// points per chunk
+    pub fn chunk_size(&self) -> usize {
+        self.chunk
+    }
+
+    /// transform every chunk with rayon, on the pool of the job, or on the calling thread again
+    ///
+    /// the chunks reach the sink in input order with the same points either way.
+    #[cfg(feature = "parallel")]
+    pub fn with_parallel(mut self, parallel:bool) -> ChunkedTransformer<F> where F: Sync {
+        self.apply = if parallel { in_parallel::<F> } else { sequential::<F> };
+        self
+    }
+
+    /// transform the points of `points`, passing every transformed chunk to `sink`
+    ///
+    /// returns the number of points transformed; an error from the sink stops the work.
+    pub fn run<I, S>(&self, points:I, sink:S) -> Result<u64>
+        where I: IntoIterator<Item = (f64, f64)>, S: FnMut(&[(f64, f64)]) -> Result<()>
+    {
+        self.run_with(points, sink, &mut Job::new())
+    }
+
+    /// [`run`](ChunkedTransformer::run) as a [`Job`], checked and reported after every chunk
+    pub fn run_with<I, S>(&self, points:I, mut sink:S, job:&mut Job) -> Result<u64>
+        where I: IntoIterator<Item = (f64, f64)>, S: FnMut(&[(f64, f64)]) -> Result<()>
+    {
+        let activity = Activity::start("chunked_run", 0);
+        let mut points = points.into_iter();
+        let mut buf = Vec::with_capacity(self.chunk);
+        let mut done = 0;
+        while !job.is_cancelled() {
+            buf.clear();
+            buf.extend(points.by_ref().take(self.chunk));
+            if buf.is_empty() {
+                break;
+            }
+            (self.apply)(&self.f, &mut buf, job);
+            sink(&buf)?;
+            done += buf.len() as u64;
+            job.report(done, 0);
+        }
+        activity.finish(done, 0);
+        Ok(done)
+    }
+
+    /// transform binary `x, y` little-endian f64 pairs from `reader` to `writer`
+    ///
+    /// returns the number of points; input ending inside a point is an error.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::chunked::ChunkedTransformer;
+    /// use mercator::wgs84_to_twd97;
+    /// let mut input = Vec::new();
+    /// for v in [121.0f64, 24.0, 121.5, 24.5] {
+    ///     input.extend_from_slice(&v.to_le_bytes());
+    /// }
+    /// let mut output = Vec::new();
+    /// let t = ChunkedTransformer::new(wgs84_to_twd97);
+    /// assert_eq!(t.run_io(&input[..], &mut output).unwrap(), 2);
+    /// assert_eq!(output.len(), 32);
+    /// assert!(t.run_io(&input[..20], &mut Vec::new()).is_err());
+    /// ```
+    pub fn run_io<R:Read, W:Write>(&self, reader:R, writer:W) -> Result<u64> {
+        self.run_io_with(reader, writer, &mut Job::new())
+    }
+
+    /// [`run_io`](ChunkedTransformer::run_io) as a [`Job`], checked and reported after every chunk
+    pub fn run_io_with<R:Read, W:Write>(&self, mut reader:R, mut writer:W, job:&mut Job) -> Result<u64> {
+        let activity = Activity::start("chunked_run_io", 0);
+        let mut bytes = vec![0; self.chunk * POINT_BYTES];
+        let mut buf = Vec::with_capacity(self.chunk);
+        let mut done = 0;
+        while !job.is_cancelled() {
+            let len = fill(&mut reader, &mut bytes)?;
+            if !len.is_multiple_of(POINT_BYTES) {
+                return Err(Error::Format(format!("point stream ends inside point {}", done + (len / POINT_BYTES) as u64)));
+            }
+            if len == 0 {
+                break;
+            }
+            buf.clear();
+            buf.extend(bytes[..len].chunks_exact(POINT_BYTES).map(|p| (le_f64(&p[..8]), le_f64(&p[8..]))));
+            (self.apply)(&self.f, &mut buf, job);
+            for (p, &(x, y)) in bytes.chunks_exact_mut(POINT_BYTES).zip(buf.iter()) {
+                p[..8].copy_from_slice(&x.to_le_bytes());
+                p[8..].copy_from_slice(&y.to_le_bytes());
+            }
+            writer.write_all(&bytes[..len])?;
+            done += buf.len() as u64;
+            job.report(done, 0);
+            if len < bytes.len() {
+                break;
+            }
+        }
+        writer.flush()?;
+        activity.finish(done, 0);
+        Ok(done)
+    }
+}
+
+fn le_f64(bytes:&[u8]) -> f64 {
+    let mut b = [0; 8];
+    b.copy_from_slice(bytes);
+    f64::from_le_bytes(b)
+}
+
+/// read until `buf` is full or the input ends, returning the bytes read
+fn fill<R:Read>(reader:&mut R, buf:&mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(len)
+}
@@ -0,0 +1,118 @@
+//! plane coordinate geometry (COGO) on grid coordinates
+//!
+//! `(easting, northing)` points of a TM grid, grid bearings in degrees
+//! clockwise from grid north and grid distances in meters. reduce observed
+//! directions and distances to the grid first with [`survey`](::survey).
+
+use heading::normalize_heading;
+
+/// a straight leg between two grid points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Leg {
+    /// grid bearing, degrees clockwise from grid north
+    pub bearing:f64,
+    /// grid distance
+    pub distance:f64,
+}
+
+impl Leg {
+    pub fn new(bearing:f64, distance:f64) -> Leg {
+        Leg { bearing, distance }
+    }
+
+    /// `(Δe, Δn)` of the leg
+    pub fn delta(&self) -> (f64, f64) {
+        let (s, c) = self.bearing.to_radians().sin_cos();
+        (self.distance * s, self.distance * c)
+    }
+}
+
+/// the point `distance` along a grid `bearing` from a grid point
+/// # Examples
+///
+/// ```
+/// use mercator::cogo::forward;
+/// let (e, n) = forward((250000.0, 2650000.0), 135.0, 100.0 * 2f64.sqrt());
+/// assert!((e - 250100.0).abs() < 1e-9 && (n - 2649900.0).abs() < 1e-9);
+/// ```
+pub fn forward(from:(f64, f64), bearing:f64, distance:f64) -> (f64, f64) {
+    let (de, dn) = Leg::new(bearing, distance).delta();
+    (from.0 + de, from.1 + dn)
+}
+
+/// grid distance and bearing in [0, 360) from one grid point to another
+/// # Examples
+///
+/// ```
+/// use mercator::cogo::inverse;
+/// let leg = inverse((250000.0, 2650000.0), (249900.0, 2650000.0));
+/// assert_eq!(leg.distance, 100.0);
+/// assert_eq!(leg.bearing, 270.0);
+/// ```
+pub fn inverse(from:(f64, f64), to:(f64, f64)) -> Leg {
+    let (de, dn) = (to.0 - from.0, to.1 - from.1);
+    Leg { bearing: normalize_heading(de.atan2(dn).to_degrees()), distance: de.hypot(dn) }
+}
+
+/// how far a traverse misses its known closing point, with the points adjusted by the compass (Bowditch) rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closure {
+    /// `(Δe, Δn)` from the known closing point to where the legs end
+    pub misclosure:(f64, f64),
+    /// length of the misclosure
+    pub linear:f64,
+    /// total length of the legs
+    pub length:f64,
+    /// the traverse points from the start to the closing point, each shifted in
+    /// proportion to the distance run to it
+    pub adjusted:Vec<(f64, f64)>,
+}
+
+impl Closure {
+    /// relative precision as the `N` of `1 : N`, infinite for a perfect closure
+    pub fn precision(&self) -> f64 {
+        self.length / self.linear
+    }
+}
+
+/// run a traverse from `start` along `legs` and close it on the known point `end`
+///
+/// a loop traverse closes on its own start.
+/// # Examples
+///
+/// ```
+/// use mercator::cogo::{closure, Leg};
+/// let start = (250000.0, 2650000.0);
+/// // a 100 m square with a 3 cm error in the last distance
+/// let legs = [Leg::new(90.0, 100.0), Leg::new(0.0, 100.0), Leg::new(270.0, 100.0), Leg::new(180.0, 100.03)];
+/// let c = closure(start, &legs, start);
+/// assert!((c.misclosure.1 + 0.03).abs() < 1e-6 && c.misclosure.0.abs() < 1e-6);
+/// assert!((c.precision() - 400.03 / 0.03).abs() < 1.0);
+/// assert_eq!(c.adjusted.len(), 5);
+/// // the adjusted traverse closes
+/// let last = c.adjusted[4];
+/// assert!((last.0 - start.0).abs() < 1e-9 && (last.1 - start.1).abs() < 1e-9);
+/// ```
+pub fn closure(start:(f64, f64), legs:&[Leg], end:(f64, f64)) -> Closure {
+    let mut points = vec![start];
+    let mut run = vec![0.0];
+    let mut length = 0.0;
+    for leg in legs {
+        let last = points[points.len() - 1];
+        points.push(forward(last, leg.bearing, leg.distance));
+        length += leg.distance.abs();
+        run.push(length);
+    }
+    let reached = points[points.len() - 1];
+    let misclosure = (reached.0 - end.0, reached.1 - end.1);
+    let adjusted = points.iter().zip(&run).map(|(p, &d)| {
+        let share = if length > 0.0 { d / length } else { 0.0 };
+        (p.0 - misclosure.0 * share, p.1 - misclosure.1 * share)
+    }).collect();
+    Closure {
+        misclosure,
+        linear: misclosure.0.hypot(misclosure.1),
+        length,
+        adjusted,
+    }
+}
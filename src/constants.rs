@@ -0,0 +1,66 @@
+//! ellipsoid derived constants of the TM series, cached per standard ellipsoid
+//!
+//! the free functions are called once per point, so eccentricities and series
+//! coefficients are computed once for the ellipsoids in
+//! [`STANDARD`](::ellipsoid::STANDARD) and on the fly only for custom ones.
+
+use std::sync::OnceLock;
+
+use ellipsoid::{Ellipsoid, STANDARD};
+
+/// constants of the classic TM series in the crate root
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TmConstants {
+    pub a:f64,
+    /// first eccentricity squared
+    pub e2:f64,
+    /// second eccentricity squared
+    pub ep2:f64,
+    /// meridian arc coefficients A, B, C, D, E
+    pub arc:[f64; 5],
+    /// meridian arc per radian of rectifying latitude μ
+    pub mu_scale:f64,
+    /// footprint latitude coefficients J1..J4
+    pub footprint:[f64; 4],
+}
+
+impl TmConstants {
+    fn new(ellipsoid:&Ellipsoid) -> TmConstants {
+        let (a, b) = (ellipsoid.a, ellipsoid.b);
+        let e2 = 1.0 - (b * b) / (a * a);
+        let n = (a - b) / (a + b);
+        let arc = [
+            a * (1.0 + n * (-1.0 + n * (5.0/4.0 + n * (-5.0/4.0 + n * (81.0/64.0 - n * 81.0/64.0))))),
+            (3.0 * a * n/2.0) * (1.0 + n * (-1.0 + n * (7.0/8.0 + n * (-7.0/8.0 + n * (55.0/64.0 - n * 55.0/64.0))))),
+            (15.0 * a * n * n/16.0) * (1.0 + n * (-1.0 + n * (3.0/4.0 - n * 3.0/4.0))),
+            (35.0 * a * n * n * n/48.0) * (1.0 + n * (-1.0 + n * (11.0/16.0 - n * 11.0/16.0))),
+            (315.0 * a * n * n * n * n/512.0) * (1.0 - n),
+        ];
+        let sq = (1.0 - e2).sqrt();
+        let e1 = (1.0 - sq) / (1.0 + sq);
+        let e1_2 = e1 * e1;
+        TmConstants {
+            a,
+            e2,
+            ep2: e2 / (1.0 - e2),
+            arc,
+            mu_scale: a * (1.0 - e2 * (1.0/4.0 + e2 * (3.0/64.0 + e2 * 5.0/256.0))),
+            footprint: [
+                e1 * (3.0/2.0 - 27.0/32.0 * e1_2),
+                e1_2 * (21.0/16.0 - 55.0/32.0 * e1_2),
+                151.0/96.0 * e1_2 * e1,
+                1097.0/512.0 * e1_2 * e1_2,
+            ],
+        }
+    }
+}
+
+static TM:[OnceLock<TmConstants>; STANDARD.len()] = [const { OnceLock::new() }; STANDARD.len()];
+
+/// the TM constants of an ellipsoid, from the cache when it is a standard one
+pub(crate) fn tm_constants(ellipsoid:&Ellipsoid) -> TmConstants {
+    match ellipsoid.standard_index() {
+        Some(i) => *TM[i].get_or_init(|| TmConstants::new(ellipsoid)),
+        None => TmConstants::new(ellipsoid),
+    }
+}
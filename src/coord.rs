@@ -0,0 +1,223 @@
+//! coordinate types carrying an ellipsoidal height
+//!
+//! the tuple based functions stay 2D; these types are for transforms where
+//! the vertical component matters (datum shifts, ECEF round trips). the
+//! height defaults to 0 when it is not known.
+//!
+//! the structs are `#[repr(C)]` triples of `f64`; with the `bytemuck` feature
+//! they are `Pod`, so buffers of them can be cast to and from bytes or `[f64]`
+//! without copying for FFI, GPU uploads and memory-mapped files. the epoch
+//! of a GNSS position rides alongside in [`Dated`], so the triples keep that
+//! layout; time-dependent transforms refuse coordinates without one.
+
+use ellipsoid::{Ellipsoid, WGS84};
+use zone::Zone;
+
+/// geographic coordinate: lng/lat in degrees, ellipsoidal height in meters
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[repr(C)]
+pub struct LngLat {
+    pub lng:f64,
+    pub lat:f64,
+    pub h:f64,
+}
+
+/// projected grid coordinate: easting/northing in meters, ellipsoidal height in meters
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[repr(C)]
+pub struct Projected {
+    pub x:f64,
+    pub y:f64,
+    pub h:f64,
+}
+
+/// earth-centered earth-fixed coordinate in meters
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[repr(C)]
+pub struct Ecef {
+    pub x:f64,
+    pub y:f64,
+    pub z:f64,
+}
+
+// all three are three f64 fields with no padding, and every bit pattern is a valid f64
+#[cfg(feature = "bytemuck")]
+unsafe impl ::bytemuck::Zeroable for LngLat {}
+#[cfg(feature = "bytemuck")]
+unsafe impl ::bytemuck::Pod for LngLat {}
+#[cfg(feature = "bytemuck")]
+unsafe impl ::bytemuck::Zeroable for Projected {}
+#[cfg(feature = "bytemuck")]
+unsafe impl ::bytemuck::Pod for Projected {}
+#[cfg(feature = "bytemuck")]
+unsafe impl ::bytemuck::Zeroable for Ecef {}
+#[cfg(feature = "bytemuck")]
+unsafe impl ::bytemuck::Pod for Ecef {}
+
+impl LngLat {
+    /// a point on the ellipsoid (h = 0)
+    pub fn new(lng:f64, lat:f64) -> LngLat {
+        LngLat { lng, lat, h: 0.0 }
+    }
+
+    pub fn with_height(lng:f64, lat:f64, h:f64) -> LngLat {
+        LngLat { lng, lat, h }
+    }
+
+    /// geocentric coordinate on wgs84
+    pub fn to_ecef(&self) -> Ecef {
+        self.to_ecef_on(&WGS84)
+    }
+
+    /// geocentric coordinate on `ellipsoid`
+    pub fn to_ecef_on(&self, ellipsoid:&Ellipsoid) -> Ecef {
+        let (x, y, z) = ellipsoid.geodetic_to_ecef(self.lng, self.lat, self.h);
+        Ecef { x, y, z }
+    }
+}
+
+impl Projected {
+    pub fn new(x:f64, y:f64) -> Projected {
+        Projected { x, y, h: 0.0 }
+    }
+
+    pub fn with_height(x:f64, y:f64, h:f64) -> Projected {
+        Projected { x, y, h }
+    }
+}
+
+impl Ecef {
+    pub fn new(x:f64, y:f64, z:f64) -> Ecef {
+        Ecef { x, y, z }
+    }
+
+    /// geographic coordinate on wgs84
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::coord::LngLat;
+    /// let p = LngLat::with_height(121.5654, 25.0330, 101.5);
+    /// let q = p.to_ecef().to_lnglat();
+    /// assert!((q.h - 101.5).abs() < 1e-6);
+    /// ```
+    pub fn to_lnglat(&self) -> LngLat {
+        self.to_lnglat_on(&WGS84)
+    }
+
+    /// geographic coordinate on `ellipsoid`
+    pub fn to_lnglat_on(&self, ellipsoid:&Ellipsoid) -> LngLat {
+        let (lng, lat, h) = ellipsoid.ecef_to_geodetic(self.x, self.y, self.z);
+        LngLat { lng, lat, h }
+    }
+}
+
+impl From<(f64, f64)> for LngLat {
+    fn from(p:(f64, f64)) -> LngLat {
+        LngLat::new(p.0, p.1)
+    }
+}
+
+impl From<(f64, f64, f64)> for LngLat {
+    fn from(p:(f64, f64, f64)) -> LngLat {
+        LngLat::with_height(p.0, p.1, p.2)
+    }
+}
+
+impl From<(f64, f64)> for Projected {
+    fn from(p:(f64, f64)) -> Projected {
+        Projected::new(p.0, p.1)
+    }
+}
+
+impl From<(f64, f64, f64)> for Projected {
+    fn from(p:(f64, f64, f64)) -> Projected {
+        Projected::with_height(p.0, p.1, p.2)
+    }
+}
+
+impl From<(f64, f64, f64)> for Ecef {
+    fn from(p:(f64, f64, f64)) -> Ecef {
+        Ecef::new(p.0, p.1, p.2)
+    }
+}
+
+/// a coordinate with the epoch it was observed at, in decimal years
+///
+/// static transforms carry the epoch along through [`Dated::map`];
+/// time-dependent ones, frame changes and plate motion, use it and fail when it
+/// is missing instead of silently applying the shift at the wrong time.
+/// # Examples
+///
+/// ```
+/// use mercator::coord::LngLat;
+/// use mercator::zone::Zone;
+/// let p = LngLat::new(121.5654, 25.0330).at_epoch(2024.5);
+/// let q = p.map(|c| Zone::twd97().project(c));
+/// assert_eq!(q.epoch, Some(2024.5));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Dated<T> {
+    pub coord:T,
+    pub epoch:Option<f64>,
+}
+
+impl<T> Dated<T> {
+    pub fn new(coord:T, epoch:f64) -> Dated<T> {
+        Dated { coord, epoch: Some(epoch) }
+    }
+
+    /// a coordinate of unknown epoch
+    pub fn undated(coord:T) -> Dated<T> {
+        Dated { coord, epoch: None }
+    }
+
+    /// transform the coordinate, keeping the epoch
+    pub fn map<U, F:FnOnce(T) -> U>(self, f:F) -> Dated<U> {
+        Dated { coord: f(self.coord), epoch: self.epoch }
+    }
+}
+
+impl LngLat {
+    /// this coordinate observed at `epoch`
+    pub fn at_epoch(self, epoch:f64) -> Dated<LngLat> {
+        Dated::new(self, epoch)
+    }
+}
+
+impl Projected {
+    /// this coordinate observed at `epoch`
+    pub fn at_epoch(self, epoch:f64) -> Dated<Projected> {
+        Dated::new(self, epoch)
+    }
+}
+
+impl Ecef {
+    /// this coordinate observed at `epoch`
+    pub fn at_epoch(self, epoch:f64) -> Dated<Ecef> {
+        Dated::new(self, epoch)
+    }
+}
+
+impl Zone {
+    /// project a geographic coordinate, passing the height through
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::coord::LngLat;
+    /// use mercator::zone::Zone;
+    /// let zone = Zone::twd97();
+    /// let p = zone.project(LngLat::with_height(121.5654, 25.0330, 12.0));
+    /// assert_eq!(p.h, 12.0);
+    /// assert_eq!(zone.unproject(p).h, 12.0);
+    /// ```
+    pub fn project(&self, p:LngLat) -> Projected {
+        let (x, y) = self.forward(p.lng, p.lat);
+        Projected { x, y, h: p.h }
+    }
+
+    /// invert a projected coordinate, passing the height through
+    pub fn unproject(&self, p:Projected) -> LngLat {
+        let (lng, lat) = self.inverse(p.x, p.y);
+        LngLat { lng, lat, h: p.h }
+    }
+}
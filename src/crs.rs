@@ -0,0 +1,569 @@
+//! coordinate reference systems and prepared transformers between them
+//!
+//! a CRS is geographic or a transverse mercator grid, on a [`Datum`].
+//! custom projections plug in through [`Projection`]. transforms between
+//! datums go through WGS84 as a hub: unproject on the
+//! source ellipsoid, shift the source datum to WGS84, shift WGS84 to the
+//! target datum and project on the target ellipsoid. the path is worked
+//! out once, when the transformer is built.
+
+use std::fmt;
+use std::sync::Arc;
+
+use accuracy;
+use axis::AxisOrder;
+use datum::{self, Datum};
+use epsg;
+use error::{Error, Result};
+use extent::Extent;
+use geometry::unwrap;
+use longitude::normalize_lng;
+use pipeline::{Pipeline, Step};
+use projection::Projection;
+use trace;
+use units::Unit;
+use zone::{Hemisphere, Zone, ZoneWidth};
+
+/// the region a CRS is meant for, in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AreaOfUse {
+    pub west:f64,
+    pub south:f64,
+    pub east:f64,
+    pub north:f64,
+}
+
+impl AreaOfUse {
+    /// `west > east` describes an area crossing the antimeridian
+    pub fn new(west:f64, south:f64, east:f64, north:f64) -> AreaOfUse {
+        AreaOfUse { west, south, east, north }
+    }
+
+    pub fn world() -> AreaOfUse {
+        AreaOfUse::new(-180.0, -90.0, 180.0, 90.0)
+    }
+
+    /// true if lng/lat lies inside the area, NaN is never inside
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::AreaOfUse;
+    /// let fiji = AreaOfUse::new(177.0, -21.0, -178.0, -12.0);
+    /// assert!(fiji.contains(179.5, -17.0) && fiji.contains(-179.5, -17.0));
+    /// assert!(!fiji.contains(0.0, -17.0));
+    /// ```
+    pub fn contains(&self, lng:f64, lat:f64) -> bool {
+        let in_lat = lat >= self.south && lat <= self.north;
+        let in_lng = if self.west <= self.east {
+            lng >= self.west && lng <= self.east
+        } else {
+            lng >= self.west || lng <= self.east
+        };
+        in_lat && in_lng
+    }
+}
+
+impl fmt::Display for AreaOfUse {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}, {}, {}, {}]", self.west, self.south, self.east, self.north)
+    }
+}
+
+/// what a transformer does with input outside the source CRS's area of use
+#[derive(Clone, Default)]
+pub enum BoundsCheck {
+    /// transform anything
+    #[default]
+    Ignore,
+    /// call the function with the offending input, then transform it anyway
+    Warn(Arc<dyn Fn(f64, f64) + Send + Sync>),
+    /// refuse the point with an error
+    Error,
+}
+
+impl fmt::Debug for BoundsCheck {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BoundsCheck::Ignore => write!(f, "Ignore"),
+            BoundsCheck::Warn(_) => write!(f, "Warn(..)"),
+            BoundsCheck::Error => write!(f, "Error"),
+        }
+    }
+}
+
+/// a coordinate reference system the crate can transform between
+#[derive(Debug, Clone)]
+pub enum Crs {
+    /// lng/lat in degrees
+    Geographic(Datum),
+    /// a transverse mercator zone grid
+    Projected(Zone, Datum),
+    /// a grid of a user supplied projection, working on lng/lat of the datum
+    Custom(Arc<dyn Projection>, Datum),
+}
+
+impl PartialEq for Crs {
+    fn eq(&self, other:&Crs) -> bool {
+        match (self, other) {
+            (Crs::Geographic(a), Crs::Geographic(b)) => a == b,
+            (Crs::Projected(z, a), Crs::Projected(w, b)) => z == w && a == b,
+            // custom projections are opaque, only the same instance is equal
+            (Crs::Custom(p, a), Crs::Custom(q, b)) => Arc::ptr_eq(p, q) && a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Crs {
+    /// WGS84 lng/lat
+    pub fn wgs84() -> Crs {
+        Crs::Geographic(datum::WGS84)
+    }
+
+    /// a zone grid on WGS84
+    pub fn projected(zone:Zone) -> Crs {
+        Crs::Projected(zone, datum::WGS84)
+    }
+
+    /// geographic 4326 / 3824, TWD67 3821 / 3827 / 3828, any code known to [`Zone::from_epsg`],
+    /// or one of the compiled-in EPSG subset (national datums, their UTM series and grids)
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::{Crs, Transformer};
+    /// use mercator::datum::TWD67;
+    /// use mercator::zone::Zone;
+    /// assert_eq!(Crs::from_epsg(4326).unwrap(), Crs::wgs84());
+    /// assert_eq!(Crs::from_epsg(3826).unwrap(), Crs::projected(Zone::twd97()));
+    /// assert_eq!(Crs::from_epsg(3828).unwrap(), Crs::Projected(Zone::twd97(), TWD67));
+    /// assert!(Crs::from_epsg(2000).is_err());
+    ///
+    /// // NAD83 / UTM zone 10N is a zone grid on NAD83
+    /// assert_eq!(Crs::from_epsg(26910).unwrap().datum().name, "NAD83");
+    /// // the British National Grid is a custom transverse mercator on OSGB36
+    /// assert_eq!(Crs::from_epsg(27700).unwrap(), Crs::from_epsg(27700).unwrap());
+    /// let (e, n) = Transformer::from_epsg(4326, 27700).unwrap().transform(-0.1276, 51.5072);
+    /// assert!((e - 530000.0).abs() < 1000.0 && (n - 180400.0).abs() < 1000.0);
+    /// ```
+    pub fn from_epsg(code:u32) -> Result<Crs> {
+        match code {
+            4326 | 3824 => Ok(Crs::wgs84()),
+            3821 => Ok(Crs::Geographic(datum::TWD67)),
+            3827 => Ok(Crs::Projected(Zone::new(ZoneWidth::Degree2, 119.0), datum::TWD67)),
+            3828 => Ok(Crs::Projected(Zone::twd97(), datum::TWD67)),
+            _ => Zone::from_epsg(code).map(Crs::projected)
+                .or_else(|| epsg::lookup(code).map(|e| e.crs()))
+                .ok_or_else(|| Error::InvalidInput(format!("unsupported CRS EPSG:{}", code))),
+        }
+    }
+
+    /// a grid of a custom projection on WGS84
+    pub fn custom<P:Projection + 'static>(projection:P) -> Crs {
+        Crs::Custom(Arc::new(projection), datum::WGS84)
+    }
+
+    /// nominal area of use: the world for geographic CRSs, the zone strip on
+    /// its hemisphere for zone grids, unknown for custom projections
+    pub fn area_of_use(&self) -> Option<AreaOfUse> {
+        match *self {
+            Crs::Geographic(_) => Some(AreaOfUse::world()),
+            Crs::Projected(ref zone, _) => {
+                let half = zone.width.degrees() / 2.0;
+                let (south, north) = match zone.hemisphere {
+                    Hemisphere::North => (0.0, 84.0),
+                    Hemisphere::South => (-80.0, 0.0),
+                };
+                let west = normalize_lng(zone.center_lng - half);
+                let east = west + 2.0 * half;
+                Some(AreaOfUse::new(west, south, if east > 180.0 { east - 360.0 } else { east }, north))
+            }
+            Crs::Custom(..) => None,
+        }
+    }
+
+    pub fn datum(&self) -> Datum {
+        match *self {
+            Crs::Geographic(d) | Crs::Projected(_, d) | Crs::Custom(_, d) => d,
+        }
+    }
+
+    /// convert a coordinate of this CRS to lng/lat on its own datum
+    pub fn to_lnglat(&self, x:f64, y:f64) -> (f64, f64) {
+        match *self {
+            Crs::Geographic(_) => (x, y),
+            Crs::Projected(ref zone, ref d) => zone.inverse_on(&d.ellipsoid, x, y),
+            Crs::Custom(ref p, _) => p.inverse(x, y),
+        }
+    }
+
+    /// convert lng/lat on this CRS's datum to a coordinate of this CRS
+    pub fn from_lnglat(&self, lng:f64, lat:f64) -> (f64, f64) {
+        match *self {
+            Crs::Geographic(_) => (lng, lat),
+            Crs::Projected(ref zone, ref d) => zone.forward_on(&d.ellipsoid, lng, lat),
+            Crs::Custom(ref p, _) => p.forward(lng, lat),
+        }
+    }
+}
+
+/// the path between two CRSs through the WGS84 hub
+fn find_path(source:&Crs, target:&Crs) -> Pipeline {
+    let mut p = Pipeline::new();
+    if source == target {
+        return p;
+    }
+    let (from, to) = (source.datum(), target.datum());
+    match *source {
+        Crs::Projected(zone, d) => p = p.then(Step::Unproject(zone, d.ellipsoid)),
+        Crs::Custom(ref proj, _) => p = p.then(Step::UnprojectWith(proj.clone())),
+        Crs::Geographic(_) => {}
+    }
+    if !from.same_frame(&to) {
+        if !from.is_wgs84() {
+            p = p.helmert(from.to_wgs84, from.ellipsoid, datum::WGS84.ellipsoid);
+        }
+        if !to.is_wgs84() {
+            p = p.helmert(to.to_wgs84.inverse(), datum::WGS84.ellipsoid, to.ellipsoid);
+        }
+    }
+    match *target {
+        Crs::Projected(zone, d) => p = p.then(Step::Project(zone, d.ellipsoid)),
+        Crs::Custom(ref proj, _) => p = p.then(Step::ProjectWith(proj.clone())),
+        Crs::Geographic(_) => {}
+    }
+    p
+}
+
+/// a prepared transform from one CRS to another
+#[derive(Debug, Clone)]
+pub struct Transformer {
+    pub source:Crs,
+    pub target:Crs,
+    pipeline:Pipeline,
+    area:Option<AreaOfUse>,
+    bounds:BoundsCheck,
+    input_axes:AxisOrder,
+    output_axes:AxisOrder,
+    input_unit:Unit,
+    output_unit:Unit,
+}
+
+impl Transformer {
+    pub fn new(source:Crs, target:Crs) -> Transformer {
+        let pipeline = find_path(&source, &target);
+        trace::pipeline_built(&pipeline);
+        let area = source.area_of_use();
+        Transformer {
+            source, target, pipeline, area,
+            bounds: BoundsCheck::Ignore,
+            input_axes: AxisOrder::LngLat,
+            output_axes: AxisOrder::LngLat,
+            input_unit: Unit::Meter,
+            output_unit: Unit::Meter,
+        }
+    }
+
+    /// read and write projected coordinates in the given linear units
+    ///
+    /// units only apply to projected sides, geographic sides stay in degrees.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Transformer;
+    /// use mercator::units::{Unit, US_SURVEY_FOOT};
+    /// let t = Transformer::from_epsg(4326, 32611).unwrap()
+    ///     .with_units(Unit::Degree, Unit::UsSurveyFoot).unwrap();
+    /// let (x, y) = t.transform(-117.0, 34.0);
+    /// let (m, _) = Transformer::from_epsg(4326, 32611).unwrap().transform(-117.0, 34.0);
+    /// assert!((x * US_SURVEY_FOOT - m).abs() < 1e-6);
+    /// let (lng, lat) = t.inverse().transform(x, y);
+    /// assert!((lng + 117.0).abs() < 1e-6 && (lat - 34.0).abs() < 1e-6);
+    ///
+    /// assert!(Transformer::from_epsg(4326, 32611).unwrap().with_units(Unit::Degree, Unit::Degree).is_err());
+    /// ```
+    pub fn with_units(mut self, input:Unit, output:Unit) -> Result<Transformer> {
+        let check = |crs:&Crs, unit:Unit| {
+            let geographic = matches!(*crs, Crs::Geographic(_));
+            if geographic == unit.is_linear() {
+                return Err(Error::InvalidInput(format!("{} does not fit a {} CRS", unit.name(),
+                    if geographic { "geographic" } else { "projected" })));
+            }
+            Ok(())
+        };
+        check(&self.source, input)?;
+        check(&self.target, output)?;
+        self.input_unit = input;
+        self.output_unit = output;
+        Ok(self)
+    }
+
+    /// the input and output units
+    pub fn units(&self) -> (Unit, Unit) {
+        let unit = |crs:&Crs, unit:Unit| if matches!(*crs, Crs::Geographic(_)) { Unit::Degree } else { unit };
+        (unit(&self.source, self.input_unit), unit(&self.target, self.output_unit))
+    }
+
+    /// read input and write output tuples in the given axis orders
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::axis::AxisOrder;
+    /// use mercator::crs::Transformer;
+    /// use mercator::wgs84_to_twd97;
+    /// // 4326 in EPSG authority order
+    /// let t = Transformer::from_epsg(4326, 3826).unwrap()
+    ///     .with_axis_order(AxisOrder::LatLng, AxisOrder::EastNorth);
+    /// assert_eq!(t.transform(25.0330, 121.5654), wgs84_to_twd97(121.5654, 25.0330));
+    /// let (x, y) = t.transform(25.0330, 121.5654);
+    /// let (lat, lng) = t.inverse().transform(x, y);
+    /// assert!((lat - 25.0330).abs() < 1e-6 && (lng - 121.5654).abs() < 1e-6);
+    /// ```
+    pub fn with_axis_order(mut self, input:AxisOrder, output:AxisOrder) -> Transformer {
+        self.input_axes = input;
+        self.output_axes = output;
+        self
+    }
+
+    /// the input and output axis orders
+    pub fn axis_order(&self) -> (AxisOrder, AxisOrder) {
+        (self.input_axes, self.output_axes)
+    }
+
+    /// replace the area of use of the source CRS
+    pub fn with_area_of_use(mut self, area:Option<AreaOfUse>) -> Transformer {
+        self.area = area;
+        self
+    }
+
+    /// check input against the source area of use in [`try_transform`](Transformer::try_transform)
+    pub fn with_bounds_check(mut self, bounds:BoundsCheck) -> Transformer {
+        self.bounds = bounds;
+        self
+    }
+
+    /// the area of use of the source CRS, when known
+    pub fn area_of_use(&self) -> Option<AreaOfUse> {
+        self.area
+    }
+
+    /// true when the input coordinate lies inside the source area of use, or the area is unknown
+    pub fn in_area_of_use(&self, x:f64, y:f64) -> bool {
+        match self.area {
+            Some(ref area) => {
+                let (x, y) = self.input_axes.to_xy(x, y);
+                let (x, y) = self.to_meters(x, y);
+                let (lng, lat) = self.source.to_lnglat(x, y);
+                area.contains(lng, lat)
+            }
+            None => true,
+        }
+    }
+
+    /// transform one coordinate, applying the bounds check
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::{BoundsCheck, Transformer};
+    /// let t = Transformer::from_epsg(4326, 3826).unwrap().with_bounds_check(BoundsCheck::Error);
+    /// assert!(t.try_transform(121.5, 25.0).is_ok());
+    /// // TWD97 meters fed in as lng/lat
+    /// assert!(t.try_transform(302000.0, 2770000.0).is_err());
+    /// ```
+    pub fn try_transform(&self, x:f64, y:f64) -> Result<(f64, f64)> {
+        self.try_transform_3d(x, y, 0.0).map(|(x, y, _)| (x, y))
+    }
+
+    /// transform one coordinate with ellipsoidal height, applying the bounds
+    /// check; errors of the pipeline come back instead of NaN
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::{BoundsCheck, Transformer};
+    /// let t = Transformer::from_epsg(4326, 3826).unwrap().with_bounds_check(BoundsCheck::Error);
+    /// let (_, _, h) = t.try_transform_3d(121.5, 25.0, 35.0).unwrap();
+    /// assert!((h - 35.0).abs() < 1e-9);
+    /// assert!(t.try_transform_3d(302000.0, 2770000.0, 35.0).is_err());
+    /// ```
+    pub fn try_transform_3d(&self, x:f64, y:f64, h:f64) -> Result<(f64, f64, f64)> {
+        match self.bounds {
+            BoundsCheck::Ignore => {}
+            _ if self.in_area_of_use(x, y) => {}
+            BoundsCheck::Warn(ref warn) => warn(x, y),
+            BoundsCheck::Error => {
+                let area = self.area.unwrap_or_else(AreaOfUse::world);
+                return Err(Error::InvalidInput(format!("({}, {}) is outside the source area of use {}", x, y, area)));
+            }
+        }
+        self.pipeline_3d(x, y, h)
+    }
+
+    /// a transformer between two EPSG codes
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Transformer;
+    /// use mercator::wgs84_to_twd97;
+    /// let t = Transformer::from_epsg(4326, 3826).unwrap();
+    /// assert_eq!(t.transform(121.5654, 25.0330), wgs84_to_twd97(121.5654, 25.0330));
+    ///
+    /// // TWD67 TM2 to TWD97 TM2 goes through a datum shift
+    /// let t = Transformer::from_epsg(3828, 3826).unwrap();
+    /// assert_eq!(t.pipeline().steps.len(), 3);
+    /// let (x, y) = t.transform(250000.0, 2650000.0);
+    /// assert!((x - 250000.0 - 828.0).abs() < 20.0 && (y - 2650000.0 + 207.0).abs() < 20.0);
+    /// ```
+    pub fn from_epsg(source:u32, target:u32) -> Result<Transformer> {
+        Ok(Transformer::new(Crs::from_epsg(source)?, Crs::from_epsg(target)?))
+    }
+
+    /// estimated accuracy of the transform in meters
+    ///
+    /// datum shifts count with the accuracy of their datum, other steps with their
+    /// [`Step::accuracy`].
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Transformer;
+    /// assert!(Transformer::from_epsg(4326, 3826).unwrap().accuracy() < 0.01);
+    /// let t = Transformer::from_epsg(3828, 3826).unwrap();
+    /// assert!(t.accuracy() >= 5.0 && t.accuracy() < 5.01);
+    /// ```
+    pub fn accuracy(&self) -> f64 {
+        let mut errors:Vec<f64> = self.pipeline.steps.iter()
+            .filter(|s| !matches!(**s, Step::Helmert { .. }))
+            .map(Step::accuracy)
+            .collect();
+        let (src, dst) = (self.source.datum(), self.target.datum());
+        if !src.same_frame(&dst) {
+            errors.push(src.accuracy);
+            errors.push(dst.accuracy);
+        }
+        accuracy::combine(&errors)
+    }
+
+    /// the steps this transformer runs
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+
+    /// transform one coordinate, heights are taken as 0
+    pub fn transform(&self, x:f64, y:f64) -> (f64, f64) {
+        let (x, y, _) = self.transform_3d(x, y, 0.0);
+        (x, y)
+    }
+
+    /// transform one coordinate with ellipsoidal height
+    ///
+    /// a failing step gives NaN, see [`try_transform_3d`](Transformer::try_transform_3d)
+    pub fn transform_3d(&self, x:f64, y:f64, h:f64) -> (f64, f64, f64) {
+        self.pipeline_3d(x, y, h).unwrap_or((f64::NAN, f64::NAN, f64::NAN))
+    }
+
+    fn pipeline_3d(&self, x:f64, y:f64, h:f64) -> Result<(f64, f64, f64)> {
+        let (x, y) = self.input_axes.to_xy(x, y);
+        let (x, y) = self.to_meters(x, y);
+        let (x, y, h) = self.pipeline.transform_3d(x, y, h)?;
+        let (x, y) = match self.target {
+            Crs::Geographic(_) => (x, y),
+            _ => (self.output_unit.from_meters(x), self.output_unit.from_meters(y)),
+        };
+        let (x, y) = self.output_axes.from_xy(x, y);
+        Ok((x, y, h))
+    }
+
+    fn to_meters(&self, x:f64, y:f64) -> (f64, f64) {
+        match self.source {
+            Crs::Geographic(_) => (x, y),
+            _ => (self.input_unit.to_meters(x), self.input_unit.to_meters(y)),
+        }
+    }
+
+    /// the bounding box of a source bounding box after the transform
+    ///
+    /// every edge is split by `n_samples` extra points before transforming, since
+    /// the edges bend: far from the central meridian a parallel bows towards the
+    /// pole on a transverse mercator grid, so the corners alone miss part of the
+    /// region. when the target is geographic the longitudes are kept continuous,
+    /// and a box over the antimeridian comes out with `max_x` above 180.
+    pub fn transform_bbox(&self, bbox:Extent, n_samples:usize) -> Result<Extent> {
+        let corners = [(bbox.min_x, bbox.min_y), (bbox.max_x, bbox.min_y), (bbox.max_x, bbox.max_y), (bbox.min_x, bbox.max_y)];
+        let n = n_samples + 1;
+        let mut ring = Vec::with_capacity(4 * n);
+        for i in 0..4 {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            for k in 0..n {
+                let t = k as f64 / n as f64;
+                ring.push(self.try_transform(a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)?);
+            }
+        }
+        let geographic = matches!(self.target, Crs::Geographic(_)) && self.output_axes == AxisOrder::LngLat;
+        if geographic {
+            ring = unwrap(&ring, normalize_lng(ring[0].0));
+        }
+        let mut e = Extent::of(&ring).ok_or_else(|| Error::Transform("bounding box has no finite corner after the transform".to_string()))?;
+        if geographic && e.min_x <= -180.0 {
+            e.min_x += 360.0;
+            e.max_x += 360.0;
+        }
+        Ok(e)
+    }
+
+    /// the transformer going the other way, axis orders and units swap places
+    ///
+    /// the bounds check carries over. the area of use becomes the one of the
+    /// target CRS, unless it was replaced with
+    /// [`with_area_of_use`](Transformer::with_area_of_use): that area is in
+    /// lng/lat and holds for both directions.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::{AreaOfUse, BoundsCheck, Transformer};
+    /// let t = Transformer::from_epsg(4326, 3826).unwrap().with_bounds_check(BoundsCheck::Error);
+    /// // the TM2 zone strip instead of the world
+    /// assert!(t.inverse().try_transform(250000.0, 2650000.0).is_ok());
+    /// assert!(t.inverse().try_transform(2500000.0, 2650000.0).is_err());
+    ///
+    /// let taiwan = Some(AreaOfUse::new(119.0, 21.5, 122.5, 25.5));
+    /// let t = t.with_area_of_use(taiwan);
+    /// assert_eq!(t.inverse().area_of_use(), taiwan);
+    /// assert!(t.inverse().try_transform(250000.0, 2000000.0).is_err());
+    /// ```
+    pub fn inverse(&self) -> Transformer {
+        let mut t = Transformer::new(self.target.clone(), self.source.clone())
+            .with_axis_order(self.output_axes, self.input_axes)
+            .with_bounds_check(self.bounds.clone());
+        if self.area != self.source.area_of_use() {
+            t.area = self.area;
+        }
+        t.input_unit = self.output_unit;
+        t.output_unit = self.input_unit;
+        t
+    }
+}
+
+/// the bounding box in `dst` of a bounding box in `src`, see [`Transformer::transform_bbox`]
+/// # Examples
+///
+/// ```
+/// use mercator::crs::{transform_bbox, Crs};
+/// use mercator::extent::Extent;
+/// use mercator::wgs84_to_twd97;
+/// let (src, dst) = (Crs::wgs84(), Crs::from_epsg(3826).unwrap());
+/// // six degrees either side of the central meridian
+/// let bbox = Extent::new(115.0, 20.0, 127.0, 26.0);
+/// let corners = transform_bbox(&src, &dst, bbox, 0).unwrap();
+/// let sampled = transform_bbox(&src, &dst, bbox, 11).unwrap();
+/// // the southern edge dips lowest on the central meridian, between the corners
+/// assert_eq!(sampled.min_y, wgs84_to_twd97(121.0, 20.0).1);
+/// assert!(corners.min_y - sampled.min_y > 10_000.0);
+/// assert_eq!(sampled.max_y, corners.max_y);
+///
+/// // back to lng/lat over the antimeridian
+/// let utm60 = Crs::from_epsg(32660).unwrap();
+/// let grid = transform_bbox(&src, &utm60, Extent::new(178.0, -20.0, 179.0, -16.0), 10).unwrap();
+/// let back = transform_bbox(&utm60, &src, grid.expand(100_000.0), 10).unwrap();
+/// assert!(back.min_x < 178.0 && back.max_x > 180.0);
+/// ```
+pub fn transform_bbox(src:&Crs, dst:&Crs, bbox:Extent, n_samples:usize) -> Result<Extent> {
+    Transformer::new(src.clone(), dst.clone()).transform_bbox(bbox, n_samples)
+}
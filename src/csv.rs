@@ -0,0 +1,36 @@
+//! minimal CSV field splitting shared by the text loaders
+
+/// split a CSV line, honoring double quoted fields
+pub(crate) fn split_fields(line:&str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(::std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// join fields into a CSV line, quoting the ones that need it
+#[cfg(feature = "tokio")]
+pub(crate) fn join_fields<S:AsRef<str>>(fields:&[S]) -> String {
+    let quoted:Vec<String> = fields.iter().map(|f| {
+        let f = f.as_ref();
+        if f.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", f.replace('"', "\"\""))
+        } else {
+            f.to_string()
+        }
+    }).collect();
+    quoted.join(",")
+}
@@ -0,0 +1,72 @@
+//! polars DataFrame integration (feature `polars`)
+//!
+//! the input columns are cast to Float64 and run through the batch kernel;
+//! a row is null in the output when either coordinate is null.
+
+use polars::prelude::{Column, DataFrame, DataType, Float64Chunked, IntoColumn, NewChunkedArray, PolarsError};
+
+use batch::transform_xy;
+use error::{Error, Result};
+
+fn polars_err(e:PolarsError) -> Error {
+    Error::InvalidInput(format!("polars: {}", e))
+}
+
+fn values(df:&DataFrame, name:&str) -> Result<Float64Chunked> {
+    let column = df.column(name).map_err(polars_err)?.cast(&DataType::Float64).map_err(polars_err)?;
+    Ok(column.f64().map_err(polars_err)?.clone())
+}
+
+/// transform the `x` and `y` columns of `df` with `f`, returning new columns named `out_x` and `out_y`
+/// # Examples
+///
+/// ```
+/// # extern crate polars;
+/// # extern crate mercator;
+/// use polars::prelude::*;
+/// use mercator::dataframe::project_columns;
+/// use mercator::wgs84_to_twd97;
+/// let df = df!("lng" => [121.5654, 120.6736], "lat" => [25.0330, 24.1477]).unwrap();
+/// let (x, y) = project_columns(&df, "lng", "lat", "x", "y", wgs84_to_twd97).unwrap();
+/// assert_eq!(x.name().as_str(), "x");
+/// assert!(x.f64().unwrap().get(0).unwrap() > 300000.0);
+/// assert!(y.f64().unwrap().get(1).unwrap() > 2600000.0);
+/// ```
+pub fn project_columns<F>(df:&DataFrame, x:&str, y:&str, out_x:&str, out_y:&str, f:F) -> Result<(Column, Column)>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let (cx, cy) = (values(df, x)?, values(df, y)?);
+    let mut xs:Vec<f64> = cx.iter().map(|v| v.unwrap_or(0.0)).collect();
+    let mut ys:Vec<f64> = cy.iter().map(|v| v.unwrap_or(0.0)).collect();
+    transform_xy(&mut xs, &mut ys, f);
+    let valid:Vec<bool> = cx.iter().zip(cy.iter()).map(|(a, b)| a.is_some() && b.is_some()).collect();
+    let column = |name:&str, v:Vec<f64>| {
+        let it = v.into_iter().zip(&valid).map(|(v, &ok)| if ok { Some(v) } else { None });
+        Float64Chunked::from_iter_options(name.into(), it).into_column()
+    };
+    Ok((column(out_x, xs), column(out_y, ys)))
+}
+
+/// [`project_columns`], returning `df` with the projected columns added (or replaced)
+/// # Examples
+///
+/// ```
+/// # extern crate polars;
+/// # extern crate mercator;
+/// use polars::prelude::*;
+/// use mercator::dataframe::with_projected_columns;
+/// use mercator::wgs84_to_twd97;
+/// let df = df!("lng" => [Some(121.5654), None], "lat" => [25.0330, 24.1477]).unwrap();
+/// let df = with_projected_columns(&df, "lng", "lat", "x", "y", wgs84_to_twd97).unwrap();
+/// assert_eq!(df.width(), 4);
+/// assert_eq!(df.column("x").unwrap().null_count(), 1);
+/// ```
+pub fn with_projected_columns<F>(df:&DataFrame, x:&str, y:&str, out_x:&str, out_y:&str, f:F) -> Result<DataFrame>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let (cx, cy) = project_columns(df, x, y, out_x, out_y, f)?;
+    let mut out = df.clone();
+    out.with_column(cx).map_err(polars_err)?;
+    out.with_column(cy).map_err(polars_err)?;
+    Ok(out)
+}
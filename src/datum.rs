@@ -0,0 +1,52 @@
+//! geodetic datums: a reference ellipsoid and the shift to WGS84
+
+use accuracy::helmert_accuracy;
+use ellipsoid::{self, Ellipsoid};
+use helmert::Helmert;
+
+/// a geodetic datum given by its ellipsoid and a Helmert shift to WGS84
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Datum {
+    pub name:&'static str,
+    pub ellipsoid:Ellipsoid,
+    /// position vector Helmert from this datum's geocentric frame to WGS84
+    pub to_wgs84:Helmert,
+    /// estimated accuracy of `to_wgs84` in meters
+    pub accuracy:f64,
+}
+
+const NO_SHIFT:Helmert = Helmert { tx: 0.0, ty: 0.0, tz: 0.0, s: 0.0, rx: 0.0, ry: 0.0, rz: 0.0 };
+
+/// WGS84, also used for TWD97 (GRS80 differs from it by 0.1 mm in the semi-minor axis)
+pub const WGS84:Datum = Datum { name: "WGS 84", ellipsoid: ellipsoid::WGS84, to_wgs84: NO_SHIFT, accuracy: 0.0 };
+
+/// TWD67 (Hu-Tzu-Shan) on GRS67 modified, with the common 3-parameter shift good to a few meters
+pub const TWD67:Datum = Datum {
+    name: "TWD67",
+    ellipsoid: ellipsoid::GRS67_MODIFIED,
+    to_wgs84: Helmert { tx: -752.0, ty: -358.0, tz: -179.0, s: 0.0, rx: 0.0, ry: 0.0, rz: 0.0 },
+    accuracy: 5.0,
+};
+
+impl Datum {
+    /// a datum whose accuracy is estimated from the kind of shift, see [`helmert_accuracy`]
+    pub fn new(name:&'static str, ellipsoid:Ellipsoid, to_wgs84:Helmert) -> Datum {
+        Datum { name, ellipsoid, to_wgs84, accuracy: helmert_accuracy(&to_wgs84) }
+    }
+
+    /// replace the estimated accuracy with a published one
+    pub fn with_accuracy(mut self, meters:f64) -> Datum {
+        self.accuracy = meters;
+        self
+    }
+
+    /// true when coordinates on this datum are WGS84 coordinates as they are
+    pub fn is_wgs84(&self) -> bool {
+        self.ellipsoid == ellipsoid::WGS84 && self.to_wgs84 == NO_SHIFT
+    }
+
+    /// true when both datums describe the same frame, whatever their names
+    pub fn same_frame(&self, other:&Datum) -> bool {
+        self.ellipsoid == other.ellipsoid && self.to_wgs84 == other.to_wgs84
+    }
+}
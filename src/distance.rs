@@ -0,0 +1,104 @@
+//! pairwise distances between points after projecting them into a zone
+//!
+//! grid distances are plain euclidean distances between the projected points.
+//! the ground option divides every distance by the scale factor of its line,
+//! taken from the point scale factors at both ends and at the middle
+//! (Simpson's rule), which brings TM grid distances back to the ellipsoid
+//! within a few millimeters per kilometer across a whole zone.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+#[cfg(feature = "parallel")]
+use job::Job;
+use zone::Zone;
+
+/// the distances between `n` points, row by row
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceMatrix {
+    pub n:usize,
+    pub data:Vec<f64>,
+}
+
+impl DistanceMatrix {
+    /// distance between point `i` and point `j`
+    pub fn get(&self, i:usize, j:usize) -> f64 {
+        self.data[i * self.n + j]
+    }
+
+    /// the distances from point `i` to every point
+    pub fn row(&self, i:usize) -> &[f64] {
+        &self.data[i * self.n..(i + 1) * self.n]
+    }
+}
+
+struct Prepared {
+    grid:Vec<(f64, f64)>,
+    k:Vec<f64>,
+}
+
+fn prepare(zone:&Zone, points:&[(f64, f64)], ground:bool) -> Prepared {
+    let grid = points.iter().map(|p| zone.forward(p.0, p.1)).collect();
+    let k = if ground { points.iter().map(|p| zone.scale_factor(p.0, p.1)).collect() } else { Vec::new() };
+    Prepared { grid, k }
+}
+
+/// the distances from point `i` to the points from `i` on
+fn upper_row(zone:&Zone, points:&[(f64, f64)], p:&Prepared, i:usize) -> Vec<f64> {
+    let (a, b) = (points[i], p.grid[i]);
+    (i..points.len()).map(|j| {
+        let q = p.grid[j];
+        let d = (q.0 - b.0).hypot(q.1 - b.1);
+        if p.k.is_empty() || j == i {
+            return d;
+        }
+        let c = points[j];
+        let km = zone.scale_factor((a.0 + c.0) / 2.0, (a.1 + c.1) / 2.0);
+        d / ((p.k[i] + 4.0 * km + p.k[j]) / 6.0)
+    }).collect()
+}
+
+fn assemble(n:usize, rows:Vec<Vec<f64>>) -> DistanceMatrix {
+    let mut data = vec![0.0; n * n];
+    for (i, row) in rows.into_iter().enumerate() {
+        for (offset, d) in row.into_iter().enumerate() {
+            let j = i + offset;
+            data[i * n + j] = d;
+            data[j * n + i] = d;
+        }
+    }
+    DistanceMatrix { n, data }
+}
+
+/// project wgs84 lng/lat points into `zone` and return their pairwise distances in meters
+///
+/// `ground` corrects the grid distances by the line scale factors, see the
+/// module documentation.
+/// # Examples
+///
+/// ```
+/// use mercator::distance::distance_matrix;
+/// use mercator::zone::Zone;
+/// let zone = Zone::utm(51);
+/// let pts = [(121.0, 24.0), (122.0, 24.0), (121.0, 25.0)];
+/// let grid = distance_matrix(&zone, &pts, false);
+/// let ground = distance_matrix(&zone, &pts, true);
+/// assert_eq!(grid.get(0, 1), grid.get(1, 0));
+/// assert_eq!(grid.get(2, 2), 0.0);
+/// // the geodesic is 101751.561 m, UTM 51 shrinks it by about 10 m near its central meridian
+/// assert!((ground.get(0, 1) - 101_751.561).abs() < 0.01);
+/// assert!(ground.get(0, 1) - grid.get(0, 1) > 10.0);
+/// ```
+pub fn distance_matrix(zone:&Zone, points:&[(f64, f64)], ground:bool) -> DistanceMatrix {
+    let p = prepare(zone, points, ground);
+    let rows = (0..points.len()).map(|i| upper_row(zone, points, &p, i)).collect();
+    assemble(points.len(), rows)
+}
+
+/// [`distance_matrix`] with the rows computed in parallel on the thread pool of `job`
+#[cfg(feature = "parallel")]
+pub fn distance_matrix_parallel(zone:&Zone, points:&[(f64, f64)], ground:bool, job:&Job) -> DistanceMatrix {
+    let p = prepare(zone, points, ground);
+    let rows = job.install(|| (0..points.len()).into_par_iter().map(|i| upper_row(zone, points, &p, i)).collect());
+    assemble(points.len(), rows)
+}
@@ -0,0 +1,307 @@
+//! distortion of projections: Tissot indicatrices and TM distortion reports
+//!
+//! an infinitesimal circle on the WGS84 ellipsoid maps to an ellipse on the
+//! grid, the Tissot indicatrix. its semi axes are the largest and smallest
+//! scale at the point, their product is the areal scale, and the gap between
+//! them is the angular distortion, zero for a conformal projection like
+//! transverse mercator. the derivatives are taken numerically, so any
+//! [`Projection`] works. for transverse mercator a [`DistortionReport`]
+//! sums up scale error and convergence over an area, to compare zone widths
+//! or central meridians for a project, and [`design_local_tm`] works out a
+//! low distortion grid of its own for a site.
+//! - https://en.wikipedia.org/wiki/Tissot%27s_indicatrix
+
+use std::fmt;
+
+use ellipsoid::WGS84;
+use error::{Error, Result};
+use extent::Extent;
+use graticule::{check_spacing, ticks};
+use heading::normalize_heading;
+use projection::Projection;
+use survey::{convergence, elevation_factor, scale_factor};
+use tmerc::TransverseMercator;
+use zone::{Zone, ZoneWidth};
+
+/// step of the central differences, in degrees
+const STEP:f64 = 1e-5;
+
+/// the distortion ellipse of a projection at one point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Indicatrix {
+    /// largest scale, the semi major axis of the ellipse
+    pub a:f64,
+    /// smallest scale, the semi minor axis
+    pub b:f64,
+    /// direction of the major axis on the grid, degrees clockwise from grid north in [0, 180)
+    pub orientation:f64,
+    /// area on the grid over area on the ellipsoid, `a × b`
+    pub areal_scale:f64,
+    /// largest change of an angle in degrees, 0 for a conformal projection
+    pub angular_distortion:f64,
+}
+
+/// the Tissot indicatrix of a projection at a lng/lat point, undefined at the poles
+/// # Examples
+///
+/// ```
+/// use mercator::distortion::tissot;
+/// use mercator::projection::Projection;
+/// use mercator::survey::scale_factor;
+/// use mercator::zone::Zone;
+/// // transverse mercator is conformal, a circle stays a circle
+/// let t = tissot(&Zone::twd97(), 122.0, 23.5);
+/// assert!((t.a - scale_factor(122.0, 23.5, 121.0, 0.9999)).abs() < 1e-8);
+/// assert!(t.angular_distortion < 1e-4);
+///
+/// // plate carrée stretches parallels and keeps meridians
+/// #[derive(Debug)]
+/// struct PlateCarree;
+/// impl Projection for PlateCarree {
+///     fn name(&self) -> &str { "plate carrée" }
+///     fn forward(&self, lng:f64, lat:f64) -> (f64, f64) { (lng.to_radians() * 6378137.0, lat.to_radians() * 6378137.0) }
+///     fn inverse(&self, x:f64, y:f64) -> (f64, f64) { ((x / 6378137.0).to_degrees(), (y / 6378137.0).to_degrees()) }
+/// }
+/// let t = tissot(&PlateCarree, 0.0, 60.0);
+/// // sec 60° on a sphere, a little less on the ellipsoid
+/// assert!((t.a - 1.995).abs() < 1e-3 && (t.b - 0.999).abs() < 1e-3);
+/// assert!((t.orientation - 90.0).abs() < 1e-6);
+/// assert!((t.areal_scale - t.a * t.b).abs() < 1e-12);
+/// ```
+pub fn tissot<P:Projection + ?Sized>(proj:&P, lng:f64, lat:f64) -> Indicatrix {
+    let (rho, nu) = WGS84.radii(lat);
+    let h = STEP.to_radians();
+    let east = (proj.forward(lng + STEP, lat), proj.forward(lng - STEP, lat));
+    let north = (proj.forward(lng, lat + STEP), proj.forward(lng, lat - STEP));
+    // grid displacement per meter east and per meter north on the ellipsoid
+    let ground_east = 2.0 * h * nu * lat.to_radians().cos();
+    let ground_north = 2.0 * h * rho;
+    let (p, r) = (((east.0).0 - (east.1).0) / ground_east, ((east.0).1 - (east.1).1) / ground_east);
+    let (q, s) = (((north.0).0 - (north.1).0) / ground_north, ((north.0).1 - (north.1).1) / ground_north);
+    // singular values of [[p, q], [r, s]] in closed form
+    let (e, f, g, hh) = ((p + s) / 2.0, (p - s) / 2.0, (r + q) / 2.0, (r - q) / 2.0);
+    let (big_q, big_r) = (e.hypot(hh), f.hypot(g));
+    let (a, b) = (big_q + big_r, (big_q - big_r).abs());
+    // rotation of the major axis from the grid x axis, counterclockwise
+    let along = (g.atan2(f) + hh.atan2(e)) / 2.0;
+    Indicatrix {
+        a,
+        b,
+        orientation: normalize_heading(90.0 - along.to_degrees()) % 180.0,
+        areal_scale: a * b,
+        angular_distortion: (2.0 * ((a - b) / (a + b)).asin()).to_degrees(),
+    }
+}
+
+/// the indicatrices at every multiple of `spacing` degrees inside a lng/lat
+/// bounding box, rows south to north and west to east in each row
+///
+/// fails with [`Error::InvalidInput`] unless the spacing is finite and above zero.
+/// # Examples
+///
+/// ```
+/// use mercator::distortion::tissot_lattice;
+/// use mercator::extent::Extent;
+/// use mercator::zone::Zone;
+/// let lattice = tissot_lattice(&Zone::twd97(), Extent::new(119.0, 21.0, 123.0, 26.0), 1.0).unwrap();
+/// assert_eq!(lattice.len(), 5 * 6);
+/// assert_eq!(lattice[0].0, (119.0, 21.0));
+/// // scale grows away from the central meridian
+/// assert!(lattice[0].1.areal_scale > lattice[2].1.areal_scale);
+/// assert!(tissot_lattice(&Zone::twd97(), Extent::new(119.0, 21.0, 123.0, 26.0), 0.0).is_err());
+/// ```
+pub fn tissot_lattice<P:Projection + ?Sized>(proj:&P, bbox:Extent, spacing:f64) -> Result<Vec<((f64, f64), Indicatrix)>> {
+    let spacing = check_spacing("lattice spacing", spacing)?;
+    let lngs = ticks(bbox.min_x, bbox.max_x, spacing);
+    let mut out = Vec::new();
+    for lat in ticks(bbox.min_y, bbox.max_y, spacing) {
+        for &lng in &lngs {
+            out.push(((lng, lat), tissot(proj, lng, lat)));
+        }
+    }
+    Ok(out)
+}
+
+/// smallest, largest and mean value of a quantity over the samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub min:f64,
+    pub max:f64,
+    pub mean:f64,
+}
+
+impl Range {
+    fn of(values:&[f64]) -> Range {
+        let (min, max) = values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), &v| (a.min(v), b.max(v)));
+        Range { min, max, mean: values.iter().sum::<f64>() / values.len() as f64 }
+    }
+
+    /// the largest absolute value
+    pub fn max_abs(&self) -> f64 {
+        self.min.abs().max(self.max.abs())
+    }
+}
+
+/// scale error and convergence of a transverse mercator over an area
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistortionReport {
+    pub center_lng:f64,
+    pub k0:f64,
+    /// number of points sampled
+    pub samples:usize,
+    /// scale error k - 1
+    pub scale_error:Range,
+    /// meridian convergence in degrees
+    pub convergence:Range,
+}
+
+impl fmt::Display for DistortionReport {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "central meridian {}°, k0 {}: scale error {:.1} to {:.1} ppm (mean {:.1}), convergence {:.3}° to {:.3}°",
+            self.center_lng, self.k0, self.scale_error.min * 1e6, self.scale_error.max * 1e6,
+            self.scale_error.mean * 1e6, self.convergence.min, self.convergence.max)
+    }
+}
+
+/// scan a lng/lat bounding box on an `n × n` lattice, edges included, for the
+/// transverse mercator centered on `center_lng` with scale `k0`
+/// # Examples
+///
+/// ```
+/// use mercator::distortion::distortion_report;
+/// use mercator::extent::Extent;
+/// let taiwan = Extent::new(120.0, 21.9, 122.0, 25.3);
+/// let r = distortion_report(121.0, 0.9999, taiwan, 11);
+/// assert_eq!(r.samples, 121);
+/// // k0 sits on the central meridian, the edges are a degree out
+/// assert!((r.scale_error.min + 1e-4).abs() < 1e-12);
+/// assert!(r.scale_error.max > 0.0 && r.scale_error.max < 5e-5);
+/// assert!(r.convergence.min < 0.0 && (r.convergence.min + r.convergence.max).abs() < 1e-9);
+/// ```
+pub fn distortion_report(center_lng:f64, k0:f64, bbox:Extent, n:usize) -> DistortionReport {
+    let n = n.max(2);
+    let at = |lo:f64, hi:f64, i:usize| lo + (hi - lo) * i as f64 / (n - 1) as f64;
+    let (mut scale, mut gamma) = (Vec::with_capacity(n * n), Vec::with_capacity(n * n));
+    for i in 0..n {
+        let lat = at(bbox.min_y, bbox.max_y, i);
+        for j in 0..n {
+            let lng = at(bbox.min_x, bbox.max_x, j);
+            scale.push(scale_factor(lng, lat, center_lng, k0) - 1.0);
+            gamma.push(convergence(lng, lat, center_lng));
+        }
+    }
+    DistortionReport {
+        center_lng,
+        k0,
+        samples: n * n,
+        scale_error: Range::of(&scale),
+        convergence: Range::of(&gamma),
+    }
+}
+
+/// [`distortion_report`] for the zone of each width around the center of the
+/// bounding box, 2°, 3° then 6°
+/// # Examples
+///
+/// ```
+/// use mercator::distortion::compare_zone_widths;
+/// use mercator::extent::Extent;
+/// use mercator::zone::ZoneWidth;
+/// // a site on Penghu
+/// let reports = compare_zone_widths(Extent::new(119.6, 23.5, 119.8, 23.7), 5);
+/// assert_eq!(reports[0].0.width, ZoneWidth::Degree2);
+/// let worst:Vec<f64> = reports.iter().map(|r| r.1.scale_error.max_abs()).collect();
+/// // the 3° zone is centered on 120°, closest to the site, and has k0 = 1
+/// assert!(worst[1] < worst[0] && worst[1] < worst[2]);
+/// ```
+pub fn compare_zone_widths(bbox:Extent, n:usize) -> Vec<(Zone, DistortionReport)> {
+    let (lng, lat) = bbox.center();
+    [ZoneWidth::Degree2, ZoneWidth::Degree3, ZoneWidth::Degree6].iter().map(|&width| {
+        let zone = Zone::for_point(lng, lat, width);
+        (zone, distortion_report(zone.center_lng, width.k0(), bbox, n))
+    }).collect()
+}
+
+/// parameters of a transverse mercator designed for one project area
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalTm {
+    /// central meridian in degrees, on a whole arc minute
+    pub center_lng:f64,
+    /// latitude of origin in degrees, the arc minute at or south of the area
+    pub lat0:f64,
+    /// scale on the central meridian, to six decimals
+    pub k0:f64,
+    pub false_easting:f64,
+    pub false_northing:f64,
+    /// largest |grid distance / ground distance - 1| over the area
+    pub max_scale_error:f64,
+}
+
+impl LocalTm {
+    /// the grid as a projection on WGS84
+    pub fn projection(&self) -> TransverseMercator {
+        TransverseMercator::new(WGS84, self.lat0, self.center_lng, self.k0, self.false_easting, self.false_northing)
+    }
+}
+
+/// design a low distortion transverse mercator for a lng/lat bounding box at
+/// a mean ellipsoidal height of `height` meters
+///
+/// the central meridian goes through the middle of the area and k0 scales the
+/// grid so the combined factor (scale × elevation factor) swings as far
+/// above 1 at the east and west edges as below it on the central meridian;
+/// grid distances then match ground distances at the height of the site. the
+/// false origin keeps every coordinate above 10 km. fails with
+/// [`Error::InvalidInput`] when the area is too wide for `max_scale_error`.
+/// # Examples
+///
+/// ```
+/// use mercator::distortion::design_local_tm;
+/// use mercator::extent::Extent;
+/// use mercator::projection::Projection;
+/// // a 30 km site around Hsinchu at 100 m
+/// let site = Extent::new(120.85, 24.65, 121.15, 24.90);
+/// let grid = design_local_tm(site, 100.0, 20e-6).unwrap();
+/// assert_eq!(grid.center_lng, 121.0);
+/// assert!(grid.k0 > 1.0 && grid.max_scale_error < 5e-6);
+/// let (x, y) = grid.projection().forward(120.85, 24.65);
+/// assert!(x > 10_000.0 && y >= 10_000.0);
+///
+/// // the whole island does not fit in 20 ppm
+/// assert!(design_local_tm(Extent::new(120.0, 21.9, 122.0, 25.3), 0.0, 20e-6).is_err());
+/// ```
+pub fn design_local_tm(bbox:Extent, height:f64, max_scale_error:f64) -> Result<LocalTm> {
+    let minute = |deg:f64| (deg * 60.0).round() / 60.0;
+    let center_lng = minute(bbox.center().0);
+    let lat0 = (bbox.min_y * 60.0).floor() / 60.0;
+    // scale error with k0 = 1 grows away from the central meridian and towards the equator
+    let n = 11;
+    let at = |lo:f64, hi:f64, i:usize| lo + (hi - lo) * i as f64 / (n - 1) as f64;
+    let mut factors = Vec::with_capacity(n * n);
+    for i in 0..n {
+        let lat = at(bbox.min_y, bbox.max_y, i);
+        for j in 0..n {
+            let lng = at(bbox.min_x, bbox.max_x, j);
+            factors.push(scale_factor(lng, lat, center_lng, 1.0) * elevation_factor(lat, height));
+        }
+    }
+    let range = Range::of(&factors);
+    let k0 = (2.0 / (range.min + range.max) * 1e6).round() / 1e6;
+    let error = (k0 * range.min - 1.0).abs().max((k0 * range.max - 1.0).abs());
+    if error > max_scale_error {
+        return Err(Error::InvalidInput(format!("the area needs a scale error of {:.1} ppm, more than the {:.1} ppm allowed; split it into zones",
+            error * 1e6, max_scale_error * 1e6)));
+    }
+    // half the width in meters, rounded up with a 10 km margin
+    let widest = if bbox.min_y * bbox.max_y <= 0.0 { 0.0 } else { bbox.min_y.abs().min(bbox.max_y.abs()) };
+    let (_, nu) = WGS84.radii(widest);
+    let half = (bbox.width() / 2.0).to_radians() * nu * widest.to_radians().cos();
+    Ok(LocalTm {
+        center_lng,
+        lat0,
+        k0,
+        false_easting: ((half + 10_000.0) / 10_000.0).ceil() * 10_000.0,
+        false_northing: 10_000.0,
+        max_scale_error: error,
+    })
+}
@@ -0,0 +1,61 @@
+//! geodetic ⇄ earth-centered earth-fixed (geocentric XYZ) conversion
+
+use ellipsoid::{Ellipsoid, WGS84};
+
+impl Ellipsoid {
+    /// convert geodetic lng/lat in degrees and ellipsoidal height in meters to ECEF XYZ in meters
+    pub fn geodetic_to_ecef(&self, lng:f64, lat:f64, h:f64) -> (f64, f64, f64) {
+        let e2 = self.e2();
+        let (slat, clat) = lat.to_radians().sin_cos();
+        let (slng, clng) = lng.to_radians().sin_cos();
+        let nu = self.a / (1.0 - e2 * slat * slat).sqrt();
+        ((nu + h) * clat * clng, (nu + h) * clat * slng, (nu * (1.0 - e2) + h) * slat)
+    }
+
+    /// convert ECEF XYZ in meters back to geodetic lng/lat in degrees and height in meters
+    ///
+    /// uses Bowring's iteration on the parametric latitude, which converges to
+    /// well below a micrometer in two or three steps for terrestrial points.
+    pub fn ecef_to_geodetic(&self, x:f64, y:f64, z:f64) -> (f64, f64, f64) {
+        let (a, b) = (self.a, self.b);
+        let e2 = self.e2();
+        let ep2 = self.ep2();
+        let p = x.hypot(y);
+        let lng = y.atan2(x);
+        let mut beta = (a * z).atan2(b * p);
+        let mut lat = 0.0;
+        for _ in 0..10 {
+            let (sb, cb) = beta.sin_cos();
+            lat = (z + ep2 * b * sb.powi(3)).atan2(p - e2 * a * cb.powi(3));
+            let next = ((b / a) * lat.sin()).atan2(lat.cos());
+            let done = (next - beta).abs() < 1e-15;
+            beta = next;
+            if done {
+                break;
+            }
+        }
+        let (slat, clat) = lat.sin_cos();
+        let h = p * clat + z * slat - a * (1.0 - e2 * slat * slat).sqrt();
+        (lng.to_degrees(), lat.to_degrees(), h)
+    }
+}
+
+/// convert wgs84 lng/lat in degrees and ellipsoidal height in meters to ECEF XYZ in meters
+/// # Examples
+///
+/// ```
+/// use mercator::{geodetic_to_ecef, ecef_to_geodetic};
+/// let (x, y, z) = geodetic_to_ecef(0.0, 0.0, 0.0);
+/// assert_eq!((x, y, z), (6378137.0, 0.0, 0.0));
+/// let (x, y, z) = geodetic_to_ecef(121.5654, 25.0330, 10.0);
+/// let (lng, lat, h) = ecef_to_geodetic(x, y, z);
+/// assert!((lng - 121.5654).abs() < 1e-12 && (lat - 25.0330).abs() < 1e-12 && (h - 10.0).abs() < 1e-6);
+/// ```
+pub fn geodetic_to_ecef(lng:f64, lat:f64, h:f64) -> (f64, f64, f64) {
+    WGS84.geodetic_to_ecef(lng, lat, h)
+}
+
+/// convert ECEF XYZ in meters to wgs84 lng/lat in degrees and ellipsoidal height in meters
+pub fn ecef_to_geodetic(x:f64, y:f64, z:f64) -> (f64, f64, f64) {
+    WGS84.ecef_to_geodetic(x, y, z)
+}
@@ -0,0 +1,83 @@
+//! reference ellipsoids
+
+/// a reference ellipsoid given by its semi-major and semi-minor axes in meters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipsoid {
+    pub a:f64,
+    pub b:f64,
+}
+
+/// WGS84, the ellipsoid used by the projection functions of this crate
+pub const WGS84:Ellipsoid = Ellipsoid { a: 6378137.0, b: 6356752.314245 };
+
+/// GRS80, used by TWD97, NAD83 and ETRS89
+pub const GRS80:Ellipsoid = Ellipsoid { a: 6378137.0, b: 6356752.314140356 };
+
+/// GRS67
+pub const GRS67:Ellipsoid = Ellipsoid { a: 6378160.0, b: 6356774.516090714 };
+
+/// GRS67 modified (flattening rounded to 1/298.25), used by TWD67 and SAD69
+pub const GRS67_MODIFIED:Ellipsoid = Ellipsoid { a: 6378160.0, b: 6356774.719195305 };
+
+/// the ellipsoids constant caches are kept for, with their names and published inverse flattening
+pub(crate) const STANDARD:[(Ellipsoid, &str, f64); 4] = [
+    (WGS84, "WGS 84", 298.257223563),
+    (GRS80, "GRS 1980", 298.257222101),
+    (GRS67, "GRS 1967", 298.247167427),
+    (GRS67_MODIFIED, "GRS 1967 Modified", 298.25),
+];
+
+impl Ellipsoid {
+    /// build an ellipsoid from its semi-major axis and inverse flattening
+    pub fn from_inverse_flattening(a:f64, rf:f64) -> Ellipsoid {
+        Ellipsoid { a, b: a * (1.0 - 1.0 / rf) }
+    }
+
+    /// name of a well known ellipsoid
+    pub fn name(&self) -> Option<&'static str> {
+        self.standard_index().map(|i| STANDARD[i].1)
+    }
+
+    /// inverse flattening 1/f, the published value for well known ellipsoids and 0 for a sphere
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::ellipsoid::{Ellipsoid, WGS84};
+    /// assert_eq!(WGS84.inverse_flattening(), 298.257223563);
+    /// assert_eq!(Ellipsoid { a: 6371000.0, b: 6371000.0 }.inverse_flattening(), 0.0);
+    /// ```
+    pub fn inverse_flattening(&self) -> f64 {
+        match self.standard_index() {
+            Some(i) => STANDARD[i].2,
+            None if self.a == self.b => 0.0,
+            None => 1.0 / self.f(),
+        }
+    }
+
+    /// position of a well known ellipsoid in `STANDARD`, used to key constant caches
+    pub(crate) fn standard_index(&self) -> Option<usize> {
+        STANDARD.iter().position(|e| e.0 == *self)
+    }
+
+    /// flattening
+    pub fn f(&self) -> f64 {
+        (self.a - self.b) / self.a
+    }
+
+    /// first eccentricity squared
+    pub fn e2(&self) -> f64 {
+        1.0 - (self.b * self.b) / (self.a * self.a)
+    }
+
+    /// second eccentricity squared
+    pub fn ep2(&self) -> f64 {
+        (self.a * self.a) / (self.b * self.b) - 1.0
+    }
+
+    /// radii of curvature (ρ meridian, ν prime vertical) at a latitude in degrees
+    pub fn radii(&self, lat:f64) -> (f64, f64) {
+        let e2 = self.e2();
+        let w2 = 1.0 - e2 * lat.to_radians().sin().powi(2);
+        (self.a * (1.0 - e2) / (w2 * w2.sqrt()), self.a / w2.sqrt())
+    }
+}
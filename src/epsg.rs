@@ -0,0 +1,84 @@
+//! the curated EPSG subset compiled into the crate
+//!
+//! `build.rs` turns `data/epsg.csv` into the tables below: geographic CRSs of
+//! the major national datums, their UTM / MGA / Gauss-Krüger series and a few
+//! national transverse mercator grids. [`Crs::from_epsg`](::crs::Crs::from_epsg)
+//! falls back to this table, so these codes work offline without registering
+//! anything.
+
+use std::sync::{Arc, OnceLock};
+
+use crs::Crs;
+use datum::Datum;
+use ellipsoid::Ellipsoid;
+use helmert::Helmert;
+use projection::Projection;
+use tmerc::TransverseMercator;
+use zone::{Hemisphere, Zone, ZoneWidth};
+
+pub(crate) enum Kind {
+    Geographic,
+    Tm { lat0:f64, lng0:f64, k0:f64, false_easting:f64, false_northing:f64 },
+}
+
+pub(crate) struct Entry {
+    pub code:u32,
+    pub name:&'static str,
+    datum:usize,
+    kind:Kind,
+}
+
+include!(concat!(env!("OUT_DIR"), "/epsg_table.rs"));
+
+/// the table entry of a code
+pub(crate) fn lookup(code:u32) -> Option<&'static Entry> {
+    ENTRIES.binary_search_by_key(&code, |e| e.code).ok().map(|i| &ENTRIES[i])
+}
+
+/// every code of the table, ascending
+pub(crate) fn codes() -> impl Iterator<Item=u32> {
+    ENTRIES.iter().map(|e| e.code)
+}
+
+/// one shared projection per entry, so equal codes give equal CRSs
+fn projections() -> &'static Vec<Option<Arc<dyn Projection>>> {
+    static PROJECTIONS:OnceLock<Vec<Option<Arc<dyn Projection>>>> = OnceLock::new();
+    PROJECTIONS.get_or_init(|| ENTRIES.iter().map(|e| match e.kind {
+        Kind::Tm { lat0, lng0, k0, false_easting, false_northing } => {
+            let tm = TransverseMercator::new(DATUMS[e.datum].ellipsoid, lat0, lng0, k0, false_easting, false_northing);
+            Some(Arc::new(tm) as Arc<dyn Projection>)
+        }
+        Kind::Geographic => None,
+    }).collect())
+}
+
+/// the zone of a built-in zone system with these parameters
+pub(crate) fn as_zone(lat0:f64, lng0:f64, k0:f64, false_easting:f64, false_northing:f64) -> Option<Zone> {
+    let width = [ZoneWidth::Degree2, ZoneWidth::Degree3, ZoneWidth::Degree6].iter().cloned()
+        .find(|w| w.k0() == k0 && w.dx() == false_easting)?;
+    let hemisphere = [Hemisphere::North, Hemisphere::South].iter().cloned().find(|h| h.dy() == false_northing)?;
+    if lat0 != 0.0 {
+        return None;
+    }
+    Some(Zone::new(width, lng0).with_hemisphere(hemisphere))
+}
+
+impl Entry {
+    /// zone system parameters become a [`Crs::Projected`], other grids a [`Crs::Custom`]
+    pub(crate) fn crs(&self) -> Crs {
+        let datum:Datum = DATUMS[self.datum];
+        match self.kind {
+            Kind::Geographic => Crs::Geographic(datum),
+            Kind::Tm { lat0, lng0, k0, false_easting, false_northing } => {
+                match as_zone(lat0, lng0, k0, false_easting, false_northing) {
+                    Some(zone) => Crs::Projected(zone, datum),
+                    None => {
+                        let i = ENTRIES.iter().position(|e| e.code == self.code).unwrap_or(0);
+                        let p = projections()[i].clone().expect("tm entries have a projection");
+                        Crs::Custom(p, datum)
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! error type for the fallible parts of the crate
+
+use std::error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    /// reading or writing a file failed
+    Io(io::Error),
+    /// input data (a grid file, a definition, ...) is malformed
+    Format(String),
+    /// the requested transform cannot be carried out
+    Transform(String),
+    /// arguments are out of range or inconsistent with each other
+    InvalidInput(String),
+}
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref e) => write!(f, "io error: {}", e),
+            Error::Format(ref msg) => write!(f, "format error: {}", msg),
+            Error::Transform(ref msg) => write!(f, "transform error: {}", msg),
+            Error::InvalidInput(ref msg) => write!(f, "invalid input: {}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e:io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+/// true when `src` and `dst` name the same existing file, which opening `dst`
+/// for writing would wipe before `src` is read
+#[cfg(any(feature = "geoparquet", feature = "memmap", feature = "las"))]
+pub(crate) fn same_file(src:&::std::path::Path, dst:&::std::path::Path) -> bool {
+    match (src.canonicalize(), dst.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
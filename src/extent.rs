@@ -0,0 +1,176 @@
+//! bounding boxes, centroids and areas of projected coordinates
+//!
+//! plain planar arithmetic on `(x, y)` slices, meant for the output of a
+//! projection where x and y are meters (or the unit of the CRS). lng/lat
+//! input gives degrees and no meaningful area.
+
+use geometry::LineString;
+
+/// an axis aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Extent {
+    pub min_x:f64,
+    pub min_y:f64,
+    pub max_x:f64,
+    pub max_y:f64,
+}
+
+impl Extent {
+    pub fn new(min_x:f64, min_y:f64, max_x:f64, max_y:f64) -> Extent {
+        Extent { min_x, min_y, max_x, max_y }
+    }
+
+    /// the bounding box of `points`, `None` when there are none; NaN coordinates are ignored
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::extent::Extent;
+    /// use mercator::wgs84_to_twd97;
+    /// let pts:Vec<(f64, f64)> = [(121.0, 24.0), (121.1, 24.2)].iter().map(|p| wgs84_to_twd97(p.0, p.1)).collect();
+    /// let e = Extent::of(&pts).unwrap();
+    /// assert!((e.width() - 10158.4).abs() < 0.1);
+    /// assert!((e.height() - 22153.4).abs() < 0.1);
+    /// assert!(e.contains(e.center().0, e.center().1));
+    /// ```
+    pub fn of(points:&[(f64, f64)]) -> Option<Extent> {
+        let mut e = Extent::new(f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for &(x, y) in points {
+            e.min_x = e.min_x.min(x);
+            e.min_y = e.min_y.min(y);
+            e.max_x = e.max_x.max(x);
+            e.max_y = e.max_y.max(y);
+        }
+        if e.min_x > e.max_x || e.min_y > e.max_y {
+            return None;
+        }
+        Some(e)
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+
+    /// `(width, height)`
+    pub fn size(&self) -> (f64, f64) {
+        (self.width(), self.height())
+    }
+
+    pub fn area(&self) -> f64 {
+        self.width() * self.height()
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        ((self.min_x + self.max_x) / 2.0, (self.min_y + self.max_y) / 2.0)
+    }
+
+    /// true if the point lies inside or on the edge
+    pub fn contains(&self, x:f64, y:f64) -> bool {
+        x >= self.min_x && x <= self.max_x && y >= self.min_y && y <= self.max_y
+    }
+
+    pub fn intersects(&self, other:&Extent) -> bool {
+        self.min_x <= other.max_x && other.min_x <= self.max_x && self.min_y <= other.max_y && other.min_y <= self.max_y
+    }
+
+    /// the smallest extent holding both
+    pub fn union(&self, other:&Extent) -> Extent {
+        Extent::new(self.min_x.min(other.min_x), self.min_y.min(other.min_y), self.max_x.max(other.max_x), self.max_y.max(other.max_y))
+    }
+
+    /// grown by `margin` on every side
+    pub fn expand(&self, margin:f64) -> Extent {
+        Extent::new(self.min_x - margin, self.min_y - margin, self.max_x + margin, self.max_y + margin)
+    }
+}
+
+/// mean of the vertices, `None` when there are none
+pub fn centroid(points:&[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.is_empty() {
+        return None;
+    }
+    let (sx, sy) = points.iter().fold((0.0, 0.0), |(sx, sy), p| (sx + p.0, sy + p.1));
+    Some((sx / points.len() as f64, sy / points.len() as f64))
+}
+
+/// planar length of a polyline, the sum of its segment lengths
+/// # Examples
+///
+/// ```
+/// use mercator::extent::length;
+/// assert_eq!(length(&[(0.0, 0.0), (3.0, 4.0), (3.0, 10.0)]), 11.0);
+/// ```
+pub fn length(line:&[(f64, f64)]) -> f64 {
+    line.windows(2).map(|w| (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1)).sum()
+}
+
+/// signed area and first moments of a ring, closed or not; counter clockwise is positive
+fn ring_moments(ring:&[(f64, f64)]) -> (f64, f64, f64) {
+    if ring.len() < 3 {
+        return (0.0, 0.0, 0.0);
+    }
+    // relative to the first vertex, for precision with large grid coordinates
+    let o = ring[0];
+    let (mut a, mut mx, mut my) = (0.0, 0.0, 0.0);
+    for i in 0..ring.len() {
+        let p = ring[i];
+        let q = ring[(i + 1) % ring.len()];
+        let (x0, y0, x1, y1) = (p.0 - o.0, p.1 - o.1, q.0 - o.0, q.1 - o.1);
+        let cross = x0 * y1 - x1 * y0;
+        a += cross;
+        mx += (x0 + x1) * cross;
+        my += (y0 + y1) * cross;
+    }
+    (a / 2.0, mx / 6.0 + o.0 * a / 2.0, my / 6.0 + o.1 * a / 2.0)
+}
+
+/// signed planar area of a ring, closed or not; counter clockwise is positive
+/// # Examples
+///
+/// ```
+/// use mercator::extent::ring_area;
+/// assert_eq!(ring_area(&[(0.0, 0.0), (4.0, 0.0), (4.0, 3.0)]), 6.0);
+/// assert_eq!(ring_area(&[(0.0, 0.0), (4.0, 3.0), (4.0, 0.0), (0.0, 0.0)]), -6.0);
+/// ```
+pub fn ring_area(ring:&[(f64, f64)]) -> f64 {
+    ring_moments(ring).0
+}
+
+/// planar area of a polygon, holes removed, whatever the orientation of the rings
+/// # Examples
+///
+/// ```
+/// use mercator::extent::{polygon_area, polygon_centroid};
+/// let square = vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0), (0.0, 0.0)];
+/// let hole = vec![(0.0, 0.0), (5.0, 0.0), (5.0, 5.0), (0.0, 5.0)];
+/// let poly = vec![square, hole];
+/// assert_eq!(polygon_area(&poly), 75.0);
+/// let (cx, cy) = polygon_centroid(&poly).unwrap();
+/// assert!((cx - 35.0 / 6.0).abs() < 1e-12 && (cy - 35.0 / 6.0).abs() < 1e-12);
+/// ```
+pub fn polygon_area(poly:&[LineString]) -> f64 {
+    poly.iter().enumerate().map(|(i, ring)| {
+        let a = ring_moments(ring).0.abs();
+        if i == 0 { a } else { -a }
+    }).sum()
+}
+
+/// area weighted centroid of a polygon, holes removed; `None` for a polygon without area
+pub fn polygon_centroid(poly:&[LineString]) -> Option<(f64, f64)> {
+    let (mut a, mut mx, mut my) = (0.0, 0.0, 0.0);
+    for (i, ring) in poly.iter().enumerate() {
+        let (ra, rx, ry) = ring_moments(ring);
+        // exterior counts positive and holes negative, whatever their orientation
+        let sign = if (ra >= 0.0) == (i == 0) { 1.0 } else { -1.0 };
+        a += sign * ra;
+        mx += sign * rx;
+        my += sign * ry;
+    }
+    if a == 0.0 || !a.is_finite() {
+        return None;
+    }
+    Some((mx / a, my / a))
+}
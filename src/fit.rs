@@ -0,0 +1,390 @@
+//! least-squares fitting of plane transforms from control point pairs (site
+//! calibration, georeferencing of scanned maps)
+
+use affine::Affine2D;
+use coord::Ecef;
+use error::{Error, Result};
+use helmert::Helmert;
+use polynomial::{terms, Polynomial2D};
+
+/// a fitted transform with its residuals (observed target − transformed source)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fit {
+    pub transform:Affine2D,
+    pub residuals:Vec<(f64, f64)>,
+    /// root mean square of the residual lengths in target units
+    pub rms:f64,
+    /// standard error of a residual length, the rms corrected for the degrees of freedom
+    pub sigma:f64,
+}
+
+fn check_pairs(src:&[(f64, f64)], dst:&[(f64, f64)], min:usize) -> Result<()> {
+    if src.len() != dst.len() {
+        return Err(Error::InvalidInput(format!("{} source points but {} target points", src.len(), dst.len())));
+    }
+    if src.len() < min {
+        return Err(Error::InvalidInput(format!("need at least {} point pairs, got {}", min, src.len())));
+    }
+    if let Some(i) = src.iter().zip(dst).position(|(s, d)| !(s.0.is_finite() && s.1.is_finite() && d.0.is_finite() && d.1.is_finite())) {
+        return Err(Error::InvalidInput(format!("point pair {} is not finite", i)));
+    }
+    Ok(())
+}
+
+fn centroid(pts:&[(f64, f64)]) -> (f64, f64) {
+    let n = pts.len() as f64;
+    let (sx, sy) = pts.iter().fold((0.0, 0.0), |(sx, sy), p| (sx + p.0, sy + p.1));
+    (sx / n, sy / n)
+}
+
+/// rms and sigma of residual lengths from their sum of squares, for `n` points
+/// of `dims` coordinates fixing `params` unknowns; sigma is infinite without redundancy
+fn spread(ss:f64, n:usize, dims:usize, params:usize) -> (f64, f64) {
+    let redundancy = (n * dims) as f64 - params as f64;
+    let sigma = if redundancy > 0.0 { (ss * dims as f64 / redundancy).sqrt() } else { f64::INFINITY };
+    ((ss / n as f64).sqrt(), sigma)
+}
+
+/// the residuals of `apply` on the pairs with their rms and sigma
+fn residuals<F:Fn(f64, f64) -> (f64, f64)>(apply:F, src:&[(f64, f64)], dst:&[(f64, f64)], params:usize) -> (Vec<(f64, f64)>, f64, f64) {
+    let residuals:Vec<(f64, f64)> = src.iter().zip(dst).map(|(s, d)| {
+        let (x, y) = apply(s.0, s.1);
+        (d.0 - x, d.1 - y)
+    }).collect();
+    let ss:f64 = residuals.iter().map(|r| r.0 * r.0 + r.1 * r.1).sum();
+    let (rms, sigma) = spread(ss, residuals.len(), 2, params);
+    (residuals, rms, sigma)
+}
+
+fn finish(t:Affine2D, src:&[(f64, f64)], dst:&[(f64, f64)], params:usize) -> Fit {
+    let (residuals, rms, sigma) = residuals(|x, y| t.apply(x, y), src, dst, params);
+    Fit { transform: t, residuals, rms, sigma }
+}
+
+/// indices of the lengths above `k · sigma`, largest first
+fn flag(lengths:Vec<f64>, sigma:f64, k:f64) -> Vec<usize> {
+    let mut out:Vec<usize> = (0..lengths.len()).filter(|&i| lengths[i] > k * sigma).collect();
+    out.sort_by(|&i, &j| lengths[j].total_cmp(&lengths[i]));
+    out
+}
+
+impl Fit {
+    /// the length of every residual, in the order of the pairs
+    pub fn residual_lengths(&self) -> Vec<f64> {
+        self.residuals.iter().map(|r| r.0.hypot(r.1)).collect()
+    }
+
+    /// the pairs whose residual is longer than `k` sigma, worst first
+    ///
+    /// a bad point drags the fit towards itself and inflates sigma, so drop
+    /// the worst pair, fit again and repeat until nothing is flagged.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::fit::fit_affine;
+    /// let mut src = Vec::new();
+    /// let mut dst = Vec::new();
+    /// for i in 0..4 {
+    ///     for j in 0..4 {
+    ///         let (x, y) = (i as f64 * 100.0, j as f64 * 100.0);
+    ///         // a few millimeters of noise
+    ///         let noise = 0.003 * ((i * 7 + j * 3) % 5) as f64 - 0.006;
+    ///         src.push((x, y));
+    ///         dst.push((250000.0 + x + noise, 2650000.0 + y - noise));
+    ///     }
+    /// }
+    /// // one control point mistyped by half a meter
+    /// dst[5].1 += 0.5;
+    /// let fit = fit_affine(&src, &dst).unwrap();
+    /// assert_eq!(fit.outliers(3.0), vec![5]);
+    /// src.remove(5);
+    /// dst.remove(5);
+    /// let fit = fit_affine(&src, &dst).unwrap();
+    /// assert!(fit.outliers(3.0).is_empty());
+    /// assert!(fit.rms < 0.01);
+    /// ```
+    pub fn outliers(&self, k:f64) -> Vec<usize> {
+        flag(self.residual_lengths(), self.sigma, k)
+    }
+}
+
+impl PolynomialFit {
+    /// the length of every residual, in the order of the pairs
+    pub fn residual_lengths(&self) -> Vec<f64> {
+        self.residuals.iter().map(|r| r.0.hypot(r.1)).collect()
+    }
+
+    /// the pairs whose residual is longer than `k` sigma, worst first, see [`Fit::outliers`]
+    pub fn outliers(&self, k:f64) -> Vec<usize> {
+        flag(self.residual_lengths(), self.sigma, k)
+    }
+}
+
+/// fit a 4-parameter similarity (rotation, uniform scale, translation), at least 2 pairs
+/// # Examples
+///
+/// ```
+/// use mercator::fit::fit_similarity;
+/// let local = [(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)];
+/// let grid = [(250000.0, 2650000.0), (250000.0, 2650100.0), (249900.0, 2650100.0), (249900.0, 2650000.0)];
+/// let fit = fit_similarity(&local, &grid).unwrap();
+/// assert!((fit.transform.rotation() - 90.0).abs() < 1e-9);
+/// assert!((fit.transform.scale() - 1.0).abs() < 1e-12);
+/// assert!(fit.rms < 1e-6);
+/// ```
+pub fn fit_similarity(src:&[(f64, f64)], dst:&[(f64, f64)]) -> Result<Fit> {
+    check_pairs(src, dst, 2)?;
+    // center both sets so the normal equations stay well conditioned for grid coordinates
+    let (cs, cd) = (centroid(src), centroid(dst));
+    let (mut sxx, mut sa, mut sb) = (0.0, 0.0, 0.0);
+    for (s, d) in src.iter().zip(dst) {
+        let (x, y) = (s.0 - cs.0, s.1 - cs.1);
+        let (u, v) = (d.0 - cd.0, d.1 - cd.1);
+        sxx += x * x + y * y;
+        sa += x * u + y * v;
+        sb += x * v - y * u;
+    }
+    if sxx == 0.0 {
+        return Err(Error::InvalidInput("source points are all identical".to_string()));
+    }
+    let (a, b) = (sa / sxx, sb / sxx);
+    let t = Affine2D::new(a, -b, cd.0 - (a * cs.0 - b * cs.1), b, a, cd.1 - (b * cs.0 + a * cs.1));
+    Ok(finish(t, src, dst, 4))
+}
+
+/// fit a 6-parameter affine transform, at least 3 non-collinear pairs
+/// # Examples
+///
+/// ```
+/// use mercator::fit::fit_affine;
+/// let src = [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0), (10.0, 10.0)];
+/// let dst = [(5.0, 5.0), (25.0, 5.0), (5.0, 35.0), (25.0, 35.0)];
+/// let fit = fit_affine(&src, &dst).unwrap();
+/// let (x, y) = fit.transform.apply(5.0, 5.0);
+/// assert!((x - 15.0).abs() < 1e-9 && (y - 20.0).abs() < 1e-9);
+/// let bad = [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0), (f64::NAN, 10.0)];
+/// assert!(fit_affine(&bad, &dst).is_err());
+/// ```
+pub fn fit_affine(src:&[(f64, f64)], dst:&[(f64, f64)]) -> Result<Fit> {
+    check_pairs(src, dst, 3)?;
+    let (cs, cd) = (centroid(src), centroid(dst));
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    let (mut sxu, mut syu, mut sxv, mut syv) = (0.0, 0.0, 0.0, 0.0);
+    for (s, d) in src.iter().zip(dst) {
+        let (x, y) = (s.0 - cs.0, s.1 - cs.1);
+        let (u, v) = (d.0 - cd.0, d.1 - cd.1);
+        sxx += x * x;
+        sxy += x * y;
+        syy += y * y;
+        sxu += x * u;
+        syu += y * u;
+        sxv += x * v;
+        syv += y * v;
+    }
+    let det = sxx * syy - sxy * sxy;
+    if det.abs() <= 1e-12 * (sxx * syy).max(f64::MIN_POSITIVE) {
+        return Err(Error::InvalidInput("source points are collinear".to_string()));
+    }
+    let a = (sxu * syy - syu * sxy) / det;
+    let b = (syu * sxx - sxu * sxy) / det;
+    let d = (sxv * syy - syv * sxy) / det;
+    let e = (syv * sxx - sxv * sxy) / det;
+    let t = Affine2D::new(a, b, cd.0 - (a * cs.0 + b * cs.1), d, e, cd.1 - (d * cs.0 + e * cs.1));
+    Ok(finish(t, src, dst, 6))
+}
+
+/// a fitted polynomial transform with its residuals (observed target − transformed source)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolynomialFit {
+    pub transform:Polynomial2D,
+    pub residuals:Vec<(f64, f64)>,
+    /// root mean square of the residual lengths in target units
+    pub rms:f64,
+    /// standard error of a residual length, the rms corrected for the degrees of freedom
+    pub sigma:f64,
+}
+
+/// fit a polynomial transform of `order` 1 to 3, with at least 3, 6 and 10 pairs
+///
+/// order 1 is the affine fit again; 2 and 3 bend the sheet. the pairs should
+/// spread over the whole map, since the fit is only held in place where
+/// there are points.
+/// # Examples
+///
+/// ```
+/// use mercator::fit::{fit_affine, fit_polynomial};
+/// // a scanned sheet in pixels, its paper stretched unevenly across the width
+/// let mut pixels = Vec::new();
+/// let mut grid = Vec::new();
+/// for i in 0..5 {
+///     for j in 0..5 {
+///         let (px, py) = (i as f64 * 1000.0, j as f64 * 1000.0);
+///         let u = px / 4000.0;
+///         pixels.push((px, py));
+///         grid.push((250000.0 + 2.5 * px + 40.0 * u * u, 2660000.0 - 2.5 * py + 15.0 * u * u));
+///     }
+/// }
+/// assert!(fit_affine(&pixels, &grid).unwrap().rms > 1.0);
+/// let fit = fit_polynomial(&pixels, &grid, 2).unwrap();
+/// assert!(fit.rms < 1e-6);
+/// let (x, y) = fit.transform.apply(2000.0, 2000.0);
+/// assert!((x - 255010.0).abs() < 1e-6 && (y - 2655003.75).abs() < 1e-6);
+/// assert!(fit_polynomial(&pixels[..9], &grid[..9], 3).is_err());
+///
+/// // points along one line leave the affine part across it open
+/// let line:Vec<(f64, f64)> = (0..8).map(|i| (i as f64 * 0.1, i as f64 * 0.3)).collect();
+/// assert!(fit_polynomial(&line, &line, 1).is_err());
+/// ```
+pub fn fit_polynomial(src:&[(f64, f64)], dst:&[(f64, f64)], order:usize) -> Result<PolynomialFit> {
+    if !(1..=3).contains(&order) {
+        return Err(Error::InvalidInput(format!("polynomial order {} is not 1, 2 or 3", order)));
+    }
+    let n = terms(order, 0.0, 0.0).len();
+    check_pairs(src, dst, n)?;
+    // fit in centered coordinates around unit size so the powers stay comparable
+    let origin = centroid(src);
+    let unit = src.iter().map(|p| (p.0 - origin.0).abs().max((p.1 - origin.1).abs())).fold(0.0, f64::max);
+    if unit == 0.0 {
+        return Err(Error::InvalidInput("source points are all identical".to_string()));
+    }
+    let rows:Vec<Vec<f64>> = src.iter().map(|p| terms(order, (p.0 - origin.0) / unit, (p.1 - origin.1) / unit)).collect();
+    let degenerate = || Error::InvalidInput(format!("source points do not fix an order {} polynomial", order));
+    let xs:Vec<f64> = dst.iter().map(|d| d.0).collect();
+    let ys:Vec<f64> = dst.iter().map(|d| d.1).collect();
+    let cx = least_squares(&rows, &xs).ok_or_else(degenerate)?;
+    let cy = least_squares(&rows, &ys).ok_or_else(degenerate)?;
+    let transform = Polynomial2D { order, origin, unit, cx, cy };
+    let (residuals, rms, sigma) = residuals(|x, y| transform.apply(x, y), src, dst, n);
+    Ok(PolynomialFit { transform, residuals, rms, sigma })
+}
+
+/// solve the square system `m x = rhs` by Gaussian elimination with partial pivoting
+///
+/// `None` when the system is singular or nearly so: a pivot below 1e-12 of
+/// the largest entry its row started with is what rounding leaves of a row
+/// that depends on the others.
+pub(crate) fn solve(mut m:Vec<Vec<f64>>, mut rhs:Vec<f64>) -> Option<Vec<f64>> {
+    let n = rhs.len();
+    let mut scale:Vec<f64> = m.iter().map(|row| row.iter().map(|v| v.abs()).fold(0.0, f64::max)).collect();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| m[i][col].abs().total_cmp(&m[j][col].abs()))?;
+        if !m[pivot][col].is_finite() || m[pivot][col].abs() <= 1e-12 * scale[pivot] {
+            return None;
+        }
+        m.swap(col, pivot);
+        rhs.swap(col, pivot);
+        scale.swap(col, pivot);
+        let pivot_row = m[col].clone();
+        for row in col + 1..n {
+            let k = m[row][col] / pivot_row[col];
+            for (v, p) in m[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *v -= k * p;
+            }
+            rhs[row] -= k * rhs[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let s:f64 = (row + 1..n).map(|c| m[row][c] * x[c]).sum();
+        x[row] = (rhs[row] - s) / m[row][row];
+    }
+    Some(x)
+}
+
+/// solve the least-squares problem for the rows `a` and observations `obs` through the normal equations
+pub(crate) fn least_squares(a:&[Vec<f64>], obs:&[f64]) -> Option<Vec<f64>> {
+    let n = a[0].len();
+    let mut m = vec![vec![0.0; n]; n];
+    let mut rhs = vec![0.0; n];
+    for (row, &o) in a.iter().zip(obs) {
+        for i in 0..n {
+            rhs[i] += row[i] * o;
+            for j in 0..n {
+                m[i][j] += row[i] * row[j];
+            }
+        }
+    }
+    solve(m, rhs)
+}
+
+/// estimated Helmert parameters with residuals (observed target − transformed source)
+#[derive(Debug, Clone, PartialEq)]
+pub struct HelmertFit {
+    pub params:Helmert,
+    pub residuals:Vec<(f64, f64, f64)>,
+    /// root mean square of the 3D residual lengths in meters
+    pub rms:f64,
+    /// standard error of a 3D residual length, the rms corrected for the degrees of freedom
+    pub sigma:f64,
+}
+
+/// estimate Bursa-Wolf (position vector) parameters from at least 3 common geocentric points
+/// # Examples
+///
+/// ```
+/// use mercator::coord::Ecef;
+/// use mercator::fit::fit_helmert;
+/// use mercator::helmert::Helmert;
+/// let truth = Helmert::new(-752.0, -358.0, -179.0, -11.3, -0.4, 0.7, -0.3);
+/// let src:Vec<Ecef> = [(121.0, 22.0), (121.5, 25.0), (120.2, 23.5), (120.9, 24.2)].iter()
+///     .map(|&(lng, lat)| mercator::coord::LngLat::new(lng, lat).to_ecef()).collect();
+/// let dst:Vec<Ecef> = src.iter().map(|&p| truth.apply(p)).collect();
+/// let fit = fit_helmert(&src, &dst).unwrap();
+/// assert!((fit.params.tx - truth.tx).abs() < 1e-3 && (fit.params.s - truth.s).abs() < 1e-6);
+/// assert!(fit.rms < 1e-6);
+/// ```
+pub fn fit_helmert(src:&[Ecef], dst:&[Ecef]) -> Result<HelmertFit> {
+    if src.len() != dst.len() {
+        return Err(Error::InvalidInput(format!("{} source points but {} target points", src.len(), dst.len())));
+    }
+    if src.len() < 3 {
+        return Err(Error::InvalidInput(format!("need at least 3 common points, got {}", src.len())));
+    }
+    if let Some(i) = src.iter().zip(dst).position(|(p, q)| ![p.x, p.y, p.z, q.x, q.y, q.z].iter().all(|v| v.is_finite())) {
+        return Err(Error::InvalidInput(format!("common point {} is not finite", i)));
+    }
+    let n = src.len() as f64;
+    let c = src.iter().fold((0.0, 0.0, 0.0), |s, p| (s.0 + p.x / n, s.1 + p.y / n, s.2 + p.z / n));
+    // unknowns: translation at the centroid, scale and rotations (radians), with
+    // the centered coordinates in 100 km units to keep the normal equations balanced
+    let unit = 1e5;
+    let mut rows = Vec::with_capacity(3 * src.len());
+    let mut obs = Vec::with_capacity(3 * src.len());
+    for (p, q) in src.iter().zip(dst) {
+        let (x, y, z) = ((p.x - c.0) / unit, (p.y - c.1) / unit, (p.z - c.2) / unit);
+        rows.push(vec![1.0, 0.0, 0.0, x, 0.0, z, -y]);
+        rows.push(vec![0.0, 1.0, 0.0, y, -z, 0.0, x]);
+        rows.push(vec![0.0, 0.0, 1.0, z, y, -x, 0.0]);
+        obs.push(q.x - p.x);
+        obs.push(q.y - p.y);
+        obs.push(q.z - p.z);
+    }
+    let sol = least_squares(&rows, &obs)
+        .ok_or_else(|| Error::InvalidInput("common points are degenerate".to_string()))?;
+    let (d, rx, ry, rz) = (sol[3] / unit, sol[4] / unit, sol[5] / unit, sol[6] / unit);
+    let arcsec = ::std::f64::consts::PI / 648000.0;
+    let params = Helmert::new(
+        sol[0] - (d * c.0 - rz * c.1 + ry * c.2),
+        sol[1] - (rz * c.0 + d * c.1 - rx * c.2),
+        sol[2] - (-ry * c.0 + rx * c.1 + d * c.2),
+        d * 1e6, rx / arcsec, ry / arcsec, rz / arcsec);
+    let residuals:Vec<(f64, f64, f64)> = src.iter().zip(dst).map(|(&p, q)| {
+        let t = params.apply(p);
+        (q.x - t.x, q.y - t.y, q.z - t.z)
+    }).collect();
+    let ss:f64 = residuals.iter().map(|r| r.0 * r.0 + r.1 * r.1 + r.2 * r.2).sum();
+    let (rms, sigma) = spread(ss, src.len(), 3, 7);
+    Ok(HelmertFit { params, rms, sigma, residuals })
+}
+
+impl HelmertFit {
+    /// the 3D length of every residual, in the order of the points
+    pub fn residual_lengths(&self) -> Vec<f64> {
+        self.residuals.iter().map(|r| (r.0 * r.0 + r.1 * r.1 + r.2 * r.2).sqrt()).collect()
+    }
+
+    /// the points whose residual is longer than `k` sigma, worst first, see [`Fit::outliers`]
+    pub fn outliers(&self, k:f64) -> Vec<usize> {
+        flag(self.residual_lengths(), self.sigma, k)
+    }
+}
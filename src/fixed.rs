@@ -0,0 +1,92 @@
+//! fixed-point integer coordinates
+//!
+//! databases and wire protocols often store coordinates as scaled integers,
+//! e.g. millimeters in an `i64` or 1e-7 degrees in an `i32`. conversion rounds
+//! half to even and fails instead of wrapping on overflow.
+
+use error::{Error, Result};
+
+/// a scale (integer steps per unit) and an offset subtracted before scaling
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedPoint {
+    pub scale:f64,
+    pub offset:(f64, f64),
+}
+
+impl FixedPoint {
+    pub fn new(scale:f64) -> FixedPoint {
+        FixedPoint { scale, offset: (0.0, 0.0) }
+    }
+
+    /// millimeters from meters
+    pub fn millimeters() -> FixedPoint {
+        FixedPoint::new(1000.0)
+    }
+
+    /// 1e-7 degrees, the resolution that fits lng/lat in an `i32`
+    pub fn degrees_e7() -> FixedPoint {
+        FixedPoint::new(1e7)
+    }
+
+    /// subtract `(x, y)` before scaling, to keep large projected values in range
+    pub fn with_offset(mut self, x:f64, y:f64) -> FixedPoint {
+        self.offset = (x, y);
+        self
+    }
+
+    fn scaled(&self, v:f64, offset:f64, min:f64, max:f64) -> Result<f64> {
+        let s = ((v - offset) * self.scale).round_ties_even();
+        // max is 2^63 or 2^31 exactly, one past the largest integer
+        if !s.is_finite() || s < min || s >= max {
+            return Err(Error::InvalidInput(format!("{} does not fit the fixed-point range at scale {}", v, self.scale)));
+        }
+        Ok(s)
+    }
+
+    /// a coordinate as scaled `i64`s
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::fixed::FixedPoint;
+    /// let mm = FixedPoint::millimeters();
+    /// assert_eq!(mm.to_i64(302000.1234, 2770000.0005).unwrap(), (302000123, 2770000000));
+    /// assert_eq!(mm.from_i64(302000123, 2770000000), (302000.123, 2770000.0));
+    /// assert!(mm.to_i64(1e17, 0.0).is_err());
+    /// assert!(mm.to_i64(f64::NAN, 0.0).is_err());
+    /// ```
+    pub fn to_i64(&self, x:f64, y:f64) -> Result<(i64, i64)> {
+        let (min, max) = (i64::MIN as f64, -(i64::MIN as f64));
+        Ok((self.scaled(x, self.offset.0, min, max)? as i64, self.scaled(y, self.offset.1, min, max)? as i64))
+    }
+
+    /// a coordinate as scaled `i32`s
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::fixed::FixedPoint;
+    /// let e7 = FixedPoint::degrees_e7();
+    /// assert_eq!(e7.to_i32(121.5654321, 25.0330).unwrap(), (1215654321, 250330000));
+    /// assert!(e7.to_i32(215.0, 0.0).is_err());
+    /// ```
+    pub fn to_i32(&self, x:f64, y:f64) -> Result<(i32, i32)> {
+        let (min, max) = (i32::MIN as f64, -(i32::MIN as f64));
+        Ok((self.scaled(x, self.offset.0, min, max)? as i32, self.scaled(y, self.offset.1, min, max)? as i32))
+    }
+
+    pub fn from_i64(&self, x:i64, y:i64) -> (f64, f64) {
+        (x as f64 / self.scale + self.offset.0, y as f64 / self.scale + self.offset.1)
+    }
+
+    pub fn from_i32(&self, x:i32, y:i32) -> (f64, f64) {
+        self.from_i64(x as i64, y as i64)
+    }
+
+    /// convert a batch, failing on the first value out of range
+    pub fn points_to_i64(&self, points:&[(f64, f64)]) -> Result<Vec<(i64, i64)>> {
+        points.iter().map(|&(x, y)| self.to_i64(x, y)).collect()
+    }
+
+    pub fn points_to_i32(&self, points:&[(f64, f64)]) -> Result<Vec<(i32, i32)>> {
+        points.iter().map(|&(x, y)| self.to_i32(x, y)).collect()
+    }
+}
@@ -0,0 +1,109 @@
+//! ITRF and ETRF reference frame realizations
+//!
+//! GNSS processing delivers coordinates in a realization of ITRF at the
+//! epoch of observation, national grids sit on a realization frozen to a
+//! plate, like ETRF2000 for ETRS89 in Europe. the frames here are linked by
+//! the 14-parameter transformations published by the IERS (ITRF2020 to past
+//! ITRFs) and EUREF (ITRF to ETRF, Technical Note 1), chained through
+//! ITRF2020; mixing frames unnoticed costs centimeters, mixing epochs
+//! across a plate decimeters.
+//! - https://itrf.ign.fr/en/solutions/transformations
+//! - https://epncb.oma.be/_productsservices/coord_trans/
+
+use coord::{Dated, Ecef};
+use error::Result;
+use helmert::{Helmert, TimeDependentHelmert};
+
+/// a terrestrial reference frame realization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Frame {
+    Itrf2020,
+    Itrf2014,
+    Itrf2008,
+    Itrf2005,
+    Itrf2000,
+    /// the realization of ETRS89 EUREF recommends
+    Etrf2000,
+    Etrf2014,
+    Etrf2020,
+}
+
+/// parameters in mm, ppb and mas and their yearly rates, converted to the units of [`Helmert`]
+fn published(p:[f64; 7], r:[f64; 7], epoch:f64) -> TimeDependentHelmert {
+    let h = |v:[f64; 7]| Helmert::new(v[0], v[1], v[2], v[3], v[4], v[5], v[6]) * 1e-3;
+    TimeDependentHelmert::new(h(p), h(r), epoch)
+}
+
+impl Frame {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Frame::Itrf2020 => "ITRF2020",
+            Frame::Itrf2014 => "ITRF2014",
+            Frame::Itrf2008 => "ITRF2008",
+            Frame::Itrf2005 => "ITRF2005",
+            Frame::Itrf2000 => "ITRF2000",
+            Frame::Etrf2000 => "ETRF2000",
+            Frame::Etrf2014 => "ETRF2014",
+            Frame::Etrf2020 => "ETRF2020",
+        }
+    }
+
+    /// the transformation from ITRF2020 to this frame
+    fn itrf2020_to(&self) -> TimeDependentHelmert {
+        let zero = [0.0; 7];
+        match *self {
+            Frame::Itrf2020 => published(zero, zero, 2015.0),
+            Frame::Itrf2014 => published([-1.4, -0.9, 1.4, -0.42, 0.0, 0.0, 0.0], [0.0, -0.1, 0.2, 0.0, 0.0, 0.0, 0.0], 2015.0),
+            Frame::Itrf2008 => published([0.2, 1.0, 3.3, -0.29, 0.0, 0.0, 0.0], [0.0, -0.1, 0.1, 0.03, 0.0, 0.0, 0.0], 2015.0),
+            Frame::Itrf2005 => published([2.7, 0.1, -1.4, 0.65, 0.0, 0.0, 0.0], [0.3, -0.1, 0.1, 0.03, 0.0, 0.0, 0.0], 2015.0),
+            Frame::Itrf2000 => published([-0.2, 0.8, -34.2, 2.25, 0.0, 0.0, 0.0], [0.1, 0.0, -1.7, 0.11, 0.0, 0.0, 0.0], 2015.0),
+            Frame::Etrf2000 => Frame::Itrf2014.itrf2020_to().then(&published(
+                [54.7, 52.2, -74.1, 2.12, 1.701, 10.290, -16.632],
+                [0.1, 0.1, -1.9, 0.11, 0.081, 0.490, -0.792], 2010.0)),
+            Frame::Etrf2014 => Frame::Itrf2014.itrf2020_to().then(&published(
+                zero, [0.0, 0.0, 0.0, 0.0, 0.085, 0.531, -0.770], 1989.0)),
+            Frame::Etrf2020 => published(zero, [0.0, 0.0, 0.0, 0.0, 0.086, 0.519, -0.753], 1989.0),
+        }
+    }
+
+    /// the transformation from this frame to `to`, through ITRF2020
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::frame::Frame;
+    /// use mercator::helmert::TimeDependentHelmert;
+    /// // the IERS chain reproduces the published ITRF2014 to ITRF2008 parameters
+    /// let t = Frame::Itrf2014.to(Frame::Itrf2008);
+    /// let published = TimeDependentHelmert::itrf2014_to_itrf2008();
+    /// let (a, b) = (t.at_epoch(2010.0), published.at_epoch(2010.0));
+    /// assert!((a.tz - b.tz).abs() < 1e-9 && (a.s - b.s).abs() < 1e-9);
+    /// ```
+    pub fn to(&self, to:Frame) -> TimeDependentHelmert {
+        self.itrf2020_to().inverse().then(&to.itrf2020_to())
+    }
+
+    /// move a geocentric coordinate observed at epoch `t` (decimal years) from this frame to `to`
+    ///
+    /// the epoch stays `t`; propagate with a [`PlateModel`](::plate::PlateModel) to change it.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::coord::Ecef;
+    /// use mercator::frame::Frame;
+    /// // Zimmerwald observed in ITRF2014 in 2020: ETRF2000 has drifted some 80 cm off with the plate since 1989
+    /// let p = Ecef::new(4331297.0, 567555.0, 4633133.0);
+    /// let q = Frame::Itrf2014.transform(Frame::Etrf2000, p, 2020.0);
+    /// let moved = ((q.x - p.x).powi(2) + (q.y - p.y).powi(2) + (q.z - p.z).powi(2)).sqrt();
+    /// assert!(moved > 0.75 && moved < 0.85);
+    /// let back = Frame::Etrf2000.transform(Frame::Itrf2014, q, 2020.0);
+    /// assert!((back.x - p.x).abs() < 1e-4 && (back.z - p.z).abs() < 1e-4);
+    /// ```
+    pub fn transform(&self, to:Frame, p:Ecef, t:f64) -> Ecef {
+        self.to(to).apply(p, t)
+    }
+
+    /// move a dated geocentric coordinate from this frame to `to`, see [`TimeDependentHelmert::apply_dated`]
+    pub fn transform_dated(&self, to:Frame, p:Dated<Ecef>) -> Result<Dated<Ecef>> {
+        self.to(to).apply_dated(p)
+    }
+}
@@ -0,0 +1,127 @@
+//! GDAL / OGR interop: spatial references and geometry reprojection
+//!
+//! CRSs travel to GDAL as WKT2 and come back through their EPSG code or
+//! their PROJ.4 form, see [`Crs::from_proj4`]. geometries are reprojected in
+//! place with the math of this crate instead of OGR's own transforms, so a
+//! GDAL based pipeline gives the same coordinates as everything else here.
+//! - https://gdal.org/api/ogr_geometry.html
+
+use std::cell::Cell;
+use std::fmt::Display;
+
+use gdal::spatial_ref::{AxisMappingStrategy, SpatialRef};
+use gdal::vector::{geometry_type_has_m, geometry_type_has_z, Geometry};
+
+use crs::{Crs, Transformer};
+use error::{Error, Result};
+
+fn gdal_err<E:Display>(e:E) -> Error {
+    Error::Format(format!("gdal: {}", e))
+}
+
+impl Crs {
+    /// the GDAL spatial reference of this CRS, in lng/lat (traditional GIS) axis order
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mercator::crs::Crs;
+    /// use mercator::zone::Zone;
+    /// let srs = Crs::projected(Zone::utm(51)).to_spatial_ref().unwrap();
+    /// assert!(srs.is_projected());
+    /// assert_eq!(Crs::from_spatial_ref(&srs).unwrap(), Crs::projected(Zone::utm(51)));
+    /// ```
+    pub fn to_spatial_ref(&self) -> Result<SpatialRef> {
+        let mut srs = SpatialRef::from_wkt(&self.to_wkt()?).map_err(gdal_err)?;
+        srs.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+        Ok(srs)
+    }
+
+    /// read a GDAL spatial reference back
+    ///
+    /// an EPSG code known to [`Crs::from_epsg`] wins; otherwise the PROJ.4 form
+    /// of the spatial reference is parsed.
+    pub fn from_spatial_ref(srs:&SpatialRef) -> Result<Crs> {
+        if srs.auth_name().as_deref() == Some("EPSG") {
+            if let Ok(crs) = srs.auth_code().map_err(gdal_err).and_then(|code| Crs::from_epsg(code as u32)) {
+                return Ok(crs);
+            }
+        }
+        Crs::from_proj4(&srs.to_proj4().map_err(gdal_err)?)
+    }
+}
+
+/// apply `f` to every vertex of a geometry and its parts, keeping z and m values
+fn reproject_with<F>(geom:&mut Geometry, f:&F) where F: Fn(f64, f64) -> (f64, f64) {
+    let ty = geom.geometry_type();
+    for i in 0..geom.geometry_count() {
+        reproject_with(&mut geom.get_geometry(i), f);
+    }
+    for i in 0..geom.point_count() {
+        if geometry_type_has_m(ty) {
+            let (x, y, z, m) = geom.get_point_zm(i as i32);
+            let (x, y) = f(x, y);
+            geom.set_point_zm(i, (x, y, z, m));
+        } else if geometry_type_has_z(ty) {
+            let (x, y, z) = geom.get_point(i as i32);
+            let (x, y) = f(x, y);
+            geom.set_point(i, (x, y, z));
+        } else {
+            let (x, y, _) = geom.get_point(i as i32);
+            geom.set_point_2d(i, f(x, y));
+        }
+    }
+}
+
+/// reproject an OGR geometry in place with any `(x, y)` function
+/// # Examples
+///
+/// ```no_run
+/// # extern crate gdal;
+/// # extern crate mercator;
+/// use gdal::vector::Geometry;
+/// use mercator::gdal_interop::reproject_geometry_with;
+/// let mut line = Geometry::from_wkt("LINESTRING (121 24, 121.5 24.5)").unwrap();
+/// reproject_geometry_with(&mut line, mercator::wgs84_to_twd97);
+/// assert!((line.get_point(0).0 - 250000.0).abs() < 1e-6);
+/// ```
+pub fn reproject_geometry_with<F>(geom:&mut Geometry, f:F) where F: Fn(f64, f64) -> (f64, f64) {
+    reproject_with(geom, &f)
+}
+
+/// reproject an OGR geometry in place and tag it with the target spatial reference
+///
+/// the transformer's source is expected in the axis order and units of the
+/// geometry. a point the transformer rejects, e.g. outside the area of use
+/// with an error bounds check, fails the call and leaves the geometry as it was.
+/// # Examples
+///
+/// ```no_run
+/// # extern crate gdal;
+/// # extern crate mercator;
+/// use gdal::vector::Geometry;
+/// use mercator::crs::Transformer;
+/// use mercator::gdal_interop::reproject_geometry;
+/// let t = Transformer::from_epsg(4326, 3826).unwrap();
+/// let mut poly = Geometry::from_wkt("POLYGON ((121 24, 121.1 24, 121.1 24.1, 121 24))").unwrap();
+/// reproject_geometry(&mut poly, &t).unwrap();
+/// assert!(poly.spatial_ref().unwrap().is_projected());
+/// ```
+pub fn reproject_geometry(geom:&mut Geometry, transformer:&Transformer) -> Result<()> {
+    let original = geom.clone();
+    let failed = Cell::new(None);
+    reproject_with(geom, &|x, y| match transformer.try_transform(x, y) {
+        Ok(p) => p,
+        Err(e) => {
+            failed.set(Some(e.to_string()));
+            (f64::NAN, f64::NAN)
+        }
+    });
+    if let Some(msg) = failed.into_inner() {
+        *geom = original;
+        return Err(Error::Transform(msg));
+    }
+    if let Ok(srs) = transformer.target.to_spatial_ref() {
+        geom.set_spatial_ref(srs);
+    }
+    Ok(())
+}
@@ -0,0 +1,1135 @@
+//! Karney's solutions of the geodesic problem
+//!
+//! the inverse always converges, nearly antipodal points included, and both
+//! directions are accurate to a few nanometers on the earth. prefer these over
+//! [`vincenty`](::vincenty) for distances and bearings on the ellipsoid. the
+//! series are of sixth order in the third flattening, as in GeographicLib.
+//! - C. F. F. Karney, Algorithms for geodesics, J. Geodesy 87 (2013)
+//! - https://geographiclib.sourceforge.io/
+
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+use ellipsoid::{Ellipsoid, WGS84};
+use geometry::LineString;
+use heading::normalize_heading;
+
+pub use vincenty::{Direct, Inverse};
+
+const ORDER:usize = 6;
+const NEWTON_ITERATIONS:usize = 20;
+const MAX_ITERATIONS:usize = NEWTON_ITERATIONS + 53 + 10;
+const TOL0:f64 = f64::EPSILON;
+const TOL1:f64 = 200.0 * TOL0;
+/// √ε, 2⁻²⁶
+const TOL2:f64 = 1.4901161193847656e-8;
+const TOLB:f64 = TOL0 * TOL2;
+const XTHRESH:f64 = 1000.0 * TOL2;
+/// √ of the smallest normal f64, 2⁻⁵¹¹
+const TINY:f64 = 1.4916681462400413e-154;
+
+const COEFF_A3:[f64; 18] = [
+    -3.0, 128.0, -2.0, -3.0, 64.0, -1.0, -3.0, -1.0, 16.0, 3.0, -1.0, -2.0, 8.0, 1.0, -1.0, 2.0,
+    1.0, 1.0,
+];
+const COEFF_C3:[f64; 45] = [
+    3.0, 128.0, 2.0, 5.0, 128.0, -1.0, 3.0, 3.0, 64.0, -1.0, 0.0, 1.0, 8.0, -1.0, 1.0, 4.0, 5.0,
+    256.0, 1.0, 3.0, 128.0, -3.0, -2.0, 3.0, 64.0, 1.0, -3.0, 2.0, 32.0, 7.0, 512.0, -10.0, 9.0,
+    384.0, 5.0, -9.0, 5.0, 192.0, 7.0, 512.0, -14.0, 7.0, 512.0, 21.0, 2560.0,
+];
+const COEFF_C4:[f64; 77] = [
+    97.0, 15015.0, 1088.0, 156.0, 45045.0, -224.0, -4784.0, 1573.0, 45045.0, -10656.0, 14144.0,
+    -4576.0, -858.0, 45045.0, 64.0, 624.0, -4576.0, 6864.0, -3003.0, 15015.0, 100.0, 208.0, 572.0,
+    3432.0, -12012.0, 30030.0, 45045.0, 1.0, 9009.0, -2944.0, 468.0, 135135.0, 5792.0, 1040.0,
+    -1287.0, 135135.0, 5952.0, -11648.0, 9152.0, -2574.0, 135135.0, -64.0, -624.0, 4576.0, -6864.0,
+    3003.0, 135135.0, 8.0, 10725.0, 1856.0, -936.0, 225225.0, -8448.0, 4992.0, -1144.0, 225225.0,
+    -1440.0, 4160.0, -4576.0, 1716.0, 225225.0, -136.0, 63063.0, 1024.0, -208.0, 105105.0, 3584.0,
+    -3328.0, 1144.0, 315315.0, -128.0, 135135.0, -2560.0, 832.0, 405405.0, 128.0, 99099.0,
+];
+const COEFF_A1:[f64; 5] = [1.0, 4.0, 64.0, 0.0, 256.0];
+const COEFF_C1:[f64; 18] = [
+    -1.0, 6.0, -16.0, 32.0, -9.0, 64.0, -128.0, 2048.0, 9.0, -16.0, 768.0, 3.0, -5.0, 512.0,
+    -7.0, 1280.0, -7.0, 2048.0,
+];
+const COEFF_C1P:[f64; 18] = [
+    205.0, -432.0, 768.0, 1536.0, 4005.0, -4736.0, 3840.0, 12288.0, -225.0, 116.0, 384.0,
+    -7173.0, 2695.0, 7680.0, 3467.0, 7680.0, 38081.0, 61440.0,
+];
+const COEFF_A2:[f64; 5] = [-11.0, -28.0, -192.0, 0.0, 256.0];
+const COEFF_C2:[f64; 18] = [
+    1.0, 2.0, 16.0, 32.0, 35.0, 64.0, 384.0, 2048.0, 15.0, 80.0, 768.0, 7.0, 35.0, 512.0, 63.0,
+    1280.0, 77.0, 2048.0,
+];
+
+fn sq(x:f64) -> f64 {
+    x * x
+}
+
+/// scale `(y, x)` to a unit vector
+fn norm(y:f64, x:f64) -> (f64, f64) {
+    let r = y.hypot(x);
+    (y / r, x / r)
+}
+
+/// the sum and its rounding error
+fn sum(u:f64, v:f64) -> (f64, f64) {
+    let s = u + v;
+    let up = s - v;
+    let vpp = s - up;
+    (s, -((up - u) + (vpp - v)))
+}
+
+fn polyval(p:&[f64], x:f64) -> f64 {
+    p.iter().fold(0.0, |y, c| y * x + c)
+}
+
+/// round tiny angles so values below about 1e-57 degrees become 0
+fn ang_round(x:f64) -> f64 {
+    let z = 1.0 / 16.0;
+    let y = x.abs();
+    let y = if y < z { z - (z - y) } else { y };
+    if x == 0.0 { 0.0 } else { y.copysign(x) }
+}
+
+/// reduce an angle in degrees to (-180, 180]
+fn ang_normalize(x:f64) -> f64 {
+    let y = x % 360.0;
+    let y = if y <= -180.0 { y + 360.0 } else if y > 180.0 { y - 360.0 } else { y };
+    if x == 0.0 { x } else { y }
+}
+
+/// `y - x` reduced to [-180, 180] exactly, with its rounding error
+fn ang_diff(x:f64, y:f64) -> (f64, f64) {
+    let (d, t) = sum(ang_normalize(-x), ang_normalize(y));
+    let d = ang_normalize(d);
+    if d == 180.0 && t > 0.0 { sum(-180.0, t) } else { sum(d, t) }
+}
+
+/// sine and cosine of an angle in degrees, exact at multiples of 90
+fn sincosd(x:f64) -> (f64, f64) {
+    let r = x % 360.0;
+    let q = (r / 90.0).round();
+    let (s, c) = (r - 90.0 * q).to_radians().sin_cos();
+    let (s, c) = match (q as i64).rem_euclid(4) {
+        0 => (s, c),
+        1 => (c, -s),
+        2 => (-s, -c),
+        _ => (-c, s),
+    };
+    (if s == 0.0 { s.copysign(x) } else { s }, c + 0.0)
+}
+
+/// atan2 in degrees, exact at multiples of 90
+fn atan2d(y:f64, x:f64) -> f64 {
+    let (mut y, mut x, mut q) = (y, x, 0);
+    if y.abs() > x.abs() {
+        ::std::mem::swap(&mut x, &mut y);
+        q = 2;
+    }
+    if x < 0.0 {
+        q += 1;
+        x = -x;
+    }
+    let ang = y.atan2(x).to_degrees();
+    match q {
+        1 => if y >= 0.0 { 180.0 - ang } else { -180.0 - ang },
+        2 => 90.0 - ang,
+        3 => -90.0 + ang,
+        _ => ang,
+    }
+}
+
+/// Clenshaw sum of `c[1] sin 2σ + c[2] sin 4σ ...`, or of the cosines
+/// `c[0] cos σ + c[1] cos 3σ ...` when `sine` is false
+fn sin_cos_series(sine:bool, sinx:f64, cosx:f64, c:&[f64]) -> f64 {
+    let mut k = c.len();
+    let mut n = k - if sine { 1 } else { 0 };
+    let ar = 2.0 * (cosx - sinx) * (cosx + sinx);
+    let mut y1 = 0.0;
+    let mut y0 = if n & 1 != 0 {
+        k -= 1;
+        c[k]
+    } else {
+        0.0
+    };
+    n /= 2;
+    while n > 0 {
+        n -= 1;
+        k -= 1;
+        y1 = ar * y0 - y1 + c[k];
+        k -= 1;
+        y0 = ar * y1 - y0 + c[k];
+    }
+    if sine { 2.0 * sinx * cosx * y0 } else { cosx * (y0 - y1) }
+}
+
+/// the positive root of the astroid equation, used for the starting guess of nearly antipodal points
+fn astroid(x:f64, y:f64) -> f64 {
+    let p = sq(x);
+    let q = sq(y);
+    let r = (p + q - 1.0) / 6.0;
+    if q == 0.0 && r <= 0.0 {
+        return 0.0;
+    }
+    let s = p * q / 4.0;
+    let r2 = sq(r);
+    let r3 = r * r2;
+    let disc = s * (s + 2.0 * r3);
+    let mut u = r;
+    if disc >= 0.0 {
+        let mut t3 = s + r3;
+        t3 += if t3 < 0.0 { -disc.sqrt() } else { disc.sqrt() };
+        let t = t3.cbrt();
+        u += t + if t != 0.0 { r2 / t } else { 0.0 };
+    } else {
+        let ang = (-disc).sqrt().atan2(-(s + r3));
+        u += 2.0 * r * (ang / 3.0).cos();
+    }
+    let v = (sq(u) + q).sqrt();
+    let uv = if u < 0.0 { q / (v - u) } else { u + v };
+    let w = (uv - q) / (2.0 * v);
+    uv / ((uv + sq(w)).sqrt() + w)
+}
+
+/// the coefficients `c[1..=6]` of a series in ε from its packed polynomial table
+fn series_coefficients(table:&[f64], eps:f64) -> [f64; ORDER + 1] {
+    let eps2 = sq(eps);
+    let mut c = [0.0; ORDER + 1];
+    let mut d = eps;
+    let mut o = 0;
+    for (l, cl) in c.iter_mut().enumerate().skip(1) {
+        let m = (ORDER - l) / 2;
+        *cl = d * polyval(&table[o..o + m + 1], eps2) / table[o + m + 1];
+        o += m + 2;
+        d *= eps;
+    }
+    c
+}
+
+fn a1m1(eps:f64) -> f64 {
+    let m = ORDER / 2;
+    let t = polyval(&COEFF_A1[..m + 1], sq(eps)) / COEFF_A1[m + 1];
+    (t + eps) / (1.0 - eps)
+}
+
+fn a2m1(eps:f64) -> f64 {
+    let m = ORDER / 2;
+    let t = polyval(&COEFF_A2[..m + 1], sq(eps)) / COEFF_A2[m + 1];
+    (t - eps) / (1.0 + eps)
+}
+
+/// ε of the auxiliary sphere for a geodesic with `k² = e'² cos² α₀`
+fn epsilon(k2:f64) -> f64 {
+    k2 / (2.0 * (1.0 + (1.0 + k2).sqrt()) + k2)
+}
+
+/// reduced latitude β of a point, with `√(1 + e'² sin² β)`
+#[derive(Debug, Clone, Copy)]
+struct Reduced {
+    sbet:f64,
+    cbet:f64,
+    dn:f64,
+}
+
+/// the state of one step of the inverse iteration
+struct Lambda {
+    lam12:f64,
+    salp2:f64,
+    calp2:f64,
+    sig12:f64,
+    sig1:(f64, f64),
+    sig2:(f64, f64),
+    eps:f64,
+    domg12:f64,
+    dlam12:f64,
+}
+
+/// the full inverse solution of [`Geodesic::solve_inverse`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Solution {
+    /// arc length on the auxiliary sphere in degrees
+    pub(crate) a12:f64,
+    pub(crate) s12:f64,
+    pub(crate) azi1:f64,
+    pub(crate) azi2:f64,
+    /// reduced length in meters
+    pub(crate) m12:f64,
+    /// area between the geodesic and the equator in m², when asked for
+    pub(crate) area:f64,
+}
+
+/// an ellipsoid prepared for geodesic calculations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geodesic {
+    a:f64,
+    f:f64,
+    f1:f64,
+    e2:f64,
+    ep2:f64,
+    n:f64,
+    b:f64,
+    c2:f64,
+    etol2:f64,
+    a3x:[f64; ORDER],
+    c3x:[f64; 15],
+    c4x:[f64; 21],
+}
+
+impl Geodesic {
+    /// the geodesic coefficients of an ellipsoid
+    pub fn new(ellipsoid:&Ellipsoid) -> Geodesic {
+        let a = ellipsoid.a;
+        let rf = ellipsoid.inverse_flattening();
+        let f = if rf == 0.0 { 0.0 } else { 1.0 / rf };
+        let f1 = 1.0 - f;
+        let e2 = f * (2.0 - f);
+        let ep2 = e2 / sq(f1);
+        let n = f / (2.0 - f);
+        let b = a * f1;
+        // authalic radius squared
+        let c2 = (sq(a) + sq(b) * if e2 == 0.0 {
+            1.0
+        } else if e2 > 0.0 {
+            e2.sqrt().atanh() / e2.sqrt()
+        } else {
+            (-e2).sqrt().atan() / (-e2).sqrt()
+        }) / 2.0;
+        let etol2 = 0.1 * TOL2 / (f.abs().max(0.001) * (1.0 - f / 2.0).min(1.0) / 2.0).sqrt();
+
+        let mut a3x = [0.0; ORDER];
+        let mut o = 0;
+        for (k, j) in (0..ORDER).rev().enumerate() {
+            let m = j.min(ORDER - j - 1);
+            a3x[k] = polyval(&COEFF_A3[o..o + m + 1], n) / COEFF_A3[o + m + 1];
+            o += m + 2;
+        }
+        let mut c3x = [0.0; 15];
+        let (mut o, mut k) = (0, 0);
+        for l in 1..ORDER {
+            for j in (l..ORDER).rev() {
+                let m = j.min(ORDER - j - 1);
+                c3x[k] = polyval(&COEFF_C3[o..o + m + 1], n) / COEFF_C3[o + m + 1];
+                k += 1;
+                o += m + 2;
+            }
+        }
+        let mut c4x = [0.0; 21];
+        let (mut o, mut k) = (0, 0);
+        for l in 0..ORDER {
+            for j in (l..ORDER).rev() {
+                let m = ORDER - j - 1;
+                c4x[k] = polyval(&COEFF_C4[o..o + m + 1], n) / COEFF_C4[o + m + 1];
+                k += 1;
+                o += m + 2;
+            }
+        }
+        Geodesic { a, f, f1, e2, ep2, n, b, c2, etol2, a3x, c3x, c4x }
+    }
+
+    /// geodesics on WGS84
+    pub fn wgs84() -> Geodesic {
+        Geodesic::new(&WGS84)
+    }
+
+    /// the total area of the ellipsoid in m²
+    pub fn ellipsoid_area(&self) -> f64 {
+        4.0 * PI * self.c2
+    }
+
+    fn a3f(&self, eps:f64) -> f64 {
+        polyval(&self.a3x, eps)
+    }
+
+    fn c3f(&self, eps:f64) -> [f64; ORDER] {
+        let mut c = [0.0; ORDER];
+        let mut mult = 1.0;
+        let mut o = 0;
+        for (l, cl) in c.iter_mut().enumerate().skip(1) {
+            let m = ORDER - l - 1;
+            mult *= eps;
+            *cl = mult * polyval(&self.c3x[o..o + m + 1], eps);
+            o += m + 1;
+        }
+        c
+    }
+
+    fn c4f(&self, eps:f64) -> [f64; ORDER] {
+        let mut c = [0.0; ORDER];
+        let mut mult = 1.0;
+        let mut o = 0;
+        for (l, cl) in c.iter_mut().enumerate() {
+            let m = ORDER - l - 1;
+            *cl = mult * polyval(&self.c4x[o..o + m + 1], eps);
+            o += m + 1;
+            mult *= eps;
+        }
+        c
+    }
+
+    fn reduced(&self, lat:f64) -> Reduced {
+        let (sbet, cbet) = sincosd(lat);
+        let (sbet, cbet) = norm(sbet * self.f1, cbet);
+        let cbet = cbet.max(TINY);
+        Reduced { sbet, cbet, dn: (1.0 + self.ep2 * sq(sbet)).sqrt() }
+    }
+
+    /// distance and reduced length on the unit sphere, with the `m0` term, between two σ
+    fn lengths(&self, eps:f64, sig12:f64, sig1:(f64, f64), sig2:(f64, f64), p1:&Reduced, p2:&Reduced) -> (f64, f64, f64) {
+        let (ssig1, csig1) = sig1;
+        let (ssig2, csig2) = sig2;
+        let c1a = series_coefficients(&COEFF_C1, eps);
+        let c2a = series_coefficients(&COEFF_C2, eps);
+        let a1 = a1m1(eps);
+        let a2 = a2m1(eps);
+        let m0 = a1 - a2;
+        let (a1, a2) = (a1 + 1.0, a2 + 1.0);
+        let b1 = sin_cos_series(true, ssig2, csig2, &c1a) - sin_cos_series(true, ssig1, csig1, &c1a);
+        let b2 = sin_cos_series(true, ssig2, csig2, &c2a) - sin_cos_series(true, ssig1, csig1, &c2a);
+        let j12 = m0 * sig12 + (a1 * b1 - a2 * b2);
+        let s12b = a1 * (sig12 + b1);
+        let m12b = p2.dn * (csig1 * ssig2) - p1.dn * (ssig1 * csig2) - csig1 * csig2 * j12;
+        (s12b, m12b, m0)
+    }
+
+    /// first guess of the azimuth at the first point, or the whole solution
+    /// for short lines: `(σ12 or -1, sin α1, cos α1, sin α2, cos α2, dnm)`
+    fn inverse_start(&self, p1:&Reduced, p2:&Reduced, lam12:f64, slam12:f64, clam12:f64) -> (f64, f64, f64, f64, f64, f64) {
+        let (sbet1, cbet1) = (p1.sbet, p1.cbet);
+        let (sbet2, cbet2) = (p2.sbet, p2.cbet);
+        let mut sig12 = -1.0;
+        let (mut salp2, mut calp2, mut dnm) = (f64::NAN, f64::NAN, f64::NAN);
+        let sbet12 = sbet2 * cbet1 - cbet2 * sbet1;
+        let cbet12 = cbet2 * cbet1 + sbet2 * sbet1;
+        let sbet12a = sbet2 * cbet1 + cbet2 * sbet1;
+        let shortline = cbet12 >= 0.0 && sbet12 < 0.5 && cbet2 * lam12 < 0.5;
+        let (mut somg12, mut comg12) = if shortline {
+            let sbetm2 = sq(sbet1 + sbet2);
+            let sbetm2 = sbetm2 / (sbetm2 + sq(cbet1 + cbet2));
+            dnm = (1.0 + self.ep2 * sbetm2).sqrt();
+            (lam12 / (self.f1 * dnm)).sin_cos()
+        } else {
+            (slam12, clam12)
+        };
+        let mut salp1 = cbet2 * somg12;
+        let mut calp1 = if comg12 >= 0.0 {
+            sbet12 + cbet2 * sbet1 * sq(somg12) / (1.0 + comg12)
+        } else {
+            sbet12a - cbet2 * sbet1 * sq(somg12) / (1.0 - comg12)
+        };
+        let ssig12 = salp1.hypot(calp1);
+        let csig12 = sbet1 * sbet2 + cbet1 * cbet2 * comg12;
+
+        if shortline && ssig12 < self.etol2 {
+            salp2 = cbet1 * somg12;
+            calp2 = sbet12 - cbet1 * sbet2 * if comg12 >= 0.0 { sq(somg12) / (1.0 + comg12) } else { 1.0 - comg12 };
+            let (s, c) = norm(salp2, calp2);
+            salp2 = s;
+            calp2 = c;
+            sig12 = ssig12.atan2(csig12);
+        } else if self.n.abs() > 0.1 || csig12 >= 0.0 || ssig12 >= 6.0 * self.n.abs() * PI * sq(cbet1) {
+            // nothing to do, the zeroth order spherical approximation is good enough
+        } else {
+            // nearly antipodal points
+            let lam12x = (-slam12).atan2(-clam12);
+            let (x, y, lamscale);
+            if self.f >= 0.0 {
+                let eps = epsilon(sq(sbet1) * self.ep2);
+                lamscale = self.f * cbet1 * self.a3f(eps) * PI;
+                let betscale = lamscale * cbet1;
+                x = lam12x / lamscale;
+                y = sbet12a / betscale;
+            } else {
+                let cbet12a = cbet2 * cbet1 - sbet2 * sbet1;
+                let bet12a = sbet12a.atan2(cbet12a);
+                let (_, m12b, m0) = self.lengths(self.n, PI + bet12a, (sbet1, -cbet1), (sbet2, cbet2), p1, p2);
+                x = -1.0 + m12b / (cbet1 * cbet2 * m0 * PI);
+                let betscale = if x < -0.01 { sbet12a / x } else { -self.f * sq(cbet1) * PI };
+                lamscale = betscale / cbet1;
+                y = lam12x / lamscale;
+            }
+            if y > -TOL1 && x > -1.0 - XTHRESH {
+                if self.f >= 0.0 {
+                    salp1 = (-x).min(1.0);
+                    calp1 = -(1.0 - sq(salp1)).sqrt();
+                } else {
+                    calp1 = x.max(if x > -TOL1 { 0.0 } else { -1.0 });
+                    salp1 = (1.0 - sq(calp1)).sqrt();
+                }
+            } else {
+                let k = astroid(x, y);
+                let omg12a = lamscale * if self.f >= 0.0 { -x * k / (1.0 + k) } else { -y * (1.0 + k) / k };
+                let (s, c) = omg12a.sin_cos();
+                somg12 = s;
+                comg12 = -c;
+                salp1 = cbet2 * somg12;
+                calp1 = sbet12a - cbet2 * sbet1 * sq(somg12) / (1.0 - comg12);
+            }
+        }
+        if salp1 > 0.0 || salp1.is_nan() {
+            let (s, c) = norm(salp1, calp1);
+            salp1 = s;
+            calp1 = c;
+        } else {
+            salp1 = 1.0;
+            calp1 = 0.0;
+        }
+        (sig12, salp1, calp1, salp2, calp2, dnm)
+    }
+
+    /// the longitude difference reached with azimuth `alp1` from the first
+    /// point, and its derivative when `diff` is set
+    fn lambda12(&self, p1:&Reduced, p2:&Reduced, alp1:(f64, f64), lam120:(f64, f64), diff:bool) -> Lambda {
+        let (sbet1, cbet1) = (p1.sbet, p1.cbet);
+        let (sbet2, cbet2) = (p2.sbet, p2.cbet);
+        let (salp1, mut calp1) = alp1;
+        let (slam120, clam120) = lam120;
+        if sbet1 == 0.0 && calp1 == 0.0 {
+            calp1 = -TINY;
+        }
+        let salp0 = salp1 * cbet1;
+        let calp0 = calp1.hypot(salp1 * sbet1);
+        let somg1 = salp0 * sbet1;
+        let comg1 = calp1 * cbet1;
+        let (ssig1, csig1) = norm(sbet1, calp1 * cbet1);
+
+        let salp2 = if cbet2 != cbet1 { salp0 / cbet2 } else { salp1 };
+        let calp2 = if cbet2 != cbet1 || sbet2.abs() != -sbet1 {
+            (sq(calp1 * cbet1) + if cbet1 < -sbet1 {
+                (cbet2 - cbet1) * (cbet1 + cbet2)
+            } else {
+                (sbet1 - sbet2) * (sbet1 + sbet2)
+            }).sqrt() / cbet2
+        } else {
+            calp1.abs()
+        };
+        let somg2 = salp0 * sbet2;
+        let comg2 = calp2 * cbet2;
+        let (ssig2, csig2) = norm(sbet2, calp2 * cbet2);
+
+        let sig12 = (csig1 * ssig2 - ssig1 * csig2).max(0.0).atan2(csig1 * csig2 + ssig1 * ssig2);
+        let somg12 = (comg1 * somg2 - somg1 * comg2).max(0.0);
+        let comg12 = comg1 * comg2 + somg1 * somg2;
+        let eta = (somg12 * clam120 - comg12 * slam120).atan2(comg12 * clam120 + somg12 * slam120);
+        let eps = epsilon(sq(calp0) * self.ep2);
+        let c3a = self.c3f(eps);
+        let b312 = sin_cos_series(true, ssig2, csig2, &c3a) - sin_cos_series(true, ssig1, csig1, &c3a);
+        let domg12 = -self.f * self.a3f(eps) * salp0 * (sig12 + b312);
+        let lam12 = eta + domg12;
+        let dlam12 = if !diff {
+            f64::NAN
+        } else if calp2 == 0.0 {
+            -2.0 * self.f1 * p1.dn / sbet1
+        } else {
+            let (_, m12b, _) = self.lengths(eps, sig12, (ssig1, csig1), (ssig2, csig2), p1, p2);
+            m12b * self.f1 / (calp2 * cbet2)
+        };
+        Lambda { lam12, salp2, calp2, sig12, sig1: (ssig1, csig1), sig2: (ssig2, csig2), eps, domg12, dlam12 }
+    }
+
+    /// the inverse problem between `(lat, lng)` pairs, with the area term when `area` is set
+    pub(crate) fn solve_inverse(&self, lat1:f64, lon1:f64, lat2:f64, lon2:f64, area:bool) -> Solution {
+        let (lon12, lon12s) = ang_diff(lon1, lon2);
+        let mut lonsign = if lon12 >= 0.0 { 1.0 } else { -1.0 };
+        let lon12 = lonsign * ang_round(lon12);
+        let lon12s = ang_round((180.0 - lon12) - lonsign * lon12s);
+        let lam12 = lon12.to_radians();
+        let (slam12, clam12) = if lon12 > 90.0 {
+            let (s, c) = sincosd(lon12s);
+            (s, -c)
+        } else {
+            sincosd(lon12)
+        };
+        let fix = |lat:f64| if lat.abs() > 90.0 { f64::NAN } else { lat };
+        let mut lat1 = ang_round(fix(lat1));
+        let mut lat2 = ang_round(fix(lat2));
+        // make lat1 the one further from the equator, and negative
+        let swapp = if lat1.abs() < lat2.abs() { -1.0 } else { 1.0 };
+        if swapp < 0.0 {
+            lonsign = -lonsign;
+            ::std::mem::swap(&mut lat1, &mut lat2);
+        }
+        let latsign = if lat1 < 0.0 { 1.0 } else { -1.0 };
+        let p1 = self.reduced(lat1 * latsign);
+        let mut p2 = self.reduced(lat2 * latsign);
+        let (sbet1, cbet1) = (p1.sbet, p1.cbet);
+        if cbet1 < -sbet1 {
+            if p2.cbet == cbet1 {
+                p2.sbet = if p2.sbet < 0.0 { sbet1 } else { -sbet1 };
+            }
+        } else if p2.sbet.abs() == -sbet1 {
+            p2.cbet = cbet1;
+        }
+        let (sbet2, cbet2) = (p2.sbet, p2.cbet);
+
+        let (mut a12, mut s12x, mut m12x) = (f64::NAN, 0.0, 0.0);
+        let (mut salp1, mut calp1, mut salp2, mut calp2) = (0.0, 0.0, 0.0, 0.0);
+        let (mut somg12, mut comg12, mut omg12) = (2.0, 0.0, 0.0);
+
+        let mut meridian = lat1 * latsign == -90.0 || slam12 == 0.0;
+        if meridian {
+            // along a meridian, or through a pole
+            calp1 = clam12;
+            salp1 = slam12;
+            calp2 = 1.0;
+            salp2 = 0.0;
+            let sig1 = (sbet1, calp1 * cbet1);
+            let sig2 = (sbet2, calp2 * cbet2);
+            let mut sig12 = (sig1.1 * sig2.0 - sig1.0 * sig2.1).max(0.0).atan2(sig1.1 * sig2.1 + sig1.0 * sig2.0);
+            let (s, m, _) = self.lengths(self.n, sig12, sig1, sig2, &p1, &p2);
+            s12x = s;
+            m12x = m;
+            if sig12 < 1.0 || m12x >= 0.0 {
+                if sig12 < 3.0 * TINY {
+                    sig12 = 0.0;
+                    m12x = 0.0;
+                    s12x = 0.0;
+                }
+                m12x *= self.b;
+                s12x *= self.b;
+                a12 = sig12.to_degrees();
+            } else {
+                // m12 < 0, the meridian is not the shortest path
+                meridian = false;
+            }
+        }
+
+        if !meridian && sbet1 == 0.0 && (self.f <= 0.0 || lon12s >= self.f * 180.0) {
+            // along the equator
+            calp1 = 0.0;
+            calp2 = 0.0;
+            salp1 = 1.0;
+            salp2 = 1.0;
+            s12x = self.a * lam12;
+            let sig12 = lam12 / self.f1;
+            omg12 = sig12;
+            m12x = self.b * sig12.sin();
+            a12 = lon12 / self.f1;
+        } else if !meridian {
+            let (sig12, s1, c1, s2, c2, dnm) = self.inverse_start(&p1, &p2, lam12, slam12, clam12);
+            salp1 = s1;
+            calp1 = c1;
+            if sig12 >= 0.0 {
+                // short line, solved by the starting guess
+                salp2 = s2;
+                calp2 = c2;
+                s12x = sig12 * self.b * dnm;
+                m12x = sq(dnm) * self.b * (sig12 / dnm).sin();
+                a12 = sig12.to_degrees();
+                omg12 = lam12 / (self.f1 * dnm);
+            } else {
+                // Newton's method on α1, falling back to bisection
+                let (mut tripn, mut tripb) = (false, false);
+                let (mut salp1a, mut calp1a) = (TINY, 1.0);
+                let (mut salp1b, mut calp1b) = (TINY, -1.0);
+                let mut numit = 0;
+                let step = loop {
+                    let step = self.lambda12(&p1, &p2, (salp1, calp1), (slam12, clam12), numit < NEWTON_ITERATIONS);
+                    let v = step.lam12;
+                    if tripb || v.abs() < if tripn { 8.0 } else { 1.0 } * TOL0 || v.is_nan() || numit + 1 == MAX_ITERATIONS {
+                        break step;
+                    }
+                    if v > 0.0 && (numit > NEWTON_ITERATIONS || calp1 / salp1 > calp1b / salp1b) {
+                        salp1b = salp1;
+                        calp1b = calp1;
+                    } else if v < 0.0 && (numit > NEWTON_ITERATIONS || calp1 / salp1 < calp1a / salp1a) {
+                        salp1a = salp1;
+                        calp1a = calp1;
+                    }
+                    numit += 1;
+                    if numit <= NEWTON_ITERATIONS && step.dlam12 > 0.0 {
+                        let dalp1 = -v / step.dlam12;
+                        let (sdalp1, cdalp1) = dalp1.sin_cos();
+                        let nsalp1 = salp1 * cdalp1 + calp1 * sdalp1;
+                        if nsalp1 > 0.0 && dalp1.abs() < PI {
+                            let (s, c) = norm(nsalp1, calp1 * cdalp1 - salp1 * sdalp1);
+                            salp1 = s;
+                            calp1 = c;
+                            tripn = v.abs() <= 16.0 * TOL0;
+                            continue;
+                        }
+                    }
+                    let (s, c) = norm((salp1a + salp1b) / 2.0, (calp1a + calp1b) / 2.0);
+                    salp1 = s;
+                    calp1 = c;
+                    tripn = false;
+                    tripb = (salp1a - salp1).abs() + (calp1a - calp1) < TOLB
+                        || (salp1 - salp1b).abs() + (calp1 - calp1b) < TOLB;
+                };
+                salp2 = step.salp2;
+                calp2 = step.calp2;
+                let (s, m, _) = self.lengths(step.eps, step.sig12, step.sig1, step.sig2, &p1, &p2);
+                s12x = s * self.b;
+                m12x = m * self.b;
+                a12 = step.sig12.to_degrees();
+                if area {
+                    let (sdomg12, cdomg12) = step.domg12.sin_cos();
+                    somg12 = slam12 * cdomg12 - clam12 * sdomg12;
+                    comg12 = clam12 * cdomg12 + slam12 * sdomg12;
+                }
+            }
+        }
+
+        let mut s12_area = f64::NAN;
+        if area {
+            let salp0 = salp1 * cbet1;
+            let calp0 = calp1.hypot(salp1 * sbet1);
+            let mut s = if calp0 != 0.0 && salp0 != 0.0 {
+                let (ssig1, csig1) = norm(sbet1, calp1 * cbet1);
+                let (ssig2, csig2) = norm(sbet2, calp2 * cbet2);
+                let eps = epsilon(sq(calp0) * self.ep2);
+                let a4 = sq(self.a) * calp0 * salp0 * self.e2;
+                let c4a = self.c4f(eps);
+                a4 * (sin_cos_series(false, ssig2, csig2, &c4a) - sin_cos_series(false, ssig1, csig1, &c4a))
+            } else {
+                0.0
+            };
+            if !meridian && somg12 > 1.0 {
+                let (so, co) = omg12.sin_cos();
+                somg12 = so;
+                comg12 = co;
+            }
+            let alp12 = if !meridian && comg12 > -FRAC_1_SQRT_2 && sbet2 - sbet1 < 1.75 {
+                let domg12 = 1.0 + comg12;
+                let dbet1 = 1.0 + cbet1;
+                let dbet2 = 1.0 + cbet2;
+                2.0 * (somg12 * (sbet1 * dbet2 + sbet2 * dbet1)).atan2(domg12 * (sbet1 * sbet2 + dbet1 * dbet2))
+            } else {
+                let mut salp12 = salp2 * calp1 - calp2 * salp1;
+                let mut calp12 = calp2 * calp1 + salp2 * salp1;
+                if salp12 == 0.0 && calp12 < 0.0 {
+                    salp12 = TINY * calp1;
+                    calp12 = -1.0;
+                }
+                salp12.atan2(calp12)
+            };
+            s += self.c2 * alp12;
+            s12_area = s * swapp * lonsign * latsign + 0.0;
+        }
+
+        if swapp < 0.0 {
+            ::std::mem::swap(&mut salp1, &mut salp2);
+            ::std::mem::swap(&mut calp1, &mut calp2);
+        }
+        salp1 *= swapp * lonsign;
+        calp1 *= swapp * latsign;
+        salp2 *= swapp * lonsign;
+        calp2 *= swapp * latsign;
+        Solution {
+            a12,
+            s12: s12x + 0.0,
+            azi1: atan2d(salp1, calp1),
+            azi2: atan2d(salp2, calp2),
+            m12: m12x + 0.0,
+            area: s12_area,
+        }
+    }
+
+    /// distance and bearings between two `(lng, lat)` points
+    ///
+    /// unlike [`vincenty::inverse`](::vincenty::inverse) this never fails.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::geodesic::Geodesic;
+    /// let g = Geodesic::wgs84();
+    /// // Flinders Peak to Buninyong
+    /// let r = g.inverse((144.424868, -37.951033), (143.926496, -37.652821));
+    /// assert!((r.distance - 54972.227).abs() < 0.001);
+    /// assert!((r.initial_bearing - 306.86816).abs() < 1e-4);
+    /// // nearly antipodal, where Vincenty does not converge
+    /// let r = g.inverse((0.0, 0.0), (179.8, 0.3));
+    /// assert!((r.distance - 19_968_298.320).abs() < 0.001);
+    /// ```
+    pub fn inverse(&self, from:(f64, f64), to:(f64, f64)) -> Inverse {
+        let s = self.solve_inverse(from.1, from.0, to.1, to.0, false);
+        Inverse {
+            distance: s.s12,
+            initial_bearing: normalize_heading(s.azi1),
+            final_bearing: normalize_heading(s.azi2),
+        }
+    }
+
+    /// the geodesic leaving a `(lng, lat)` point at `bearing` degrees clockwise from north
+    pub fn line(&self, from:(f64, f64), bearing:f64) -> Line {
+        Line::new(self, from, bearing)
+    }
+
+    /// the point `distance` meters from a `(lng, lat)` point along the geodesic of initial `bearing`
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::geodesic::Geodesic;
+    /// let g = Geodesic::wgs84();
+    /// // 10000 km north east of JFK
+    /// let d = g.direct((-73.78, 40.64), 45.0, 10e6);
+    /// assert!((d.point.0 - 49.052487093).abs() < 1e-9);
+    /// assert!((d.point.1 - 32.621100464).abs() < 1e-9);
+    /// let back = g.inverse((-73.78, 40.64), d.point);
+    /// assert!((back.distance - 10e6).abs() < 1e-6);
+    /// ```
+    pub fn direct(&self, from:(f64, f64), bearing:f64, distance:f64) -> Direct {
+        self.line(from, bearing).position(distance)
+    }
+
+    /// the geodesic from one point to another and its length
+    fn between(&self, from:(f64, f64), to:(f64, f64)) -> (Line, f64) {
+        let s = self.solve_inverse(from.1, from.0, to.1, to.0, false);
+        (self.line(from, s.azi1), s.s12)
+    }
+
+    /// the point at `fraction` (0 at `from`, 1 at `to`) of the geodesic between two `(lng, lat)` points
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::geodesic::Geodesic;
+    /// let g = Geodesic::wgs84();
+    /// let (tpe, sfo) = ((121.23, 25.08), (-122.38, 37.62));
+    /// let mid = g.interpolate(tpe, sfo, 0.5);
+    /// let (a, b) = (g.inverse(tpe, mid).distance, g.inverse(mid, sfo).distance);
+    /// assert!((a - b).abs() < 1e-6);
+    /// // the great circle route bends north of both ends
+    /// assert!(mid.1 > 45.0);
+    /// ```
+    pub fn interpolate(&self, from:(f64, f64), to:(f64, f64), fraction:f64) -> (f64, f64) {
+        let (line, length) = self.between(from, to);
+        line.position(length * fraction).point
+    }
+
+    /// points along the geodesic between two `(lng, lat)` points, evenly spaced
+    /// no more than `meters` apart, both ends included
+    ///
+    /// projecting the result gives a route polyline that follows the geodesic
+    /// on the map.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::geodesic::Geodesic;
+    /// use mercator::zone::Zone;
+    /// let g = Geodesic::wgs84();
+    /// let route = g.sample_every((120.3, 22.6), (121.6, 25.1), 10_000.0);
+    /// assert_eq!(route.len(), 32);
+    /// assert_eq!(route[31], (121.6, 25.1));
+    /// let zone = Zone::twd97();
+    /// let projected:Vec<(f64, f64)> = route.iter().map(|p| zone.forward(p.0, p.1)).collect();
+    /// assert!(projected[0].1 < projected[31].1);
+    /// ```
+    pub fn sample_every(&self, from:(f64, f64), to:(f64, f64), meters:f64) -> LineString {
+        let (line, length) = self.between(from, to);
+        let n = if meters > 0.0 { (length / meters).ceil().max(1.0) as usize } else { 1 };
+        let mut points:LineString = (0..n).map(|i| line.position(length * i as f64 / n as f64).point).collect();
+        points.push(to);
+        points
+    }
+
+    /// where a `(lng, lat)` point lies relative to the geodesic from `start` through `end`
+    ///
+    /// the foot of the perpendicular is found by iterating on the line, after
+    /// Baselga and Martínez-Llario, and the distances are geodesic to the
+    /// accuracy of [`Geodesic::inverse`]. should the iteration not settle, the
+    /// last foot is returned, with the cross track distance signed all the same.
+    /// - S. Baselga, J. C. Martínez-Llario, Intersection and point-to-line
+    ///   solutions for geodesics on the ellipsoid, Stud. Geophys. Geod. 62 (2018)
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::geodesic::Geodesic;
+    /// let g = Geodesic::wgs84();
+    /// // a point one degree north of a route along the equator is on its left
+    /// let t = g.track((0.0, 0.0), (10.0, 0.0), (3.0, 1.0));
+    /// assert!((t.cross_track + 110_574.389).abs() < 1e-3);
+    /// assert!((t.along_track - 333_958.472).abs() < 1e-3);
+    /// assert!((t.foot.0 - 3.0).abs() < 1e-9 && t.foot.1.abs() < 1e-9);
+    /// ```
+    pub fn track(&self, start:(f64, f64), end:(f64, f64), point:(f64, f64)) -> Track {
+        let (line, _) = self.between(start, end);
+        let mut along_track = 0.0;
+        let mut iterations = 0;
+        loop {
+            let foot = line.position(along_track);
+            let s = self.solve_inverse(foot.point.1, foot.point.0, point.1, point.0, false);
+            let (sin_angle, cos_angle) = (s.azi1 - foot.final_bearing).to_radians().sin_cos();
+            // the spherical right triangle at the foot gives the next step
+            let (sin_d, cos_d) = (s.s12 / self.a).sin_cos();
+            let step = self.a * (cos_angle * sin_d).atan2(cos_d);
+            if step.abs() < 1e-9 || s.s12 == 0.0 || iterations == MAX_ITERATIONS {
+                let cross_track = if sin_angle < 0.0 { -s.s12 } else { s.s12 };
+                return Track { cross_track, along_track, foot: foot.point };
+            }
+            along_track += step;
+            iterations += 1;
+        }
+    }
+
+    /// geodesic distance from a `(lng, lat)` point to the nearest point of the segment between `start` and `end`
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::geodesic::Geodesic;
+    /// let g = Geodesic::wgs84();
+    /// let (a, b) = ((121.0, 24.0), (121.0, 25.0));
+    /// assert!((g.distance_to_segment(a, b, (121.01, 24.5)) - 1013.0).abs() < 1.0);
+    /// // past the end the distance is to the end point
+    /// let beyond = (121.0, 25.2);
+    /// assert_eq!(g.distance_to_segment(a, b, beyond), g.inverse(b, beyond).distance);
+    /// ```
+    pub fn distance_to_segment(&self, start:(f64, f64), end:(f64, f64), point:(f64, f64)) -> f64 {
+        let length = self.inverse(start, end).distance;
+        let t = self.track(start, end, point);
+        if t.along_track <= 0.0 {
+            self.inverse(start, point).distance
+        } else if t.along_track >= length {
+            self.inverse(end, point).distance
+        } else {
+            t.cross_track.abs()
+        }
+    }
+}
+
+impl Geodesic {
+    /// perimeter in meters, summed area terms in m² and antimeridian crossings of a ring
+    fn ring_sums(&self, ring:&[(f64, f64)]) -> (f64, f64, i64) {
+        let (mut perimeter, mut area, mut crossings) = (0.0, 0.0, 0);
+        for (i, p) in ring.iter().enumerate() {
+            let q = ring[(i + 1) % ring.len()];
+            let s = self.solve_inverse(p.1, p.0, q.1, q.0, true);
+            perimeter += s.s12;
+            area += s.area;
+            crossings += transit(p.0, q.0);
+        }
+        (perimeter, area, crossings)
+    }
+
+    /// signed area in m² of a ring of `(lng, lat)` points on the ellipsoid,
+    /// positive when counterclockwise, with the edges taken as geodesics
+    ///
+    /// the ring may be closed or open; of the two parts of the ellipsoid it
+    /// divides, the one of less than half the total area is measured.
+    /// Karney's method is exact to the accuracy of the geodesics, whatever the
+    /// size of the ring, so no equal-area projection is needed.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::geodesic::Geodesic;
+    /// let g = Geodesic::wgs84();
+    /// let square = vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+    /// assert!((g.ring_area(&square) - 12_308_778_361.469).abs() < 1e-2);
+    /// let reversed:Vec<(f64, f64)> = square.iter().rev().cloned().collect();
+    /// assert!((g.ring_area(&reversed) + 12_308_778_361.469).abs() < 1e-2);
+    /// assert!((g.ring_perimeter(&square) - 443_770.917).abs() < 1e-3);
+    /// ```
+    pub fn ring_area(&self, ring:&[(f64, f64)]) -> f64 {
+        if ring.len() < 3 {
+            return 0.0;
+        }
+        let (_, sum, crossings) = self.ring_sums(ring);
+        let total = self.ellipsoid_area();
+        let mut area = sum % total;
+        if crossings % 2 != 0 {
+            area += if area < 0.0 { total / 2.0 } else { -total / 2.0 };
+        }
+        // the area terms are positive clockwise
+        area = -area;
+        if area > total / 2.0 {
+            area -= total;
+        } else if area <= -total / 2.0 {
+            area += total;
+        }
+        area + 0.0
+    }
+
+    /// length in meters of a ring of `(lng, lat)` points, closed or open, back to its first point
+    pub fn ring_perimeter(&self, ring:&[(f64, f64)]) -> f64 {
+        if ring.len() < 2 { 0.0 } else { self.ring_sums(ring).0 }
+    }
+
+    /// area in m² of a polygon of `(lng, lat)` rings on the ellipsoid, holes
+    /// removed, whatever the orientation of the rings
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::geodesic::Geodesic;
+    /// let g = Geodesic::wgs84();
+    /// let parcel = vec![(121.5, 25.0), (121.501, 25.0), (121.501, 25.001), (121.5, 25.001), (121.5, 25.0)];
+    /// let hole = vec![(121.5004, 25.0004), (121.5006, 25.0004), (121.5006, 25.0006), (121.5004, 25.0006)];
+    /// let area = g.polygon_area(&[parcel.clone(), hole.clone()]);
+    /// assert!((g.polygon_area(&[parcel]) - area - g.ring_area(&hole)).abs() < 1e-6);
+    /// assert!((area - 10_735.189).abs() < 1e-3);
+    /// ```
+    pub fn polygon_area(&self, poly:&[LineString]) -> f64 {
+        poly.iter().enumerate().map(|(i, ring)| {
+            let a = self.ring_area(ring).abs();
+            if i == 0 { a } else { -a }
+        }).sum()
+    }
+
+    /// total length in meters of the rings of a polygon, holes included
+    pub fn polygon_perimeter(&self, poly:&[LineString]) -> f64 {
+        poly.iter().map(|ring| self.ring_perimeter(ring)).sum()
+    }
+}
+
+/// +1 or -1 when going from `lng1` to `lng2` the short way crosses the prime meridian eastward or westward
+fn transit(lng1:f64, lng2:f64) -> i64 {
+    let (lng12, _) = ang_diff(lng1, lng2);
+    let lng1 = ang_normalize(lng1);
+    let lng2 = ang_normalize(lng2);
+    if lng12 > 0.0 && ((lng1 < 0.0 && lng2 >= 0.0) || (lng1 > 0.0 && lng2 == 0.0)) {
+        1
+    } else if lng12 < 0.0 && lng1 >= 0.0 && lng2 < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
+
+/// position of a point relative to a geodesic, see [`Geodesic::track`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Track {
+    /// distance in meters from the geodesic, positive to the right of the direction of travel
+    pub cross_track:f64,
+    /// distance in meters from the start to the foot of the perpendicular,
+    /// negative before the start and beyond the length of the segment past its end
+    pub along_track:f64,
+    /// the foot of the perpendicular as `(lng, lat)`
+    pub foot:(f64, f64),
+}
+
+/// geodesic length in meters on WGS84 of a polyline of `(lng, lat)` points,
+/// the ellipsoidal counterpart of [`extent::length`](::extent::length)
+/// # Examples
+///
+/// ```
+/// use mercator::geodesic::length;
+/// use mercator::extent;
+/// use mercator::wgs84_to_twd97;
+/// let track = vec![(121.50, 25.03), (121.51, 25.04), (121.53, 25.04)];
+/// let meters = length(&track);
+/// assert!((meters - 3_516.877).abs() < 1e-3);
+/// // TWD97 grid distances are scaled by about 0.9999 near the central meridian
+/// let grid:Vec<(f64, f64)> = track.iter().map(|p| wgs84_to_twd97(p.0, p.1)).collect();
+/// assert!((extent::length(&grid) / meters - 1.0).abs() < 2e-4);
+/// ```
+pub fn length(line:&[(f64, f64)]) -> f64 {
+    Geodesic::wgs84().length(line)
+}
+
+impl Geodesic {
+    /// geodesic length in meters of a polyline of `(lng, lat)` points
+    pub fn length(&self, line:&[(f64, f64)]) -> f64 {
+        line.windows(2).map(|w| self.inverse(w[0], w[1]).distance).sum()
+    }
+}
+
+impl Default for Geodesic {
+    fn default() -> Geodesic {
+        Geodesic::wgs84()
+    }
+}
+
+/// a geodesic through a point with a given bearing, prepared for finding positions along it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Line {
+    lng1:f64,
+    b:f64,
+    f1:f64,
+    salp0:f64,
+    calp0:f64,
+    sig1:(f64, f64),
+    omg1:(f64, f64),
+    tau1:(f64, f64),
+    k2:f64,
+    a1m1:f64,
+    a3c:f64,
+    b11:f64,
+    b31:f64,
+    c1a:[f64; ORDER + 1],
+    c1pa:[f64; ORDER + 1],
+    c3a:[f64; ORDER],
+}
+
+impl Line {
+    fn new(geodesic:&Geodesic, from:(f64, f64), bearing:f64) -> Line {
+        let (salp1, calp1) = sincosd(ang_round(ang_normalize(bearing)));
+        let lat1 = if from.1.abs() > 90.0 { f64::NAN } else { from.1 };
+        let p1 = geodesic.reduced(ang_round(lat1));
+        let (sbet1, cbet1) = (p1.sbet, p1.cbet);
+        let salp0 = salp1 * cbet1;
+        let calp0 = calp1.hypot(salp1 * sbet1);
+        let somg1 = salp0 * sbet1;
+        let csig1 = if sbet1 != 0.0 || calp1 != 0.0 { cbet1 * calp1 } else { 1.0 };
+        let comg1 = csig1;
+        let (ssig1, csig1) = norm(sbet1, csig1);
+        let k2 = sq(calp0) * geodesic.ep2;
+        let eps = epsilon(k2);
+        let c1a = series_coefficients(&COEFF_C1, eps);
+        let b11 = sin_cos_series(true, ssig1, csig1, &c1a);
+        let (s, c) = b11.sin_cos();
+        let c3a = geodesic.c3f(eps);
+        Line {
+            lng1: from.0,
+            b: geodesic.b,
+            f1: geodesic.f1,
+            salp0,
+            calp0,
+            sig1: (ssig1, csig1),
+            omg1: (somg1, comg1),
+            tau1: (ssig1 * c + csig1 * s, csig1 * c - ssig1 * s),
+            k2,
+            a1m1: a1m1(eps),
+            a3c: -geodesic.f * salp0 * geodesic.a3f(eps),
+            b11,
+            b31: sin_cos_series(true, ssig1, csig1, &c3a),
+            c1a,
+            c1pa: series_coefficients(&COEFF_C1P, eps),
+            c3a,
+        }
+    }
+
+    /// the point `distance` meters along the line, negative distances going backwards
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::geodesic::Geodesic;
+    /// let line = Geodesic::wgs84().line((121.0, 24.0), 90.0);
+    /// let p = line.position(100_000.0);
+    /// assert!(p.point.0 > 121.98 && p.point.1 < 24.0);
+    /// assert!(p.final_bearing > 90.0);
+    /// ```
+    pub fn position(&self, distance:f64) -> Direct {
+        let (ssig1, csig1) = self.sig1;
+        let (stau1, ctau1) = self.tau1;
+        let tau12 = distance / (self.b * (1.0 + self.a1m1));
+        let (s, c) = tau12.sin_cos();
+        let b12 = -sin_cos_series(true, stau1 * c + ctau1 * s, ctau1 * c - stau1 * s, &self.c1pa);
+        let mut sig12 = tau12 - (b12 - self.b11);
+        let (mut ssig12, mut csig12) = sig12.sin_cos();
+        if (1.0 - self.f1).abs() > 0.01 {
+            // strongly flattened ellipsoids need one Newton step
+            let ssig2 = ssig1 * csig12 + csig1 * ssig12;
+            let csig2 = csig1 * csig12 - ssig1 * ssig12;
+            let b12 = sin_cos_series(true, ssig2, csig2, &self.c1a);
+            let serr = (1.0 + self.a1m1) * (sig12 + (b12 - self.b11)) - distance / self.b;
+            sig12 -= serr / (1.0 + self.k2 * sq(ssig2)).sqrt();
+            let (s, c) = sig12.sin_cos();
+            ssig12 = s;
+            csig12 = c;
+        }
+        let ssig2 = ssig1 * csig12 + csig1 * ssig12;
+        let mut csig2 = csig1 * csig12 - ssig1 * ssig12;
+        let sbet2 = self.calp0 * ssig2;
+        let mut cbet2 = self.salp0.hypot(self.calp0 * csig2);
+        if cbet2 == 0.0 {
+            cbet2 = TINY;
+            csig2 = TINY;
+        }
+        let (somg1, comg1) = self.omg1;
+        let somg2 = self.salp0 * ssig2;
+        let comg2 = csig2;
+        let omg12 = (somg2 * comg1 - comg2 * somg1).atan2(comg2 * comg1 + somg2 * somg1);
+        let lam12 = omg12 + self.a3c * (sig12 + (sin_cos_series(true, ssig2, csig2, &self.c3a) - self.b31));
+        let lng2 = ang_normalize(ang_normalize(self.lng1) + ang_normalize(lam12.to_degrees()));
+        Direct {
+            point: (lng2, atan2d(sbet2, self.f1 * cbet2)),
+            final_bearing: normalize_heading(atan2d(self.salp0, self.calp0 * csig2)),
+        }
+    }
+}
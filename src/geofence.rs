@@ -0,0 +1,157 @@
+//! point-in-polygon geofencing with fences in any CRS
+//!
+//! fences are registered in the CRS they were drawn in, e.g. TWD97 parcels
+//! or UTM work zones, and points are tested as WGS84 lng/lat, each one
+//! reprojected into the CRS of the fence. grid fences are first checked
+//! against their lng/lat extent, so a point on the far side of the globe is
+//! never pushed through a transverse mercator zone it is far outside of,
+//! while a point just across the zone edge still projects correctly.
+//! lng/lat fences may cross the antimeridian: their longitudes are made
+//! continuous and every point is wrapped to the same side before testing.
+
+use crs::{Crs, Transformer};
+use error::{Error, Result};
+use extent::Extent;
+use geometry::{unwrap, Polygon};
+use longitude::{normalize_lng, wrap_lng_around};
+
+#[derive(Debug, Clone)]
+struct Fence<K> {
+    key:K,
+    transformer:Transformer,
+    polygon:Polygon,
+    /// extent in the CRS of the fence
+    extent:Extent,
+    /// lng/lat extent with a margin, for grid fences
+    lnglat:Option<Extent>,
+}
+
+impl<K> Fence<K> {
+    fn contains(&self, lng:f64, lat:f64) -> bool {
+        if let Some(ref e) = self.lnglat {
+            if !e.contains(wrap_lng_around(lng, e.center().0), lat) {
+                return false;
+            }
+            let (x, y) = self.transformer.transform(lng, lat);
+            return self.extent.contains(x, y) && in_polygon(&self.polygon, x, y);
+        }
+        let (x, y) = self.transformer.transform(lng, lat);
+        let x = wrap_lng_around(x, self.extent.center().0);
+        self.extent.contains(x, y) && in_polygon(&self.polygon, x, y)
+    }
+}
+
+/// even-odd test of a point against every ring of a polygon, so holes are left out
+fn in_polygon(poly:&Polygon, x:f64, y:f64) -> bool {
+    let mut inside = false;
+    for ring in poly {
+        let n = ring.len();
+        for i in 0..n {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            if (a.1 > y) != (b.1 > y) && x < a.0 + (y - a.1) * (b.0 - a.0) / (b.1 - a.1) {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// a set of polygons, each in its own CRS, tested against WGS84 points
+/// # Examples
+///
+/// ```
+/// use mercator::crs::Crs;
+/// use mercator::geofence::Geofence;
+/// use mercator::zone::Zone;
+/// let mut fences = Geofence::new();
+/// // a 2 km square in TWD97 around (121.0, 24.0)
+/// let square = vec![vec![(249000.0, 2654000.0), (251000.0, 2654000.0), (251000.0, 2656000.0), (249000.0, 2656000.0)]];
+/// fences.add("site", square, Crs::projected(Zone::twd97())).unwrap();
+/// // a lng/lat box over the antimeridian
+/// let fiji = vec![vec![(177.0, -19.0), (-178.0, -19.0), (-178.0, -15.0), (177.0, -15.0)]];
+/// fences.add("fiji", fiji, Crs::wgs84()).unwrap();
+///
+/// assert_eq!(fences.fences_at(121.0, 24.0), vec![&"site"]);
+/// assert_eq!(fences.fences_at(-179.5, -17.0), vec![&"fiji"]);
+/// assert!(fences.contains(179.5, -17.0));
+/// assert!(!fences.contains(-59.0, -24.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Geofence<K> {
+    fences:Vec<Fence<K>>,
+}
+
+impl<K> Default for Geofence<K> {
+    fn default() -> Geofence<K> {
+        Geofence { fences: Vec::new() }
+    }
+}
+
+impl<K> Geofence<K> {
+    pub fn new() -> Geofence<K> {
+        Geofence::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fences.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fences.is_empty()
+    }
+
+    /// register a polygon in the coordinates of `crs`, the first ring being the exterior
+    ///
+    /// fails with [`Error::InvalidInput`] when the exterior has fewer than three
+    /// vertices, a vertex is not finite or a grid vertex has no lng/lat.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Crs;
+    /// use mercator::geofence::Geofence;
+    /// let mut fences = Geofence::new();
+    /// let broken = vec![vec![(121.0, 24.0), (f64::NAN, 24.0), (121.0, 25.0)]];
+    /// assert!(fences.add("broken", broken, Crs::wgs84()).is_err());
+    /// assert!(fences.is_empty());
+    /// ```
+    pub fn add(&mut self, key:K, polygon:Polygon, crs:Crs) -> Result<()> {
+        if polygon.first().is_none_or(|ring| ring.len() < 3) {
+            return Err(Error::InvalidInput("geofence: the exterior ring needs at least three vertices".to_string()));
+        }
+        if polygon.iter().flatten().any(|p| !p.0.is_finite() || !p.1.is_finite()) {
+            return Err(Error::InvalidInput("geofence: a vertex is not finite".to_string()));
+        }
+        let geographic = matches!(crs, Crs::Geographic(_));
+        let transformer = Transformer::new(Crs::wgs84(), crs);
+        let (polygon, lnglat) = if geographic {
+            let start = normalize_lng(polygon[0][0].0);
+            (polygon.iter().map(|ring| unwrap(ring, start)).collect(), None)
+        } else {
+            let back = transformer.inverse();
+            let start = back.transform(polygon[0][0].0, polygon[0][0].1).0;
+            let outline:Vec<(f64, f64)> = polygon[0].iter().map(|&(x, y)| back.transform(x, y)).collect();
+            if outline.iter().any(|p| !p.0.is_finite() || !p.1.is_finite()) {
+                return Err(Error::InvalidInput("geofence: a vertex has no lng/lat".to_string()));
+            }
+            let e = Extent::of(&unwrap(&outline, normalize_lng(start)))
+                .ok_or_else(|| Error::InvalidInput("geofence: the exterior ring has no lng/lat extent".to_string()))?;
+            // edges straight on the grid bow a little in lng/lat
+            let margin = 0.1 * e.width().max(e.height()) + 1e-3;
+            (polygon, Some(e.expand(margin)))
+        };
+        let extent = Extent::of(&polygon[0])
+            .ok_or_else(|| Error::InvalidInput("geofence: the exterior ring has no extent".to_string()))?;
+        self.fences.push(Fence { key, transformer, polygon, extent, lnglat });
+        Ok(())
+    }
+
+    /// the keys of the fences holding a WGS84 point, in the order they were added
+    pub fn fences_at(&self, lng:f64, lat:f64) -> Vec<&K> {
+        self.fences.iter().filter(|f| f.contains(lng, lat)).map(|f| &f.key).collect()
+    }
+
+    /// true if any fence holds the WGS84 point
+    pub fn contains(&self, lng:f64, lat:f64) -> bool {
+        self.fences.iter().any(|f| f.contains(lng, lat))
+    }
+}
@@ -0,0 +1,136 @@
+//! geoid undulation lookup from a user-supplied grid (feature `geoid`)
+//!
+//! reads the 16-bit PGM grids distributed with GeographicLib (egm96-5.pgm,
+//! egm2008-1.pgm, ...). the grid is not bundled, download the model you need
+//! and point [`GeoidGrid::open`] at it.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use error::{Error, Result};
+use longitude::normalize_lng;
+use trace::Activity;
+
+/// a global geoid undulation grid
+#[derive(Debug, Clone)]
+pub struct GeoidGrid {
+    width:usize,
+    height:usize,
+    offset:f64,
+    scale:f64,
+    data:Vec<u16>,
+}
+
+fn format_err(msg:&str) -> Error {
+    Error::Format(format!("geoid grid: {}", msg))
+}
+
+impl GeoidGrid {
+    /// read a GeographicLib PGM geoid grid from a file
+    pub fn open<P: AsRef<Path>>(path:P) -> Result<GeoidGrid> {
+        let activity = Activity::file("read_geoid_grid", path.as_ref());
+        let grid = GeoidGrid::from_reader(BufReader::new(File::open(path)?))?;
+        activity.finish(0, 0);
+        Ok(grid)
+    }
+
+    /// read a GeographicLib PGM geoid grid
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::geoid::GeoidGrid;
+    /// // a 4 x 3 grid (90° spacing) with every node at 20 m
+    /// let mut pgm = b"P5\n# Offset -100\n# Scale 0.01\n4 3\n65535\n".to_vec();
+    /// for _ in 0..12 { pgm.extend_from_slice(&12000u16.to_be_bytes()); }
+    /// let grid = GeoidGrid::from_reader(&pgm[..]).unwrap();
+    /// assert!((grid.geoid_height(121.0, 24.0) - 20.0).abs() < 1e-9);
+    /// assert!((grid.ellipsoidal_to_orthometric(121.0, 24.0, 50.0) - 30.0).abs() < 1e-9);
+    /// // a header whose sample count overflows
+    /// let huge = format!("P5\n# Offset 0\n# Scale 1\n{} 3\n65535\n", usize::MAX / 2);
+    /// assert!(GeoidGrid::from_reader(huge.as_bytes()).is_err());
+    /// ```
+    pub fn from_reader<R: Read>(mut reader:R) -> Result<GeoidGrid> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let mut pos = 0;
+        let mut offset = None;
+        let mut scale = None;
+        let mut fields = Vec::new();
+        // header: magic, width, height, maxval, with '#' comment lines in between
+        while fields.len() < 4 {
+            while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if pos >= bytes.len() {
+                return Err(format_err("truncated header"));
+            }
+            if bytes[pos] == b'#' {
+                let end = bytes[pos..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |e| pos + e);
+                let line = String::from_utf8_lossy(&bytes[pos + 1..end]).into_owned();
+                let mut words = line.split_whitespace();
+                match (words.next(), words.next().and_then(|w| w.parse::<f64>().ok())) {
+                    (Some("Offset"), Some(v)) => offset = Some(v),
+                    (Some("Scale"), Some(v)) => scale = Some(v),
+                    _ => {}
+                }
+                pos = end;
+                continue;
+            }
+            let end = bytes[pos..].iter().position(|b| b.is_ascii_whitespace()).map_or(bytes.len(), |e| pos + e);
+            fields.push(String::from_utf8_lossy(&bytes[pos..end]).into_owned());
+            pos = end;
+        }
+        if fields[0] != "P5" {
+            return Err(format_err("not a binary PGM file"));
+        }
+        let parse = |s:&str| s.parse::<usize>().map_err(|_| format_err("bad header field"));
+        let (width, height, maxval) = (parse(&fields[1])?, parse(&fields[2])?, parse(&fields[3])?);
+        if maxval != 65535 || width < 2 || height < 2 {
+            return Err(format_err("expected a 16-bit grid of at least 2 x 2"));
+        }
+        // a single whitespace byte separates the header from the samples
+        pos += 1;
+        let bytes_needed = width.checked_mul(height).and_then(|n| n.checked_mul(2))
+            .ok_or_else(|| Error::InvalidInput(format!("geoid grid: {} x {} samples overflow", width, height)))?;
+        if bytes.len() - pos.min(bytes.len()) < bytes_needed {
+            return Err(format_err("truncated data"));
+        }
+        let data = bytes[pos..pos + bytes_needed].chunks(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+        Ok(GeoidGrid {
+            width,
+            height,
+            offset: offset.ok_or_else(|| format_err("missing Offset"))?,
+            scale: scale.ok_or_else(|| format_err("missing Scale"))?,
+            data,
+        })
+    }
+
+    fn node(&self, col:usize, row:usize) -> f64 {
+        self.offset + self.scale * self.data[row * self.width + col % self.width] as f64
+    }
+
+    /// geoid undulation N in meters at a wgs84 point, bilinearly interpolated
+    pub fn geoid_height(&self, lng:f64, lat:f64) -> f64 {
+        let dlng = 360.0 / self.width as f64;
+        let dlat = 180.0 / (self.height - 1) as f64;
+        let x = normalize_lng(lng).rem_euclid(360.0) / dlng;
+        let y = ((90.0 - lat) / dlat).max(0.0).min((self.height - 1) as f64);
+        let col = x.floor() as usize;
+        let row = (y.floor() as usize).min(self.height - 2);
+        let (fx, fy) = (x - col as f64, y - row as f64);
+        let top = self.node(col, row) * (1.0 - fx) + self.node(col + 1, row) * fx;
+        let bottom = self.node(col, row + 1) * (1.0 - fx) + self.node(col + 1, row + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    /// orthometric height H = h - N from an ellipsoidal height h
+    pub fn ellipsoidal_to_orthometric(&self, lng:f64, lat:f64, h:f64) -> f64 {
+        h - self.geoid_height(lng, lat)
+    }
+
+    /// ellipsoidal height h = H + N from an orthometric height H
+    pub fn orthometric_to_ellipsoidal(&self, lng:f64, lat:f64, height:f64) -> f64 {
+        height + self.geoid_height(lng, lat)
+    }
+}
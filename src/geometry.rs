@@ -0,0 +1,348 @@
+//! simple geometry types and antimeridian-aware reprojection
+//!
+//! geometries are kept as plain `(x, y)` tuples like the rest of the crate.
+//! splitting happens in lng/lat space: project the parts with any of the
+//! forward functions, or split the output of an inverse projection directly.
+//! a projection can flip a ring or fold it over itself, so projected
+//! polygons can be checked and reoriented with [`fix_polygon`] before they are
+//! written out.
+
+use error::{Error, Result};
+use extent::ring_area;
+use longitude::{lng_diff, normalize_lng};
+
+/// a polyline of `(lng, lat)` or `(x, y)` vertices
+pub type LineString = Vec<(f64, f64)>;
+
+/// a polygon as a list of rings, the first ring is the exterior
+pub type Polygon = Vec<LineString>;
+
+/// where a geometry should be cut before reprojection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Split {
+    /// keep the geometry in one part
+    None,
+    /// cut where it crosses ±180°
+    Antimeridian,
+    /// cut where it crosses the given meridian (e.g. a zone boundary)
+    Meridian(f64),
+}
+
+impl Split {
+    fn meridian(&self) -> Option<f64> {
+        match *self {
+            Split::None => None,
+            Split::Antimeridian => Some(180.0),
+            Split::Meridian(m) => Some(m),
+        }
+    }
+}
+
+/// make longitudes continuous along a path so no step is longer than 180°
+pub(crate) fn unwrap(line:&[(f64, f64)], start:f64) -> LineString {
+    let mut out = Vec::with_capacity(line.len());
+    let mut prev_raw = start;
+    let mut prev = start;
+    for &(lng, lat) in line {
+        let cur = prev + lng_diff(lng, prev_raw);
+        out.push((cur, lat));
+        prev_raw = lng;
+        prev = cur;
+    }
+    out
+}
+
+/// shift that moves a part back into [-180, 180] as a whole
+fn wrap_shift<'a, I>(points:I) -> f64 where I: Iterator<Item=&'a (f64, f64)> {
+    let (min, max) = points.fold((f64::MAX, f64::MIN), |(a, b), p| (a.min(p.0), b.max(p.0)));
+    let mid = (min + max) / 2.0;
+    mid - normalize_lng(mid)
+}
+
+fn rewrap(part:LineString) -> LineString {
+    let shift = wrap_shift(part.iter());
+    part.into_iter().map(|(lng, lat)| (lng - shift, lat)).collect()
+}
+
+fn cut_index(lng:f64, m:f64) -> f64 {
+    ((lng - m) / 360.0).floor()
+}
+
+/// split a lng/lat linestring where it crosses the requested meridian
+/// # Examples
+///
+/// ```
+/// use mercator::geometry::{split_linestring, Split};
+/// let line = vec![(179.0, 10.0), (-179.0, 12.0)];
+/// let parts = split_linestring(&line, Split::Antimeridian);
+/// assert_eq!(parts, vec![
+///     vec![(179.0, 10.0), (180.0, 11.0)],
+///     vec![(-180.0, 11.0), (-179.0, 12.0)],
+/// ]);
+/// ```
+pub fn split_linestring(line:&[(f64, f64)], split:Split) -> Vec<LineString> {
+    let m = match split.meridian() {
+        Some(m) if line.len() > 1 => m,
+        _ => return vec![line.to_vec()],
+    };
+    let path = unwrap(line, normalize_lng(line[0].0));
+    let mut parts = Vec::new();
+    let mut part = vec![path[0]];
+    for w in path.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        let (ka, kb) = (cut_index(a.0, m), cut_index(b.0, m));
+        if ka != kb {
+            let c = m + 360.0 * ka.max(kb);
+            let t = (c - a.0) / (b.0 - a.0);
+            let cut = (c, a.1 + t * (b.1 - a.1));
+            if cut != a {
+                part.push(cut);
+            }
+            parts.push(part);
+            part = vec![cut];
+            if cut != b {
+                part.push(b);
+            }
+        } else {
+            part.push(b);
+        }
+    }
+    parts.push(part);
+    parts.into_iter().filter(|p| p.len() > 1).map(rewrap).collect()
+}
+
+/// Sutherland-Hodgman clip of an open ring against `sign * (lng - cut) >= 0`
+fn clip_ring(ring:&[(f64, f64)], cut:f64, sign:f64) -> LineString {
+    let inside = |p:&(f64, f64)| sign * (p.0 - cut) >= 0.0;
+    let mut out = Vec::new();
+    for i in 0..ring.len() {
+        let cur = ring[i];
+        let prev = ring[(i + ring.len() - 1) % ring.len()];
+        let crossing = || {
+            let t = (cut - prev.0) / (cur.0 - prev.0);
+            (cut, prev.1 + t * (cur.1 - prev.1))
+        };
+        match (inside(&prev), inside(&cur)) {
+            (true, true) => out.push(cur),
+            (true, false) => out.push(crossing()),
+            (false, true) => {
+                out.push(crossing());
+                out.push(cur);
+            }
+            (false, false) => {}
+        }
+    }
+    out.dedup();
+    out
+}
+
+/// split a lng/lat polygon where it crosses the requested meridian
+///
+/// each ring is clipped against the strips between successive cuts, so a
+/// polygon straddling ±180° becomes one polygon on each side.
+/// # Examples
+///
+/// ```
+/// use mercator::geometry::{split_polygon, Split};
+/// let square = vec![vec![(178.0, 0.0), (-178.0, 0.0), (-178.0, 2.0), (178.0, 2.0), (178.0, 0.0)]];
+/// let parts = split_polygon(&square, Split::Antimeridian);
+/// assert_eq!(parts.len(), 2);
+/// assert!(parts[0][0].iter().all(|p| p.0 >= 178.0));
+/// assert!(parts[1][0].iter().all(|p| p.0 <= -178.0));
+/// ```
+pub fn split_polygon(poly:&[LineString], split:Split) -> Vec<Polygon> {
+    let m = match split.meridian() {
+        Some(m) if !poly.is_empty() && !poly[0].is_empty() => m,
+        _ => return vec![poly.to_vec()],
+    };
+    let start = normalize_lng(poly[0][0].0);
+    let rings:Vec<(LineString, bool)> = poly.iter().map(|r| {
+        let closed = r.len() > 1 && r.first() == r.last();
+        let open = if closed { &r[..r.len() - 1] } else { &r[..] };
+        (unwrap(open, start), closed)
+    }).collect();
+    let (lo, hi) = rings[0].0.iter().fold((f64::MAX, f64::MIN), |(a, b), p| {
+        let k = cut_index(p.0, m);
+        (a.min(k), b.max(k))
+    });
+    if lo == hi {
+        return vec![poly.to_vec()];
+    }
+    let mut parts = Vec::new();
+    let mut k = lo;
+    while k <= hi {
+        let (west, east) = (m + 360.0 * k, m + 360.0 * (k + 1.0));
+        let mut strip = Vec::new();
+        for (i, (ring, closed)) in rings.iter().enumerate() {
+            let mut r = clip_ring(&clip_ring(ring, west, 1.0), east, -1.0);
+            if r.len() < 3 {
+                if i == 0 {
+                    // no exterior in this strip, so no polygon either
+                    break;
+                }
+                continue;
+            }
+            if *closed {
+                r.push(r[0]);
+            }
+            strip.push(r);
+        }
+        if !strip.is_empty() {
+            parts.push(strip);
+        }
+        k += 1.0;
+    }
+    parts.into_iter().map(|p:Polygon| {
+        let shift = wrap_shift(p.iter().flatten());
+        p.into_iter().map(|r| r.into_iter().map(|(lng, lat)| (lng - shift, lat)).collect()).collect()
+    }).collect()
+}
+
+/// split a lng/lat linestring as requested, then project every part with `f`
+/// # Examples
+///
+/// ```
+/// use mercator::wgs84_to_6degree_zone;
+/// use mercator::geometry::{project_linestring, Split};
+/// let line = vec![(179.0, -17.0), (-179.0, -16.0)];
+/// let parts = project_linestring(&line, Split::Antimeridian, |lng, lat| wgs84_to_6degree_zone(lng, lat, 177.0));
+/// assert_eq!(parts.len(), 2);
+/// ```
+pub fn project_linestring<F>(line:&[(f64, f64)], split:Split, f:F) -> Vec<LineString>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    split_linestring(line, split).into_iter()
+        .map(|part| part.into_iter().map(|(lng, lat)| f(lng, lat)).collect())
+        .collect()
+}
+
+/// split a lng/lat polygon as requested, then project every part with `f`
+pub fn project_polygon<F>(poly:&[LineString], split:Split, f:F) -> Vec<Polygon>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    split_polygon(poly, split).into_iter()
+        .map(|part| part.into_iter()
+            .map(|ring| ring.into_iter().map(|(lng, lat)| f(lng, lat)).collect())
+            .collect())
+        .collect()
+}
+
+/// orient the rings of a polygon by the right-hand rule of RFC 7946: the
+/// exterior counterclockwise and the holes clockwise
+/// # Examples
+///
+/// ```
+/// use mercator::extent::ring_area;
+/// use mercator::geometry::orient_polygon;
+/// // a southern square and its hole with the y axis flipped, as after a
+/// // projection with southing
+/// let mut poly = vec![
+///     vec![(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0), (0.0, 0.0)],
+///     vec![(2.0, 2.0), (4.0, 2.0), (4.0, 4.0), (2.0, 4.0), (2.0, 2.0)],
+/// ];
+/// orient_polygon(&mut poly);
+/// assert!(ring_area(&poly[0]) > 0.0 && ring_area(&poly[1]) < 0.0);
+/// assert_eq!(poly[0][0], (0.0, 0.0));
+/// ```
+pub fn orient_polygon(poly:&mut Polygon) {
+    for (i, ring) in poly.iter_mut().enumerate() {
+        let area = ring_area(ring);
+        if (i == 0 && area < 0.0) || (i > 0 && area > 0.0) {
+            // keep the first vertex first
+            if ring.len() > 1 && ring.first() == ring.last() {
+                ring.reverse();
+            } else if ring.len() > 1 {
+                ring[1..].reverse();
+            }
+        }
+    }
+}
+
+/// which side of the line through `a` and `b` the point `c` is on, 0 when on it
+fn orient(a:(f64, f64), b:(f64, f64), c:(f64, f64)) -> f64 {
+    ((b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)).signum()
+}
+
+/// true if `c`, known to be on the line through `a` and `b`, lies within the segment
+fn on_segment(a:(f64, f64), b:(f64, f64), c:(f64, f64)) -> bool {
+    c.0 >= a.0.min(b.0) && c.0 <= a.0.max(b.0) && c.1 >= a.1.min(b.1) && c.1 <= a.1.max(b.1)
+}
+
+fn segments_meet(p1:(f64, f64), p2:(f64, f64), q1:(f64, f64), q2:(f64, f64)) -> bool {
+    let (d1, d2) = (orient(q1, q2, p1), orient(q1, q2, p2));
+    let (d3, d4) = (orient(p1, p2, q1), orient(p1, p2, q2));
+    if d1 * d2 < 0.0 && d3 * d4 < 0.0 {
+        return true;
+    }
+    (d1 == 0.0 && on_segment(q1, q2, p1)) || (d2 == 0.0 && on_segment(q1, q2, p2))
+        || (d3 == 0.0 && on_segment(p1, p2, q1)) || (d4 == 0.0 && on_segment(p1, p2, q2))
+}
+
+/// pairs of edges of a ring, closed or not, that cross or touch other than
+/// at the vertex they share; edge `i` runs from vertex `i` to the next one
+/// # Examples
+///
+/// ```
+/// use mercator::geometry::self_intersections;
+/// let square = vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0), (0.0, 0.0)];
+/// assert!(self_intersections(&square).is_empty());
+/// let bowtie = vec![(0.0, 0.0), (10.0, 10.0), (10.0, 0.0), (0.0, 10.0)];
+/// assert_eq!(self_intersections(&bowtie), vec![(0, 2)]);
+/// ```
+pub fn self_intersections(ring:&[(f64, f64)]) -> Vec<(usize, usize)> {
+    let mut points:LineString = Vec::with_capacity(ring.len());
+    for &p in ring {
+        if points.last() != Some(&p) {
+            points.push(p);
+        }
+    }
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    let n = points.len();
+    let mut found = Vec::new();
+    if n < 4 {
+        return found;
+    }
+    for i in 0..n {
+        // edges next to each other share a vertex, and the last edge is next to the first
+        for j in i + 2..n {
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            if segments_meet(points[i], points[(i + 1) % n], points[j], points[(j + 1) % n]) {
+                found.push((i, j));
+            }
+        }
+    }
+    found
+}
+
+/// check a polygon for rings crossing themselves and orient its rings with
+/// [`orient_polygon`], meant for the output of a projection
+///
+/// fails with [`Error::InvalidInput`] naming the first ring that crosses
+/// itself; the edge indices count vertices after repeated ones are dropped.
+/// # Examples
+///
+/// ```
+/// use mercator::geometry::fix_polygon;
+/// use mercator::crs::Transformer;
+/// let t = Transformer::from_epsg(4326, 3826).unwrap();
+/// let ring = vec![(121.0, 24.0), (121.0, 24.1), (121.1, 24.1), (121.1, 24.0), (121.0, 24.0)];
+/// let mut poly = vec![ring.iter().map(|p| t.transform(p.0, p.1)).collect()];
+/// fix_polygon(&mut poly).unwrap();
+/// assert!(poly[0][1].0 > poly[0][0].0);
+///
+/// let mut bowtie = vec![vec![(0.0, 0.0), (10.0, 10.0), (10.0, 0.0), (0.0, 10.0)]];
+/// assert!(fix_polygon(&mut bowtie).is_err());
+/// ```
+pub fn fix_polygon(poly:&mut Polygon) -> Result<()> {
+    for (i, ring) in poly.iter().enumerate() {
+        if let Some(&(a, b)) = self_intersections(ring).first() {
+            return Err(Error::InvalidInput(format!("ring {} crosses itself at edges {} and {}", i, a, b)));
+        }
+    }
+    orient_polygon(poly);
+    Ok(())
+}
@@ -0,0 +1,317 @@
+//! GeoParquet reprojection (feature `geoparquet`)
+//!
+//! the input is streamed one record batch at a time: every geometry column
+//! listed in the `geo` metadata is transformed, either WKB or GeoArrow native,
+//! and the metadata is rewritten with the new crs and bounding boxes.
+
+use std::cell::Cell;
+use std::fs::File;
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow_array::{Array, ArrayRef, GenericBinaryArray, GenericListArray, OffsetSizeTrait, RecordBatch};
+use arrow_array::cast::AsArray;
+use arrow_array::types::Float64Type;
+use arrow_schema::{DataType, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::file::metadata::KeyValue;
+use parquet::file::reader::ChunkReader;
+use serde_json::Value;
+
+use arrow::transform_geoarrow;
+use error::{same_file, Error, Result};
+use job::Job;
+use trace::Activity;
+use wkb::transform_wkb;
+
+fn parquet_err(e:parquet::errors::ParquetError) -> Error {
+    Error::Format(format!("parquet: {}", e))
+}
+
+fn arrow_err(e:arrow_schema::ArrowError) -> Error {
+    Error::InvalidInput(format!("arrow: {}", e))
+}
+
+/// running bounding box of the transformed coordinates
+struct Bbox {
+    min:Cell<(f64, f64)>,
+    max:Cell<(f64, f64)>,
+}
+
+impl Bbox {
+    fn new() -> Bbox {
+        Bbox { min: Cell::new((f64::MAX, f64::MAX)), max: Cell::new((f64::MIN, f64::MIN)) }
+    }
+
+    fn add(&self, x:f64, y:f64) {
+        let (min, max) = (self.min.get(), self.max.get());
+        self.min.set((min.0.min(x), min.1.min(y)));
+        self.max.set((max.0.max(x), max.1.max(y)));
+    }
+
+    fn to_json(&self) -> Option<Value> {
+        let (min, max) = (self.min.get(), self.max.get());
+        if min.0 > max.0 {
+            return None;
+        }
+        Some(Value::from(vec![min.0, min.1, max.0, max.1]))
+    }
+}
+
+fn transform_binary<O: OffsetSizeTrait, F>(wkb:&GenericBinaryArray<O>, f:&F) -> Result<ArrayRef>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let offsets = wkb.offsets();
+    let mut values = wkb.values().to_vec();
+    for i in 0..wkb.len() {
+        if wkb.is_null(i) {
+            continue;
+        }
+        let (start, end) = (offsets[i].as_usize(), offsets[i + 1].as_usize());
+        transform_wkb(&mut values[start..end], f)?;
+    }
+    let out = GenericBinaryArray::<O>::try_new(offsets.clone(), values.into(), wkb.nulls().cloned()).map_err(arrow_err)?;
+    Ok(Arc::new(out))
+}
+
+/// add the coordinates of the valid rows `rows` of a GeoArrow native array to `bbox`
+fn add_valid(array:&dyn Array, rows:Range<usize>, bbox:&Bbox) {
+    match array.data_type() {
+        DataType::Struct(_) => {
+            let points = array.as_struct();
+            let (x, y) = (points.column(0).as_primitive::<Float64Type>(), points.column(1).as_primitive::<Float64Type>());
+            for i in rows.filter(|&i| points.is_valid(i)) {
+                bbox.add(x.value(i), y.value(i));
+            }
+        }
+        DataType::FixedSizeList(_, _) => {
+            let points = array.as_fixed_size_list();
+            let dim = points.value_length() as usize;
+            let values = points.values().as_primitive::<Float64Type>();
+            for i in rows.filter(|&i| points.is_valid(i)) {
+                bbox.add(values.value(i * dim), values.value(i * dim + 1));
+            }
+        }
+        DataType::List(_) => add_valid_lists(array.as_list::<i32>(), rows, bbox),
+        DataType::LargeList(_) => add_valid_lists(array.as_list::<i64>(), rows, bbox),
+        _ => {}
+    }
+}
+
+fn add_valid_lists<O: OffsetSizeTrait>(lists:&GenericListArray<O>, rows:Range<usize>, bbox:&Bbox) {
+    let offsets = lists.offsets();
+    for i in rows.filter(|&i| lists.is_valid(i)) {
+        add_valid(lists.values().as_ref(), offsets[i].as_usize()..offsets[i + 1].as_usize(), bbox);
+    }
+}
+
+/// transform a geometry column, adding the coordinates of its non-null rows to `bbox`
+fn transform_column<F>(column:&dyn Array, f:&F, bbox:&Bbox) -> Result<ArrayRef>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let g = |x, y| {
+        let (x, y) = f(x, y);
+        bbox.add(x, y);
+        (x, y)
+    };
+    match column.data_type() {
+        // null rows are skipped before their bytes are parsed
+        DataType::Binary => transform_binary(column.as_binary::<i32>(), &g),
+        DataType::LargeBinary => transform_binary(column.as_binary::<i64>(), &g),
+        _ => {
+            // native arrays transform every child value, null rows included
+            let out = transform_geoarrow(column, f)?;
+            add_valid(out.as_ref(), 0..out.len(), bbox);
+            Ok(out)
+        }
+    }
+}
+
+/// reproject every geometry column of a GeoParquet file
+///
+/// `crs` replaces the crs of each geometry column: PROJJSON, or `Value::Null`
+/// for an undefined crs. bounding boxes are recomputed from the output and
+/// `covering` bbox columns are dropped from the metadata since their values
+/// are not reprojected. returns the number of rows written.
+/// # Examples
+///
+/// ```
+/// # extern crate arrow_array;
+/// # extern crate arrow_schema;
+/// # extern crate parquet;
+/// # extern crate serde_json;
+/// # extern crate mercator;
+/// use std::collections::HashMap;
+/// use std::sync::Arc;
+/// use arrow_array::{BinaryArray, RecordBatch};
+/// use arrow_schema::{DataType, Field, Schema};
+/// use parquet::arrow::ArrowWriter;
+/// use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+/// use mercator::geoparquet::reproject_geoparquet;
+/// use mercator::wgs84_to_twd97;
+///
+/// let mut point = vec![1u8, 1, 0, 0, 0];
+/// point.extend_from_slice(&121f64.to_le_bytes());
+/// point.extend_from_slice(&24f64.to_le_bytes());
+/// let geo = r#"{"version":"1.1.0","primary_column":"geometry","columns":{"geometry":{"encoding":"WKB","geometry_types":["Point"]}}}"#;
+/// let schema = Arc::new(Schema::new_with_metadata(vec![Field::new("geometry", DataType::Binary, false)],
+///     HashMap::from([("geo".to_string(), geo.to_string())])));
+/// let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(BinaryArray::from_vec(vec![&point]))]).unwrap();
+/// let src = std::env::temp_dir().join("mercator-doc-wgs84.parquet");
+/// let mut writer = ArrowWriter::try_new(std::fs::File::create(&src).unwrap(), schema, None).unwrap();
+/// writer.write(&batch).unwrap();
+/// writer.close().unwrap();
+///
+/// let dst = std::env::temp_dir().join("mercator-doc-twd97.parquet");
+/// let crs = serde_json::json!({"id": {"authority": "EPSG", "code": 3826}});
+/// let rows = reproject_geoparquet(std::fs::File::open(&src).unwrap(), std::fs::File::create(&dst).unwrap(), crs, wgs84_to_twd97);
+/// assert_eq!(rows.unwrap(), 1);
+///
+/// let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&dst).unwrap()).unwrap();
+/// let geo:serde_json::Value = serde_json::from_str(&reader.schema().metadata()["geo"]).unwrap();
+/// assert_eq!(geo["columns"]["geometry"]["crs"]["id"]["code"], 3826);
+/// assert!((geo["columns"]["geometry"]["bbox"][0].as_f64().unwrap() - 250000.0).abs() < 1e-6);
+/// ```
+pub fn reproject_geoparquet<R, W, F>(input:R, output:W, crs:Value, f:F) -> Result<usize>
+    where R: ChunkReader + 'static, W: Write + Send, F: Fn(f64, f64) -> (f64, f64)
+{
+    reproject_geoparquet_with(input, output, crs, f, &mut Job::new())
+}
+
+/// [`reproject_geoparquet`] as a [`Job`], checked and reported after every record batch
+///
+/// a cancelled job still closes the output, holding the rows written so far.
+/// # Examples
+///
+/// ```
+/// # extern crate arrow_array;
+/// # extern crate arrow_buffer;
+/// # extern crate arrow_schema;
+/// # extern crate parquet;
+/// # extern crate serde_json;
+/// # extern crate mercator;
+/// use std::collections::HashMap;
+/// use std::sync::Arc;
+/// use arrow_array::{Array, FixedSizeListArray, Float64Array, RecordBatch};
+/// use arrow_buffer::NullBuffer;
+/// use arrow_schema::{DataType, Field, Schema};
+/// use parquet::arrow::ArrowWriter;
+/// use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+/// use mercator::geoparquet::reproject_geoparquet_with;
+/// use mercator::job::Job;
+/// use mercator::wgs84_to_twd97;
+///
+/// // a GeoArrow point column whose second row is null over a (0, 0) slot
+/// let values = Float64Array::from(vec![121.0, 24.0, 0.0, 0.0]);
+/// let points = FixedSizeListArray::new(Arc::new(Field::new("xy", DataType::Float64, false)), 2,
+///     Arc::new(values), Some(NullBuffer::from(vec![true, false])));
+/// let geo = r#"{"version":"1.1.0","primary_column":"geometry","columns":{"geometry":{"encoding":"point","geometry_types":["Point"]}}}"#;
+/// let schema = Arc::new(Schema::new_with_metadata(vec![Field::new("geometry", points.data_type().clone(), true)],
+///     HashMap::from([("geo".to_string(), geo.to_string())])));
+/// let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(points)]).unwrap();
+/// let src = std::env::temp_dir().join("mercator-doc-null-points.parquet");
+/// let mut writer = ArrowWriter::try_new(std::fs::File::create(&src).unwrap(), schema, None).unwrap();
+/// writer.write(&batch).unwrap();
+/// writer.close().unwrap();
+///
+/// let dst = std::env::temp_dir().join("mercator-doc-null-points-twd97.parquet");
+/// let (input, output) = (std::fs::File::open(&src).unwrap(), std::fs::File::create(&dst).unwrap());
+/// reproject_geoparquet_with(input, output, serde_json::Value::Null, wgs84_to_twd97, &mut Job::new()).unwrap();
+/// let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&dst).unwrap()).unwrap();
+/// let geo:serde_json::Value = serde_json::from_str(&reader.schema().metadata()["geo"]).unwrap();
+/// // the null row does not widen the box
+/// let bbox = &geo["columns"]["geometry"]["bbox"];
+/// assert!((bbox[0].as_f64().unwrap() - 250000.0).abs() < 1e-6);
+/// assert_eq!(bbox[0], bbox[2]);
+/// ```
+pub fn reproject_geoparquet_with<R, W, F>(input:R, output:W, crs:Value, f:F, job:&mut Job) -> Result<usize>
+    where R: ChunkReader + 'static, W: Write + Send, F: Fn(f64, f64) -> (f64, f64)
+{
+    let reader = ParquetRecordBatchReaderBuilder::try_new(input).map_err(parquet_err)?;
+    let schema = reader.schema().clone();
+    let total = reader.metadata().file_metadata().num_rows().max(0) as u64;
+    let activity = Activity::start("reproject_geoparquet", total);
+    let mut geo:Value = match schema.metadata().get("geo") {
+        Some(geo) => serde_json::from_str(geo).map_err(|e| Error::Format(format!("geo metadata: {}", e)))?,
+        None => return Err(Error::Format("not a GeoParquet file: no geo metadata".to_string())),
+    };
+    let names:Vec<String> = match geo["columns"].as_object() {
+        Some(columns) => columns.keys().cloned().collect(),
+        None => return Err(Error::Format("geo metadata has no columns".to_string())),
+    };
+    let mut columns = Vec::new();
+    for name in &names {
+        let index = schema.index_of(name).map_err(arrow_err)?;
+        columns.push((index, name.clone(), Bbox::new()));
+    }
+
+    // `geo` is written once at the end, when the bounding boxes are known
+    let mut metadata = schema.metadata().clone();
+    metadata.remove("geo");
+    let out_schema = Arc::new(Schema::new_with_metadata(schema.fields().clone(), metadata));
+    let mut writer = ArrowWriter::try_new(output, out_schema.clone(), None).map_err(parquet_err)?;
+    let mut rows = 0;
+    for batch in reader.build().map_err(parquet_err)? {
+        if job.is_cancelled() {
+            break;
+        }
+        let batch = batch.map_err(arrow_err)?;
+        let mut arrays = batch.columns().to_vec();
+        for &(index, _, ref bbox) in &columns {
+            arrays[index] = transform_column(batch.column(index).as_ref(), &f, bbox)?;
+        }
+        let batch = RecordBatch::try_new(out_schema.clone(), arrays).map_err(arrow_err)?;
+        writer.write(&batch).map_err(parquet_err)?;
+        rows += batch.num_rows();
+        job.report(rows as u64, total);
+    }
+
+    for (_, name, bbox) in &columns {
+        let column = &mut geo["columns"][name.as_str()];
+        column["crs"] = crs.clone();
+        if let Some(obj) = column.as_object_mut() {
+            obj.remove("covering");
+            match bbox.to_json() {
+                Some(b) => obj.insert("bbox".to_string(), b),
+                None => obj.remove("bbox"),
+            };
+        }
+    }
+    writer.append_key_value_metadata(KeyValue::new("geo".to_string(), geo.to_string()));
+    writer.close().map_err(parquet_err)?;
+    activity.finish(rows as u64, 0);
+    Ok(rows)
+}
+
+/// [`reproject_geoparquet`] from one file path to another
+///
+/// fails with [`Error::InvalidInput`] when both paths name the same file.
+/// # Examples
+///
+/// ```
+/// # extern crate serde_json;
+/// # extern crate mercator;
+/// use mercator::Error;
+/// use mercator::geoparquet::reproject_file;
+/// let path = std::env::temp_dir().join("mercator-doc-same.parquet");
+/// std::fs::write(&path, b"PAR1").unwrap();
+/// let err = reproject_file(&path, &path, serde_json::Value::Null, |x, y| (x, y)).unwrap_err();
+/// assert!(matches!(err, Error::InvalidInput(_)));
+/// assert_eq!(std::fs::read(&path).unwrap(), b"PAR1");
+/// ```
+pub fn reproject_file<P, Q, F>(src:P, dst:Q, crs:Value, f:F) -> Result<usize>
+    where P: AsRef<Path>, Q: AsRef<Path>, F: Fn(f64, f64) -> (f64, f64)
+{
+    if same_file(src.as_ref(), dst.as_ref()) {
+        return Err(Error::InvalidInput(format!("{} is both the source and the destination", src.as_ref().display())));
+    }
+    let activity = Activity::file("reproject_geoparquet_file", src.as_ref());
+    let input = File::open(src)?;
+    let output = File::create(dst)?;
+    let rows = reproject_geoparquet(input, output, crs, f)?;
+    activity.finish(rows as u64, 0);
+    Ok(rows)
+}
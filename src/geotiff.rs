@@ -0,0 +1,160 @@
+//! GeoTIFF georeferencing (feature `geotiff`)
+//!
+//! reads the tie point / pixel scale (or model transformation) tags and the
+//! CRS geo keys of a GeoTIFF, and converts between pixels, the file's CRS and
+//! wgs84 using the projections of this crate.
+
+use std::io::{Read, Seek};
+
+use tiff::decoder::Decoder;
+use tiff::tags::Tag;
+
+use affine::Affine2D;
+use error::{Error, Result};
+use trace::Activity;
+use worldfile::WorldFile;
+use zone::Zone;
+
+const GT_MODEL_TYPE:u16 = 1024;
+const GT_RASTER_TYPE:u16 = 1025;
+const GEOGRAPHIC_TYPE:u16 = 2048;
+const PROJECTED_CS_TYPE:u16 = 3072;
+const PIXEL_IS_POINT:u16 = 2;
+
+fn tiff_err(e:tiff::TiffError) -> Error {
+    Error::Format(format!("geotiff: {}", e))
+}
+
+/// georeferencing information of a GeoTIFF image
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoTiff {
+    pub width:u32,
+    pub height:u32,
+    /// maps pixel centers (col, row) to CRS coordinates, like a world file
+    pub transform:Affine2D,
+    /// EPSG code of the projected or geographic CRS, if given
+    pub epsg:Option<u32>,
+    /// true for a geographic (lng/lat) model, false for a projected one
+    pub geographic:bool,
+}
+
+impl GeoTiff {
+    /// read the georeferencing of the first image in a GeoTIFF
+    pub fn from_reader<R: Read + Seek>(reader:R) -> Result<GeoTiff> {
+        let mut dec = Decoder::new(reader).map_err(tiff_err)?;
+        let (width, height) = dec.dimensions().map_err(tiff_err)?;
+        let keys = match dec.find_tag(Tag::GeoKeyDirectoryTag).map_err(tiff_err)? {
+            Some(v) => v.into_u16_vec().map_err(tiff_err)?,
+            None => Vec::new(),
+        };
+        let key = |id:u16| keys.get(4..).and_then(|entries| {
+            // (key, location, count, value); only values stored inline (location 0)
+            entries.chunks(4).find(|k| k.len() == 4 && k[0] == id && k[1] == 0).map(|k| k[3])
+        });
+        let geographic = key(GT_MODEL_TYPE) == Some(2);
+        let epsg = if geographic { key(GEOGRAPHIC_TYPE) } else { key(PROJECTED_CS_TYPE) }
+            .filter(|&c| c != 0 && c != 32767)
+            .map(|c| c as u32);
+        // PixelIsArea rasters put (0, 0) at the outer corner of the first pixel
+        let center = if key(GT_RASTER_TYPE) == Some(PIXEL_IS_POINT) { 0.0 } else { 0.5 };
+
+        let raster = if let Some(m) = dec.find_tag(Tag::ModelTransformationTag).map_err(tiff_err)? {
+            let m = m.into_f64_vec().map_err(tiff_err)?;
+            if m.len() < 8 {
+                return Err(Error::Format("geotiff: short model transformation".to_string()));
+            }
+            Affine2D::new(m[0], m[1], m[3], m[4], m[5], m[7])
+        } else {
+            let scale = dec.get_tag_f64_vec(Tag::ModelPixelScaleTag).map_err(tiff_err)?;
+            let tie = dec.get_tag_f64_vec(Tag::ModelTiepointTag).map_err(tiff_err)?;
+            if scale.len() < 2 || tie.len() < 6 {
+                return Err(Error::Format("geotiff: short tie point or pixel scale".to_string()));
+            }
+            let (sx, sy) = (scale[0], scale[1]);
+            Affine2D::new(sx, 0.0, tie[3] - tie[0] * sx, 0.0, -sy, tie[4] + tie[1] * sy)
+        };
+        let (cx, cy) = raster.apply(center, center);
+        let transform = Affine2D { c: cx, f: cy, ..raster };
+        Ok(GeoTiff { width, height, transform, epsg, geographic })
+    }
+
+    pub fn open<P: AsRef<::std::path::Path>>(path:P) -> Result<GeoTiff> {
+        let activity = Activity::file("read_geotiff", path.as_ref());
+        let tiff = GeoTiff::from_reader(::std::io::BufReader::new(::std::fs::File::open(path)?))?;
+        activity.finish(0, 0);
+        Ok(tiff)
+    }
+
+    /// the equivalent world file
+    pub fn world_file(&self) -> WorldFile {
+        WorldFile { transform: self.transform }
+    }
+
+    /// CRS coordinate of a pixel center
+    pub fn pixel_to_crs(&self, col:f64, row:f64) -> (f64, f64) {
+        self.transform.apply(col, row)
+    }
+
+    /// pixel position of a CRS coordinate
+    pub fn crs_to_pixel(&self, x:f64, y:f64) -> Result<(f64, f64)> {
+        self.transform.inverse().map(|t| t.apply(x, y))
+            .ok_or_else(|| Error::Format("geotiff: singular pixel transform".to_string()))
+    }
+
+    fn zone(&self) -> Result<Option<Zone>> {
+        match self.epsg {
+            Some(4326) | Some(3824) if self.geographic => Ok(None),
+            Some(code) if !self.geographic => Zone::from_epsg(code).map(Some)
+                .ok_or_else(|| Error::Transform(format!("unsupported CRS EPSG:{}", code))),
+            Some(code) => Err(Error::Transform(format!("unsupported CRS EPSG:{}", code))),
+            None => Err(Error::Transform("GeoTIFF has no EPSG CRS code".to_string())),
+        }
+    }
+
+    /// wgs84 lng/lat of a pixel center
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tiff;
+    /// # extern crate mercator;
+    /// use std::io::Cursor;
+    /// use tiff::encoder::{colortype, TiffEncoder};
+    /// use tiff::tags::Tag;
+    /// use mercator::geotiff::GeoTiff;
+    ///
+    /// // a 4 x 4 TWD97 image with 10 m pixels
+    /// let mut buf = Cursor::new(Vec::new());
+    /// {
+    ///     let mut enc = TiffEncoder::new(&mut buf).unwrap();
+    ///     let mut img = enc.new_image::<colortype::Gray8>(4, 4).unwrap();
+    ///     img.encoder().write_tag(Tag::ModelPixelScaleTag, &[10.0f64, 10.0, 0.0][..]).unwrap();
+    ///     img.encoder().write_tag(Tag::ModelTiepointTag, &[0.0f64, 0.0, 0.0, 300000.0, 2770000.0, 0.0][..]).unwrap();
+    ///     img.encoder().write_tag(Tag::GeoKeyDirectoryTag,
+    ///         &[1u16, 1, 0, 3, 1024, 0, 1, 1, 1025, 0, 1, 1, 3072, 0, 1, 3826][..]).unwrap();
+    ///     img.write_data(&[0u8; 16]).unwrap();
+    /// }
+    /// buf.set_position(0);
+    /// let gt = GeoTiff::from_reader(buf).unwrap();
+    /// assert_eq!(gt.epsg, Some(3826));
+    /// assert_eq!(gt.pixel_to_crs(0.0, 0.0), (300005.0, 2769995.0));
+    /// let (lng, lat) = gt.pixel_to_lnglat(0.0, 0.0).unwrap();
+    /// let (col, row) = gt.lnglat_to_pixel(lng, lat).unwrap();
+    /// assert!(col.abs() < 1e-3 && row.abs() < 1e-3);
+    /// ```
+    pub fn pixel_to_lnglat(&self, col:f64, row:f64) -> Result<(f64, f64)> {
+        let (x, y) = self.pixel_to_crs(col, row);
+        Ok(match self.zone()? {
+            Some(zone) => zone.inverse(x, y),
+            None => (x, y),
+        })
+    }
+
+    /// pixel position of a wgs84 lng/lat
+    pub fn lnglat_to_pixel(&self, lng:f64, lat:f64) -> Result<(f64, f64)> {
+        let (x, y) = match self.zone()? {
+            Some(zone) => zone.forward(lng, lat),
+            None => (lng, lat),
+        };
+        self.crs_to_pixel(x, y)
+    }
+}
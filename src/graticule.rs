@@ -0,0 +1,277 @@
+//! graticules, kilometer grids and zone boundaries for map frames
+//!
+//! the lines are generated in lng/lat inside a bounding box and densified, so
+//! they keep their curve once projected: on a TWD97 or UTM map meridians
+//! away from the central meridian lean and parallels bow. a [`Grid`] does the
+//! same for the easting and northing lines of a projected CRS, straight on
+//! their own grid and curved on any other map, as in MGRS style overlays.
+
+use crs::Transformer;
+use error::{Error, Result};
+use extent::Extent;
+use geometry::{LineString, Polygon};
+use longitude::normalize_lng;
+use zone::Zone;
+
+/// what a grid line follows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridValue {
+    /// a meridian, longitude in degrees
+    Meridian(f64),
+    /// a parallel, latitude in degrees
+    Parallel(f64),
+    /// a grid line of constant easting
+    Easting(f64),
+    /// a grid line of constant northing
+    Northing(f64),
+}
+
+/// one line of a grid with its label value
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridLine {
+    pub value:GridValue,
+    pub line:LineString,
+}
+
+/// `value` when it is finite and above zero, as spacings and steps must be
+pub(crate) fn check_spacing(what:&str, value:f64) -> Result<f64> {
+    if value.is_finite() && value > 0.0 {
+        Ok(value)
+    } else {
+        Err(Error::InvalidInput(format!("{} must be finite and above zero, not {}", what, value)))
+    }
+}
+
+/// multiples of `spacing` from `min` to `max`, both included
+pub(crate) fn ticks(min:f64, max:f64, spacing:f64) -> Vec<f64> {
+    let (first, last) = ((min / spacing).ceil() as i64, (max / spacing).floor() as i64);
+    (first..=last).map(|k| k as f64 * spacing).collect()
+}
+
+/// points from `from` to `to` no further apart than `step`, both ends included
+fn densify(from:f64, to:f64, step:f64) -> Vec<f64> {
+    let n = ((to - from).abs() / step).ceil().max(1.0) as usize;
+    (0..=n).map(|i| if i == n { to } else { from + (to - from) * i as f64 / n as f64 }).collect()
+}
+
+/// a graticule with its spacing and densification in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Graticule {
+    lng_spacing:f64,
+    lat_spacing:f64,
+    step:f64,
+}
+
+impl Graticule {
+    /// meridians every `lng_spacing` and parallels every `lat_spacing` degrees,
+    /// with a vertex every tenth of the smaller spacing
+    ///
+    /// fails with [`Error::InvalidInput`] unless both spacings are finite and above zero.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::graticule::Graticule;
+    /// assert!(Graticule::new(1.0, 0.5).is_ok());
+    /// assert!(Graticule::new(0.0, 1.0).is_err());
+    /// assert!(Graticule::new(1.0, f64::NAN).is_err());
+    /// assert!(Graticule::new(1.0, 1.0).unwrap().with_densify(-0.1).is_err());
+    /// ```
+    pub fn new(lng_spacing:f64, lat_spacing:f64) -> Result<Graticule> {
+        let lng_spacing = check_spacing("graticule spacing", lng_spacing)?;
+        let lat_spacing = check_spacing("graticule spacing", lat_spacing)?;
+        Ok(Graticule { lng_spacing, lat_spacing, step: lng_spacing.min(lat_spacing) / 10.0 })
+    }
+
+    /// at most `step` degrees between the vertices of a line, finite and above zero
+    pub fn with_densify(mut self, step:f64) -> Result<Graticule> {
+        self.step = check_spacing("graticule densify step", step)?;
+        Ok(self)
+    }
+
+    /// the lines inside a lng/lat bounding box, meridians west to east, then parallels south to north
+    ///
+    /// a box over the antimeridian has `max_x` above 180; the line longitudes
+    /// stay continuous and the meridian values are normalized.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::extent::Extent;
+    /// use mercator::graticule::{Graticule, GridValue};
+    /// let lines = Graticule::new(1.0, 1.0).unwrap().lines(Extent::new(119.5, 21.5, 122.5, 25.5));
+    /// assert_eq!(lines.len(), 3 + 4);
+    /// assert_eq!(lines[0].value, GridValue::Meridian(120.0));
+    /// assert_eq!(lines[3].value, GridValue::Parallel(22.0));
+    /// // a vertex every 0.1°
+    /// assert_eq!(lines[0].line.len(), 41);
+    /// assert_eq!(lines[0].line[40], (120.0, 25.5));
+    /// ```
+    pub fn lines(&self, bbox:Extent) -> Vec<GridLine> {
+        let mut lines = Vec::new();
+        for lng in ticks(bbox.min_x, bbox.max_x, self.lng_spacing) {
+            lines.push(GridLine {
+                value: GridValue::Meridian(normalize_lng(lng)),
+                line: densify(bbox.min_y, bbox.max_y, self.step).into_iter().map(|lat| (lng, lat)).collect(),
+            });
+        }
+        for lat in ticks(bbox.min_y, bbox.max_y, self.lat_spacing) {
+            lines.push(GridLine {
+                value: GridValue::Parallel(lat),
+                line: densify(bbox.min_x, bbox.max_x, self.step).into_iter().map(|lng| (lng, lat)).collect(),
+            });
+        }
+        lines
+    }
+
+    /// [`Graticule::lines`] projected with a transformer from lng/lat
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Transformer;
+    /// use mercator::extent::Extent;
+    /// use mercator::graticule::{Graticule, GridValue};
+    /// let t = Transformer::from_epsg(4326, 3826).unwrap();
+    /// let lines = Graticule::new(1.0, 1.0).unwrap().project(Extent::new(119.5, 21.5, 122.5, 25.5), &t).unwrap();
+    /// // the central meridian is straight up the grid, the others lean in towards the pole
+    /// let cm = &lines[1];
+    /// assert_eq!(cm.value, GridValue::Meridian(121.0));
+    /// assert!(cm.line.iter().all(|p| (p.0 - 250000.0).abs() < 1e-6));
+    /// let west = &lines[0].line;
+    /// assert!(west[west.len() - 1].0 > west[0].0);
+    /// ```
+    pub fn project(&self, bbox:Extent, transformer:&Transformer) -> Result<Vec<GridLine>> {
+        project_lines(self.lines(bbox), transformer)
+    }
+}
+
+/// grid lines of a projected CRS, e.g. every kilometer of TWD97
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    spacing:f64,
+    step:f64,
+}
+
+impl Grid {
+    /// lines every `spacing` units of the grid, with a vertex every tenth of it
+    ///
+    /// fails with [`Error::InvalidInput`] unless the spacing is finite and above zero.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::graticule::Grid;
+    /// assert!(Grid::new(1000.0).is_ok());
+    /// assert!(Grid::new(-1000.0).is_err());
+    /// assert!(Grid::new(1000.0).unwrap().with_densify(f64::INFINITY).is_err());
+    /// ```
+    pub fn new(spacing:f64) -> Result<Grid> {
+        let spacing = check_spacing("grid spacing", spacing)?;
+        Ok(Grid { spacing, step: spacing / 10.0 })
+    }
+
+    /// at most `step` grid units between the vertices of a line, finite and above zero
+    pub fn with_densify(mut self, step:f64) -> Result<Grid> {
+        self.step = check_spacing("grid densify step", step)?;
+        Ok(self)
+    }
+
+    /// the lines inside a bounding box of grid coordinates, eastings west to
+    /// east, then northings south to north
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::extent::Extent;
+    /// use mercator::graticule::{Grid, GridValue};
+    /// let lines = Grid::new(1000.0).unwrap().lines(Extent::new(249500.0, 2654200.0, 252500.0, 2656900.0));
+    /// assert_eq!(lines.len(), 3 + 2);
+    /// assert_eq!(lines[0].value, GridValue::Easting(250000.0));
+    /// assert_eq!(lines[4].value, GridValue::Northing(2656000.0));
+    /// assert_eq!(lines[0].line[0], (250000.0, 2654200.0));
+    /// ```
+    pub fn lines(&self, bbox:Extent) -> Vec<GridLine> {
+        let mut lines = Vec::new();
+        for x in ticks(bbox.min_x, bbox.max_x, self.spacing) {
+            lines.push(GridLine {
+                value: GridValue::Easting(x),
+                line: densify(bbox.min_y, bbox.max_y, self.step).into_iter().map(|y| (x, y)).collect(),
+            });
+        }
+        for y in ticks(bbox.min_y, bbox.max_y, self.spacing) {
+            lines.push(GridLine {
+                value: GridValue::Northing(y),
+                line: densify(bbox.min_x, bbox.max_x, self.step).into_iter().map(|x| (x, y)).collect(),
+            });
+        }
+        lines
+    }
+
+    /// [`Grid::lines`] drawn on another map, with a transformer from the grid CRS
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Transformer;
+    /// use mercator::extent::Extent;
+    /// use mercator::graticule::Grid;
+    /// // the TWD97 kilometer grid drawn on UTM 51N
+    /// let t = Transformer::from_epsg(3826, 32651).unwrap();
+    /// let lines = Grid::new(1000.0).unwrap().project(Extent::new(300000.0, 2770000.0, 305000.0, 2775000.0), &t).unwrap();
+    /// assert_eq!(lines.len(), 12);
+    /// // an easting line is no longer vertical
+    /// let line = &lines[0].line;
+    /// assert!((line[line.len() - 1].0 - line[0].0).abs() > 10.0);
+    /// ```
+    pub fn project(&self, bbox:Extent, transformer:&Transformer) -> Result<Vec<GridLine>> {
+        project_lines(self.lines(bbox), transformer)
+    }
+}
+
+fn project_lines(lines:Vec<GridLine>, transformer:&Transformer) -> Result<Vec<GridLine>> {
+    lines.into_iter().map(|l| {
+        let line = l.line.into_iter().map(|(x, y)| transformer.try_transform(x, y)).collect::<Result<_>>()?;
+        Ok(GridLine { value: l.value, line })
+    }).collect()
+}
+
+/// the outline of a zone in lng/lat between two latitudes, its edge
+/// meridians half a zone width either side of the central meridian, with a
+/// vertex at most every `step` degrees; the ring is counterclockwise and closed
+///
+/// fails with [`Error::InvalidInput`] unless the step is finite and above zero.
+/// # Examples
+///
+/// ```
+/// use mercator::graticule::zone_boundary;
+/// use mercator::zone::Zone;
+/// let ring = &zone_boundary(&Zone::utm(51), 0.0, 8.0, 1.0).unwrap()[0];
+/// assert_eq!(ring[0], (120.0, 0.0));
+/// assert_eq!(ring[6], (126.0, 0.0));
+/// assert_eq!(ring.len(), 7 + 8 + 6 + 8);
+/// assert_eq!(ring[0], ring[ring.len() - 1]);
+/// assert!(zone_boundary(&Zone::utm(51), 0.0, 8.0, 0.0).is_err());
+/// ```
+pub fn zone_boundary(zone:&Zone, south:f64, north:f64, step:f64) -> Result<Polygon> {
+    let step = check_spacing("zone boundary step", step)?;
+    let half = zone.width.degrees() / 2.0;
+    let (west, east) = (zone.center_lng - half, zone.center_lng + half);
+    let mut ring:LineString = densify(west, east, step).into_iter().map(|lng| (lng, south)).collect();
+    ring.extend(densify(south, north, step).into_iter().skip(1).map(|lat| (east, lat)));
+    ring.extend(densify(east, west, step).into_iter().skip(1).map(|lng| (lng, north)));
+    ring.extend(densify(north, south, step).into_iter().skip(1).map(|lat| (west, lat)));
+    Ok(vec![ring])
+}
+
+/// [`zone_boundary`] in the grid coordinates of the zone itself
+/// # Examples
+///
+/// ```
+/// use mercator::extent::Extent;
+/// use mercator::graticule::zone_boundary_grid;
+/// use mercator::zone::Zone;
+/// let e = Extent::of(&zone_boundary_grid(&Zone::twd97(), 21.5, 25.5, 0.1).unwrap()[0]).unwrap();
+/// // the zone is widest on its southern edge
+/// assert!(e.min_x < 250000.0 - 100000.0 && e.max_x > 250000.0 + 100000.0);
+/// assert!((e.min_x + e.max_x - 500000.0).abs() < 1e-6);
+/// ```
+pub fn zone_boundary_grid(zone:&Zone, south:f64, north:f64, step:f64) -> Result<Polygon> {
+    Ok(zone_boundary(zone, south, north, step)?.into_iter()
+        .map(|ring| ring.into_iter().map(|(lng, lat)| zone.forward(lng, lat)).collect())
+        .collect())
+}
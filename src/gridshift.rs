@@ -0,0 +1,138 @@
+//! correction grid transforms (national datum shift grids and the like)
+//!
+//! a regular grid of dx/dy (and optionally dz) corrections is interpolated at
+//! each point and added to it. the grid nodes and corrections are in the same
+//! units as the coordinates being corrected, so convert e.g. NTv2 arc-second
+//! shifts to degrees when building the grid.
+
+use error::{Error, Result};
+
+/// how corrections are interpolated between grid nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    Bilinear,
+    /// Catmull-Rom bicubic on the surrounding 4 × 4 nodes
+    Bicubic,
+}
+
+/// a regular grid of corrections, stored row by row from the south-west corner
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrectionGrid {
+    /// south-west node
+    pub origin:(f64, f64),
+    /// node spacing along x and y
+    pub spacing:(f64, f64),
+    pub cols:usize,
+    pub rows:usize,
+    pub dx:Vec<f64>,
+    pub dy:Vec<f64>,
+    pub dz:Option<Vec<f64>>,
+    pub interpolation:Interpolation,
+}
+
+fn catmull_rom(p0:f64, p1:f64, p2:f64, p3:f64, t:f64) -> f64 {
+    p1 + 0.5 * t * (p2 - p0 + t * (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3 + t * (3.0 * (p1 - p2) + p3 - p0)))
+}
+
+impl CorrectionGrid {
+    /// a bilinear grid of horizontal corrections
+    pub fn new(origin:(f64, f64), spacing:(f64, f64), cols:usize, rows:usize, dx:Vec<f64>, dy:Vec<f64>) -> Result<CorrectionGrid> {
+        if cols < 2 || rows < 2 {
+            return Err(Error::InvalidInput("correction grid needs at least 2 x 2 nodes".to_string()));
+        }
+        if spacing.0 <= 0.0 || spacing.1 <= 0.0 {
+            return Err(Error::InvalidInput("correction grid spacing must be positive".to_string()));
+        }
+        if dx.len() != cols * rows || dy.len() != cols * rows {
+            return Err(Error::InvalidInput(format!("expected {} corrections per component", cols * rows)));
+        }
+        Ok(CorrectionGrid { origin, spacing, cols, rows, dx, dy, dz: None, interpolation: Interpolation::Bilinear })
+    }
+
+    /// add vertical corrections
+    pub fn with_dz(mut self, dz:Vec<f64>) -> Result<CorrectionGrid> {
+        if dz.len() != self.cols * self.rows {
+            return Err(Error::InvalidInput(format!("expected {} vertical corrections", self.cols * self.rows)));
+        }
+        self.dz = Some(dz);
+        Ok(self)
+    }
+
+    pub fn with_interpolation(mut self, interpolation:Interpolation) -> CorrectionGrid {
+        self.interpolation = interpolation;
+        self
+    }
+
+    fn sample(&self, values:&[f64], col:usize, row:usize, fx:f64, fy:f64) -> f64 {
+        let at = |c:isize, r:isize| {
+            let c = c.max(0).min(self.cols as isize - 1) as usize;
+            let r = r.max(0).min(self.rows as isize - 1) as usize;
+            values[r * self.cols + c]
+        };
+        let (c, r) = (col as isize, row as isize);
+        match self.interpolation {
+            Interpolation::Bilinear => {
+                let bottom = at(c, r) * (1.0 - fx) + at(c + 1, r) * fx;
+                let top = at(c, r + 1) * (1.0 - fx) + at(c + 1, r + 1) * fx;
+                bottom * (1.0 - fy) + top * fy
+            }
+            Interpolation::Bicubic => {
+                let row_at = |rr:isize| catmull_rom(at(c - 1, rr), at(c, rr), at(c + 1, rr), at(c + 2, rr), fx);
+                catmull_rom(row_at(r - 1), row_at(r), row_at(r + 1), row_at(r + 2), fy)
+            }
+        }
+    }
+
+    /// interpolated (dx, dy, dz) at a point, `None` outside the grid; dz is 0 without vertical corrections
+    pub fn correction(&self, x:f64, y:f64) -> Option<(f64, f64, f64)> {
+        let gx = (x - self.origin.0) / self.spacing.0;
+        let gy = (y - self.origin.1) / self.spacing.1;
+        let (maxc, maxr) = ((self.cols - 1) as f64, (self.rows - 1) as f64);
+        if !(0.0..=maxc).contains(&gx) || !(0.0..=maxr).contains(&gy) {
+            return None;
+        }
+        let col = (gx.floor() as usize).min(self.cols - 2);
+        let row = (gy.floor() as usize).min(self.rows - 2);
+        let (fx, fy) = (gx - col as f64, gy - row as f64);
+        let dz = self.dz.as_ref().map_or(0.0, |dz| self.sample(dz, col, row, fx, fy));
+        Some((self.sample(&self.dx, col, row, fx, fy), self.sample(&self.dy, col, row, fx, fy), dz))
+    }
+
+    /// apply the horizontal corrections to a point, `None` outside the grid
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::gridshift::CorrectionGrid;
+    /// let grid = CorrectionGrid::new((120.0, 22.0), (1.0, 1.0), 2, 2,
+    ///     vec![0.001, 0.002, 0.001, 0.002], vec![0.0; 4]).unwrap();
+    /// let (x, y) = grid.apply(120.5, 22.5).unwrap();
+    /// assert!((x - 120.5015).abs() < 1e-12 && y == 22.5);
+    /// let (bx, by) = grid.apply_inverse(x, y).unwrap();
+    /// assert!((bx - 120.5).abs() < 1e-12 && (by - 22.5).abs() < 1e-12);
+    /// assert!(grid.apply(125.0, 22.5).is_none());
+    /// ```
+    pub fn apply(&self, x:f64, y:f64) -> Option<(f64, f64)> {
+        self.correction(x, y).map(|(dx, dy, _)| (x + dx, y + dy))
+    }
+
+    /// apply corrections to a point with height
+    pub fn apply_3d(&self, x:f64, y:f64, z:f64) -> Option<(f64, f64, f64)> {
+        self.correction(x, y).map(|(dx, dy, dz)| (x + dx, y + dy, z + dz))
+    }
+
+    /// undo the horizontal corrections by fixed-point iteration, `None` outside the grid
+    pub fn apply_inverse(&self, x:f64, y:f64) -> Option<(f64, f64)> {
+        let (mut px, mut py) = (x, y);
+        for _ in 0..20 {
+            let (dx, dy, _) = self.correction(px, py)?;
+            let (nx, ny) = (x - dx, y - dy);
+            let done = (nx - px).abs() < 1e-12 * (1.0 + x.abs()) && (ny - py).abs() < 1e-12 * (1.0 + y.abs());
+            px = nx;
+            py = ny;
+            if done {
+                break;
+            }
+        }
+        Some((px, py))
+    }
+}
@@ -0,0 +1,97 @@
+//! course and heading conversion between true, grid and magnetic north
+//!
+//! navigation software mixes headings measured from true north (gyro, GNSS
+//! course over ground), from grid north (courses plotted on a TM chart) and
+//! from magnetic north (compass). grid north is turned from true north by the
+//! meridian convergence of the zone; magnetic north by the declination, which
+//! comes from the user or a magnetic model.
+
+use zone::Zone;
+
+/// the direction a heading is measured from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum North {
+    True,
+    Grid,
+    Magnetic,
+}
+
+/// a heading in degrees folded into [0, 360)
+pub fn normalize_heading(deg:f64) -> f64 {
+    let h = deg.rem_euclid(360.0);
+    // rem_euclid of a tiny negative value rounds up to 360
+    if h >= 360.0 { 0.0 } else { h }
+}
+
+/// the norths at a place, as clockwise angles from true north in degrees
+/// # Examples
+///
+/// ```
+/// use mercator::heading::{North, Norths};
+/// use mercator::zone::Zone;
+/// // east of the TWD97 central meridian grid north is turned east of true north
+/// let n = Norths::at(&Zone::twd97(), 122.0, 23.5).with_declination(-4.5);
+/// assert!(n.convergence > 0.39 && n.convergence < 0.40);
+/// let grid = n.convert(90.0, North::True, North::Grid);
+/// assert!((grid - (90.0 - n.convergence)).abs() < 1e-12);
+/// assert!((n.convert(0.0, North::True, North::Magnetic) - 4.5).abs() < 1e-12);
+/// assert!((n.convert(grid, North::Grid, North::True) - 90.0).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Norths {
+    /// meridian convergence γ, the angle from true to grid north
+    pub convergence:f64,
+    /// magnetic declination, positive when magnetic north is east of true north
+    pub declination:f64,
+}
+
+impl Norths {
+    /// the norths of a zone grid at a wgs84 point, without declination
+    pub fn at(zone:&Zone, lng:f64, lat:f64) -> Norths {
+        Norths { convergence: zone.convergence(lng, lat), declination: 0.0 }
+    }
+
+    pub fn with_declination(mut self, declination:f64) -> Norths {
+        self.declination = declination;
+        self
+    }
+
+    /// the angle of a north clockwise from true north
+    pub fn offset(&self, north:North) -> f64 {
+        match north {
+            North::True => 0.0,
+            North::Grid => self.convergence,
+            North::Magnetic => self.declination,
+        }
+    }
+
+    /// a heading measured from `from` as measured from `to`, in [0, 360)
+    pub fn convert(&self, heading:f64, from:North, to:North) -> f64 {
+        normalize_heading(heading + self.offset(from) - self.offset(to))
+    }
+
+    /// the grid-magnetic angle, from grid north to magnetic north
+    pub fn grid_magnetic_angle(&self) -> f64 {
+        self.declination - self.convergence
+    }
+}
+
+/// a true heading at a wgs84 point as a grid heading of `zone`
+pub fn true_to_grid(zone:&Zone, lng:f64, lat:f64, heading:f64) -> f64 {
+    Norths::at(zone, lng, lat).convert(heading, North::True, North::Grid)
+}
+
+/// a grid heading of `zone` at a wgs84 point as a true heading
+pub fn grid_to_true(zone:&Zone, lng:f64, lat:f64, heading:f64) -> f64 {
+    Norths::at(zone, lng, lat).convert(heading, North::Grid, North::True)
+}
+
+/// a true heading as a magnetic heading, given the declination
+pub fn true_to_magnetic(heading:f64, declination:f64) -> f64 {
+    normalize_heading(heading - declination)
+}
+
+/// a magnetic heading as a true heading, given the declination
+pub fn magnetic_to_true(heading:f64, declination:f64) -> f64 {
+    normalize_heading(heading + declination)
+}
@@ -0,0 +1,158 @@
+//! Helmert (Bursa-Wolf) datum transformations on geocentric coordinates
+
+use std::ops::{Add, Mul};
+
+use coord::{Dated, Ecef};
+use error::{Error, Result};
+
+const ARCSEC:f64 = ::std::f64::consts::PI / 648000.0;
+
+/// 7-parameter Helmert transformation in the position vector convention (IERS, EPSG 1033)
+///
+/// X' = T + (1 + s) X + R × X for small rotations. translations are in meters,
+/// scale in ppm and rotations in arc-seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Helmert {
+    pub tx:f64,
+    pub ty:f64,
+    pub tz:f64,
+    pub s:f64,
+    pub rx:f64,
+    pub ry:f64,
+    pub rz:f64,
+}
+
+impl Helmert {
+    pub fn new(tx:f64, ty:f64, tz:f64, s:f64, rx:f64, ry:f64, rz:f64) -> Helmert {
+        Helmert { tx, ty, tz, s, rx, ry, rz }
+    }
+
+    /// build from parameters published in the coordinate frame convention (EPSG 1032),
+    /// which only differs in the sign of the rotations
+    pub fn coordinate_frame(tx:f64, ty:f64, tz:f64, s:f64, rx:f64, ry:f64, rz:f64) -> Helmert {
+        Helmert::new(tx, ty, tz, s, -rx, -ry, -rz)
+    }
+
+    /// a 3-parameter (translation only) shift
+    pub fn translation(tx:f64, ty:f64, tz:f64) -> Helmert {
+        Helmert { tx, ty, tz, ..Default::default() }
+    }
+
+    /// the reverse transformation, to first order in the small parameters
+    pub fn inverse(&self) -> Helmert {
+        *self * -1.0
+    }
+
+    /// apply the transformation to a geocentric coordinate
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::coord::Ecef;
+    /// use mercator::helmert::Helmert;
+    /// let h = Helmert::translation(1.0, -2.0, 3.0);
+    /// assert_eq!(h.apply(Ecef::new(10.0, 10.0, 10.0)), Ecef::new(11.0, 8.0, 13.0));
+    /// ```
+    pub fn apply(&self, p:Ecef) -> Ecef {
+        let d = self.s * 1e-6;
+        let (rx, ry, rz) = (self.rx * ARCSEC, self.ry * ARCSEC, self.rz * ARCSEC);
+        Ecef {
+            x: p.x + self.tx + d * p.x - rz * p.y + ry * p.z,
+            y: p.y + self.ty + rz * p.x + d * p.y - rx * p.z,
+            z: p.z + self.tz - ry * p.x + rx * p.y + d * p.z,
+        }
+    }
+}
+
+impl Add for Helmert {
+    type Output = Helmert;
+    fn add(self, o:Helmert) -> Helmert {
+        Helmert::new(self.tx + o.tx, self.ty + o.ty, self.tz + o.tz, self.s + o.s,
+            self.rx + o.rx, self.ry + o.ry, self.rz + o.rz)
+    }
+}
+
+impl Mul<f64> for Helmert {
+    type Output = Helmert;
+    fn mul(self, k:f64) -> Helmert {
+        Helmert::new(self.tx * k, self.ty * k, self.tz * k, self.s * k, self.rx * k, self.ry * k, self.rz * k)
+    }
+}
+
+/// 14-parameter time-dependent Helmert transformation
+///
+/// the parameters at epoch t are `params + rates × (t − epoch)`, rates are
+/// in the units of [`Helmert`] per year and epochs in decimal years.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeDependentHelmert {
+    pub params:Helmert,
+    pub rates:Helmert,
+    pub epoch:f64,
+}
+
+impl TimeDependentHelmert {
+    pub fn new(params:Helmert, rates:Helmert, epoch:f64) -> TimeDependentHelmert {
+        TimeDependentHelmert { params, rates, epoch }
+    }
+
+    /// ITRF2014 → ITRF2008 (IGN, reference epoch 2010.0)
+    pub fn itrf2014_to_itrf2008() -> TimeDependentHelmert {
+        TimeDependentHelmert::new(
+            Helmert::new(0.0016, 0.0019, 0.0024, -0.00002, 0.0, 0.0, 0.0),
+            Helmert::new(0.0, 0.0, -0.0001, 0.00003, 0.0, 0.0, 0.0),
+            2010.0)
+    }
+
+    /// the 7 parameters valid at coordinate epoch `t`
+    pub fn at_epoch(&self, t:f64) -> Helmert {
+        self.params + self.rates * (t - self.epoch)
+    }
+
+    /// the reverse transformation, to first order in the small parameters
+    pub fn inverse(&self) -> TimeDependentHelmert {
+        TimeDependentHelmert::new(self.params.inverse(), self.rates.inverse(), self.epoch)
+    }
+
+    /// this transformation followed by `next`, to first order in the small
+    /// parameters, at the reference epoch of this one
+    pub fn then(&self, next:&TimeDependentHelmert) -> TimeDependentHelmert {
+        TimeDependentHelmert::new(self.params + next.at_epoch(self.epoch), self.rates + next.rates, self.epoch)
+    }
+
+    /// apply the transformation to a geocentric coordinate observed at epoch `t`
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::coord::Ecef;
+    /// use mercator::helmert::TimeDependentHelmert;
+    /// let h = TimeDependentHelmert::itrf2014_to_itrf2008();
+    /// let p = Ecef::new(-3027904.0, 4928011.0, 2681331.0);
+    /// let q = h.apply(p, 2020.0);
+    /// // tz = 2.4 - 0.1 × 10 mm, scale = -0.02 + 0.03 × 10 ppb
+    /// assert!((q.z - p.z - (0.0014 + 0.28e-9 * p.z)).abs() < 1e-9);
+    /// ```
+    pub fn apply(&self, p:Ecef, t:f64) -> Ecef {
+        self.at_epoch(t).apply(p)
+    }
+
+    /// apply the transformation at the epoch of a dated coordinate
+    ///
+    /// fails with [`Error::Transform`] for an undated coordinate unless every
+    /// rate is zero.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::coord::{Dated, Ecef};
+    /// use mercator::helmert::TimeDependentHelmert;
+    /// let h = TimeDependentHelmert::itrf2014_to_itrf2008();
+    /// let p = Ecef::new(-3027904.0, 4928011.0, 2681331.0);
+    /// assert_eq!(h.apply_dated(p.at_epoch(2020.0)).unwrap(), h.apply(p, 2020.0).at_epoch(2020.0));
+    /// assert!(h.apply_dated(Dated::undated(p)).is_err());
+    /// ```
+    pub fn apply_dated(&self, p:Dated<Ecef>) -> Result<Dated<Ecef>> {
+        match p.epoch {
+            Some(t) => Ok(p.map(|c| self.apply(c, t))),
+            None if self.rates == Helmert::default() => Ok(p.map(|c| self.params.apply(c))),
+            None => Err(Error::Transform("a time-dependent transformation needs the epoch of the coordinate".to_string())),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! descriptive metadata of the supported CRSs
+//!
+//! enough to fill a CRS dropdown and check a selection: names and axis order
+//! follow the EPSG registry for built-in codes.
+
+use axis::AxisOrder;
+use crs::{AreaOfUse, Crs};
+use ellipsoid::Ellipsoid;
+use epsg;
+use error::Result;
+use registry::{normalize_key, Registry};
+use units::Unit;
+
+/// what a CRS is, in human terms
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrsInfo {
+    /// registry key, `EPSG:nnnn` for built-in codes
+    pub key:String,
+    pub name:String,
+    pub geographic:bool,
+    pub datum:&'static str,
+    /// estimated accuracy of the datum's shift to WGS84 in meters
+    pub datum_accuracy:f64,
+    pub ellipsoid:Ellipsoid,
+    pub ellipsoid_name:Option<&'static str>,
+    pub units:Unit,
+    /// authority axis order
+    pub axis_order:AxisOrder,
+    pub area_of_use:Option<AreaOfUse>,
+}
+
+/// every built-in EPSG code, in ascending order
+pub fn supported_epsg_codes() -> Vec<u32> {
+    let mut codes = vec![3821, 3824, 3825, 3826, 3827, 3828, 4326];
+    codes.extend(32601..=32660);
+    codes.extend(32701..=32760);
+    codes.extend(epsg::codes());
+    codes.sort();
+    codes
+}
+
+pub(crate) fn epsg_name(code:u32) -> Option<String> {
+    Some(match code {
+        4326 => "WGS 84".to_string(),
+        3824 => "TWD97".to_string(),
+        3821 => "TWD67".to_string(),
+        3825 => "TWD97 / TM2 zone 119".to_string(),
+        3826 => "TWD97 / TM2 zone 121".to_string(),
+        3827 => "TWD67 / TM2 zone 119".to_string(),
+        3828 => "TWD67 / TM2 zone 121".to_string(),
+        32601..=32660 => format!("WGS 84 / UTM zone {}N", code - 32600),
+        32701..=32760 => format!("WGS 84 / UTM zone {}S", code - 32700),
+        _ => return epsg::lookup(code).map(|e| e.name.to_string()),
+    })
+}
+
+fn describe(key:String, name:String, crs:&Crs, area:Option<AreaOfUse>) -> CrsInfo {
+    let datum = crs.datum();
+    let geographic = matches!(*crs, Crs::Geographic(_));
+    let axis_order = if geographic { AxisOrder::LatLng } else { AxisOrder::EastNorth };
+    CrsInfo {
+        key,
+        name,
+        geographic,
+        datum: datum.name,
+        datum_accuracy: datum.accuracy,
+        ellipsoid: datum.ellipsoid,
+        ellipsoid_name: datum.ellipsoid.name(),
+        units: if geographic { Unit::Degree } else { Unit::Meter },
+        axis_order,
+        area_of_use: area,
+    }
+}
+
+/// metadata of a built-in EPSG code
+/// # Examples
+///
+/// ```
+/// use mercator::axis::AxisOrder;
+/// use mercator::info::crs_info;
+/// use mercator::units::Unit;
+/// let info = crs_info(3826).unwrap();
+/// assert_eq!(info.name, "TWD97 / TM2 zone 121");
+/// assert_eq!(info.units, Unit::Meter);
+/// assert_eq!(crs_info(4326).unwrap().axis_order, AxisOrder::LatLng);
+/// assert_eq!(crs_info(32751).unwrap().name, "WGS 84 / UTM zone 51S");
+/// ```
+pub fn crs_info(code:u32) -> Result<CrsInfo> {
+    let crs = Crs::from_epsg(code)?;
+    let name = epsg_name(code).unwrap_or_else(|| format!("EPSG:{}", code));
+    let area = crs.area_of_use();
+    Ok(describe(format!("EPSG:{}", code), name, &crs, area))
+}
+
+impl Registry {
+    /// metadata of a built-in or custom CRS, custom ones are named by their key
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Crs;
+    /// use mercator::registry::Registry;
+    /// use mercator::zone::Zone;
+    /// let reg = Registry::new();
+    /// reg.register("site:utm51", Crs::projected(Zone::utm(51))).unwrap();
+    /// let info = reg.info("site:utm51").unwrap();
+    /// assert_eq!(info.name, "SITE:UTM51");
+    /// assert_eq!(info.datum, "WGS 84");
+    /// let all = reg.all_info();
+    /// assert_eq!(all.len(), mercator::info::supported_epsg_codes().len() + 1);
+    /// ```
+    pub fn info(&self, key:&str) -> Result<CrsInfo> {
+        let key = normalize_key(key);
+        if let Some(code) = key.strip_prefix("EPSG:").and_then(|c| c.parse().ok()) {
+            if let Ok(info) = crs_info(code) {
+                return Ok(info);
+            }
+        }
+        let crs = self.get(&key)?;
+        let area = self.area_of_use(&key)?;
+        Ok(describe(key.clone(), key, &crs, area))
+    }
+
+    /// metadata of every built-in and custom CRS, built-in codes first
+    pub fn all_info(&self) -> Vec<CrsInfo> {
+        let mut all:Vec<CrsInfo> = supported_epsg_codes().into_iter().filter_map(|c| crs_info(c).ok()).collect();
+        all.extend(self.custom_keys().into_iter().filter_map(|k| self.info(&k).ok()));
+        all
+    }
+}
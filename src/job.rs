@@ -0,0 +1,184 @@
+//! progress reporting and cancellation for long running transforms
+//!
+//! the `*_with` variants of the batch and file functions take a `Job`,
+//! report to it after every chunk of points and stop before the next chunk
+//! once it is cancelled. the work done so far is kept: in place transforms
+//! leave the remaining points untouched, files are closed and stay valid.
+//! with the `parallel` feature a job also chooses the rayon thread pool the
+//! parallel work runs on, so a server can keep a conversion to a few cores.
+//! parallel transforms write every point back to its own index, so the output
+//! keeps the input order and carries the same bits as on the calling thread;
+//! only sums gathered alongside, as in a [`Summary`](::batch::Summary), may
+//! be added up in another order, see [`Job::with_bit_identical`].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "parallel")]
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+#[cfg(feature = "parallel")]
+use error::{Error, Result};
+
+/// points handled between two progress reports of the batch kernels
+pub const CHUNK:usize = 1 << 16;
+
+/// options shared by long running transforms
+/// # Examples
+///
+/// ```
+/// use mercator::job::Job;
+/// use mercator::batch::transform_points_with;
+/// use mercator::wgs84_to_twd97;
+/// let mut pts = vec![(121.0, 24.0); 100000];
+/// let mut reports = Vec::new();
+/// transform_points_with(&mut pts, wgs84_to_twd97, &mut Job::new().with_progress(|done, total| reports.push((done, total))));
+/// assert_eq!(reports, vec![(65536, 100000), (100000, 100000)]);
+/// ```
+pub struct Job<'a> {
+    progress:Option<Box<dyn FnMut(u64, u64) + 'a>>,
+    cancel:Option<&'a AtomicBool>,
+    #[cfg(feature = "parallel")]
+    pool:Option<Pool<'a>>,
+    #[cfg(feature = "parallel")]
+    bit_identical:bool,
+}
+
+#[cfg(feature = "parallel")]
+enum Pool<'a> {
+    Shared(&'a ThreadPool),
+    Owned(ThreadPool),
+}
+
+impl<'a> Job<'a> {
+    pub fn new() -> Job<'a> {
+        Job {
+            progress: None,
+            cancel: None,
+            #[cfg(feature = "parallel")]
+            pool: None,
+            #[cfg(feature = "parallel")]
+            bit_identical: true,
+        }
+    }
+
+    /// call `f(points processed, total points)` as the work advances
+    ///
+    /// the total is 0 when it is not known up front.
+    pub fn with_progress<F>(mut self, f:F) -> Job<'a>
+        where F: FnMut(u64, u64) + 'a
+    {
+        self.progress = Some(Box::new(f));
+        self
+    }
+
+    /// stop the work once `flag` is set, e.g. from a GUI or another thread
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use mercator::job::Job;
+    /// use mercator::batch::transform_points_with;
+    /// use mercator::wgs84_to_twd97;
+    /// let cancel = AtomicBool::new(false);
+    /// let mut pts = vec![(121.0, 24.0); 100000];
+    /// let mut job = Job::new().with_cancel(&cancel).with_progress(|_, _| cancel.store(true, Ordering::Relaxed));
+    /// let done = transform_points_with(&mut pts, wgs84_to_twd97, &mut job);
+    /// assert_eq!(done, 65536);
+    /// assert!(job.is_cancelled());
+    /// assert_eq!(pts[99999], (121.0, 24.0));
+    /// ```
+    pub fn with_cancel(mut self, flag:&'a AtomicBool) -> Job<'a> {
+        self.cancel = Some(flag);
+        self
+    }
+
+    /// run the parallel work on `pool` instead of rayon's global pool
+    #[cfg(feature = "parallel")]
+    pub fn with_pool(mut self, pool:&'a ThreadPool) -> Job<'a> {
+        self.pool = Some(Pool::Shared(pool));
+        self
+    }
+
+    /// run the parallel work on a pool of its own with `threads` threads
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::chunked::ChunkedTransformer;
+    /// use mercator::job::Job;
+    /// use mercator::wgs84_to_twd97;
+    /// let mut job = Job::new().with_threads(2).unwrap();
+    /// assert_eq!(job.threads(), 2);
+    /// let t = ChunkedTransformer::new(wgs84_to_twd97).with_parallel(true);
+    /// let mut out = Vec::new();
+    /// t.run_with(vec![(121.0, 24.0); 10000], |chunk| {
+    ///     out.extend_from_slice(chunk);
+    ///     Ok(())
+    /// }, &mut job).unwrap();
+    /// assert!((out[9999].0 - 250000.0).abs() < 1e-6);
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn with_threads(mut self, threads:usize) -> Result<Job<'a>> {
+        let pool = ThreadPoolBuilder::new().num_threads(threads).build()
+            .map_err(|e| Error::InvalidInput(format!("thread pool: {}", e)))?;
+        self.pool = Some(Pool::Owned(pool));
+        Ok(self)
+    }
+
+    /// whether parallel work has to give exactly the bits of the serial path,
+    /// the default
+    ///
+    /// the transformed points always do; with `false` the sums of a
+    /// [`Summary`](::batch::Summary) are gathered per chunk and merged, which
+    /// still gives the same result for any number of threads but may differ
+    /// from the serial sum in the last bits.
+    #[cfg(feature = "parallel")]
+    pub fn with_bit_identical(mut self, bit_identical:bool) -> Job<'a> {
+        self.bit_identical = bit_identical;
+        self
+    }
+
+    /// true when parallel work gives exactly the bits of the serial path
+    #[cfg(feature = "parallel")]
+    pub fn is_bit_identical(&self) -> bool {
+        self.bit_identical
+    }
+
+    /// threads the parallel work of this job runs on
+    #[cfg(feature = "parallel")]
+    pub fn threads(&self) -> usize {
+        match self.pool {
+            Some(Pool::Shared(pool)) => pool.current_num_threads(),
+            Some(Pool::Owned(ref pool)) => pool.current_num_threads(),
+            None => ::rayon::current_num_threads(),
+        }
+    }
+
+    /// run `op` on the pool of this job
+    #[cfg(feature = "parallel")]
+    pub(crate) fn install<R, OP>(&self, op:OP) -> R
+        where R: Send, OP: FnOnce() -> R + Send
+    {
+        match self.pool {
+            Some(Pool::Shared(pool)) => pool.install(op),
+            Some(Pool::Owned(ref pool)) => pool.install(op),
+            None => op(),
+        }
+    }
+
+    /// true once the cancel flag has been set
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_some_and(|c| c.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn report(&mut self, done:u64, total:u64) {
+        if let Some(ref mut f) = self.progress {
+            f(done, total);
+        }
+    }
+}
+
+impl<'a> Default for Job<'a> {
+    fn default() -> Job<'a> {
+        Job::new()
+    }
+}
@@ -0,0 +1,141 @@
+//! exact transverse mercator after Krüger, in the form given by Karney (2011)
+//!
+//! the extended Krüger series maps conformal latitude to the ellipsoidal TM
+//! grid with errors of a few nanometers inside 4000 km of the central meridian,
+//! where the classic series in the crate root drifts by millimeters or more.
+//! - https://arxiv.org/abs/1002.1417
+
+use std::sync::OnceLock;
+
+use ellipsoid::{Ellipsoid, STANDARD};
+use longitude::{normalize_lng, wrap_lng_around};
+
+/// coefficients of α_j in powers n^1..n^8 (Karney 2011, eq. 35)
+const ALPHA:[[f64; 8]; 8] = [
+    [1.0/2.0, -2.0/3.0, 5.0/16.0, 41.0/180.0, -127.0/288.0, 7891.0/37800.0, 72161.0/387072.0, -18975107.0/50803200.0],
+    [0.0, 13.0/48.0, -3.0/5.0, 557.0/1440.0, 281.0/630.0, -1983433.0/1935360.0, 13769.0/28800.0, 148003883.0/174182400.0],
+    [0.0, 0.0, 61.0/240.0, -103.0/140.0, 15061.0/26880.0, 167603.0/181440.0, -67102379.0/29030400.0, 79682431.0/79833600.0],
+    [0.0, 0.0, 0.0, 49561.0/161280.0, -179.0/168.0, 6601661.0/7257600.0, 97445.0/49896.0, -40176129013.0/7664025600.0],
+    [0.0, 0.0, 0.0, 0.0, 34729.0/80640.0, -3418889.0/1995840.0, 14644087.0/9123840.0, 2605413599.0/622702080.0],
+    [0.0, 0.0, 0.0, 0.0, 0.0, 212378941.0/319334400.0, -30705481.0/10378368.0, 175214326799.0/58118860800.0],
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1522256789.0/1383782400.0, -16759934899.0/3113510400.0],
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1424729850961.0/743921418240.0],
+];
+
+/// coefficients of β_j in powers n^1..n^8 (Karney 2011, eq. 36)
+const BETA:[[f64; 8]; 8] = [
+    [1.0/2.0, -2.0/3.0, 37.0/96.0, -1.0/360.0, -81.0/512.0, 96199.0/604800.0, -5406467.0/38707200.0, 7944359.0/67737600.0],
+    [0.0, 1.0/48.0, 1.0/15.0, -437.0/1440.0, 46.0/105.0, -1118711.0/3870720.0, 51841.0/1209600.0, 24749483.0/348364800.0],
+    [0.0, 0.0, 17.0/480.0, -37.0/840.0, -209.0/4480.0, 5569.0/90720.0, 9261899.0/58060800.0, -6457463.0/17740800.0],
+    [0.0, 0.0, 0.0, 4397.0/161280.0, -11.0/504.0, -830251.0/7257600.0, 466511.0/2494800.0, 324154477.0/7664025600.0],
+    [0.0, 0.0, 0.0, 0.0, 4583.0/161280.0, -108847.0/3991680.0, -8005831.0/63866880.0, 22894433.0/124540416.0],
+    [0.0, 0.0, 0.0, 0.0, 0.0, 20648693.0/638668800.0, -16363163.0/518918400.0, -2204645983.0/12915302400.0],
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 219941297.0/5535129600.0, -497323811.0/12454041600.0],
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 191773887257.0/3719607091200.0],
+];
+
+/// the ellipsoid dependent constants of the series
+#[derive(Debug, Clone, Copy)]
+struct Kruger {
+    e:f64,
+    /// rectifying radius A
+    radius:f64,
+    alpha:[f64; 8],
+    beta:[f64; 8],
+    order:usize,
+}
+
+impl Kruger {
+    fn new(ellipsoid:&Ellipsoid, order:usize) -> Kruger {
+        let n = (ellipsoid.a - ellipsoid.b) / (ellipsoid.a + ellipsoid.b);
+        let n2 = n * n;
+        let radius = ellipsoid.a / (1.0 + n) * (1.0 + n2 * (1.0 / 4.0 + n2 * (1.0 / 64.0 + n2 * (1.0 / 256.0 + n2 * 25.0 / 16384.0))));
+        let series = |table:&[[f64; 8]; 8]| {
+            let mut out = [0.0; 8];
+            for (j, row) in table.iter().enumerate().take(order) {
+                // Horner over n^1..n^order, the terms below n^(j+1) are zero
+                out[j] = n * row[..order].iter().rev().fold(0.0, |acc, c| acc * n + c);
+            }
+            out
+        };
+        Kruger { e: ellipsoid.e2().sqrt(), radius, alpha: series(&ALPHA), beta: series(&BETA), order }
+    }
+
+    /// conformal τ' from τ = tan φ
+    fn taup(&self, tau:f64) -> f64 {
+        let tau1 = tau.hypot(1.0);
+        let sig = (self.e * (self.e * tau / tau1).atanh()).sinh();
+        tau * sig.hypot(1.0) - sig * tau1
+    }
+
+    /// τ from τ' by Newton's method
+    fn tau(&self, taup:f64) -> f64 {
+        let e2m = 1.0 - self.e * self.e;
+        let mut tau = taup / e2m;
+        // quadratic convergence: one step past this leaves an error of order ε
+        let tol = f64::EPSILON.sqrt() / 10.0;
+        for _ in 0..8 {
+            let t = self.taup(tau);
+            let d = (taup - t) * (1.0 + e2m * tau * tau) / (e2m * tau.hypot(1.0) * t.hypot(1.0));
+            tau += d;
+            if d.abs() <= tol {
+                break;
+            }
+        }
+        tau
+    }
+
+    fn forward(&self, lng:f64, lat:f64) -> (f64, f64) {
+        let (sl, cl) = lng.sin_cos();
+        let taup = self.taup(lat.tan());
+        let xip = taup.atan2(cl);
+        let etap = (sl / taup.hypot(cl)).asinh();
+        let (mut xi, mut eta) = (xip, etap);
+        for j in 0..self.order {
+            let k = 2.0 * (j + 1) as f64;
+            xi += self.alpha[j] * (k * xip).sin() * (k * etap).cosh();
+            eta += self.alpha[j] * (k * xip).cos() * (k * etap).sinh();
+        }
+        (self.radius * eta, self.radius * xi)
+    }
+
+    fn inverse(&self, x:f64, y:f64) -> (f64, f64) {
+        let (xi, eta) = (y / self.radius, x / self.radius);
+        let (mut xip, mut etap) = (xi, eta);
+        for j in 0..self.order {
+            let k = 2.0 * (j + 1) as f64;
+            xip -= self.beta[j] * (k * xi).sin() * (k * eta).cosh();
+            etap -= self.beta[j] * (k * xi).cos() * (k * eta).sinh();
+        }
+        let (s, c) = xip.sin_cos();
+        let taup = s / etap.sinh().hypot(c);
+        let lng = etap.sinh().atan2(c);
+        (lng, self.tau(taup).atan())
+    }
+}
+
+static CACHE:[[OnceLock<Kruger>; 8]; STANDARD.len()] = [const { [const { OnceLock::new() }; 8] }; STANDARD.len()];
+
+/// the series constants, from the cache for standard ellipsoids
+fn kruger(ellipsoid:&Ellipsoid, order:usize) -> Kruger {
+    let order = order.clamp(1, 8);
+    match ellipsoid.standard_index() {
+        Some(i) => *CACHE[i][order - 1].get_or_init(|| Kruger::new(ellipsoid, order)),
+        None => Kruger::new(ellipsoid, order),
+    }
+}
+
+/// Krüger transverse mercator forward projection to n^`order`, otherwise the arguments of the series version
+pub(crate) fn tm_exact_forward(ellipsoid:&Ellipsoid, order:usize, lng:f64, lat:f64, center_lng:f64, k0:f64, dx:f64) -> (f64, f64) {
+    let k = kruger(ellipsoid, order);
+    let dlng = (wrap_lng_around(lng, center_lng) - center_lng).to_radians();
+    let (x, y) = k.forward(dlng, lat.to_radians());
+    (k0 * x + dx, k0 * y)
+}
+
+/// Krüger transverse mercator inverse projection to n^`order`
+pub(crate) fn tm_exact_inverse(ellipsoid:&Ellipsoid, order:usize, x:f64, y:f64, center_lng:f64, k0:f64, dx:f64) -> (f64, f64) {
+    let k = kruger(ellipsoid, order);
+    let (dlng, lat) = k.inverse((x - dx) / k0, y / k0);
+    (normalize_lng(center_lng + dlng.to_degrees()), lat.to_degrees())
+}
@@ -1,6 +1,147 @@
 
 use std::f64::consts::PI;
 
+mod longitude;
+pub mod geometry;
+pub mod extent;
+pub mod binning;
+pub mod quadkey;
+pub mod distance;
+#[cfg(feature = "kdtree")]
+pub mod nearest;
+pub mod zone;
+mod kruger;
+mod constants;
+pub mod survey;
+pub mod distortion;
+pub mod cogo;
+pub mod heading;
+pub mod spherical;
+pub mod vincenty;
+pub mod geodesic;
+pub mod rhumb;
+pub mod buffer;
+pub mod geofence;
+pub mod graticule;
+#[cfg(feature = "wmm")]
+pub mod wmm;
+pub mod ellipsoid;
+pub mod datum;
+mod ecef;
+pub mod local;
+pub mod coord;
+#[cfg(feature = "approx")]
+mod approx_eq;
+pub mod error;
+pub mod projection;
+pub mod tmerc;
+pub mod newton;
+pub mod crs;
+mod epsg;
+pub mod axis;
+pub mod units;
+pub mod precision;
+pub mod fixed;
+pub mod accuracy;
+pub mod info;
+pub mod wkt;
+pub mod registry;
+#[cfg(feature = "epsg-loader")]
+pub mod loader;
+#[cfg(feature = "proj-validate")]
+pub mod validate;
+#[cfg(feature = "gdal")]
+pub mod gdal_interop;
+#[cfg(feature = "async")]
+pub mod stream;
+#[cfg(feature = "tokio")]
+pub mod async_io;
+#[cfg(any(feature = "epsg-loader", feature = "tokio"))]
+mod csv;
+pub mod cache;
+pub mod pipeline;
+mod trace;
+#[cfg(feature = "geoid")]
+pub mod geoid;
+pub mod vertical;
+pub mod helmert;
+pub mod plate;
+pub mod frame;
+pub mod affine;
+pub mod polynomial;
+pub mod fit;
+pub mod gridshift;
+pub mod worldfile;
+pub mod raster;
+pub mod job;
+pub mod batch;
+pub mod chunked;
+#[cfg(feature = "memmap")]
+pub mod mapped;
+pub mod wkb;
+pub mod nmea;
+#[cfg(feature = "ndarray")]
+pub mod array;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+#[cfg(feature = "las")]
+pub mod pointcloud;
+#[cfg(feature = "geotiff")]
+pub mod geotiff;
+#[cfg(feature = "geoparquet")]
+pub mod geoparquet;
+#[cfg(feature = "projjson")]
+pub mod projjson;
+
+pub use error::{Error, Result};
+pub use ecef::{geodetic_to_ecef, ecef_to_geodetic};
+
+use constants::{tm_constants, TmConstants};
+use ellipsoid::Ellipsoid;
+use zone::{Hemisphere, Zone, ZoneWidth};
+
+#[cfg(feature = "tiff")]
+extern crate tiff;
+#[cfg(feature = "ndarray")]
+extern crate ndarray;
+#[cfg(feature = "arrow")]
+extern crate arrow_array;
+#[cfg(feature = "arrow")]
+extern crate arrow_buffer;
+#[cfg(feature = "arrow")]
+extern crate arrow_schema;
+#[cfg(feature = "parquet")]
+extern crate parquet;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+#[cfg(feature = "polars")]
+extern crate polars;
+#[cfg(feature = "las")]
+extern crate las;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "bytemuck")]
+extern crate bytemuck;
+#[cfg(feature = "approx")]
+extern crate approx;
+#[cfg(feature = "epsg-sqlite")]
+extern crate rusqlite;
+#[cfg(feature = "proj-validate")]
+extern crate proj;
+#[cfg(feature = "gdal")]
+extern crate gdal;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "async")]
+extern crate futures_core;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "memmap")]
+extern crate memmap2;
+pub use longitude::{normalize_lng, wrap_lng_around, lng_diff, crosses_antimeridian};
+
 /// convert croodinate from wgs84 to mercator projection
 /// - https://en.wikipedia.org/wiki/Mercator_projection
 /// # Examples
@@ -13,38 +154,51 @@ use std::f64::consts::PI;
 /// let lat:f64 = 23.973875;
 /// let (x, y) = lnglat_to_mercator(lng, lat, 121.0, k0, dx);
 /// ```
-#[allow(non_snake_case)]
 pub fn lnglat_to_mercator(lng:f64, lat:f64, center_lng:f64, k0:f64, dx:f64) ->(f64, f64) {
-    let a:f64 = 6378137.0;
-    let b:f64 = 6356752.314245;
-    let lng0:f64 = center_lng * PI / 180.0;
-    let lng = (lng/180.0) * PI;
+    tm_forward(&ellipsoid::WGS84, lng, lat, center_lng, k0, dx)
+}
+
+/// sin and cos of 2φ, 4φ, 6φ and 8φ from sin φ and cos φ by the double-angle formulas
+fn multiple_angles(s:f64, c:f64) -> [(f64, f64); 4] {
+    let (s2, c2) = (2.0 * s * c, c * c - s * s);
+    let (s4, c4) = (2.0 * s2 * c2, c2 * c2 - s2 * s2);
+    let (s6, c6) = (s4 * c2 + c4 * s2, c4 * c2 - s4 * s2);
+    let (s8, c8) = (2.0 * s4 * c4, c4 * c4 - s4 * s4);
+    [(s2, c2), (s4, c4), (s6, c6), (s8, c8)]
+}
+
+/// transverse mercator forward projection on any ellipsoid
+///
+/// the series are evaluated in Horner form on precomputed powers, without `powf`.
+#[allow(non_snake_case)]
+pub(crate) fn tm_forward(ellipsoid:&Ellipsoid, lng:f64, lat:f64, center_lng:f64, k0:f64, dx:f64) -> (f64, f64) {
+    let TmConstants { e2, ep2, arc: [A, B, C, D, E], .. } = tm_constants(ellipsoid);
+    // measure from the central meridian the short way round so that points
+    // across ±180° stay in the intended zone
+    let p:f64 = (wrap_lng_around(lng, center_lng) - center_lng) * PI / 180.0;
     let lat = (lat/180.0) * PI;
-    
+    let (sin, cos) = lat.sin_cos();
+
     //---------------------------------------------------------
-    let e:f64 = (1.0 - b.powf(2.0) / a.powf(2.0)).powf(0.5);
-    let e2:f64 = e.powf(2.0)/(1.0 - e.powf(2.0)); 
-    let n:f64 = ( a - b ) / ( a + b );
-    let nu:f64 = a / ((1.0 - e.powf(2.0) * lat.sin().powf(2.0))).powf(0.5);
-    let p:f64 = lng - lng0;
-    let A:f64 = a * (1.0 - n + (5.0/4.0) * (n.powf(2.0) - n.powf(3.0)) + (81.0/64.0) * (n.powf(4.0)  - n.powf(5.0)));
-    let B:f64 = (3.0 * a * n/2.0) * (1.0 - n + (7.0/8.0)*(n.powf(2.0) - n.powf(3.0)) + (55.0/64.0)*(n.powf(4.0) - n.powf(5.0)));
-    let C:f64 = (15.0 * a * (n.powf(2.0))/16.0)*(1.0 - n + (3.0/4.0)*(n.powf(2.0) - n.powf(3.0)));
-    let D:f64 = (35.0 * a * (n.powf(3.0))/48.0)*(1.0 - n + (11.0/16.0)*(n.powf(2.0) - n.powf(3.0)));
-    let E:f64 = (315.0 * a * (n.powf(4.0))/51.0)*(1.0 - n);
-    
-    let S:f64 = A * lat - B * (2.0 * lat).sin() +C * (4.0 * lat).sin() - D * (6.0 * lat).sin() + E * (8.0 * lat).sin();
+    let nu:f64 = ellipsoid.a / (1.0 - e2 * sin * sin).sqrt();
 
-    // get x
-    let K1 = S*k0;
-    let K2 = k0*nu*(2.0*lat).sin()/4.0;
-    let K3 = (k0*nu*lat.sin()*lat.cos().powf(3.0)/24.0) * (5.0 - lat.tan().powf(2.0) + 9.0 * e2 * lat.cos().powf(2.0) + 4.0*(e2.powf(2.0))*(lat.cos().powf(4.0)));        
-    let y = K1 + K2*p.powf(2.0) + K3*p.powf(4.0);
+    let [(s2, _), (s4, _), (s6, _), (s8, _)] = multiple_angles(sin, cos);
+    let S:f64 = A * lat - B * s2 + C * s4 - D * s6 + E * s8;
+
+    let t2 = (sin * sin) / (cos * cos);
+    let c2 = cos * cos;
+    let p2 = p * p;
 
     // get y
-    let K4 = k0*nu*lat.cos();
-    let K5 = (k0*nu*lat.cos().powf(3.0)/6.0) * (1.0 - lat.tan().powf(2.0) + e2*(lat.cos().powf(2.0)));
-    let x = K4 * p + K5 * p.powf(3.0) + dx;
+    let K1 = S*k0;
+    let K2 = k0*nu*sin*cos/2.0;
+    let K3 = (k0*nu*sin*cos*c2/24.0) * (5.0 - t2 + c2 * ep2 * (9.0 + 4.0 * ep2 * c2));
+    let y = K1 + p2 * (K2 + p2 * K3);
+
+    // get x
+    let K4 = k0*nu*cos;
+    let K5 = (k0*nu*cos*c2/6.0) * (1.0 - t2 + ep2 * c2);
+    let x = p * (K4 + p2 * K5) + dx;
 
     (x, y)
 }
@@ -52,25 +206,39 @@ pub fn lnglat_to_mercator(lng:f64, lat:f64, center_lng:f64, k0:f64, dx:f64) ->(f
 pub fn wgs84_to_twd97(lng:f64, lat:f64) -> (f64, f64) {
     let k0:f64 = 0.9999;
     let dx:f64 = 250000.0;
-    return lnglat_to_mercator(lng, lat, 121.0, k0, dx)
+    lnglat_to_mercator(lng, lat, 121.0, k0, dx)
 }
 
 pub fn wgs84_to_2degree_zone(lng:f64, lat:f64, center_lng:f64) -> (f64, f64) {
-    let k0:f64 = 0.9999;
-    let dx:f64 = 250000.0;
-    return lnglat_to_mercator(lng, lat, center_lng, k0, dx)
+    let zone = ZoneWidth::Degree2;
+    lnglat_to_mercator(lng, lat, center_lng, zone.k0(), zone.dx())
 }
 
 pub fn wgs84_to_3degree_zone(lng:f64, lat:f64, center_lng:f64) -> (f64, f64) {
-    let k0:f64 = 1.0;
-    let dx:f64 = 350000.0;
-    return lnglat_to_mercator(lng, lat, center_lng, k0, dx)
+    let zone = ZoneWidth::Degree3;
+    lnglat_to_mercator(lng, lat, center_lng, zone.k0(), zone.dx())
 }
 
 pub fn wgs84_to_6degree_zone(lng:f64, lat:f64, center_lng:f64) -> (f64, f64) {
-    let k0:f64 = 0.9996;
-    let dx:f64 = 500000.0;
-    return lnglat_to_mercator(lng, lat, center_lng, k0, dx)
+    let zone = ZoneWidth::Degree6;
+    lnglat_to_mercator(lng, lat, center_lng, zone.k0(), zone.dx())
+}
+
+/// convert wgs84 to UTM, picking the zone and hemisphere from the point
+/// # Examples
+///
+/// ```
+/// use mercator::wgs84_to_utm;
+/// use mercator::zone::Hemisphere;
+/// let (zone, hemisphere, _x, y) = wgs84_to_utm(-58.3816, -34.6037);
+/// assert_eq!((zone, hemisphere), (21, Hemisphere::South));
+/// assert!(y > 0.0);
+/// ```
+pub fn wgs84_to_utm(lng:f64, lat:f64) -> (u8, Hemisphere, f64, f64) {
+    let number = zone::best_utm_zone(lng, lat);
+    let hemisphere = Hemisphere::from_lat(lat);
+    let (x, y) = Zone::utm(number).with_hemisphere(hemisphere).forward(lng, lat);
+    (number, hemisphere, x, y)
 }
 
 /// convert croodinate from mercator projection to wgs84
@@ -86,12 +254,15 @@ pub fn wgs84_to_6degree_zone(lng:f64, lat:f64, center_lng:f64) -> (f64, f64) {
 /// let (lng, lat) = lnglat_to_mercator(x, y, 121.0, k0, dx);
 /// ```
 pub fn mercator_to_lnglat(x:f64, y:f64, center_lng:f64, k0:f64, dx:f64) -> (f64, f64) {
-    let a:f64 = 6378137.0;
-    let b:f64 = 6356752.314245;
+    tm_inverse(&ellipsoid::WGS84, x, y, center_lng, k0, dx)
+}
+
+/// transverse mercator inverse projection on any ellipsoid
+pub(crate) fn tm_inverse(ellipsoid:&Ellipsoid, x:f64, y:f64, center_lng:f64, k0:f64, dx:f64) -> (f64, f64) {
+    let TmConstants { a, e2, ep2, mu_scale, footprint: [j1, j2, j3, j4], .. } = tm_constants(ellipsoid);
     let lng0:f64 = center_lng * PI / 180.0;
 
     let dy:f64 = 0.0;
-    let e:f64 = (1.0 - b.powf(2.0)/a.powf(2.0)).powf(0.5);
 
     let x:f64 = x - dx;
     let y:f64 = y - dy;
@@ -100,58 +271,56 @@ pub fn mercator_to_lnglat(x:f64, y:f64, center_lng:f64, k0:f64, dx:f64) -> (f64,
     let m:f64 = y/k0;
 
     // calculate Footprint Latitude
-    let mu:f64 = m/(a*(1.0 - e.powf(2.0)/4.0 - 3.0*e.powf(4.0)/64.0 - 5.0*e.powf(6.0)/256.0));
-    let e1:f64 = (1.0 - (1.0 - e.powf(2.0)).powf(0.5)) / (1.0 + (1.0 - e.powf(2.0)).powf(0.5));
-
-    let j1 = 3.0*e1/2.0 - 27.0*e1.powf(3.0)/32.0;
-    let j2 = 21.0*e1.powf(2.0)/16.0 - 55.0*e1.powf(4.0)/32.0;
-    let j3 = 151.0*e1.powf(3.0)/96.0;
-    let j4 = 1097.0*e1.powf(4.0)/512.0;
+    let mu:f64 = m/mu_scale;
 
-    let fp = mu + j1*(2.0*mu).sin() + j2*(4.0*mu).sin() + j3*(6.0*mu).sin() + j4*(8.0*mu).sin();
+    let (sin_mu, cos_mu) = mu.sin_cos();
+    let [(s2, _), (s4, _), (s6, _), (s8, _)] = multiple_angles(sin_mu, cos_mu);
+    let fp = mu + j1*s2 + j2*s4 + j3*s6 + j4*s8;
 
     // calculate Latitude and Longitude
 
-    let e2 = (e*a/b).powf(2.0);
-    let c1 = e2*fp.cos().powf(2.0);
-    let t1 = fp.tan().powf(2.0);
-    let r1 = a*(1.0-e.powf(2.0))/(1.0-e.powf(2.0)*fp.sin().powf(2.0)).powf(3.0/2.0);
-    let n1 = a/(1.0-e.powf(2.0)*fp.sin().powf(2.0)).powf(0.5);
+    let (sin, cos) = fp.sin_cos();
+    let tan = sin / cos;
+    let c1 = ep2 * cos * cos;
+    let t1 = tan * tan;
+    let w = 1.0 - e2 * sin * sin;
+    let r1 = a * (1.0 - e2) / (w * w.sqrt());
+    let n1 = a / w.sqrt();
 
     let d = x/(n1*k0);
+    let d2 = d * d;
 
     // get lat
-    let q1 = n1*fp.tan()/r1;
-    let q2 = d.powf(2.0)/2.0;
-    let q3 = (5.0 + 3.0*t1 + 10.0*c1 - 4.0*c1.powf(2.0) - 9.0*e2)*d.powf(4.0)/24.0;
-    let q4 = (61.0 + 90.0*t1 + 298.0*c1 + 45.0*t1.powf(2.0) - 3.0*c1.powf(2.0) - 252.0*e2)*d.powf(6.0)/720.0;
-    let lat = fp - q1*(q2 - q3 + q4);
+    let q1 = n1*tan/r1;
+    let q3 = (5.0 + 3.0*t1 + 10.0*c1 - 4.0*c1*c1 - 9.0*ep2)/24.0;
+    let q4 = (61.0 + 90.0*t1 + 298.0*c1 + 45.0*t1*t1 - 3.0*c1*c1 - 252.0*ep2)/720.0;
+    let lat = fp - q1 * d2 * (0.5 - d2 * (q3 - d2 * q4));
 
     // get lng
-    let q5 = d;
-    let q6 = (1.0 + 2.0*t1 + c1)*d.powf(3.0)/6.0;
-    let q7 = (5.0 - 2.0*c1 + 28.0*t1 - 3.0*c1.powf(2.0) + 8.0*e2 + 24.0*t1.powf(2.0))*d.powf(5.0)/120.0;
-    let lng = lng0 + (q5 - q6 + q7)/fp.cos();
+    let q6 = (1.0 + 2.0*t1 + c1)/6.0;
+    let q7 = (5.0 - 2.0*c1 + 28.0*t1 - 3.0*c1*c1 + 8.0*ep2 + 24.0*t1*t1)/120.0;
+    let lng = lng0 + d * (1.0 - d2 * (q6 - d2 * q7)) / cos;
 
     let lat = (lat * 180.0) / PI;
-    let lng = (lng * 180.0) / PI;
-    return (lng, lat)
+    let lng = normalize_lng((lng * 180.0) / PI);
+    (lng, lat)
 }
 
 pub fn f2degree_zone_to_wgs84(x:f64, y:f64, center_lng:f64) -> (f64, f64) {
-    let k0:f64 = 0.9999;
-    let dx:f64 = 250000.0;
-    return mercator_to_lnglat(x, y, center_lng, k0, dx)
+    let zone = ZoneWidth::Degree2;
+    mercator_to_lnglat(x, y, center_lng, zone.k0(), zone.dx())
 }
 
 pub fn f3degree_zone_to_wgs84(x:f64, y:f64, center_lng:f64) -> (f64, f64) {
-    let k0:f64 = 1.0;
-    let dx:f64 = 350000.0;
-    return mercator_to_lnglat(x, y, center_lng, k0, dx)
+    let zone = ZoneWidth::Degree3;
+    mercator_to_lnglat(x, y, center_lng, zone.k0(), zone.dx())
 }
 
 pub fn f6degree_zone_to_wgs84(x:f64, y:f64, center_lng:f64) -> (f64, f64) {
-    let k0:f64 = 0.9996;
-    let dx:f64 = 500000.0;
-    return mercator_to_lnglat(x, y, center_lng, k0, dx)
+    let zone = ZoneWidth::Degree6;
+    mercator_to_lnglat(x, y, center_lng, zone.k0(), zone.dx())
+}
+
+pub fn utm_to_wgs84(x:f64, y:f64, number:u8, hemisphere:Hemisphere) -> (f64, f64) {
+    Zone::utm(number).with_hemisphere(hemisphere).inverse(x, y)
 }
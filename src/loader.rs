@@ -0,0 +1,370 @@
+//! runtime loading of a full EPSG dataset into a registry
+//!
+//! the compiled-in table only covers the common national datums and grids. a
+//! GIS server that has to accept any code can load a dump of the registry at
+//! start up instead: a CSV of `code,name,proj4` rows, the `epsg` init file of
+//! older PROJ releases, or (with the `epsg-sqlite` feature) a query against a
+//! SQLite database. each definition is parsed with [`Crs::from_proj4`], so only
+//! geographic and transverse mercator CRSs in meters load; everything else is
+//! reported as skipped together with the reason.
+
+use std::io::BufRead;
+#[cfg(feature = "epsg-sqlite")]
+use std::path::Path;
+
+#[cfg(feature = "epsg-sqlite")]
+use rusqlite::{self, Connection, OpenFlags};
+#[cfg(feature = "epsg-sqlite")]
+use rusqlite::types::ValueRef;
+
+use crs::Crs;
+use csv::split_fields;
+use datum::{self, Datum};
+use ellipsoid::{self, Ellipsoid};
+use epsg::{self, as_zone};
+use error::{Error, Result};
+use helmert::Helmert;
+use registry::Registry;
+use tmerc::TransverseMercator;
+use trace::Activity;
+use zone::Zone;
+
+/// outcome of loading a dataset
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadReport {
+    /// number of CRSs registered
+    pub loaded:usize,
+    /// `(key, reason)` of every definition that was not registered
+    pub skipped:Vec<(String, String)>,
+}
+
+/// `+ellps` names and their (a, rf); rf 0 is a sphere
+const ELLIPSOIDS:[(&str, f64, f64); 13] = [
+    ("WGS84", 6378137.0, 298.257223563),
+    ("GRS80", 6378137.0, 298.257222101),
+    ("GRS67", 6378160.0, 298.247167427),
+    ("aust_SA", 6378160.0, 298.25),
+    ("WGS72", 6378135.0, 298.26),
+    ("airy", 6377563.396, 299.3249646),
+    ("mod_airy", 6377340.189, 299.3249646),
+    ("bessel", 6377397.155, 299.1528128),
+    ("intl", 6378388.0, 297.0),
+    ("krass", 6378245.0, 298.3),
+    ("clrk80", 6378249.145, 293.465),
+    ("evrst30", 6377276.345, 300.8017),
+    ("sphere", 6370997.0, 0.0),
+];
+
+fn format_err(msg:String) -> Error {
+    Error::Format(msg)
+}
+
+fn number(key:&str, value:Option<&str>) -> Result<f64> {
+    value.and_then(|v| v.trim().parse().ok())
+        .ok_or_else(|| format_err(format!("+{} needs a number", key)))
+}
+
+fn ellipsoid_from(a:f64, rf:f64) -> Ellipsoid {
+    let e = if rf == 0.0 { Ellipsoid { a, b: a } } else { Ellipsoid::from_inverse_flattening(a, rf) };
+    // snap to the exact constants of a known ellipsoid
+    ellipsoid::STANDARD.iter().map(|s| s.0)
+        .find(|s| (s.a - e.a).abs() < 1e-6 && (s.b - e.b).abs() < 1e-6)
+        .unwrap_or(e)
+}
+
+fn named_ellipsoid(name:&str) -> Result<Ellipsoid> {
+    if name == "clrk66" {
+        return Ok(Ellipsoid { a: 6378206.4, b: 6356583.8 });
+    }
+    ELLIPSOIDS.iter().find(|e| e.0 == name).map(|e| ellipsoid_from(e.1, e.2))
+        .ok_or_else(|| format_err(format!("unknown ellipsoid {}", name)))
+}
+
+/// `+datum` names, by the datum of the compiled-in table they stand for
+fn named_datum(name:&str) -> Result<Datum> {
+    let table = match name {
+        "WGS84" => return Ok(datum::WGS84),
+        "NAD83" => "NAD83",
+        "OSGB36" => "OSGB36",
+        "potsdam" => "DHDN",
+        _ => return Err(format_err(format!("datum {} needs a grid shift or is unknown", name))),
+    };
+    epsg::DATUMS.iter().find(|d| d.name == table).cloned()
+        .ok_or_else(|| format_err(format!("unknown datum {}", name)))
+}
+
+fn towgs84(value:Option<&str>) -> Result<Helmert> {
+    let p:Vec<f64> = value.unwrap_or("").split(',').map(|v| v.trim().parse())
+        .collect::<::std::result::Result<_, _>>()
+        .map_err(|_| format_err("+towgs84 needs numbers".to_string()))?;
+    match p.len() {
+        // PROJ reads the rotations in the position vector convention, like Helmert
+        3 => Ok(Helmert::translation(p[0], p[1], p[2])),
+        7 => Ok(Helmert::new(p[0], p[1], p[2], p[6], p[3], p[4], p[5])),
+        n => Err(format_err(format!("+towgs84 needs 3 or 7 parameters, got {}", n))),
+    }
+}
+
+impl Crs {
+    /// parse a PROJ.4 definition string
+    ///
+    /// `longlat` and `tmerc` / `utm` in meters are understood, with `+ellps`,
+    /// `+a` / `+b` / `+rf` / `+f` / `+R`, `+datum` and 3 or 7 parameter `+towgs84`.
+    /// transverse mercator grids of the built-in zone systems become
+    /// [`Crs::Projected`], other grids a [`Crs::Custom`] [`TransverseMercator`].
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::{Crs, Transformer};
+    /// use mercator::zone::Zone;
+    /// let utm = Crs::from_proj4("+proj=utm +zone=51 +south +datum=WGS84 +units=m +no_defs").unwrap();
+    /// assert_eq!(utm, Crs::projected(Zone::utm_south(51)));
+    /// let twd97 = Crs::from_proj4("+proj=tmerc +lat_0=0 +lon_0=121 +k=0.9999 +x_0=250000 +y_0=0 +ellps=GRS80").unwrap();
+    /// assert!(matches!(twd97, Crs::Projected(zone, _) if zone == Zone::twd97()));
+    ///
+    /// // Irish Transverse Mercator, not a zone system
+    /// let itm = Crs::from_proj4("+proj=tmerc +lat_0=53.5 +lon_0=-8 +k=0.99982 +x_0=600000 +y_0=750000 +ellps=GRS80").unwrap();
+    /// let (e, n) = Transformer::new(Crs::wgs84(), itm).transform(-8.0, 53.5);
+    /// assert!((e - 600000.0).abs() < 1e-3 && (n - 750000.0).abs() < 1e-3);
+    ///
+    /// assert!(Crs::from_proj4("+proj=lcc +lat_1=33 +lat_2=45 +datum=NAD83").is_err());
+    /// assert!(Crs::from_proj4("+proj=utm +zone=11 +datum=NAD83 +units=us-ft").is_err());
+    /// ```
+    pub fn from_proj4(text:&str) -> Result<Crs> {
+        let mut params:Vec<(&str, Option<&str>)> = Vec::new();
+        for token in text.split_whitespace() {
+            let token = token.strip_prefix('+').unwrap_or(token);
+            let mut kv = token.splitn(2, '=');
+            let key = kv.next().unwrap_or("");
+            if !key.is_empty() {
+                params.push((key, kv.next()));
+            }
+        }
+        let get = |key:&str| params.iter().find(|p| p.0 == key).map(|p| p.1);
+        let num_or = |key:&str, default:f64| match get(key) {
+            Some(v) => number(key, v),
+            None => Ok(default),
+        };
+
+        if let Some(units) = get("units") {
+            if units != Some("m") {
+                return Err(format_err(format!("units {} are not supported", units.unwrap_or(""))));
+            }
+        }
+        if get("to_meter").is_some() {
+            return Err(format_err("+to_meter is not supported".to_string()));
+        }
+        if let Some(pm) = get("pm") {
+            if pm != Some("greenwich") && pm != Some("0") {
+                return Err(format_err("prime meridians other than Greenwich are not supported".to_string()));
+            }
+        }
+        if let Some(Some(grids)) = get("nadgrids") {
+            if grids != "@null" {
+                return Err(format_err(format!("grid shift {} is not supported", grids)));
+            }
+        }
+        if let Some(axis) = get("axis") {
+            if axis != Some("enu") {
+                return Err(format_err("axis orders other than enu are not supported".to_string()));
+            }
+        }
+
+        let mut datum = match get("datum") {
+            Some(name) => named_datum(name.unwrap_or(""))?,
+            None => Datum::new("unknown", ellipsoid::WGS84, Helmert::default()),
+        };
+        if let Some(name) = get("ellps") {
+            datum.ellipsoid = named_ellipsoid(name.unwrap_or(""))?;
+        }
+        if let Some(r) = get("R") {
+            let r = number("R", r)?;
+            datum.ellipsoid = Ellipsoid { a: r, b: r };
+        }
+        if let Some(a) = get("a") {
+            let a = number("a", a)?;
+            datum.ellipsoid = if let Some(rf) = get("rf") {
+                ellipsoid_from(a, number("rf", rf)?)
+            } else if let Some(f) = get("f") {
+                let f = number("f", f)?;
+                ellipsoid_from(a, if f == 0.0 { 0.0 } else { 1.0 / f })
+            } else if let Some(b) = get("b") {
+                Ellipsoid { a, b: number("b", b)? }
+            } else {
+                Ellipsoid { a, b: a }
+            };
+        }
+        if let Some(value) = get("towgs84") {
+            datum.to_wgs84 = towgs84(value)?;
+        }
+        if get("datum").is_none() {
+            datum = if datum.is_wgs84() { datum::WGS84 } else { Datum::new("unknown", datum.ellipsoid, datum.to_wgs84) };
+        }
+
+        let proj = get("proj").and_then(|p| p).ok_or_else(|| format_err("missing +proj".to_string()))?;
+        match proj {
+            "longlat" | "latlong" | "lonlat" | "latlon" => Ok(Crs::Geographic(datum)),
+            "utm" => {
+                let zone = num_or("zone", f64::NAN)?;
+                if !((1.0..=60.0).contains(&zone) && zone.fract() == 0.0) {
+                    return Err(format_err("+proj=utm needs a +zone from 1 to 60".to_string()));
+                }
+                let zone = zone as u8;
+                let zone = if get("south").is_some() { Zone::utm_south(zone) } else { Zone::utm(zone) };
+                Ok(Crs::Projected(zone, datum))
+            }
+            "tmerc" | "etmerc" => {
+                let lat0 = num_or("lat_0", 0.0)?;
+                let lng0 = num_or("lon_0", 0.0)?;
+                let k0 = match get("k_0") {
+                    Some(k) => number("k_0", k)?,
+                    None => num_or("k", 1.0)?,
+                };
+                let (x0, y0) = (num_or("x_0", 0.0)?, num_or("y_0", 0.0)?);
+                Ok(match as_zone(lat0, lng0, k0, x0, y0) {
+                    Some(zone) => Crs::Projected(zone, datum),
+                    None => Crs::custom(TransverseMercator::new(datum.ellipsoid, lat0, lng0, k0, x0, y0)).with_datum(datum),
+                })
+            }
+            other => Err(format_err(format!("projection {} is not supported", other))),
+        }
+    }
+
+    fn with_datum(self, datum:Datum) -> Crs {
+        match self {
+            Crs::Custom(p, _) => Crs::Custom(p, datum),
+            other => other,
+        }
+    }
+}
+
+impl Registry {
+    fn load_definition(&self, report:&mut LoadReport, code:&str, proj4:&str) {
+        let key = ::registry::normalize_key(code);
+        match Crs::from_proj4(proj4).and_then(|crs| self.register(&key, crs)) {
+            Ok(()) => report.loaded += 1,
+            Err(e) => report.skipped.push((key, e.to_string())),
+        }
+    }
+
+    /// register every row of a `code,name,proj4` CSV
+    ///
+    /// codes are bare numbers or full keys such as `EPSG:2157`; a header line,
+    /// blank lines and lines starting with `#` are ignored. the proj4 field may
+    /// be quoted or, as the last field, carry unquoted commas of `+towgs84`.
+    /// built-in codes cannot be redefined and show up in the skipped list.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::registry::Registry;
+    /// let csv = "code,name,proj4\n\
+    ///     2157,IRENET95 / Irish Transverse Mercator,+proj=tmerc +lat_0=53.5 +lon_0=-8 +k=0.99982 +x_0=600000 +y_0=750000 +ellps=GRS80 +towgs84=0,0,0,0,0,0,0 +units=m\n\
+    ///     3035,ETRS89-extended / LAEA Europe,+proj=laea +lat_0=52 +lon_0=10 +x_0=4321000 +y_0=3210000 +ellps=GRS80\n\
+    ///     4326,WGS 84,+proj=longlat +datum=WGS84\n";
+    /// let reg = Registry::new();
+    /// let report = reg.load_csv(csv.as_bytes()).unwrap();
+    /// assert_eq!(report.loaded, 1);
+    /// assert_eq!(report.skipped.len(), 2);
+    /// let (e, n) = reg.transformer("EPSG:4326", "2157").unwrap().transform(-6.26, 53.35);
+    /// assert!((e - 715845.9).abs() < 0.1 && (n - 734720.3).abs() < 0.1);
+    /// ```
+    pub fn load_csv<R:BufRead>(&self, reader:R) -> Result<LoadReport> {
+        let activity = Activity::start("load_csv", 0);
+        let mut report = LoadReport::default();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields = split_fields(line);
+            if fields.len() < 3 {
+                return Err(format_err(format!("expected code,name,proj4: {}", line)));
+            }
+            let code = fields[0].trim();
+            if code.eq_ignore_ascii_case("code") {
+                continue;
+            }
+            self.load_definition(&mut report, code, &fields[2..].join(","));
+        }
+        activity.finish(report.loaded as u64, report.skipped.len() as u64);
+        Ok(report)
+    }
+
+    /// register every definition of a PROJ `epsg` init file
+    ///
+    /// the file has `<code> +proj=... <>` lines, each usually preceded by a
+    /// `# name` comment line.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::registry::Registry;
+    /// let init = "## OSGB 1936 / British National Grid\n\
+    ///     <27700> +proj=tmerc +lat_0=49 +lon_0=-2 +k=0.9996012717 +x_0=400000 +y_0=-100000 +ellps=airy +units=m +no_defs  <>\n\
+    ///     ## Tokyo / UTM zone 54N\n\
+    ///     <3095> +proj=utm +zone=54 +ellps=bessel +towgs84=-146.414,507.337,680.507,0,0,0,0 +units=m +no_defs  <>\n";
+    /// let reg = Registry::new();
+    /// let report = reg.load_proj_epsg(init.as_bytes()).unwrap();
+    /// assert_eq!(report.loaded, 1);
+    /// assert_eq!(report.skipped[0].0, "EPSG:27700");
+    /// assert_eq!(reg.get("EPSG:3095").unwrap().datum().to_wgs84.tx, -146.414);
+    /// ```
+    pub fn load_proj_epsg<R:BufRead>(&self, reader:R) -> Result<LoadReport> {
+        let activity = Activity::start("load_proj_epsg", 0);
+        let mut report = LoadReport::default();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let rest = line.strip_prefix('<').and_then(|l| l.split_once('>'))
+                .ok_or_else(|| format_err(format!("expected <code> +proj=... <>: {}", line)))?;
+            let (code, definition) = rest;
+            let definition = definition.trim().strip_suffix("<>").unwrap_or(definition);
+            self.load_definition(&mut report, code.trim(), definition);
+        }
+        activity.finish(report.loaded as u64, report.skipped.len() as u64);
+        Ok(report)
+    }
+
+    /// register every row of an SQLite query returning `code, name, proj4` columns
+    ///
+    /// `query` defaults to `SELECT code, name, proj4 FROM epsg`. codes may be
+    /// integers or text keys.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::registry::Registry;
+    /// let path = std::env::temp_dir().join("mercator_epsg_doctest.db");
+    /// let _ = std::fs::remove_file(&path);
+    /// let db = rusqlite::Connection::open(&path).unwrap();
+    /// db.execute_batch("CREATE TABLE crs (auth_code INTEGER, name TEXT, def TEXT);
+    ///     INSERT INTO crs VALUES (32650, 'WGS 84 / UTM zone 50N', '+proj=utm +zone=50 +datum=WGS84 +units=m');
+    ///     INSERT INTO crs VALUES (900001, 'site grid', '+proj=tmerc +lon_0=121.5 +k=1 +x_0=10000 +ellps=WGS84');").unwrap();
+    /// let reg = Registry::new();
+    /// let report = reg.load_sqlite(&path, Some("SELECT auth_code, name, def FROM crs")).unwrap();
+    /// assert_eq!((report.loaded, report.skipped.len()), (1, 1));
+    /// assert!(reg.get("900001").is_ok());
+    /// ```
+    #[cfg(feature = "epsg-sqlite")]
+    pub fn load_sqlite<P:AsRef<Path>>(&self, path:P, query:Option<&str>) -> Result<LoadReport> {
+        let activity = Activity::file("load_sqlite", path.as_ref());
+        let sql_err = |e:rusqlite::Error| format_err(format!("sqlite: {}", e));
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(sql_err)?;
+        let mut stmt = conn.prepare(query.unwrap_or("SELECT code, name, proj4 FROM epsg")).map_err(sql_err)?;
+        let mut rows = stmt.query([]).map_err(sql_err)?;
+        let mut report = LoadReport::default();
+        while let Some(row) = rows.next().map_err(sql_err)? {
+            let code = match row.get_ref(0).map_err(sql_err)? {
+                ValueRef::Integer(i) => i.to_string(),
+                ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned(),
+                _ => return Err(format_err("sqlite: code must be an integer or text".to_string())),
+            };
+            let proj4:String = row.get(2).map_err(sql_err)?;
+            self.load_definition(&mut report, &code, &proj4);
+        }
+        activity.finish(report.loaded as u64, report.skipped.len() as u64);
+        Ok(report)
+    }
+}
@@ -0,0 +1,119 @@
+//! local tangent plane frames (East-North-Up / North-East-Down) anchored at a reference point
+
+use ellipsoid::{Ellipsoid, WGS84};
+
+/// a local tangent plane anchored at a geodetic reference point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalFrame {
+    pub ellipsoid:Ellipsoid,
+    /// reference lng/lat in degrees and ellipsoidal height in meters
+    pub origin:(f64, f64, f64),
+    origin_ecef:(f64, f64, f64),
+    // sin/cos of the reference lng and lat
+    sl:f64,
+    cl:f64,
+    sp:f64,
+    cp:f64,
+}
+
+impl LocalFrame {
+    /// a frame on wgs84 at `lng`, `lat` (degrees), `h` (meters)
+    pub fn new(lng:f64, lat:f64, h:f64) -> LocalFrame {
+        LocalFrame::on(WGS84, lng, lat, h)
+    }
+
+    /// a frame on `ellipsoid` at `lng`, `lat` (degrees), `h` (meters)
+    pub fn on(ellipsoid:Ellipsoid, lng:f64, lat:f64, h:f64) -> LocalFrame {
+        let (sl, cl) = lng.to_radians().sin_cos();
+        let (sp, cp) = lat.to_radians().sin_cos();
+        LocalFrame {
+            ellipsoid,
+            origin: (lng, lat, h),
+            origin_ecef: ellipsoid.geodetic_to_ecef(lng, lat, h),
+            sl, cl, sp, cp,
+        }
+    }
+
+    /// rotate an ECEF vector into east, north, up components
+    fn ecef_vec_to_enu(&self, dx:f64, dy:f64, dz:f64) -> (f64, f64, f64) {
+        let (sl, cl, sp, cp) = (self.sl, self.cl, self.sp, self.cp);
+        let e = -sl * dx + cl * dy;
+        let n = -sp * cl * dx - sp * sl * dy + cp * dz;
+        let u = cp * cl * dx + cp * sl * dy + sp * dz;
+        (e, n, u)
+    }
+
+    /// rotate east, north, up components into an ECEF vector
+    fn enu_vec_to_ecef(&self, e:f64, n:f64, u:f64) -> (f64, f64, f64) {
+        let (sl, cl, sp, cp) = (self.sl, self.cl, self.sp, self.cp);
+        let dx = -sl * e - sp * cl * n + cp * cl * u;
+        let dy = cl * e - sp * sl * n + cp * sl * u;
+        let dz = cp * n + sp * u;
+        (dx, dy, dz)
+    }
+
+    /// ECEF XYZ to local east, north, up in meters
+    pub fn ecef_to_enu(&self, x:f64, y:f64, z:f64) -> (f64, f64, f64) {
+        let o = self.origin_ecef;
+        self.ecef_vec_to_enu(x - o.0, y - o.1, z - o.2)
+    }
+
+    /// local east, north, up in meters to ECEF XYZ
+    pub fn enu_to_ecef(&self, e:f64, n:f64, u:f64) -> (f64, f64, f64) {
+        let o = self.origin_ecef;
+        let (dx, dy, dz) = self.enu_vec_to_ecef(e, n, u);
+        (o.0 + dx, o.1 + dy, o.2 + dz)
+    }
+
+    /// geodetic lng/lat (degrees) and height (meters) to local east, north, up
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::local::LocalFrame;
+    /// let frame = LocalFrame::new(121.5654, 25.0330, 0.0);
+    /// let (e, n, u) = frame.geodetic_to_enu(121.5664, 25.0330, 0.0);
+    /// assert!((e - 100.9).abs() < 0.1 && n.abs() < 0.01 && u < 0.0);
+    /// let (lng, lat, h) = frame.enu_to_geodetic(e, n, u);
+    /// assert!((lng - 121.5664).abs() < 1e-12 && (lat - 25.0330).abs() < 1e-12 && h.abs() < 1e-6);
+    /// ```
+    pub fn geodetic_to_enu(&self, lng:f64, lat:f64, h:f64) -> (f64, f64, f64) {
+        let (x, y, z) = self.ellipsoid.geodetic_to_ecef(lng, lat, h);
+        self.ecef_to_enu(x, y, z)
+    }
+
+    /// local east, north, up to geodetic lng/lat (degrees) and height (meters)
+    pub fn enu_to_geodetic(&self, e:f64, n:f64, u:f64) -> (f64, f64, f64) {
+        let (x, y, z) = self.enu_to_ecef(e, n, u);
+        self.ellipsoid.ecef_to_geodetic(x, y, z)
+    }
+
+    /// geodetic lng/lat (degrees) and height (meters) to local north, east, down
+    pub fn geodetic_to_ned(&self, lng:f64, lat:f64, h:f64) -> (f64, f64, f64) {
+        let (e, n, u) = self.geodetic_to_enu(lng, lat, h);
+        (n, e, -u)
+    }
+
+    /// local north, east, down to geodetic lng/lat (degrees) and height (meters)
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::local::LocalFrame;
+    /// let frame = LocalFrame::new(8.55, 47.37, 400.0);
+    /// let (lng, lat, h) = frame.ned_to_geodetic(0.0, 0.0, -120.0);
+    /// assert!((lng - 8.55).abs() < 1e-12 && (lat - 47.37).abs() < 1e-12 && (h - 520.0).abs() < 1e-6);
+    /// ```
+    pub fn ned_to_geodetic(&self, n:f64, e:f64, d:f64) -> (f64, f64, f64) {
+        self.enu_to_geodetic(e, n, -d)
+    }
+
+    /// ECEF XYZ to local north, east, down in meters
+    pub fn ecef_to_ned(&self, x:f64, y:f64, z:f64) -> (f64, f64, f64) {
+        let (e, n, u) = self.ecef_to_enu(x, y, z);
+        (n, e, -u)
+    }
+
+    /// local north, east, down in meters to ECEF XYZ
+    pub fn ned_to_ecef(&self, n:f64, e:f64, d:f64) -> (f64, f64, f64) {
+        self.enu_to_ecef(e, n, -d)
+    }
+}
@@ -0,0 +1,52 @@
+//! longitude normalization and antimeridian helpers
+
+/// normalize a longitude into the range [-180, 180)
+/// # Examples
+///
+/// ```
+/// use mercator::normalize_lng;
+/// assert_eq!(normalize_lng(190.0), -170.0);
+/// assert_eq!(normalize_lng(-540.0), -180.0);
+/// assert_eq!(normalize_lng(121.0), 121.0);
+/// ```
+pub fn normalize_lng(lng:f64) -> f64 {
+    wrap_lng_around(lng, 0.0)
+}
+
+/// wrap a longitude into the 360° window [center - 180, center + 180) around `center`
+/// # Examples
+///
+/// ```
+/// use mercator::wrap_lng_around;
+/// // a point just east of the antimeridian seen from the 177°E zone
+/// assert_eq!(wrap_lng_around(-179.0, 177.0), 181.0);
+/// ```
+pub fn wrap_lng_around(lng:f64, center:f64) -> f64 {
+    let d = (lng - center + 180.0).rem_euclid(360.0) - 180.0;
+    center + d
+}
+
+/// signed shortest longitude difference `lng - from` in degrees, in the range [-180, 180)
+/// # Examples
+///
+/// ```
+/// use mercator::lng_diff;
+/// assert_eq!(lng_diff(-179.0, 179.0), 2.0);
+/// assert_eq!(lng_diff(179.0, -179.0), -2.0);
+/// ```
+pub fn lng_diff(lng:f64, from:f64) -> f64 {
+    wrap_lng_around(lng, from) - from
+}
+
+/// true if the shortest path from `lng1` to `lng2` crosses the ±180° meridian
+/// # Examples
+///
+/// ```
+/// use mercator::crosses_antimeridian;
+/// assert!(crosses_antimeridian(179.5, -179.5));
+/// assert!(!crosses_antimeridian(120.0, 122.0));
+/// ```
+pub fn crosses_antimeridian(lng1:f64, lng2:f64) -> bool {
+    let end = normalize_lng(lng1) + lng_diff(lng2, lng1);
+    !(-180.0..=180.0).contains(&end)
+}
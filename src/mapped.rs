@@ -0,0 +1,159 @@
+//! memory-mapped transforms of flat binary point files (feature `memmap`)
+//!
+//! the files hold `x, y` pairs of little-endian f64 and nothing else, 16
+//! bytes a point. source and destination are mapped a window of [`CHUNK`]
+//! points at a time and the coordinates are read from and written to the
+//! mappings directly, so a file much larger than RAM is converted without a
+//! read buffer and with the dirty pages flushed behind the work.
+
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+use memmap2::{MmapMut, MmapOptions};
+
+use error::{same_file, Error, Result};
+use job::{Job, CHUNK};
+use trace::Activity;
+
+/// bytes of one `(x, y)` point
+pub const POINT_BYTES:usize = 16;
+
+/// number of points in a file of `len` bytes
+fn point_count(len:u64) -> Result<u64> {
+    if !len.is_multiple_of(POINT_BYTES as u64) {
+        return Err(Error::Format(format!("point file of {} bytes is not a whole number of f64 pairs", len)));
+    }
+    Ok(len / POINT_BYTES as u64)
+}
+
+fn read_point(bytes:&[u8]) -> (f64, f64) {
+    let mut x = [0; 8];
+    let mut y = [0; 8];
+    x.copy_from_slice(&bytes[..8]);
+    y.copy_from_slice(&bytes[8..16]);
+    (f64::from_le_bytes(x), f64::from_le_bytes(y))
+}
+
+fn write_point(bytes:&mut [u8], (x, y):(f64, f64)) {
+    bytes[..8].copy_from_slice(&x.to_le_bytes());
+    bytes[8..16].copy_from_slice(&y.to_le_bytes());
+}
+
+/// map `points` points of `file` starting at point `first` for writing
+fn map_window(file:&File, first:u64, points:usize) -> Result<MmapMut> {
+    // windows start at multiples of CHUNK points, a multiple of any page size
+    let map = unsafe { MmapOptions::new().offset(first * POINT_BYTES as u64).len(points * POINT_BYTES).map_mut(file)? };
+    Ok(map)
+}
+
+/// write `f` applied to every point of `src` to `dst`, returning the number of points
+///
+/// `dst` is created or truncated to the size of `src`.
+/// # Examples
+///
+/// ```
+/// use std::convert::TryInto;
+/// use std::fs;
+/// use mercator::mapped::transform_file;
+/// use mercator::wgs84_to_twd97;
+/// let src = std::env::temp_dir().join("mercator-doc-points.bin");
+/// let dst = std::env::temp_dir().join("mercator-doc-points-twd97.bin");
+/// let mut bytes = Vec::new();
+/// for v in [121.0f64, 24.0, 121.5, 24.5] {
+///     bytes.extend_from_slice(&v.to_le_bytes());
+/// }
+/// fs::write(&src, bytes).unwrap();
+/// assert_eq!(transform_file(&src, &dst, wgs84_to_twd97).unwrap(), 2);
+/// let out = fs::read(&dst).unwrap();
+/// let x = f64::from_le_bytes(out[..8].try_into().unwrap());
+/// assert!((x - 250000.0).abs() < 1e-6);
+/// ```
+pub fn transform_file<P, Q, F>(src:P, dst:Q, f:F) -> Result<u64>
+    where P: AsRef<Path>, Q: AsRef<Path>, F: Fn(f64, f64) -> (f64, f64)
+{
+    transform_file_with(src, dst, f, &mut Job::new())
+}
+
+/// [`transform_file`] as a [`Job`], checked and reported after every window
+///
+/// a cancelled job leaves the rest of `dst` zeroed. `src` and `dst` naming the
+/// same file is an [`Error::InvalidInput`], use [`transform_file_in_place`] for that.
+/// # Examples
+///
+/// ```
+/// use std::fs;
+/// use mercator::Error;
+/// use mercator::job::Job;
+/// use mercator::mapped::transform_file_with;
+/// let path = std::env::temp_dir().join("mercator-doc-points-same.bin");
+/// let mut bytes = Vec::new();
+/// for v in [121.0f64, 24.0, 121.5, 24.5] {
+///     bytes.extend_from_slice(&v.to_le_bytes());
+/// }
+/// fs::write(&path, &bytes).unwrap();
+/// let err = transform_file_with(&path, &path, |x, y| (x + 1.0, y + 1.0), &mut Job::new()).unwrap_err();
+/// assert!(matches!(err, Error::InvalidInput(_)));
+/// assert_eq!(fs::read(&path).unwrap(), bytes);
+/// ```
+pub fn transform_file_with<P, Q, F>(src:P, dst:Q, f:F, job:&mut Job) -> Result<u64>
+    where P: AsRef<Path>, Q: AsRef<Path>, F: Fn(f64, f64) -> (f64, f64)
+{
+    if same_file(src.as_ref(), dst.as_ref()) {
+        return Err(Error::InvalidInput(format!("{} is both the source and the destination, use transform_file_in_place", src.as_ref().display())));
+    }
+    let activity = Activity::file("transform_file", src.as_ref());
+    let input = File::open(src)?;
+    let len = input.metadata()?.len();
+    let total = point_count(len)?;
+    let output = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(dst)?;
+    output.set_len(len)?;
+    let mut done = 0;
+    while done < total && !job.is_cancelled() {
+        let points = (total - done).min(CHUNK as u64) as usize;
+        let from = unsafe { MmapOptions::new().offset(done * POINT_BYTES as u64).len(points * POINT_BYTES).map(&input)? };
+        let mut to = map_window(&output, done, points)?;
+        for (a, b) in from.chunks_exact(POINT_BYTES).zip(to.chunks_exact_mut(POINT_BYTES)) {
+            let (x, y) = read_point(a);
+            write_point(b, f(x, y));
+        }
+        to.flush_async()?;
+        done += points as u64;
+        job.report(done, total);
+    }
+    output.sync_data()?;
+    activity.finish(done, 0);
+    Ok(done)
+}
+
+/// apply `f` to every point of the file at `path` in place, returning the number of points
+pub fn transform_file_in_place<P, F>(path:P, f:F) -> Result<u64>
+    where P: AsRef<Path>, F: Fn(f64, f64) -> (f64, f64)
+{
+    transform_file_in_place_with(path, f, &mut Job::new())
+}
+
+/// [`transform_file_in_place`] as a [`Job`], checked and reported after every window
+///
+/// a cancelled job leaves the remaining points untouched.
+pub fn transform_file_in_place_with<P, F>(path:P, f:F, job:&mut Job) -> Result<u64>
+    where P: AsRef<Path>, F: Fn(f64, f64) -> (f64, f64)
+{
+    let activity = Activity::file("transform_file_in_place", path.as_ref());
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+    let total = point_count(file.metadata()?.len())?;
+    let mut done = 0;
+    while done < total && !job.is_cancelled() {
+        let points = (total - done).min(CHUNK as u64) as usize;
+        let mut map = map_window(&file, done, points)?;
+        for p in map.chunks_exact_mut(POINT_BYTES) {
+            let (x, y) = read_point(p);
+            write_point(p, f(x, y));
+        }
+        map.flush_async()?;
+        done += points as u64;
+        job.report(done, total);
+    }
+    file.sync_data()?;
+    activity.finish(done, 0);
+    Ok(done)
+}
@@ -0,0 +1,198 @@
+//! nearest neighbor search over projected points (feature `kdtree`)
+//!
+//! the points are projected once and sorted into a 2d tree, queries given in
+//! the input CRS are projected the same way and answered with distances in
+//! the units of the projection, meters for the zone grids. the projection
+//! should be conformal near the data, as all the TM zones are, for the
+//! neighbors found to be the neighbors on the ground.
+
+use std::cmp::Ordering;
+
+/// a point found by a query
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Neighbor {
+    /// index of the point in the slice the tree was built from
+    pub index:usize,
+    pub distance:f64,
+}
+
+/// a balanced 2d tree over planar points, stored as the median-ordered point list
+#[derive(Debug, Clone)]
+pub struct KdTree {
+    /// `(x, y, index)` ordered so that every range has its splitting point at its middle
+    nodes:Vec<(f64, f64, usize)>,
+}
+
+fn coord(p:&(f64, f64, usize), axis:usize) -> f64 {
+    if axis == 0 { p.0 } else { p.1 }
+}
+
+fn build(nodes:&mut [(f64, f64, usize)], axis:usize) {
+    if nodes.len() <= 1 {
+        return;
+    }
+    let mid = nodes.len() / 2;
+    nodes.select_nth_unstable_by(mid, |a, b| coord(a, axis).partial_cmp(&coord(b, axis)).unwrap_or(Ordering::Equal));
+    let (left, right) = nodes.split_at_mut(mid);
+    build(left, 1 - axis);
+    build(&mut right[1..], 1 - axis);
+}
+
+/// the best neighbors so far, closest first, at most `k`
+struct Best {
+    k:usize,
+    found:Vec<Neighbor>,
+}
+
+impl Best {
+    fn bound(&self) -> f64 {
+        if self.found.len() < self.k { f64::INFINITY } else { self.found[self.found.len() - 1].distance }
+    }
+
+    fn offer(&mut self, n:Neighbor) {
+        if n.distance >= self.bound() {
+            return;
+        }
+        let at = self.found.iter().position(|f| f.distance > n.distance).unwrap_or(self.found.len());
+        self.found.insert(at, n);
+        self.found.truncate(self.k);
+    }
+}
+
+impl KdTree {
+    /// a tree over projected points, those with a NaN coordinate are left out
+    pub fn new(points:&[(f64, f64)]) -> KdTree {
+        let mut nodes:Vec<_> = points.iter().enumerate()
+            .filter(|&(_, p)| !p.0.is_nan() && !p.1.is_nan())
+            .map(|(i, p)| (p.0, p.1, i))
+            .collect();
+        build(&mut nodes, 0);
+        KdTree { nodes }
+    }
+
+    /// number of points in the tree
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    fn search(&self, nodes:&[(f64, f64, usize)], axis:usize, x:f64, y:f64, best:&mut Best) {
+        if nodes.is_empty() {
+            return;
+        }
+        let mid = nodes.len() / 2;
+        let p = &nodes[mid];
+        best.offer(Neighbor { index: p.2, distance: (p.0 - x).hypot(p.1 - y) });
+        let diff = if axis == 0 { x - p.0 } else { y - p.1 };
+        let (near, far) = if diff < 0.0 { (&nodes[..mid], &nodes[mid + 1..]) } else { (&nodes[mid + 1..], &nodes[..mid]) };
+        self.search(near, 1 - axis, x, y, best);
+        if diff.abs() < best.bound() {
+            self.search(far, 1 - axis, x, y, best);
+        }
+    }
+
+    fn collect(&self, nodes:&[(f64, f64, usize)], axis:usize, x:f64, y:f64, radius:f64, out:&mut Vec<Neighbor>) {
+        if nodes.is_empty() {
+            return;
+        }
+        let mid = nodes.len() / 2;
+        let p = &nodes[mid];
+        let distance = (p.0 - x).hypot(p.1 - y);
+        if distance <= radius {
+            out.push(Neighbor { index: p.2, distance });
+        }
+        let diff = if axis == 0 { x - p.0 } else { y - p.1 };
+        if diff <= radius {
+            self.collect(&nodes[..mid], 1 - axis, x, y, radius, out);
+        }
+        if diff >= -radius {
+            self.collect(&nodes[mid + 1..], 1 - axis, x, y, radius, out);
+        }
+    }
+
+    /// the point closest to a projected position
+    pub fn nearest(&self, x:f64, y:f64) -> Option<Neighbor> {
+        self.k_nearest(x, y, 1).into_iter().next()
+    }
+
+    /// the `k` points closest to a projected position, closest first
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::nearest::KdTree;
+    /// let tree = KdTree::new(&[(0.0, 0.0), (3.0, 4.0), (1.0, 0.0)]);
+    /// let all = tree.k_nearest(0.0, 0.0, usize::MAX);
+    /// assert_eq!(all.iter().map(|n| n.index).collect::<Vec<_>>(), vec![0, 2, 1]);
+    /// assert_eq!(all[2].distance, 5.0);
+    /// ```
+    pub fn k_nearest(&self, x:f64, y:f64, k:usize) -> Vec<Neighbor> {
+        let mut best = Best { k, found: Vec::with_capacity(k.min(self.len()) + 1) };
+        if k > 0 && !x.is_nan() && !y.is_nan() {
+            self.search(&self.nodes, 0, x, y, &mut best);
+        }
+        best.found
+    }
+
+    /// the points within `radius` of a projected position, closest first
+    pub fn within(&self, x:f64, y:f64, radius:f64) -> Vec<Neighbor> {
+        let mut out = Vec::new();
+        if !x.is_nan() && !y.is_nan() {
+            self.collect(&self.nodes, 0, x, y, radius, &mut out);
+        }
+        out.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap_or(Ordering::Equal));
+        out
+    }
+}
+
+/// points projected with `f` and indexed, queried with positions in the input CRS
+/// # Examples
+///
+/// ```
+/// use mercator::nearest::PointIndex;
+/// use mercator::wgs84_to_twd97;
+/// let stations = [(121.5654, 25.0330), (120.6736, 24.1477), (120.3014, 22.6273)];
+/// let index = PointIndex::new(&stations, wgs84_to_twd97);
+/// let n = index.nearest(121.52, 25.05).unwrap();
+/// assert_eq!(n.index, 0);
+/// assert!((n.distance - 5000.0).abs() < 200.0);
+/// let two = index.k_nearest(120.5, 23.5, 2);
+/// assert_eq!(two.iter().map(|n| n.index).collect::<Vec<_>>(), vec![1, 2]);
+/// assert_eq!(index.within(121.0, 24.5, 60_000.0).len(), 1);
+/// ```
+pub struct PointIndex<F> {
+    tree:KdTree,
+    f:F,
+}
+
+impl<F> PointIndex<F> where F: Fn(f64, f64) -> (f64, f64) {
+    pub fn new(points:&[(f64, f64)], f:F) -> PointIndex<F> {
+        let projected:Vec<_> = points.iter().map(|p| f(p.0, p.1)).collect();
+        PointIndex { tree: KdTree::new(&projected), f }
+    }
+
+    /// the tree of the projected points
+    pub fn tree(&self) -> &KdTree {
+        &self.tree
+    }
+
+    /// the point closest to a position in the input CRS
+    pub fn nearest(&self, x:f64, y:f64) -> Option<Neighbor> {
+        let (px, py) = (self.f)(x, y);
+        self.tree.nearest(px, py)
+    }
+
+    /// the `k` points closest to a position in the input CRS, closest first
+    pub fn k_nearest(&self, x:f64, y:f64, k:usize) -> Vec<Neighbor> {
+        let (px, py) = (self.f)(x, y);
+        self.tree.k_nearest(px, py, k)
+    }
+
+    /// the points within `radius` meters of a position in the input CRS, closest first
+    pub fn within(&self, x:f64, y:f64, radius:f64) -> Vec<Neighbor> {
+        let (px, py) = (self.f)(x, y);
+        self.tree.within(px, py, radius)
+    }
+}
@@ -0,0 +1,112 @@
+//! iterative inverse projection by Newton's method
+//!
+//! closed-form inverses (the footprint latitude series of the crate root) are
+//! only as good as their truncation, and far outside a zone they can be wrong
+//! without saying so. [`Newton`] refines any inverse against its forward
+//! projection until the grid residual drops below a tolerance, and reports an
+//! error when it does not.
+
+use error::{Error, Result};
+use projection::Projection;
+use zone::Zone;
+
+/// settings of the Newton iteration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Newton {
+    /// largest accepted residual in grid units (meters for the built-in zones)
+    pub tolerance:f64,
+    pub max_iterations:u32,
+}
+
+impl Default for Newton {
+    fn default() -> Newton {
+        Newton { tolerance: 1e-6, max_iterations: 10 }
+    }
+}
+
+/// finite difference step in degrees, about a meter on the ground
+const STEP:f64 = 1e-5;
+
+impl Newton {
+    pub fn new(tolerance:f64, max_iterations:u32) -> Newton {
+        Newton { tolerance, max_iterations }
+    }
+
+    /// find `(lng, lat)` with `forward(lng, lat) == (x, y)`, starting from `guess`
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::newton::Newton;
+    /// use mercator::wgs84_to_twd97;
+    /// let (x, y) = wgs84_to_twd97(121.5, 25.0);
+    /// let (lng, lat) = Newton::default().solve(wgs84_to_twd97, x, y, (121.0, 24.0)).unwrap();
+    /// let (x2, y2) = wgs84_to_twd97(lng, lat);
+    /// assert!((x2 - x).abs() < 1e-6 && (y2 - y).abs() < 1e-6);
+    ///
+    /// // a forward function that cannot reach the target
+    /// assert!(Newton::default().solve(|lng, lat| (lng.sin(), lat.sin()), 3.0, 3.0, (0.0, 0.0)).is_err());
+    /// ```
+    pub fn solve<F>(&self, forward:F, x:f64, y:f64, guess:(f64, f64)) -> Result<(f64, f64)>
+        where F: Fn(f64, f64) -> (f64, f64)
+    {
+        let (mut lng, mut lat) = guess;
+        let mut residual = f64::INFINITY;
+        for _ in 0..=self.max_iterations {
+            let (fx, fy) = forward(lng, lat);
+            let (rx, ry) = (x - fx, y - fy);
+            residual = rx.hypot(ry);
+            if residual <= self.tolerance {
+                return Ok((lng, lat));
+            }
+            if !residual.is_finite() {
+                break;
+            }
+            // central differences, the latitude step shrinks near the poles to stay inside ±90°
+            let hl = STEP.min((90.0 - lat.abs()) / 2.0).max(f64::EPSILON);
+            let (ax, ay) = forward(lng + STEP, lat);
+            let (bx, by) = forward(lng - STEP, lat);
+            let (cx, cy) = forward(lng, lat + hl);
+            let (dx, dy) = forward(lng, lat - hl);
+            let (j11, j21) = ((ax - bx) / (2.0 * STEP), (ay - by) / (2.0 * STEP));
+            let (j12, j22) = ((cx - dx) / (2.0 * hl), (cy - dy) / (2.0 * hl));
+            let det = j11 * j22 - j12 * j21;
+            if det == 0.0 || !det.is_finite() {
+                break;
+            }
+            lng += (j22 * rx - j12 * ry) / det;
+            lat += (j11 * ry - j21 * rx) / det;
+            lat = lat.clamp(-90.0, 90.0);
+        }
+        Err(Error::Transform(format!("inverse of ({}, {}) did not converge in {} iterations, residual {:e}",
+            x, y, self.max_iterations, residual)))
+    }
+
+    /// invert a projection, refining its own inverse
+    pub fn invert<P:Projection + ?Sized>(&self, proj:&P, x:f64, y:f64) -> Result<(f64, f64)> {
+        let guess = proj.inverse(x, y);
+        let guess = if guess.0.is_finite() && guess.1.is_finite() { guess } else { (0.0, 0.0) };
+        self.solve(|lng, lat| proj.forward(lng, lat), x, y, guess)
+    }
+}
+
+impl Zone {
+    /// convert grid coordinates back to wgs84, refined by Newton's method
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::newton::Newton;
+    /// use mercator::zone::Zone;
+    /// let zone = Zone::twd97();
+    /// // 8° off the central meridian the closed-form series inverse is off by meters
+    /// let (x, y) = zone.forward(129.0, 25.0);
+    /// let (lng, lat) = zone.try_inverse(x, y, &Newton::default()).unwrap();
+    /// let (x2, y2) = zone.forward(lng, lat);
+    /// assert!((x2 - x).abs() < 1e-6 && (y2 - y).abs() < 1e-6);
+    /// assert!(zone.try_inverse(x, y, &Newton::new(1e-6, 0)).is_err());
+    /// ```
+    pub fn try_inverse(&self, x:f64, y:f64, newton:&Newton) -> Result<(f64, f64)> {
+        let guess = self.inverse(x, y);
+        let guess = if guess.0.is_finite() && guess.1.is_finite() { guess } else { (self.center_lng, 0.0) };
+        newton.solve(|lng, lat| self.forward(lng, lat), x, y, guess)
+    }
+}
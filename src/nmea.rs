@@ -0,0 +1,256 @@
+//! NMEA 0183 GGA / RMC sentence parsing
+//!
+//! any talker id is accepted (`$GP`, `$GN`, `$GL`, ...). the checksum is
+//! verified when the sentence carries one.
+
+use std::io::BufRead;
+use std::str::FromStr;
+
+use coord::{LngLat, Projected};
+use error::{Error, Result};
+
+/// GGA fix quality indicator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FixQuality {
+    Invalid,
+    Gps,
+    Dgps,
+    Pps,
+    Rtk,
+    FloatRtk,
+    Estimated,
+    Manual,
+    Simulation,
+    Other(u8),
+}
+
+impl FixQuality {
+    fn from_code(code:u8) -> FixQuality {
+        match code {
+            0 => FixQuality::Invalid,
+            1 => FixQuality::Gps,
+            2 => FixQuality::Dgps,
+            3 => FixQuality::Pps,
+            4 => FixQuality::Rtk,
+            5 => FixQuality::FloatRtk,
+            6 => FixQuality::Estimated,
+            7 => FixQuality::Manual,
+            8 => FixQuality::Simulation,
+            c => FixQuality::Other(c),
+        }
+    }
+
+    /// true for any fix that carries a position
+    pub fn is_valid(&self) -> bool {
+        *self != FixQuality::Invalid
+    }
+}
+
+/// a position fix read from a GGA or RMC sentence
+#[derive(Debug, Clone, PartialEq)]
+pub struct NmeaFix {
+    /// sentence type without the talker id, `"GGA"` or `"RMC"`
+    pub sentence:String,
+    /// UTC time of the fix as sent, `hhmmss.ss`
+    pub time:String,
+    pub lng:f64,
+    pub lat:f64,
+    /// from the GGA quality field, RMC only tells valid (`Gps`) or not
+    pub quality:FixQuality,
+    pub satellites:Option<u8>,
+    pub hdop:Option<f64>,
+    /// GGA antenna altitude above mean sea level in meters
+    pub altitude:Option<f64>,
+    /// GGA geoid separation (ellipsoid height of the geoid) in meters
+    pub geoid_separation:Option<f64>,
+}
+
+fn nmea_err(msg:String) -> Error {
+    Error::Format(format!("nmea: {}", msg))
+}
+
+fn checked_body(line:&str) -> Result<&str> {
+    let line = line.trim();
+    let body = if line.starts_with('$') || line.starts_with('!') {
+        &line[1..]
+    } else {
+        return Err(nmea_err(format!("not a sentence: {:?}", line)));
+    };
+    match body.find('*') {
+        Some(star) => {
+            let (data, sum) = (&body[..star], &body[star + 1..]);
+            let want = u8::from_str_radix(sum, 16).map_err(|_| nmea_err(format!("bad checksum {:?}", sum)))?;
+            let got = data.bytes().fold(0u8, |acc, b| acc ^ b);
+            if got != want {
+                return Err(nmea_err(format!("checksum mismatch, got {:02X} expected {:02X}", got, want)));
+            }
+            Ok(data)
+        }
+        None => Ok(body),
+    }
+}
+
+fn optional<T:FromStr>(field:&str) -> Result<Option<T>> {
+    if field.is_empty() {
+        return Ok(None);
+    }
+    field.parse().map(Some).map_err(|_| nmea_err(format!("bad field {:?}", field)))
+}
+
+/// `ddmm.mmmm` / `dddmm.mmmm` plus hemisphere to signed degrees
+fn angle(value:&str, hemisphere:&str) -> Result<f64> {
+    let v:f64 = value.parse().map_err(|_| nmea_err(format!("bad coordinate {:?}", value)))?;
+    let deg = (v / 100.0).trunc();
+    let d = deg + (v - deg * 100.0) / 60.0;
+    match hemisphere {
+        "N" | "E" => Ok(d),
+        "S" | "W" => Ok(-d),
+        h => Err(nmea_err(format!("bad hemisphere {:?}", h))),
+    }
+}
+
+/// parse one sentence, `Ok(None)` for well-formed sentences other than GGA and RMC
+/// # Examples
+///
+/// ```
+/// use mercator::nmea::{parse_sentence, FixQuality};
+/// let gga = "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47";
+/// let fix = parse_sentence(gga).unwrap().unwrap();
+/// assert!((fix.lat - 48.1173).abs() < 1e-9);
+/// assert!((fix.lng - 11.516666666666667).abs() < 1e-9);
+/// assert_eq!(fix.quality, FixQuality::Gps);
+/// assert_eq!(fix.ellipsoidal_height(), Some(592.3));
+/// assert!(parse_sentence("$GPGSV,1,1,00*79").unwrap().is_none());
+/// // line noise in the sentence id
+/// assert!(parse_sentence("$AAA€1,1,2").is_err());
+/// ```
+pub fn parse_sentence(line:&str) -> Result<Option<NmeaFix>> {
+    let data = checked_body(line)?;
+    let fields:Vec<&str> = data.split(',').collect();
+    let kind = fields[0];
+    if kind.len() < 5 || !kind.is_ascii() {
+        return Err(nmea_err(format!("bad sentence id {:?}", kind)));
+    }
+    let sentence = &kind[kind.len() - 3..];
+    let need = |n:usize| if fields.len() < n {
+        Err(nmea_err(format!("{} needs {} fields, got {}", sentence, n, fields.len())))
+    } else {
+        Ok(())
+    };
+    match sentence {
+        "GGA" => {
+            need(12)?;
+            let quality = FixQuality::from_code(optional(fields[6])?.unwrap_or(0));
+            let (lng, lat) = if quality.is_valid() {
+                (angle(fields[4], fields[5])?, angle(fields[2], fields[3])?)
+            } else {
+                (f64::NAN, f64::NAN)
+            };
+            Ok(Some(NmeaFix {
+                sentence: sentence.to_string(),
+                time: fields[1].to_string(),
+                lng,
+                lat,
+                quality,
+                satellites: optional(fields[7])?,
+                hdop: optional(fields[8])?,
+                altitude: optional(fields[9])?,
+                geoid_separation: optional(fields[11])?,
+            }))
+        }
+        "RMC" => {
+            need(7)?;
+            let quality = if fields[2] == "A" { FixQuality::Gps } else { FixQuality::Invalid };
+            let (lng, lat) = if quality.is_valid() {
+                (angle(fields[5], fields[6])?, angle(fields[3], fields[4])?)
+            } else {
+                (f64::NAN, f64::NAN)
+            };
+            Ok(Some(NmeaFix {
+                sentence: sentence.to_string(),
+                time: fields[1].to_string(),
+                lng,
+                lat,
+                quality,
+                satellites: None,
+                hdop: None,
+                altitude: None,
+                geoid_separation: None,
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+impl NmeaFix {
+    /// ellipsoidal height, when both altitude and geoid separation were sent
+    pub fn ellipsoidal_height(&self) -> Option<f64> {
+        match (self.altitude, self.geoid_separation) {
+            (Some(a), Some(n)) => Some(a + n),
+            _ => None,
+        }
+    }
+
+    /// the fix as a coordinate, the height is 0 when it is not known
+    pub fn to_lnglat(&self) -> LngLat {
+        LngLat::with_height(self.lng, self.lat, self.ellipsoidal_height().unwrap_or(0.0))
+    }
+
+    /// project the fix with `f`, carrying the height over
+    pub fn project<F>(&self, f:F) -> Projected
+        where F: Fn(f64, f64) -> (f64, f64)
+    {
+        let p = self.to_lnglat();
+        let (x, y) = f(p.lng, p.lat);
+        Projected::with_height(x, y, p.h)
+    }
+}
+
+/// parse a GGA or RMC sentence and project it with `f` in one call
+/// # Examples
+///
+/// ```
+/// use mercator::nmea::project_sentence;
+/// use mercator::wgs84_to_twd97;
+/// let rmc = "$GNRMC,083559.00,A,2501.9800,N,12133.9240,E,0.004,77.52,091219,,,A*49";
+/// let p = project_sentence(rmc, wgs84_to_twd97).unwrap();
+/// assert!(p.x > 300000.0 && p.y > 2700000.0);
+/// ```
+pub fn project_sentence<F>(line:&str, f:F) -> Result<Projected>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    match parse_sentence(line)? {
+        Some(ref fix) if fix.quality.is_valid() => Ok(fix.project(f)),
+        Some(_) => Err(Error::InvalidInput("nmea: sentence has no valid fix".to_string())),
+        None => Err(Error::InvalidInput(format!("nmea: not a GGA or RMC sentence: {}", line.trim()))),
+    }
+}
+
+/// read valid GGA / RMC fixes from a stream of sentences, e.g. a serial port
+///
+/// other sentence types, blank lines and fixes without a position are skipped;
+/// corrupt sentences are returned as errors so the caller can decide.
+/// # Examples
+///
+/// ```
+/// use mercator::nmea::read_fixes;
+/// let log = "$GPGSV,1,1,00*79\n$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\n";
+/// let fixes:Vec<_> = read_fixes(log.as_bytes()).collect();
+/// assert_eq!(fixes.len(), 1);
+/// ```
+pub fn read_fixes<R:BufRead>(reader:R) -> impl Iterator<Item=Result<NmeaFix>> {
+    reader.lines().filter_map(|line| {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => return Some(Err(Error::from(e))),
+        };
+        if line.trim().is_empty() {
+            return None;
+        }
+        match parse_sentence(&line) {
+            Ok(Some(fix)) => if fix.quality.is_valid() { Some(Ok(fix)) } else { None },
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    })
+}
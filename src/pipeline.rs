@@ -0,0 +1,271 @@
+//! explicit transform pipelines, in the spirit of PROJ's `+proj=pipeline`
+//!
+//! a pipeline is a list of steps applied in order to `(x, y, z)`; steps
+//! working on geographic coordinates expect lng/lat in degrees and an
+//! ellipsoidal height in meters, projected steps work on grid meters.
+
+use std::fmt;
+use std::sync::Arc;
+
+use accuracy;
+use affine::Affine2D;
+use coord::Ecef;
+use ellipsoid::{Ellipsoid, WGS84};
+use error::{Error, Result};
+use gridshift::CorrectionGrid;
+use helmert::Helmert;
+use projection::Projection;
+use trace;
+use units::Unit;
+use zone::{TmMethod, Zone};
+
+/// one step of a [`Pipeline`]
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// grid coordinates of a zone on an ellipsoid to lng/lat
+    Unproject(Zone, Ellipsoid),
+    /// lng/lat to grid coordinates of a zone on an ellipsoid
+    Project(Zone, Ellipsoid),
+    /// grid coordinates of a custom projection to lng/lat
+    UnprojectWith(Arc<dyn Projection>),
+    /// lng/lat to grid coordinates of a custom projection
+    ProjectWith(Arc<dyn Projection>),
+    /// datum shift of lng/lat/h through geocentric coordinates
+    Helmert { params:Helmert, from:Ellipsoid, to:Ellipsoid },
+    /// add the corrections of a grid
+    GridShift(Arc<CorrectionGrid>),
+    /// remove the corrections of a grid
+    InverseGridShift(Arc<CorrectionGrid>),
+    /// 2D affine transform of x / y
+    Affine(Affine2D),
+    /// multiply x and y, e.g. for a unit conversion
+    Scale(f64),
+    /// exchange x and y
+    SwapAxes,
+}
+
+impl Step {
+    fn apply(&self, x:f64, y:f64, z:f64) -> Result<(f64, f64, f64)> {
+        let outside = || Error::Transform(format!("({}, {}) is outside the correction grid", x, y));
+        Ok(match *self {
+            Step::Unproject(ref zone, ref ellipsoid) => {
+                let (lng, lat) = zone.inverse_on(ellipsoid, x, y);
+                (lng, lat, z)
+            }
+            Step::Project(ref zone, ref ellipsoid) => {
+                let (e, n) = zone.forward_on(ellipsoid, x, y);
+                (e, n, z)
+            }
+            Step::UnprojectWith(ref p) => {
+                let (lng, lat) = p.inverse(x, y);
+                (lng, lat, z)
+            }
+            Step::ProjectWith(ref p) => {
+                let (e, n) = p.forward(x, y);
+                (e, n, z)
+            }
+            Step::Helmert { ref params, ref from, ref to } => {
+                let (ex, ey, ez) = from.geodetic_to_ecef(x, y, z);
+                let p = params.apply(Ecef::new(ex, ey, ez));
+                to.ecef_to_geodetic(p.x, p.y, p.z)
+            }
+            Step::GridShift(ref grid) => grid.apply_3d(x, y, z).ok_or_else(outside)?,
+            Step::InverseGridShift(ref grid) => {
+                let (px, py) = grid.apply_inverse(x, y).ok_or_else(outside)?;
+                let (_, _, dz) = grid.correction(px, py).ok_or_else(outside)?;
+                (px, py, z - dz)
+            }
+            Step::Affine(ref a) => {
+                let (ax, ay) = a.apply(x, y);
+                (ax, ay, z)
+            }
+            Step::Scale(k) => (x * k, y * k, z),
+            Step::SwapAxes => (y, x, z),
+        })
+    }
+
+    /// estimated accuracy of the step in meters, see [`accuracy`](::accuracy)
+    pub fn accuracy(&self) -> f64 {
+        match *self {
+            Step::Unproject(ref zone, _) | Step::Project(ref zone, _) => match zone.method {
+                TmMethod::Series => accuracy::TM_SERIES,
+                TmMethod::Fast => accuracy::TM_FAST,
+                TmMethod::Exact | TmMethod::Accurate => accuracy::TM_EXACT,
+            },
+            Step::Helmert { ref params, .. } => accuracy::helmert_accuracy(params),
+            Step::GridShift(_) | Step::InverseGridShift(_) => accuracy::GRID,
+            // custom projections and exact arithmetic add nothing we know of
+            _ => 0.0,
+        }
+    }
+
+    fn inverse(&self) -> Option<Step> {
+        Some(match *self {
+            Step::Unproject(zone, ellipsoid) => Step::Project(zone, ellipsoid),
+            Step::Project(zone, ellipsoid) => Step::Unproject(zone, ellipsoid),
+            Step::UnprojectWith(ref p) => Step::ProjectWith(p.clone()),
+            Step::ProjectWith(ref p) => Step::UnprojectWith(p.clone()),
+            Step::Helmert { params, from, to } => Step::Helmert { params: params.inverse(), from: to, to: from },
+            Step::GridShift(ref grid) => Step::InverseGridShift(grid.clone()),
+            Step::InverseGridShift(ref grid) => Step::GridShift(grid.clone()),
+            Step::Affine(ref a) => Step::Affine(a.inverse()?),
+            Step::Scale(k) if k != 0.0 => Step::Scale(1.0 / k),
+            Step::Scale(_) => return None,
+            Step::SwapAxes => Step::SwapAxes,
+        })
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Step::Unproject(ref z, ref e) => write!(f, "unproject tm lon_0={} k0={} a={}", z.center_lng, z.width.k0(), e.a),
+            Step::Project(ref z, ref e) => write!(f, "project tm lon_0={} k0={} a={}", z.center_lng, z.width.k0(), e.a),
+            Step::UnprojectWith(ref p) => write!(f, "unproject {}", p.name()),
+            Step::ProjectWith(ref p) => write!(f, "project {}", p.name()),
+            Step::Helmert { ref params, .. } => write!(f, "helmert {:?}", params),
+            Step::GridShift(_) => write!(f, "gridshift"),
+            Step::InverseGridShift(_) => write!(f, "inverse gridshift"),
+            Step::Affine(ref a) => write!(f, "affine {:?}", a),
+            Step::Scale(k) => write!(f, "scale {}", k),
+            Step::SwapAxes => write!(f, "axisswap"),
+        }
+    }
+}
+
+/// a reusable chain of transform steps
+/// # Examples
+///
+/// ```
+/// use mercator::ellipsoid::{GRS67_MODIFIED, WGS84};
+/// use mercator::helmert::Helmert;
+/// use mercator::pipeline::Pipeline;
+/// use mercator::zone::Zone;
+/// // TM2 grid on one datum to the same grid on another, in US survey feet
+/// let p = Pipeline::new()
+///     .unproject(Zone::twd97())
+///     .helmert(Helmert::translation(752.0, 358.0, 179.0), WGS84, GRS67_MODIFIED)
+///     .project(Zone::twd97())
+///     .scale(3937.0 / 1200.0);
+/// let (x, y) = p.transform(250000.0, 2650000.0).unwrap();
+/// assert!((x / (3937.0 / 1200.0) - 250000.0).abs() < 1000.0);
+/// let back = p.inverse().unwrap().transform(x, y).unwrap();
+/// assert!((back.0 - 250000.0).abs() < 0.01 && (back.1 - 2650000.0).abs() < 0.01);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    pub steps:Vec<Step>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline { steps: Vec::new() }
+    }
+
+    /// append any step
+    pub fn then(mut self, step:Step) -> Pipeline {
+        self.steps.push(step);
+        self
+    }
+
+    /// unproject grid coordinates of a WGS84 zone
+    pub fn unproject(self, zone:Zone) -> Pipeline {
+        self.then(Step::Unproject(zone, WGS84))
+    }
+
+    /// project onto a WGS84 zone
+    pub fn project(self, zone:Zone) -> Pipeline {
+        self.then(Step::Project(zone, WGS84))
+    }
+
+    /// project with a custom projection
+    pub fn project_with(self, projection:Arc<dyn Projection>) -> Pipeline {
+        self.then(Step::ProjectWith(projection))
+    }
+
+    /// unproject with a custom projection
+    pub fn unproject_with(self, projection:Arc<dyn Projection>) -> Pipeline {
+        self.then(Step::UnprojectWith(projection))
+    }
+
+    pub fn helmert(self, params:Helmert, from:Ellipsoid, to:Ellipsoid) -> Pipeline {
+        self.then(Step::Helmert { params, from, to })
+    }
+
+    pub fn grid_shift(self, grid:Arc<CorrectionGrid>) -> Pipeline {
+        self.then(Step::GridShift(grid))
+    }
+
+    /// an affine step on the plane coordinates, before unprojecting or after projecting
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::affine::Affine2D;
+    /// use mercator::pipeline::Pipeline;
+    /// use mercator::zone::Zone;
+    /// // site grid coordinates to lng/lat through TWD97
+    /// let to_twd97 = Affine2D::rotating(30.0).then(&Affine2D::translation(250000.0, 2650000.0));
+    /// let p = Pipeline::new().affine(to_twd97).unproject(Zone::twd97());
+    /// let (lng, lat) = p.transform(100.0, 0.0).unwrap();
+    /// let (e, n) = to_twd97.apply(100.0, 0.0);
+    /// assert_eq!((lng, lat), Zone::twd97().inverse(e, n));
+    /// let (x, y) = p.inverse().unwrap().transform(lng, lat).unwrap();
+    /// assert!((x - 100.0).abs() < 1e-3 && y.abs() < 1e-3);
+    /// ```
+    pub fn affine(self, a:Affine2D) -> Pipeline {
+        self.then(Step::Affine(a))
+    }
+
+    pub fn scale(self, k:f64) -> Pipeline {
+        self.then(Step::Scale(k))
+    }
+
+    /// rescale linear coordinates from one unit to another
+    pub fn convert_units(self, from:Unit, to:Unit) -> Result<Pipeline> {
+        Ok(self.scale(from.factor_to(to)?))
+    }
+
+    pub fn swap_axes(self) -> Pipeline {
+        self.then(Step::SwapAxes)
+    }
+
+    /// run the steps on a 2D coordinate, heights start at 0
+    pub fn transform(&self, x:f64, y:f64) -> Result<(f64, f64)> {
+        self.transform_3d(x, y, 0.0).map(|(x, y, _)| (x, y))
+    }
+
+    /// run the steps on a coordinate with height
+    pub fn transform_3d(&self, x:f64, y:f64, z:f64) -> Result<(f64, f64, f64)> {
+        self.steps.iter().try_fold((x, y, z), |(x, y, z), step| step.apply(x, y, z).inspect_err(|e| trace::step_failed(step, e)))
+    }
+
+    /// estimated accuracy of the whole pipeline in meters
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::pipeline::Pipeline;
+    /// use mercator::zone::Zone;
+    /// let p = Pipeline::new().unproject(Zone::twd97()).project(Zone::utm(51));
+    /// assert!(p.accuracy() < 0.01);
+    /// ```
+    pub fn accuracy(&self) -> f64 {
+        let errors:Vec<f64> = self.steps.iter().map(Step::accuracy).collect();
+        accuracy::combine(&errors)
+    }
+
+    /// the pipeline running the other way, `None` when a step cannot be inverted
+    pub fn inverse(&self) -> Option<Pipeline> {
+        let steps = self.steps.iter().rev().map(Step::inverse).collect::<Option<Vec<Step>>>()?;
+        Some(Pipeline { steps })
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "pipeline")?;
+        for step in &self.steps {
+            write!(f, " | {}", step)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+//! epoch propagation with plate rotation (Euler pole) models
+
+use coord::{Dated, Ecef, LngLat};
+use error::{Error, Result};
+
+const MAS:f64 = ::std::f64::consts::PI / 648000000.0;
+
+/// rotation of a tectonic plate as a cartesian rotation rate vector in milliarcseconds per year
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EulerPole {
+    pub wx:f64,
+    pub wy:f64,
+    pub wz:f64,
+}
+
+impl EulerPole {
+    pub fn new(wx:f64, wy:f64, wz:f64) -> EulerPole {
+        EulerPole { wx, wy, wz }
+    }
+
+    /// build from the pole position (degrees) and rotation rate in degrees per million years
+    pub fn from_pole(lng:f64, lat:f64, rate:f64) -> EulerPole {
+        // deg/Myr to mas/yr
+        let w = rate * 3600.0 * 1000.0 / 1e6;
+        let (slat, clat) = lat.to_radians().sin_cos();
+        let (slng, clng) = lng.to_radians().sin_cos();
+        EulerPole::new(w * clat * clng, w * clat * slng, w * slat)
+    }
+
+    /// plate velocity at a geocentric position, meters per year
+    pub fn velocity(&self, p:Ecef) -> (f64, f64, f64) {
+        let (wx, wy, wz) = (self.wx * MAS, self.wy * MAS, self.wz * MAS);
+        (wy * p.z - wz * p.y, wz * p.x - wx * p.z, wx * p.y - wy * p.x)
+    }
+
+    /// move a geocentric position observed at epoch `from` to epoch `to` (decimal years)
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::coord::Ecef;
+    /// use mercator::plate::PlateModel;
+    /// let eura = PlateModel::itrf2014().get("EURA").unwrap();
+    /// // Zimmerwald moves about 2.5 cm a year towards the north-east
+    /// let p = Ecef::new(4331297.0, 567555.0, 4633133.0);
+    /// let q = eura.propagate(p, 2010.0, 2020.0);
+    /// let moved = ((q.x - p.x).powi(2) + (q.y - p.y).powi(2) + (q.z - p.z).powi(2)).sqrt();
+    /// assert!(moved > 0.2 && moved < 0.3);
+    /// ```
+    pub fn propagate(&self, p:Ecef, from:f64, to:f64) -> Ecef {
+        let (vx, vy, vz) = self.velocity(p);
+        let dt = to - from;
+        Ecef::new(p.x + vx * dt, p.y + vy * dt, p.z + vz * dt)
+    }
+
+    /// move a dated geocentric position to epoch `to`, failing when it has no epoch
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::coord::{Dated, Ecef};
+    /// use mercator::plate::PlateModel;
+    /// let eura = PlateModel::itrf2014().get("EURA").unwrap();
+    /// let p = Ecef::new(4331297.0, 567555.0, 4633133.0);
+    /// let q = eura.propagate_dated(p.at_epoch(2010.0), 2020.0).unwrap();
+    /// assert_eq!(q, eura.propagate(p, 2010.0, 2020.0).at_epoch(2020.0));
+    /// assert!(eura.propagate_dated(Dated::undated(p), 2020.0).is_err());
+    /// ```
+    pub fn propagate_dated(&self, p:Dated<Ecef>, to:f64) -> Result<Dated<Ecef>> {
+        let from = p.epoch.ok_or_else(|| Error::Transform("plate motion needs the epoch of the coordinate".to_string()))?;
+        Ok(Dated::new(self.propagate(p.coord, from, to), to))
+    }
+
+    /// move a geographic position observed at epoch `from` to epoch `to` (wgs84 / ITRF ellipsoid)
+    pub fn propagate_lnglat(&self, p:LngLat, from:f64, to:f64) -> LngLat {
+        self.propagate(p.to_ecef(), from, to).to_lnglat()
+    }
+}
+
+/// a set of named plates with their rotation poles
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PlateModel {
+    pub plates:Vec<(String, EulerPole)>,
+}
+
+impl PlateModel {
+    pub fn new() -> PlateModel {
+        PlateModel::default()
+    }
+
+    /// ITRF2014 plate motion model (Altamimi et al. 2017, table 1), rotation rates in mas/yr
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::plate::{EulerPole, PlateModel};
+    /// assert_eq!(PlateModel::itrf2014().get("AUST"), Some(EulerPole::new(1.510, 1.182, 1.215)));
+    /// ```
+    pub fn itrf2014() -> PlateModel {
+        let poles = [
+            ("ANTA", -0.248, -0.324, 0.675),
+            ("ARAB", 1.154, -0.136, 1.444),
+            ("AUST", 1.510, 1.182, 1.215),
+            ("EURA", -0.085, -0.531, 0.770),
+            ("INDI", 1.154, -0.005, 1.454),
+            ("NAZC", -0.333, -1.544, 1.623),
+            ("NOAM", 0.024, -0.694, -0.063),
+            ("NUBI", 0.099, -0.614, 0.733),
+            ("PCFC", -0.409, 1.047, -2.169),
+            ("SOAM", -0.270, -0.301, -0.140),
+            ("SOMA", -0.121, -0.794, 0.884),
+        ];
+        let mut model = PlateModel::new();
+        for &(name, wx, wy, wz) in poles.iter() {
+            model.add(name, EulerPole::new(wx, wy, wz));
+        }
+        model
+    }
+
+    /// add or replace a plate
+    pub fn add(&mut self, name:&str, pole:EulerPole) {
+        match self.plates.iter_mut().find(|p| p.0 == name) {
+            Some(p) => p.1 = pole,
+            None => self.plates.push((name.to_string(), pole)),
+        }
+    }
+
+    pub fn get(&self, name:&str) -> Option<EulerPole> {
+        self.plates.iter().find(|p| p.0 == name).map(|p| p.1)
+    }
+}
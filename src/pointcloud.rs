@@ -0,0 +1,154 @@
+//! LAS / LAZ point cloud reprojection (feature `las`)
+//!
+//! points are streamed in chunks of about a million; each chunk is
+//! transformed in parallel and written before the next one is read, so the
+//! memory use does not grow with the size of the cloud.
+
+use std::path::Path;
+
+use las::{Builder, Header, Point, PointDataBuilder, Reader, Transform, Vector, Writer};
+use rayon::prelude::*;
+
+use error::{same_file, Error, Result};
+use job::Job;
+use trace::Activity;
+
+const CHUNK:u64 = 1 << 20;
+const SAMPLES:usize = 16;
+
+fn las_err(e:las::Error) -> Error {
+    Error::Format(format!("las: {}", e))
+}
+
+/// largest power of ten not above `s`
+fn round_scale(s:f64) -> f64 {
+    10f64.powf(s.log10().floor())
+}
+
+fn axis_transform(old:&Transform, old_extent:f64, min:f64, max:f64) -> Transform {
+    let extent = max - min;
+    let mut scale = if old_extent > 0.0 && extent > 0.0 {
+        round_scale(old.scale * extent / old_extent)
+    } else {
+        old.scale
+    };
+    // keep every coordinate well inside the i32 range around the offset
+    while extent / 2.0 / scale > 0.9 * i32::MAX as f64 {
+        scale *= 10.0;
+    }
+    let offset = ((min + max) / 2.0 / scale).round() * scale;
+    Transform { scale, offset }
+}
+
+/// scales and offsets for the reprojected cloud, from a grid of samples over the input bounds
+fn output_transforms<F>(header:&Header, f:&F) -> Result<Vector<Transform>>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    let b = header.bounds();
+    let (mut min, mut max) = ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN));
+    for i in 0..=SAMPLES {
+        for j in 0..=SAMPLES {
+            let x = b.min.x + (b.max.x - b.min.x) * i as f64 / SAMPLES as f64;
+            let y = b.min.y + (b.max.y - b.min.y) * j as f64 / SAMPLES as f64;
+            let (px, py) = f(x, y);
+            if !px.is_finite() || !py.is_finite() {
+                return Err(Error::Transform(format!("bounds do not transform near ({}, {})", x, y)));
+            }
+            min = (min.0.min(px), min.1.min(py));
+            max = (max.0.max(px), max.1.max(py));
+        }
+    }
+    // the samples can miss the true extremes of a curved edge
+    let pad = ((max.0 - min.0) * 0.01, (max.1 - min.1) * 0.01);
+    let old = header.transforms();
+    Ok(Vector {
+        x: axis_transform(&old.x, b.max.x - b.min.x, min.0 - pad.0, max.0 + pad.0),
+        y: axis_transform(&old.y, b.max.y - b.min.y, min.1 - pad.1, max.1 + pad.1),
+        z: old.z,
+    })
+}
+
+/// reproject the x / y of every point of a LAS or LAZ file with `f`, returning the number of points written
+///
+/// the output is compressed when `dst` ends in `.laz`. scales and offsets
+/// are chosen for the new extent, keeping the resolution relative to the
+/// extent of the input. the CRS VLRs of the input are removed and replaced
+/// by a WKT CRS when `wkt` is given, which needs LAS 1.4.
+/// # Examples
+///
+/// ```
+/// # extern crate las;
+/// # extern crate mercator;
+/// use las::{Builder, Point, Reader, Writer};
+/// use mercator::pointcloud::reproject_las;
+/// use mercator::wgs84_to_twd97;
+///
+/// let src = std::env::temp_dir().join("mercator-doc-wgs84.las");
+/// let mut header = Builder::from((1, 4));
+/// header.transforms.x.scale = 1e-7;
+/// header.transforms.y.scale = 1e-7;
+/// let mut writer = Writer::from_path(&src, header.into_header().unwrap()).unwrap();
+/// writer.write_point(Point { x: 121.0, y: 24.0, z: 10.0, ..Default::default() }).unwrap();
+/// writer.write_point(Point { x: 121.01, y: 24.01, z: 12.0, ..Default::default() }).unwrap();
+/// writer.close().unwrap();
+///
+/// let dst = std::env::temp_dir().join("mercator-doc-twd97.las");
+/// assert_eq!(reproject_las(&src, &dst, Some("PROJCS[\"TWD97 / TM2 zone 121\"]"), wgs84_to_twd97).unwrap(), 2);
+/// let mut reader = Reader::from_path(&dst).unwrap();
+/// assert!(reader.header().has_wkt_crs());
+/// let p = reader.read_all().unwrap().points().next().unwrap().unwrap();
+/// assert!((p.x - 250000.0).abs() < 0.01 && p.z == 10.0);
+/// assert!(reproject_las(&dst, &dst, None, wgs84_to_twd97).is_err());
+/// assert_eq!(Reader::from_path(&dst).unwrap().header().number_of_points(), 2);
+/// ```
+pub fn reproject_las<P, Q, F>(src:P, dst:Q, wkt:Option<&str>, f:F) -> Result<u64>
+    where P: AsRef<Path>, Q: AsRef<Path>, F: Fn(f64, f64) -> (f64, f64) + Sync
+{
+    reproject_las_with(src, dst, wkt, f, &mut Job::new())
+}
+
+/// [`reproject_las`] as a [`Job`], checked and reported after every chunk
+///
+/// the chunks are transformed on the thread pool of the job.
+/// a cancelled job still closes the output, holding the points written so far.
+pub fn reproject_las_with<P, Q, F>(src:P, dst:Q, wkt:Option<&str>, f:F, job:&mut Job) -> Result<u64>
+    where P: AsRef<Path>, Q: AsRef<Path>, F: Fn(f64, f64) -> (f64, f64) + Sync
+{
+    if same_file(src.as_ref(), dst.as_ref()) {
+        return Err(Error::InvalidInput(format!("{} is both the source and the destination", src.as_ref().display())));
+    }
+    let activity = Activity::file("reproject_las", src.as_ref());
+    let mut reader = Reader::from_path(src).map_err(las_err)?;
+    let mut builder = Builder::from(reader.header().clone());
+    builder.transforms = output_transforms(reader.header(), &f)?;
+    let mut header = builder.into_header().map_err(las_err)?;
+    header.remove_crs_vlrs();
+    if let Some(wkt) = wkt {
+        header.set_wkt_crs(wkt.as_bytes().to_vec()).map_err(|e| Error::InvalidInput(format!("las: {}", e)))?;
+    }
+    let count = reader.header().number_of_points();
+    let points_out = PointDataBuilder::new().for_header(&header);
+    let mut writer = Writer::from_path(dst, header).map_err(las_err)?;
+    let mut total = 0;
+    while !job.is_cancelled() {
+        let data = reader.read_points(CHUNK).map_err(las_err)?;
+        if data.is_empty() {
+            break;
+        }
+        let mut points:Vec<Point> = data.points().collect::<::std::result::Result<_, _>>().map_err(las_err)?;
+        job.install(|| points.par_chunks_mut(4096).for_each(|chunk| {
+            for p in chunk {
+                let (x, y) = f(p.x, p.y);
+                p.x = x;
+                p.y = y;
+            }
+        }));
+        total += points.len() as u64;
+        let data = points_out.clone().build_from_points(points).map_err(|e| Error::Transform(format!("las: {}", e)))?;
+        writer.write_points(&data).map_err(las_err)?;
+        job.report(total, count);
+    }
+    writer.close().map_err(las_err)?;
+    activity.finish(total, 0);
+    Ok(total)
+}
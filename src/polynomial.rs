@@ -0,0 +1,104 @@
+//! 2D polynomial (rubber-sheet) transforms on plane coordinates
+//!
+//! second and third order polynomials take up the uneven stretch of old paper
+//! maps and loose surveys that a similarity or affine transform leaves in the
+//! residuals. they are fit from control points with
+//! [`fit_polynomial`](::fit::fit_polynomial) and only behave inside the area
+//! the control points cover; outside it the higher terms quickly run away.
+
+/// x' = Σ cx · u^i v^j, y' = Σ cy · u^i v^j over i + j ≤ order, with
+/// `(u, v) = ((x, y) - origin) / unit`
+///
+/// the terms run by total degree and then by falling power of u:
+/// 1, u, v, u², uv, v², u³, u²v, uv², v³.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial2D {
+    pub order:usize,
+    pub origin:(f64, f64),
+    pub unit:f64,
+    pub cx:Vec<f64>,
+    pub cy:Vec<f64>,
+}
+
+/// the exponents `(i, j)` of u and v for every term up to `order`
+pub(crate) fn exponents(order:usize) -> Vec<(i32, i32)> {
+    (0..=order as i32).flat_map(|deg| (0..=deg).map(move |j| (deg - j, j))).collect()
+}
+
+/// the terms of a polynomial of `order` at `(u, v)`
+pub(crate) fn terms(order:usize, u:f64, v:f64) -> Vec<f64> {
+    exponents(order).into_iter().map(|(i, j)| u.powi(i) * v.powi(j)).collect()
+}
+
+impl Polynomial2D {
+    fn normalize(&self, x:f64, y:f64) -> (f64, f64) {
+        ((x - self.origin.0) / self.unit, (y - self.origin.1) / self.unit)
+    }
+
+    pub fn apply(&self, x:f64, y:f64) -> (f64, f64) {
+        let (u, v) = self.normalize(x, y);
+        let t = terms(self.order, u, v);
+        (dot(&self.cx, &t), dot(&self.cy, &t))
+    }
+
+    /// the derivatives `(∂x'/∂x, ∂x'/∂y, ∂y'/∂x, ∂y'/∂y)` at `(x, y)`
+    pub fn jacobian(&self, x:f64, y:f64) -> (f64, f64, f64, f64) {
+        let (u, v) = self.normalize(x, y);
+        let (mut du, mut dv) = (Vec::new(), Vec::new());
+        for (i, j) in exponents(self.order) {
+            du.push(if i > 0 { i as f64 * u.powi(i - 1) * v.powi(j) } else { 0.0 });
+            dv.push(if j > 0 { j as f64 * u.powi(i) * v.powi(j - 1) } else { 0.0 });
+        }
+        let s = self.unit;
+        (dot(&self.cx, &du) / s, dot(&self.cx, &dv) / s, dot(&self.cy, &du) / s, dot(&self.cy, &dv) / s)
+    }
+
+    /// the point mapping onto `(x, y)`, by Newton iteration from the linear
+    /// part of the polynomial; `None` when it does not converge, as happens
+    /// far outside the control points
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::polynomial::Polynomial2D;
+    /// let p = Polynomial2D {
+    ///     order: 2,
+    ///     origin: (0.0, 0.0),
+    ///     unit: 1000.0,
+    ///     cx: vec![250000.0, 1000.0, 0.0, 2.0, 0.0, -1.0],
+    ///     cy: vec![2650000.0, 0.0, 1000.0, 0.0, 3.0, 0.0],
+    /// };
+    /// let (x, y) = p.apply(300.0, 700.0);
+    /// assert!((x - (250300.0 + 2.0 * 0.09 - 0.49)).abs() < 1e-9 && (y - (2650700.0 + 3.0 * 0.21)).abs() < 1e-9);
+    /// let (u, v) = p.inverse_apply(x, y).unwrap();
+    /// assert!((u - 300.0).abs() < 1e-8 && (v - 700.0).abs() < 1e-8);
+    /// ```
+    pub fn inverse_apply(&self, x:f64, y:f64) -> Option<(f64, f64)> {
+        let (c, (a, b, d, e)) = (self.apply(self.origin.0, self.origin.1), self.jacobian(self.origin.0, self.origin.1));
+        let det = a * e - b * d;
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let (dx, dy) = (x - c.0, y - c.1);
+        let (mut px, mut py) = (self.origin.0 + (e * dx - b * dy) / det, self.origin.1 + (a * dy - d * dx) / det);
+        let tolerance = 1e-12 * self.unit + 1e-14 * x.abs().max(y.abs());
+        for _ in 0..50 {
+            let (fx, fy) = self.apply(px, py);
+            let (rx, ry) = (x - fx, y - fy);
+            if rx.abs().max(ry.abs()) <= tolerance {
+                return Some((px, py));
+            }
+            let (a, b, d, e) = self.jacobian(px, py);
+            let det = a * e - b * d;
+            if det == 0.0 || !det.is_finite() {
+                return None;
+            }
+            px += (e * rx - b * ry) / det;
+            py += (a * ry - d * rx) / det;
+        }
+        None
+    }
+}
+
+fn dot(a:&[f64], b:&[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
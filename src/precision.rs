@@ -0,0 +1,103 @@
+//! output precision and rounding
+//!
+//! rounding is round-half-even on the decimal value, done with plain float
+//! arithmetic that gives the same digits on every platform, so exported files
+//! stay stable between runs.
+
+/// how many digits of a coordinate to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    /// keep every digit, formatting gives the shortest string that parses back exactly
+    #[default]
+    Full,
+    /// digits after the decimal point
+    Decimals(u32),
+    /// significant digits
+    Significant(u32),
+}
+
+impl Precision {
+    /// decimal places needed for `v`, `None` when nothing is cut
+    fn decimals_for(&self, v:f64) -> Option<i32> {
+        match *self {
+            Precision::Full => None,
+            Precision::Decimals(n) => Some(n as i32),
+            Precision::Significant(n) => {
+                if v == 0.0 {
+                    return Some(n.max(1) as i32 - 1);
+                }
+                Some(n.max(1) as i32 - 1 - v.abs().log10().floor() as i32)
+            }
+        }
+    }
+
+    /// round a value, ties go to the even digit
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::precision::Precision;
+    /// assert_eq!(Precision::Decimals(0).round(2.5), 2.0);
+    /// assert_eq!(Precision::Decimals(0).round(3.5), 4.0);
+    /// assert_eq!(Precision::Decimals(3).round(302000.123456), 302000.123);
+    /// assert_eq!(Precision::Significant(3).round(2766412.0), 2770000.0);
+    /// assert_eq!(Precision::Significant(2).round(0.000125), 0.00012);
+    /// ```
+    pub fn round(&self, v:f64) -> f64 {
+        match self.decimals_for(v) {
+            Some(d) if v.is_finite() => {
+                if d >= 0 {
+                    let k = 10f64.powi(d);
+                    let r = (v * k).round_ties_even() / k;
+                    if r.is_finite() { r } else { v }
+                } else {
+                    let k = 10f64.powi(-d);
+                    (v / k).round_ties_even() * k
+                }
+            }
+            _ => v,
+        }
+    }
+
+    /// round both values of a coordinate
+    pub fn round_xy(&self, x:f64, y:f64) -> (f64, f64) {
+        (self.round(x), self.round(y))
+    }
+
+    /// format a value with exactly the kept digits
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::precision::Precision;
+    /// assert_eq!(Precision::Decimals(2).format(0.125), "0.12");
+    /// assert_eq!(Precision::Decimals(3).format(121.5), "121.500");
+    /// assert_eq!(Precision::Significant(4).format(25.03301), "25.03");
+    /// assert_eq!(Precision::Full.format(0.1), "0.1");
+    /// ```
+    pub fn format(&self, v:f64) -> String {
+        let r = self.round(v);
+        match self.decimals_for(v) {
+            Some(d) if v.is_finite() => format!("{:.*}", d.max(0) as usize, r),
+            _ => format!("{}", v),
+        }
+    }
+
+    /// wrap a transform so its output is rounded, for the closure based IO functions
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::precision::Precision;
+    /// use mercator::wgs84_to_twd97;
+    /// let mut f = Precision::Decimals(3).wrap(wgs84_to_twd97);
+    /// let (x, y) = f(121.5, 25.0);
+    /// assert_eq!(x, (x * 1000.0).round() / 1000.0);
+    /// assert_eq!(y, (y * 1000.0).round() / 1000.0);
+    /// ```
+    pub fn wrap<F>(self, mut f:F) -> impl FnMut(f64, f64) -> (f64, f64)
+        where F: FnMut(f64, f64) -> (f64, f64)
+    {
+        move |x, y| {
+            let (x, y) = f(x, y);
+            self.round_xy(x, y)
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! plugin interface for projections defined outside the crate
+//!
+//! implement [`Projection`] for a local oblique system or any other exotic
+//! projection, then wrap it in a [`Crs::Custom`](::crs::Crs::Custom) or
+//! register it with [`Registry::register_projection`](::registry::Registry::register_projection)
+//! to use it through transformers, pipelines and the batch, geometry and
+//! file functions (they all take a plain `Fn(f64, f64) -> (f64, f64)`).
+
+use std::fmt;
+
+use ecef::geodetic_to_ecef;
+use zone::Zone;
+
+/// a map projection between lng/lat in degrees and grid coordinates
+pub trait Projection: fmt::Debug + Send + Sync {
+    /// short human readable name
+    fn name(&self) -> &str;
+
+    /// lng/lat to grid coordinates
+    fn forward(&self, lng:f64, lat:f64) -> (f64, f64);
+
+    /// grid coordinates to lng/lat
+    fn inverse(&self, x:f64, y:f64) -> (f64, f64);
+}
+
+impl Projection for Zone {
+    fn name(&self) -> &str {
+        "transverse mercator"
+    }
+
+    fn forward(&self, lng:f64, lat:f64) -> (f64, f64) {
+        Zone::forward(self, lng, lat)
+    }
+
+    fn inverse(&self, x:f64, y:f64) -> (f64, f64) {
+        Zone::inverse(self, x, y)
+    }
+}
+
+/// position error in meters after projecting `(lng, lat)` and inverting again
+///
+/// the distance is measured through WGS84 ECEF so it stays meaningful near the poles.
+/// # Examples
+///
+/// ```
+/// use mercator::projection::roundtrip_error;
+/// use mercator::zone::Zone;
+/// let zone = Zone::twd97();
+/// assert!(roundtrip_error(&zone, 121.5, 25.0) < 0.01);
+/// // far from the central meridian the series degrades
+/// assert!(roundtrip_error(&zone, 131.0, 25.0) > roundtrip_error(&zone, 121.5, 25.0));
+/// ```
+pub fn roundtrip_error<P:Projection + ?Sized>(proj:&P, lng:f64, lat:f64) -> f64 {
+    let (x, y) = proj.forward(lng, lat);
+    let (lng2, lat2) = proj.inverse(x, y);
+    let a = geodetic_to_ecef(lng, lat, 0.0);
+    let b = geodetic_to_ecef(lng2, lat2, 0.0);
+    let d = ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) + (a.2 - b.2).powi(2)).sqrt();
+    if d.is_nan() { f64::INFINITY } else { d }
+}
+
+/// summary of the round-trip errors over a set of points, in meters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundtripStats {
+    pub count:usize,
+    pub max:f64,
+    pub mean:f64,
+    pub rms:f64,
+    /// index of the point with the largest error
+    pub worst:Option<usize>,
+}
+
+/// round-trip errors of many points, to check a projection over a region
+/// # Examples
+///
+/// ```
+/// use mercator::projection::roundtrip_errors;
+/// use mercator::zone::Zone;
+/// let grid:Vec<(f64, f64)> = (0..10).flat_map(|i| (0..10).map(move |j| (120.0 + 0.2 * i as f64, 22.0 + 0.3 * j as f64))).collect();
+/// let stats = roundtrip_errors(&Zone::twd97(), &grid);
+/// assert_eq!(stats.count, 100);
+/// assert!(stats.max < 0.01 && stats.mean <= stats.rms && stats.rms <= stats.max);
+/// ```
+pub fn roundtrip_errors<P:Projection + ?Sized>(proj:&P, points:&[(f64, f64)]) -> RoundtripStats {
+    let mut stats = RoundtripStats { count: points.len(), max: 0.0, mean: 0.0, rms: 0.0, worst: None };
+    for (i, &(lng, lat)) in points.iter().enumerate() {
+        let e = roundtrip_error(proj, lng, lat);
+        if stats.worst.is_none() || e > stats.max {
+            stats.max = e;
+            stats.worst = Some(i);
+        }
+        stats.mean += e;
+        stats.rms += e * e;
+    }
+    if !points.is_empty() {
+        stats.mean /= points.len() as f64;
+        stats.rms = (stats.rms / points.len() as f64).sqrt();
+    }
+    stats
+}
@@ -0,0 +1,286 @@
+//! PROJJSON import and export of CRS definitions
+//!
+//! geographic and zone CRSs are written as `GeographicCRS` / `ProjectedCRS`
+//! with EPSG parameter ids; a datum with a shift to WGS84 is wrapped in a
+//! `BoundCRS` carrying the Helmert parameters, like PROJ writes `+towgs84`.
+//! custom projections have no definition to write and fail to export.
+//! - https://proj.org/specifications/projjson.html
+
+use serde_json::{json, Map, Value};
+
+use crs::Crs;
+use datum::{self, Datum};
+use ellipsoid::Ellipsoid;
+use error::{Error, Result};
+use helmert::Helmert;
+use info::epsg_name;
+use zone::{Hemisphere, Zone, ZoneWidth};
+
+const SCHEMA:&str = "https://proj.org/schemas/v0.7/projjson.schema.json";
+
+fn id(code:u32) -> Value {
+    json!({"authority": "EPSG", "code": code})
+}
+
+fn ellipsoid_json(e:&Ellipsoid) -> Value {
+    let name = e.name().unwrap_or("unknown");
+    if e.a == e.b {
+        json!({"name": name, "radius": e.a})
+    } else {
+        json!({"name": name, "semi_major_axis": e.a, "inverse_flattening": e.inverse_flattening()})
+    }
+}
+
+fn geographic_json(d:&Datum, name:&str) -> Value {
+    json!({
+        "type": "GeographicCRS",
+        "name": name,
+        "datum": {"type": "GeodeticReferenceFrame", "name": d.name, "ellipsoid": ellipsoid_json(&d.ellipsoid)},
+        "coordinate_system": {
+            "subtype": "ellipsoidal",
+            "axis": [
+                {"name": "Geodetic latitude", "abbreviation": "Lat", "direction": "north", "unit": "degree"},
+                {"name": "Geodetic longitude", "abbreviation": "Lon", "direction": "east", "unit": "degree"},
+            ],
+        },
+    })
+}
+
+fn param(name:&str, value:f64, unit:&str, code:u32) -> Value {
+    json!({"name": name, "value": value, "unit": unit, "id": id(code)})
+}
+
+fn projected_json(zone:&Zone, d:&Datum, name:&str) -> Value {
+    json!({
+        "type": "ProjectedCRS",
+        "name": name,
+        "base_crs": geographic_json(d, d.name),
+        "conversion": {
+            "name": format!("Transverse Mercator {}", zone.center_lng),
+            "method": {"name": "Transverse Mercator", "id": id(9807)},
+            "parameters": [
+                param("Latitude of natural origin", 0.0, "degree", 8801),
+                param("Longitude of natural origin", zone.center_lng, "degree", 8802),
+                param("Scale factor at natural origin", zone.width.k0(), "unity", 8805),
+                param("False easting", zone.width.dx(), "metre", 8806),
+                param("False northing", zone.hemisphere.dy(), "metre", 8807),
+            ],
+        },
+        "coordinate_system": {
+            "subtype": "Cartesian",
+            "axis": [
+                {"name": "Easting", "abbreviation": "E", "direction": "east", "unit": "metre"},
+                {"name": "Northing", "abbreviation": "N", "direction": "north", "unit": "metre"},
+            ],
+        },
+    })
+}
+
+fn bound_json(source:Value, d:&Datum) -> Value {
+    let h = &d.to_wgs84;
+    json!({
+        "type": "BoundCRS",
+        "source_crs": source,
+        "target_crs": geographic_json(&datum::WGS84, "WGS 84"),
+        "transformation": {
+            "name": format!("{} to WGS 84", d.name),
+            "method": {"name": "Position Vector transformation (geog2D domain)", "id": id(9606)},
+            "parameters": [
+                param("X-axis translation", h.tx, "metre", 8605),
+                param("Y-axis translation", h.ty, "metre", 8606),
+                param("Z-axis translation", h.tz, "metre", 8607),
+                param("X-axis rotation", h.rx, "arc-second", 8608),
+                param("Y-axis rotation", h.ry, "arc-second", 8609),
+                param("Z-axis rotation", h.rz, "arc-second", 8610),
+                param("Scale difference", h.s, "parts per million", 8611),
+            ],
+        },
+    })
+}
+
+fn with_schema(mut v:Value) -> Value {
+    let mut out = Map::new();
+    out.insert("$schema".to_string(), Value::from(SCHEMA));
+    if let Some(obj) = v.as_object_mut() {
+        out.append(obj);
+    }
+    Value::Object(out)
+}
+
+fn export(crs:&Crs, name:Option<String>) -> Result<Value> {
+    let v = match *crs {
+        Crs::Geographic(ref d) => geographic_json(d, &name.unwrap_or_else(|| d.name.to_string())),
+        Crs::Projected(ref zone, ref d) => {
+            let name = name.unwrap_or_else(|| format!("{} / TM {}", d.name, zone.center_lng));
+            projected_json(zone, d, &name)
+        }
+        Crs::Custom(ref p, _) => return Err(Error::InvalidInput(format!("custom projection {} has no PROJJSON form", p.name()))),
+    };
+    let d = crs.datum();
+    Ok(with_schema(if d.is_wgs84() { v } else { bound_json(v, &d) }))
+}
+
+impl Crs {
+    /// the PROJJSON definition of this CRS
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Crs;
+    /// use mercator::zone::Zone;
+    /// let v = Crs::projected(Zone::utm_south(56)).to_projjson().unwrap();
+    /// assert_eq!(v["type"], "ProjectedCRS");
+    /// assert_eq!(v["conversion"]["parameters"][1]["value"], 153.0);
+    /// assert_eq!(Crs::from_projjson(&v).unwrap(), Crs::projected(Zone::utm_south(56)));
+    ///
+    /// // without an EPSG id the datum shift travels in a BoundCRS
+    /// let twd67 = Crs::Projected(Zone::twd97(), mercator::datum::TWD67);
+    /// let text = twd67.to_projjson().unwrap().to_string();
+    /// assert_eq!(Crs::parse_projjson(&text).unwrap(), twd67);
+    /// assert!(Crs::custom(Zone::twd97()).to_projjson().is_err());
+    /// ```
+    pub fn to_projjson(&self) -> Result<Value> {
+        export(self, None)
+    }
+
+    /// the PROJJSON definition of a built-in EPSG code, with its EPSG name and id
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Crs;
+    /// let v = Crs::epsg_projjson(3828).unwrap();
+    /// assert_eq!(v["type"], "BoundCRS");
+    /// assert_eq!(v["source_crs"]["name"], "TWD67 / TM2 zone 121");
+    /// assert_eq!(Crs::from_projjson(&v).unwrap(), Crs::from_epsg(3828).unwrap());
+    /// ```
+    pub fn epsg_projjson(code:u32) -> Result<Value> {
+        let crs = Crs::from_epsg(code)?;
+        let mut v = export(&crs, epsg_name(code))?;
+        let target = if v["type"] == "BoundCRS" { &mut v["source_crs"] } else { &mut v };
+        target["id"] = id(code);
+        Ok(v)
+    }
+
+    /// read a PROJJSON definition back
+    ///
+    /// an EPSG id of a built-in code wins; otherwise geographic and transverse mercator
+    /// CRSs of the supported zone systems are rebuilt from their parameters.
+    pub fn from_projjson(v:&Value) -> Result<Crs> {
+        import(v)
+    }
+
+    /// parse PROJJSON text
+    pub fn parse_projjson(text:&str) -> Result<Crs> {
+        let v:Value = serde_json::from_str(text).map_err(|e| Error::Format(format!("projjson: {}", e)))?;
+        import(&v)
+    }
+}
+
+fn format_err(msg:String) -> Error {
+    Error::Format(format!("projjson: {}", msg))
+}
+
+/// a number, or the `value` of a `{"value", "unit"}` object
+fn number(v:&Value) -> Option<f64> {
+    v.as_f64().or_else(|| v["value"].as_f64())
+}
+
+fn epsg_id(v:&Value) -> Option<u32> {
+    let id = &v["id"];
+    if id["authority"].as_str().map(|a| a.eq_ignore_ascii_case("EPSG")) != Some(true) {
+        return None;
+    }
+    id["code"].as_u64().or_else(|| id["code"].as_str().and_then(|c| c.parse().ok())).map(|c| c as u32)
+}
+
+/// parameters keyed by EPSG id code, falling back to the lower case name
+fn parameter(params:&[Value], code:u32, name:&str) -> Option<f64> {
+    params.iter()
+        .find(|p| epsg_id(p) == Some(code) || p["name"].as_str().map(|n| n.eq_ignore_ascii_case(name)) == Some(true))
+        .and_then(|p| number(&p["value"]))
+}
+
+fn import_ellipsoid(v:&Value) -> Result<Ellipsoid> {
+    let a = number(&v["semi_major_axis"]).or_else(|| number(&v["radius"]))
+        .ok_or_else(|| format_err("ellipsoid without semi_major_axis".to_string()))?;
+    let e = if let Some(rf) = number(&v["inverse_flattening"]) {
+        if rf == 0.0 { Ellipsoid { a, b: a } } else { Ellipsoid::from_inverse_flattening(a, rf) }
+    } else if let Some(b) = number(&v["semi_minor_axis"]) {
+        Ellipsoid { a, b }
+    } else {
+        Ellipsoid { a, b: a }
+    };
+    // snap to the exact constants of a known ellipsoid
+    Ok(::ellipsoid::STANDARD.iter().map(|s| s.0)
+        .find(|s| (s.a - e.a).abs() < 1e-6 && (s.b - e.b).abs() < 1e-6)
+        .unwrap_or(e))
+}
+
+fn import_datum(crs:&Value, to_wgs84:Helmert) -> Result<Datum> {
+    let frame = if crs["datum"].is_object() { &crs["datum"] } else { &crs["datum_ensemble"] };
+    let ellipsoid = import_ellipsoid(&frame["ellipsoid"])?;
+    let known = [datum::WGS84, datum::TWD67];
+    Ok(known.iter().cloned()
+        .find(|d| d.ellipsoid == ellipsoid && d.to_wgs84 == to_wgs84)
+        .unwrap_or_else(|| Datum::new("unknown", ellipsoid, to_wgs84)))
+}
+
+fn import_zone(conversion:&Value) -> Result<Zone> {
+    let method = &conversion["method"];
+    if epsg_id(method) != Some(9807) && method["name"] != "Transverse Mercator" {
+        return Err(format_err(format!("unsupported projection method {}", method["name"])));
+    }
+    let params = conversion["parameters"].as_array().map(|p| &p[..]).unwrap_or(&[]);
+    let lat0 = parameter(params, 8801, "Latitude of natural origin").unwrap_or(0.0);
+    let lng0 = parameter(params, 8802, "Longitude of natural origin").unwrap_or(0.0);
+    let k0 = parameter(params, 8805, "Scale factor at natural origin").unwrap_or(1.0);
+    let fe = parameter(params, 8806, "False easting").unwrap_or(0.0);
+    let fn_ = parameter(params, 8807, "False northing").unwrap_or(0.0);
+    let width = [ZoneWidth::Degree2, ZoneWidth::Degree3, ZoneWidth::Degree6].iter().cloned()
+        .find(|w| w.k0() == k0 && w.dx() == fe);
+    let hemisphere = [Hemisphere::North, Hemisphere::South].iter().cloned().find(|h| h.dy() == fn_);
+    match (width, hemisphere) {
+        (Some(width), Some(hemisphere)) if lat0 == 0.0 => Ok(Zone::new(width, lng0).with_hemisphere(hemisphere)),
+        _ => Err(format_err(format!("transverse mercator with k0 {} and false origin ({}, {}) is not a supported zone system", k0, fe, fn_))),
+    }
+}
+
+fn import_helmert(transformation:&Value) -> Result<Helmert> {
+    let method = &transformation["method"];
+    let params = transformation["parameters"].as_array().map(|p| &p[..]).unwrap_or(&[]);
+    let get = |code, name| parameter(params, code, name).unwrap_or(0.0);
+    let (tx, ty, tz) = (get(8605, "X-axis translation"), get(8606, "Y-axis translation"), get(8607, "Z-axis translation"));
+    let (rx, ry, rz) = (get(8608, "X-axis rotation"), get(8609, "Y-axis rotation"), get(8610, "Z-axis rotation"));
+    let s = get(8611, "Scale difference");
+    let code = epsg_id(method);
+    let name = method["name"].as_str().unwrap_or("");
+    if matches!(code, Some(9607) | Some(1032)) || name.starts_with("Coordinate Frame") {
+        Ok(Helmert::coordinate_frame(tx, ty, tz, s, rx, ry, rz))
+    } else {
+        Ok(Helmert::new(tx, ty, tz, s, rx, ry, rz))
+    }
+}
+
+fn import_with_shift(v:&Value, to_wgs84:Helmert) -> Result<Crs> {
+    if let Some(code) = epsg_id(v) {
+        if let Ok(crs) = Crs::from_epsg(code) {
+            return Ok(crs);
+        }
+    }
+    match v["type"].as_str() {
+        Some("GeographicCRS") | Some("GeodeticCRS") => Ok(Crs::Geographic(import_datum(v, to_wgs84)?)),
+        Some("ProjectedCRS") => {
+            let d = import_datum(&v["base_crs"], to_wgs84)?;
+            Ok(Crs::Projected(import_zone(&v["conversion"])?, d))
+        }
+        other => Err(format_err(format!("unsupported CRS type {:?}", other))),
+    }
+}
+
+fn import(v:&Value) -> Result<Crs> {
+    if v["type"] == "BoundCRS" {
+        let h = import_helmert(&v["transformation"])?;
+        import_with_shift(&v["source_crs"], h)
+    } else {
+        import_with_shift(v, Helmert::default())
+    }
+}
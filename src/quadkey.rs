@@ -0,0 +1,155 @@
+//! hierarchical quadtree keys over a projected extent
+//!
+//! the extent is halved in x and y at every level down to a given depth. a
+//! key names a cell with one digit per level, `0` to `3` for the north west,
+//! north east, south west and south east quarter, like Bing maps quadkeys:
+//! a prefix of a key is the key of a parent cell, which makes keys usable as
+//! spatial index and shard keys directly.
+
+use crs::Crs;
+use extent::Extent;
+use longitude::normalize_lng;
+
+/// deepest supported level, so that cell numbers fit in `u32` and Morton codes in `u64`
+pub const MAX_DEPTH:u8 = 31;
+
+/// samples along every edge of an area of use
+const EDGE_SAMPLES:usize = 64;
+
+/// a quadtree over `extent`, `depth` levels deep
+/// # Examples
+///
+/// ```
+/// use mercator::extent::Extent;
+/// use mercator::quadkey::QuadTree;
+/// let tree = QuadTree::new(Extent::new(0.0, 0.0, 1024.0, 1024.0), 3);
+/// assert_eq!(tree.quadkey(10.0, 1000.0).unwrap(), "000");
+/// assert_eq!(tree.quadkey(1000.0, 10.0).unwrap(), "333");
+/// assert_eq!(tree.quadkey(520.0, 1020.0).unwrap(), "100");
+/// assert_eq!(tree.key_extent("1").unwrap(), Extent::new(512.0, 512.0, 1024.0, 1024.0));
+/// assert_eq!(tree.quadkey(-1.0, 0.0), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuadTree {
+    pub extent:Extent,
+    pub depth:u8,
+}
+
+impl QuadTree {
+    /// depths above [`MAX_DEPTH`] are lowered to it
+    pub fn new(extent:Extent, depth:u8) -> QuadTree {
+        QuadTree { extent, depth: depth.min(MAX_DEPTH) }
+    }
+
+    /// a quadtree over the projected area of use of a CRS, `None` when the area is unknown
+    ///
+    /// the extent is the bounding box of the boundary of the area of use in
+    /// the coordinates of the CRS.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Crs;
+    /// use mercator::quadkey::QuadTree;
+    /// let tree = QuadTree::for_crs(&Crs::from_epsg(3826).unwrap(), 12).unwrap();
+    /// let (x, y) = mercator::wgs84_to_twd97(121.5654, 25.0330);
+    /// let key = tree.quadkey(x, y).unwrap();
+    /// assert_eq!(key.len(), 12);
+    /// assert!(tree.key_extent(&key).unwrap().contains(x, y));
+    /// ```
+    pub fn for_crs(crs:&Crs, depth:u8) -> Option<QuadTree> {
+        let area = crs.area_of_use()?;
+        let east = if area.east < area.west { area.east + 360.0 } else { area.east };
+        let mut boundary = Vec::with_capacity(4 * (EDGE_SAMPLES + 1));
+        for i in 0..=EDGE_SAMPLES {
+            let t = i as f64 / EDGE_SAMPLES as f64;
+            let lng = normalize_lng(area.west + t * (east - area.west));
+            let lat = area.south + t * (area.north - area.south);
+            boundary.push(crs.from_lnglat(lng, area.south));
+            boundary.push(crs.from_lnglat(lng, area.north));
+            boundary.push(crs.from_lnglat(normalize_lng(area.west), lat));
+            boundary.push(crs.from_lnglat(normalize_lng(east), lat));
+        }
+        Extent::of(&boundary).map(|e| QuadTree::new(e, depth))
+    }
+
+    /// the column (from the west) and row (from the north) of the cell holding a point
+    /// at the deepest level, `None` outside the extent
+    pub fn cell(&self, x:f64, y:f64) -> Option<(u32, u32)> {
+        if !self.extent.contains(x, y) {
+            return None;
+        }
+        let n = (1u64 << self.depth) as f64;
+        let last = n - 1.0;
+        let col = ((x - self.extent.min_x) / self.extent.width() * n).floor().clamp(0.0, last);
+        let row = ((self.extent.max_y - y) / self.extent.height() * n).floor().clamp(0.0, last);
+        if !col.is_finite() || !row.is_finite() {
+            return None;
+        }
+        Some((col as u32, row as u32))
+    }
+
+    /// the key of the cell holding a point, one digit per level
+    pub fn quadkey(&self, x:f64, y:f64) -> Option<String> {
+        let (col, row) = self.cell(x, y)?;
+        Some((0..self.depth).rev().map(|level| {
+            let digit = ((col >> level) & 1) | (((row >> level) & 1) << 1);
+            (b'0' + digit as u8) as char
+        }).collect())
+    }
+
+    /// the Morton code of the cell holding a point: the bits of a
+    /// [`quadkey`](QuadTree::quadkey), two per level, as a number
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::extent::Extent;
+    /// use mercator::quadkey::QuadTree;
+    /// let tree = QuadTree::new(Extent::new(0.0, 0.0, 1024.0, 1024.0), 3);
+    /// assert_eq!(tree.morton(520.0, 1020.0), Some(0b01_00_00));
+    /// assert_eq!(tree.morton(1000.0, 10.0), Some(0b11_11_11));
+    /// ```
+    pub fn morton(&self, x:f64, y:f64) -> Option<u64> {
+        let (col, row) = self.cell(x, y)?;
+        Some((0..self.depth as u64).fold(0, |key, level| {
+            key | ((col as u64 >> level) & 1) << (2 * level) | ((row as u64 >> level) & 1) << (2 * level + 1)
+        }))
+    }
+
+    /// the area of the cell named by a key of any length up to the depth, `None` for an invalid key
+    pub fn key_extent(&self, key:&str) -> Option<Extent> {
+        if key.len() > self.depth as usize {
+            return None;
+        }
+        let mut e = self.extent;
+        for digit in key.chars() {
+            let (cx, cy) = e.center();
+            e = match digit {
+                '0' => Extent::new(e.min_x, cy, cx, e.max_y),
+                '1' => Extent::new(cx, cy, e.max_x, e.max_y),
+                '2' => Extent::new(e.min_x, e.min_y, cx, cy),
+                '3' => Extent::new(cx, e.min_y, e.max_x, cy),
+                _ => return None,
+            };
+        }
+        Some(e)
+    }
+}
+
+/// the key of the parent cell, `None` for the root and for a key with a
+/// digit other than 0 to 3
+/// # Examples
+///
+/// ```
+/// use mercator::quadkey::parent;
+/// assert_eq!(parent("0123"), Some("012"));
+/// assert_eq!(parent("1"), Some(""));
+/// assert_eq!(parent(""), None);
+/// assert_eq!(parent("1é"), None);
+/// ```
+pub fn parent(key:&str) -> Option<&str> {
+    if key.is_empty() || !key.bytes().all(|b| (b'0'..=b'3').contains(&b)) {
+        None
+    } else {
+        Some(&key[..key.len() - 1])
+    }
+}
@@ -0,0 +1,105 @@
+//! reprojection of gridded values (DEMs, rain grids, ...) between CRSs
+//!
+//! target cells are mapped back into the source CRS and sampled there, so
+//! every cell of the output gets exactly one value. cells that fall outside
+//! the source grid (or on nodata) are NaN.
+
+use affine::Affine2D;
+
+/// how source values are sampled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resampling {
+    Nearest,
+    /// bilinear between the 4 surrounding cells, nearest where one of them is nodata
+    Bilinear,
+}
+
+/// a grid of values stored row by row from the top-left cell, NaN is nodata
+#[derive(Debug, Clone, PartialEq)]
+pub struct Raster {
+    pub width:usize,
+    pub height:usize,
+    /// maps cell centers (col, row) to CRS coordinates
+    pub transform:Affine2D,
+    pub data:Vec<f64>,
+}
+
+impl Raster {
+    /// a raster filled with NaN
+    pub fn new(width:usize, height:usize, transform:Affine2D) -> Raster {
+        Raster { width, height, transform, data: vec![f64::NAN; width * height] }
+    }
+
+    pub fn get(&self, col:usize, row:usize) -> f64 {
+        self.data[row * self.width + col]
+    }
+
+    pub fn set(&mut self, col:usize, row:usize, value:f64) {
+        self.data[row * self.width + col] = value;
+    }
+
+    /// sample the raster at a CRS coordinate, NaN outside
+    pub fn sample(&self, x:f64, y:f64, resampling:Resampling) -> f64 {
+        let inv = match self.transform.inverse() {
+            Some(inv) => inv,
+            None => return f64::NAN,
+        };
+        let (c, r) = inv.apply(x, y);
+        let (maxc, maxr) = (self.width as f64 - 0.5, self.height as f64 - 0.5);
+        if !(c >= -0.5 && c < maxc && r >= -0.5 && r < maxr) {
+            return f64::NAN;
+        }
+        let nearest = || self.get(c.round().max(0.0) as usize, r.round().max(0.0) as usize);
+        match resampling {
+            Resampling::Nearest => nearest(),
+            Resampling::Bilinear => {
+                let c0 = (c.floor().max(0.0) as usize).min(self.width.saturating_sub(2));
+                let r0 = (r.floor().max(0.0) as usize).min(self.height.saturating_sub(2));
+                if self.width < 2 || self.height < 2 {
+                    return nearest();
+                }
+                let (fx, fy) = ((c - c0 as f64).clamp(0.0, 1.0), (r - r0 as f64).clamp(0.0, 1.0));
+                let v = [self.get(c0, r0), self.get(c0 + 1, r0), self.get(c0, r0 + 1), self.get(c0 + 1, r0 + 1)];
+                if v.iter().any(|v| v.is_nan()) {
+                    return nearest();
+                }
+                let top = v[0] * (1.0 - fx) + v[1] * fx;
+                let bottom = v[2] * (1.0 - fx) + v[3] * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+        }
+    }
+
+    /// warp into a `width` × `height` grid placed by `transform` in the target CRS
+    ///
+    /// `to_source` maps target CRS coordinates to this raster's CRS.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::affine::Affine2D;
+    /// use mercator::raster::{Raster, Resampling};
+    /// use mercator::{wgs84_to_twd97, f2degree_zone_to_wgs84};
+    /// // a 0.01° lng/lat grid whose values are the cell longitude
+    /// let mut src = Raster::new(20, 20, Affine2D::new(0.01, 0.0, 121.0, 0.0, -0.01, 24.2));
+    /// for r in 0..20 { for c in 0..20 { src.set(c, r, 121.0 + 0.01 * c as f64); } }
+    /// // into a 500 m TWD97 grid inside it
+    /// let (x0, y0) = wgs84_to_twd97(121.02, 24.18);
+    /// let dst = src.warp(10, 10, Affine2D::new(500.0, 0.0, x0, 0.0, -500.0, y0), Resampling::Bilinear,
+    ///     |x, y| f2degree_zone_to_wgs84(x, y, 121.0));
+    /// let v = dst.get(0, 0);
+    /// assert!((v - 121.02).abs() < 1e-6);
+    /// ```
+    pub fn warp<F>(&self, width:usize, height:usize, transform:Affine2D, resampling:Resampling, to_source:F) -> Raster
+        where F: Fn(f64, f64) -> (f64, f64)
+    {
+        let mut out = Raster::new(width, height, transform);
+        for row in 0..height {
+            for col in 0..width {
+                let (x, y) = transform.apply(col as f64, row as f64);
+                let (sx, sy) = to_source(x, y);
+                out.set(col, row, self.sample(sx, sy, resampling));
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,190 @@
+//! registry of CRS definitions addressed by code or name
+//!
+//! keys are case insensitive; a bare number means an EPSG code, so `"3826"`
+//! and `"epsg:3826"` are the same key. built-in EPSG codes are always
+//! available and cannot be redefined, custom ones are added with
+//! [`Registry::register`]. a global registry backs the string / EPSG keyed
+//! transform functions of [`cache`](::cache).
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{OnceLock, RwLock};
+
+use crs::{AreaOfUse, Crs, Transformer};
+use datum;
+use projection::Projection;
+use error::{Error, Result};
+
+/// normalized form of a CRS key: trimmed, upper case, bare numbers prefixed with `EPSG:`
+/// # Examples
+///
+/// ```
+/// use mercator::registry::normalize_key;
+/// assert_eq!(normalize_key(" epsg:3826 "), "EPSG:3826");
+/// assert_eq!(normalize_key("3826"), "EPSG:3826");
+/// assert_eq!(normalize_key("city:grid"), "CITY:GRID");
+/// ```
+pub fn normalize_key(key:&str) -> String {
+    let key = key.trim();
+    if !key.is_empty() && key.bytes().all(|b| b.is_ascii_digit()) {
+        format!("EPSG:{}", key)
+    } else {
+        key.to_uppercase()
+    }
+}
+
+fn builtin(key:&str) -> Option<Crs> {
+    key.strip_prefix("EPSG:").and_then(|code| code.parse().ok()).and_then(|code| Crs::from_epsg(code).ok())
+}
+
+/// a thread safe set of CRS definitions
+pub struct Registry {
+    entries:RwLock<HashMap<String, (Crs, Option<AreaOfUse>)>>,
+    generation:AtomicU64,
+}
+
+impl Registry {
+    /// a registry holding only the built-in EPSG codes
+    pub fn new() -> Registry {
+        Registry { entries: RwLock::new(HashMap::new()), generation: AtomicU64::new(0) }
+    }
+
+    /// add or replace a custom CRS under `key`
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Crs;
+    /// use mercator::registry::Registry;
+    /// use mercator::zone::{Zone, ZoneWidth};
+    /// let reg = Registry::new();
+    /// reg.register("city:tm3", Crs::projected(Zone::new(ZoneWidth::Degree3, 120.0))).unwrap();
+    /// reg.register("900913", Crs::projected(Zone::utm(51))).unwrap();
+    /// assert!(reg.get("CITY:TM3").is_ok());
+    /// let t = reg.transformer("EPSG:4326", "City:TM3").unwrap();
+    /// assert!((t.transform(120.0, 24.0).0 - 350000.0).abs() < 1e-6);
+    /// // built-in codes cannot be redefined
+    /// assert!(reg.register("EPSG:3826", Crs::wgs84()).is_err());
+    /// ```
+    pub fn register(&self, key:&str, crs:Crs) -> Result<()> {
+        let area = crs.area_of_use();
+        self.register_with_area(key, crs, area)
+    }
+
+    /// add or replace a custom CRS with an explicit area of use
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::{AreaOfUse, BoundsCheck, Crs};
+    /// use mercator::registry::Registry;
+    /// use mercator::zone::Zone;
+    /// let reg = Registry::new();
+    /// let taiwan = AreaOfUse::new(119.99, 21.87, 122.06, 25.34);
+    /// reg.register_with_area("island:tm2", Crs::projected(Zone::twd97()), Some(taiwan)).unwrap();
+    /// let t = reg.transformer("island:tm2", "EPSG:4326").unwrap().with_bounds_check(BoundsCheck::Error);
+    /// assert!(t.try_transform(250000.0, 2650000.0).is_ok());
+    /// // this part of the zone strip is outside the island
+    /// assert!(t.try_transform(250000.0, 2000000.0).is_err());
+    /// ```
+    pub fn register_with_area(&self, key:&str, crs:Crs, area:Option<AreaOfUse>) -> Result<()> {
+        let key = normalize_key(key);
+        if key.is_empty() {
+            return Err(Error::InvalidInput("empty CRS key".to_string()));
+        }
+        if builtin(&key).is_some() {
+            return Err(Error::InvalidInput(format!("{} is a built-in CRS", key)));
+        }
+        self.entries.write().unwrap_or_else(|e| e.into_inner()).insert(key, (crs, area));
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// register a custom projection on WGS84 under `key`
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::projection::Projection;
+    /// use mercator::registry::Registry;
+    /// use mercator::batch::transform_points;
+    ///
+    /// /// a local plane tangent at a site, good for a few kilometers
+    /// #[derive(Debug)]
+    /// struct SitePlane { lng0:f64, lat0:f64 }
+    /// const M_PER_DEG:f64 = 111319.49;
+    /// impl Projection for SitePlane {
+    ///     fn name(&self) -> &str { "site plane" }
+    ///     fn forward(&self, lng:f64, lat:f64) -> (f64, f64) {
+    ///         ((lng - self.lng0) * M_PER_DEG * self.lat0.to_radians().cos(), (lat - self.lat0) * M_PER_DEG)
+    ///     }
+    ///     fn inverse(&self, x:f64, y:f64) -> (f64, f64) {
+    ///         (self.lng0 + x / (M_PER_DEG * self.lat0.to_radians().cos()), self.lat0 + y / M_PER_DEG)
+    ///     }
+    /// }
+    ///
+    /// let reg = Registry::new();
+    /// reg.register_projection("site:plane", Box::new(SitePlane { lng0: 121.5, lat0: 25.0 })).unwrap();
+    /// let t = reg.transformer("EPSG:3826", "site:plane").unwrap();
+    /// let mut pts = vec![mercator::wgs84_to_twd97(121.5, 25.0)];
+    /// transform_points(&mut pts, |x, y| t.transform(x, y));
+    /// assert!(pts[0].0.abs() < 0.01 && pts[0].1.abs() < 0.01);
+    /// ```
+    pub fn register_projection(&self, key:&str, projection:Box<dyn Projection>) -> Result<()> {
+        self.register(key, Crs::Custom(projection.into(), datum::WGS84))
+    }
+
+    /// remove a custom CRS, returning its definition
+    pub fn unregister(&self, key:&str) -> Option<Crs> {
+        let removed = self.entries.write().unwrap_or_else(|e| e.into_inner()).remove(&normalize_key(key));
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        removed.map(|e| e.0)
+    }
+
+    fn entry(&self, key:&str) -> Result<(Crs, Option<AreaOfUse>)> {
+        let key = normalize_key(key);
+        if let Some(crs) = builtin(&key) {
+            let area = crs.area_of_use();
+            return Ok((crs, area));
+        }
+        self.entries.read().unwrap_or_else(|e| e.into_inner()).get(&key).cloned()
+            .ok_or_else(|| Error::InvalidInput(format!("unknown CRS {}", key)))
+    }
+
+    /// the CRS registered under `key`, custom or built-in
+    pub fn get(&self, key:&str) -> Result<Crs> {
+        self.entry(key).map(|e| e.0)
+    }
+
+    /// the area of use registered with `key`
+    pub fn area_of_use(&self, key:&str) -> Result<Option<AreaOfUse>> {
+        self.entry(key).map(|e| e.1)
+    }
+
+    /// keys of every custom CRS, sorted
+    pub fn custom_keys(&self) -> Vec<String> {
+        let mut keys:Vec<String> = self.entries.read().unwrap_or_else(|e| e.into_inner()).keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    /// a transformer between two registered keys
+    pub fn transformer(&self, source:&str, target:&str) -> Result<Transformer> {
+        let (source, area) = self.entry(source)?;
+        Ok(Transformer::new(source, self.get(target)?).with_area_of_use(area))
+    }
+
+    /// changes every time a definition is added or removed
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        Registry::new()
+    }
+}
+
+/// the registry shared by the whole process
+pub fn registry() -> &'static Registry {
+    static REGISTRY:OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Registry::new)
+}
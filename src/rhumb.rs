@@ -0,0 +1,168 @@
+//! rhumb lines (loxodromes) on the ellipsoid
+//!
+//! a rhumb line crosses every meridian at the same bearing, so it is the
+//! straight line of a Mercator chart and the course a ship steers without
+//! changing heading. it is longer than the geodesic except along a meridian
+//! or the equator. the shorter way round in longitude is taken, and a line
+//! running past a pole stops at it.
+//! - https://en.wikipedia.org/wiki/Rhumb_line
+
+use std::f64::consts::FRAC_PI_2;
+
+use constants::{tm_constants, TmConstants};
+use ellipsoid::Ellipsoid;
+use heading::normalize_heading;
+use longitude::{lng_diff, normalize_lng};
+use multiple_angles;
+
+/// below this latitude difference in radians the meridian arc over the
+/// isometric latitude is taken from its derivative at the middle
+const FLAT:f64 = 1e-6;
+
+/// length and constant bearing of a rhumb line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rhumb {
+    /// length in meters
+    pub distance:f64,
+    /// bearing, degrees clockwise from north in [0, 360)
+    pub bearing:f64,
+}
+
+/// meridian arc from the equator to a latitude in radians
+fn meridian_arc(c:&TmConstants, lat:f64) -> f64 {
+    let [a, b, cc, d, e] = c.arc;
+    let [(s2, _), (s4, _), (s6, _), (s8, _)] = multiple_angles(lat.sin(), lat.cos());
+    a * lat - b * s2 + cc * s4 - d * s6 + e * s8
+}
+
+/// latitude in radians of a meridian arc, the footprint latitude polished by Newton steps
+fn arc_latitude(c:&TmConstants, m:f64) -> f64 {
+    let [j1, j2, j3, j4] = c.footprint;
+    let mu = m / c.mu_scale;
+    let [(s2, _), (s4, _), (s6, _), (s8, _)] = multiple_angles(mu.sin(), mu.cos());
+    let mut lat = mu + j1 * s2 + j2 * s4 + j3 * s6 + j4 * s8;
+    for _ in 0..2 {
+        let w = 1.0 - c.e2 * lat.sin().powi(2);
+        let rho = c.a * (1.0 - c.e2) / (w * w.sqrt());
+        lat -= (meridian_arc(c, lat) - m) / rho;
+    }
+    lat
+}
+
+/// isometric latitude ψ of a latitude in radians
+fn isometric(c:&TmConstants, lat:f64) -> f64 {
+    let e = c.e2.sqrt();
+    let s = lat.sin();
+    s.atanh() - e * (e * s).atanh()
+}
+
+/// meridian arc per unit of isometric latitude between two latitudes in
+/// radians, which is the parallel radius `ν cos φ` when they coincide
+fn arc_per_isometric(c:&TmConstants, lat1:f64, lat2:f64) -> f64 {
+    if (lat2 - lat1).abs() < FLAT {
+        let lat = (lat1 + lat2) / 2.0;
+        c.a * lat.cos() / (1.0 - c.e2 * lat.sin().powi(2)).sqrt()
+    } else {
+        (meridian_arc(c, lat2) - meridian_arc(c, lat1)) / (isometric(c, lat2) - isometric(c, lat1))
+    }
+}
+
+/// length and bearing of the rhumb line between two `(lng, lat)` points
+/// # Examples
+///
+/// ```
+/// use mercator::ellipsoid::WGS84;
+/// use mercator::rhumb::inverse;
+/// // along the equator and along a meridian the rhumb line is the geodesic
+/// let r = inverse(&WGS84, (0.0, 0.0), (1.0, 0.0));
+/// assert!((r.distance - 111_319.491).abs() < 1e-3 && r.bearing == 90.0);
+/// let r = inverse(&WGS84, (121.0, 0.0), (121.0, 90.0));
+/// assert!((r.distance - 10_001_965.729).abs() < 1e-3 && r.bearing == 0.0);
+/// ```
+pub fn inverse(ellipsoid:&Ellipsoid, from:(f64, f64), to:(f64, f64)) -> Rhumb {
+    let c = tm_constants(ellipsoid);
+    let (lat1, lat2) = (from.1.to_radians(), to.1.to_radians());
+    let dlng = lng_diff(to.0, from.0).to_radians();
+    if (lat2 - lat1).abs() < FLAT {
+        // along a parallel, or close to one
+        let lat = (lat1 + lat2) / 2.0;
+        let dpsi = (lat2 - lat1) * (1.0 - c.e2) / ((1.0 - c.e2 * lat.sin().powi(2)) * lat.cos());
+        return Rhumb {
+            distance: arc_per_isometric(&c, lat1, lat2) * dlng.hypot(dpsi),
+            bearing: normalize_heading(dlng.atan2(dpsi).to_degrees()),
+        };
+    }
+    let course = dlng.atan2(isometric(&c, lat2) - isometric(&c, lat1));
+    Rhumb {
+        distance: ((meridian_arc(&c, lat2) - meridian_arc(&c, lat1)) / course.cos()).abs(),
+        bearing: normalize_heading(course.to_degrees()),
+    }
+}
+
+/// length in meters of the rhumb line between two `(lng, lat)` points
+pub fn distance(ellipsoid:&Ellipsoid, from:(f64, f64), to:(f64, f64)) -> f64 {
+    inverse(ellipsoid, from, to).distance
+}
+
+/// constant bearing of the rhumb line from `from` to `to`
+/// # Examples
+///
+/// ```
+/// use mercator::ellipsoid::WGS84;
+/// use mercator::rhumb::bearing;
+/// // across the antimeridian the short way
+/// assert!((bearing(&WGS84, (179.0, 10.0), (-179.0, 10.0)) - 90.0).abs() < 1e-12);
+/// assert!(bearing(&WGS84, (121.5, 25.0), (139.7, 35.7)) > 50.0);
+/// ```
+pub fn bearing(ellipsoid:&Ellipsoid, from:(f64, f64), to:(f64, f64)) -> f64 {
+    inverse(ellipsoid, from, to).bearing
+}
+
+/// the point reached steering a constant `bearing` for `distance` meters from a `(lng, lat)` point
+/// # Examples
+///
+/// ```
+/// use mercator::ellipsoid::WGS84;
+/// use mercator::rhumb::{destination, inverse};
+/// let start = (121.5, 25.0);
+/// let end = destination(&WGS84, start, 60.0, 2_000_000.0);
+/// let back = inverse(&WGS84, start, end);
+/// assert!((back.distance - 2_000_000.0).abs() < 1e-6);
+/// assert!((back.bearing - 60.0).abs() < 1e-9);
+/// ```
+pub fn destination(ellipsoid:&Ellipsoid, from:(f64, f64), bearing:f64, distance:f64) -> (f64, f64) {
+    let c = tm_constants(ellipsoid);
+    let (sin_b, cos_b) = bearing.to_radians().sin_cos();
+    let lat1 = from.1.to_radians();
+    let pole = meridian_arc(&c, FRAC_PI_2);
+    let m2 = meridian_arc(&c, lat1) + distance * cos_b;
+    if m2.abs() >= pole {
+        return (normalize_lng(from.0), 90f64.copysign(m2));
+    }
+    let lat2 = if (distance * cos_b).abs() < 1e-9 { lat1 } else { arc_latitude(&c, m2) };
+    let q = arc_per_isometric(&c, lat1, lat2);
+    (normalize_lng(from.0 + (distance * sin_b / q).to_degrees()), lat2.to_degrees())
+}
+
+/// `n + 1` points evenly spaced along the rhumb line from `from` to `to`, both ends included
+/// # Examples
+///
+/// ```
+/// use mercator::ellipsoid::WGS84;
+/// use mercator::rhumb::{bearing, sample};
+/// let points = sample(&WGS84, (121.5, 25.0), (-122.4, 37.8), 10);
+/// assert_eq!(points.len(), 11);
+/// assert!((points[10].0 + 122.4).abs() < 1e-9 && (points[10].1 - 37.8).abs() < 1e-9);
+/// // every leg keeps the same course
+/// let course = bearing(&WGS84, points[0], points[10]);
+/// assert!((bearing(&WGS84, points[4], points[5]) - course).abs() < 1e-6);
+/// ```
+pub fn sample(ellipsoid:&Ellipsoid, from:(f64, f64), to:(f64, f64), n:usize) -> Vec<(f64, f64)> {
+    let line = inverse(ellipsoid, from, to);
+    let n = n.max(1);
+    let mut points:Vec<(f64, f64)> = (0..n)
+        .map(|i| destination(ellipsoid, from, line.bearing, line.distance * i as f64 / n as f64))
+        .collect();
+    points.push(to);
+    points
+}
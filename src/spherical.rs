@@ -0,0 +1,110 @@
+//! great-circle distance, bearing and midpoint on a sphere
+//!
+//! fast approximations for code that does not need the ellipsoid: the
+//! distances are within about 0.5 % of the geodesic on WGS84. points are
+//! `(lng, lat)` in degrees, bearings are degrees clockwise from north in
+//! [0, 360).
+
+use heading::normalize_heading;
+use longitude::normalize_lng;
+
+/// mean earth radius (IUGG R1) in meters
+pub const EARTH_RADIUS:f64 = 6371008.8;
+
+/// great-circle distance in meters on a sphere of [`EARTH_RADIUS`] (haversine formula)
+/// # Examples
+///
+/// ```
+/// use mercator::spherical::haversine;
+/// let taipei = (121.5654, 25.0330);
+/// let kaohsiung = (120.3014, 22.6273);
+/// let d = haversine(taipei, kaohsiung);
+/// assert!((d - 297_000.0).abs() < 1500.0);
+/// ```
+pub fn haversine(from:(f64, f64), to:(f64, f64)) -> f64 {
+    haversine_on(EARTH_RADIUS, from, to)
+}
+
+/// [`haversine`] on a sphere of radius `r`
+pub fn haversine_on(r:f64, from:(f64, f64), to:(f64, f64)) -> f64 {
+    let (p1, p2) = (from.1.to_radians(), to.1.to_radians());
+    let dp = p2 - p1;
+    let dl = (to.0 - from.0).to_radians();
+    let a = (dp / 2.0).sin().powi(2) + p1.cos() * p2.cos() * (dl / 2.0).sin().powi(2);
+    2.0 * r * a.sqrt().min(1.0).asin()
+}
+
+/// central angle between two points in radians
+pub(crate) fn central_angle(from:(f64, f64), to:(f64, f64)) -> f64 {
+    haversine_on(1.0, from, to)
+}
+
+/// bearing of the great circle at `from` towards `to`
+/// # Examples
+///
+/// ```
+/// use mercator::spherical::{final_bearing, initial_bearing};
+/// assert!((initial_bearing((0.0, 0.0), (0.0, 10.0)) - 0.0).abs() < 1e-12);
+/// assert!((initial_bearing((0.0, 0.0), (10.0, 0.0)) - 90.0).abs() < 1e-12);
+/// // flying east from Taipei to San Francisco the course starts north east and ends south east
+/// let (tpe, sfo) = ((121.23, 25.08), (-122.38, 37.62));
+/// assert!(initial_bearing(tpe, sfo) < 60.0 && final_bearing(tpe, sfo) > 110.0);
+/// ```
+pub fn initial_bearing(from:(f64, f64), to:(f64, f64)) -> f64 {
+    let (p1, p2) = (from.1.to_radians(), to.1.to_radians());
+    let dl = (to.0 - from.0).to_radians();
+    let y = dl.sin() * p2.cos();
+    let x = p1.cos() * p2.sin() - p1.sin() * p2.cos() * dl.cos();
+    normalize_heading(y.atan2(x).to_degrees())
+}
+
+/// bearing of the great circle arriving at `to` from `from`
+pub fn final_bearing(from:(f64, f64), to:(f64, f64)) -> f64 {
+    normalize_heading(initial_bearing(to, from) + 180.0)
+}
+
+/// the point halfway along the great circle between two points
+/// # Examples
+///
+/// ```
+/// use mercator::spherical::midpoint;
+/// let (lng, lat) = midpoint((179.0, 0.0), (-179.0, 0.0));
+/// assert!((lng.abs() - 180.0).abs() < 1e-9 && lat.abs() < 1e-9);
+/// ```
+pub fn midpoint(from:(f64, f64), to:(f64, f64)) -> (f64, f64) {
+    intermediate(from, to, 0.5)
+}
+
+/// the point at `fraction` (0 at `from`, 1 at `to`) of the great circle between two points
+pub fn intermediate(from:(f64, f64), to:(f64, f64), fraction:f64) -> (f64, f64) {
+    let d = central_angle(from, to);
+    if d == 0.0 {
+        return from;
+    }
+    let (p1, l1) = (from.1.to_radians(), from.0.to_radians());
+    let (p2, l2) = (to.1.to_radians(), to.0.to_radians());
+    let a = ((1.0 - fraction) * d).sin() / d.sin();
+    let b = (fraction * d).sin() / d.sin();
+    let x = a * p1.cos() * l1.cos() + b * p2.cos() * l2.cos();
+    let y = a * p1.cos() * l1.sin() + b * p2.cos() * l2.sin();
+    let z = a * p1.sin() + b * p2.sin();
+    (y.atan2(x).to_degrees(), z.atan2(x.hypot(y)).to_degrees())
+}
+
+/// the point reached going `distance` meters from `from` on the great circle of initial `bearing`
+/// # Examples
+///
+/// ```
+/// use mercator::spherical::{destination, haversine};
+/// let start = (121.0, 24.0);
+/// let end = destination(start, 45.0, 10_000.0);
+/// assert!((haversine(start, end) - 10_000.0).abs() < 1e-6);
+/// ```
+pub fn destination(from:(f64, f64), bearing:f64, distance:f64) -> (f64, f64) {
+    let d = distance / EARTH_RADIUS;
+    let (p1, l1) = (from.1.to_radians(), from.0.to_radians());
+    let t = bearing.to_radians();
+    let p2 = (p1.sin() * d.cos() + p1.cos() * d.sin() * t.cos()).asin();
+    let l2 = l1 + (t.sin() * d.sin() * p1.cos()).atan2(d.cos() - p1.sin() * p2.sin());
+    (normalize_lng(l2.to_degrees()), p2.to_degrees())
+}
@@ -0,0 +1,186 @@
+//! async `Stream` adapters transforming coordinates as they arrive
+//!
+//! an ingestion service receiving points, linestrings or polygons from a
+//! socket or a message queue wraps its stream and gets transformed items out,
+//! one at a time and without blocking on the whole input. the adapters need
+//! an `Unpin` stream; pin anything else with `Box::pin` first.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+
+use coord::{LngLat, Projected};
+use error::{Error, Result};
+
+/// items whose `(x, y)` coordinates can be transformed in place
+///
+/// implemented for `(f64, f64)` points, the coordinate structs and nested
+/// `Vec`s of them, which covers [`LineString`](::geometry::LineString),
+/// [`Polygon`](::geometry::Polygon) and multi geometries.
+pub trait Reproject {
+    /// apply `f` to every coordinate
+    fn reproject<F:Fn(f64, f64) -> (f64, f64)>(&mut self, f:&F);
+
+    /// apply a fallible `f` to every coordinate, stopping at the first failure;
+    /// a non-finite result counts as a failure
+    fn try_reproject<F:Fn(f64, f64) -> Result<(f64, f64)>>(&mut self, f:&F) -> Result<()>;
+}
+
+fn checked<F:Fn(f64, f64) -> Result<(f64, f64)>>(f:&F, x:f64, y:f64) -> Result<(f64, f64)> {
+    match f(x, y)? {
+        (nx, ny) if nx.is_finite() && ny.is_finite() => Ok((nx, ny)),
+        (nx, ny) => Err(Error::Transform(format!("({}, {}) gave non-finite ({}, {})", x, y, nx, ny))),
+    }
+}
+
+impl Reproject for (f64, f64) {
+    fn reproject<F:Fn(f64, f64) -> (f64, f64)>(&mut self, f:&F) {
+        *self = f(self.0, self.1);
+    }
+
+    fn try_reproject<F:Fn(f64, f64) -> Result<(f64, f64)>>(&mut self, f:&F) -> Result<()> {
+        *self = checked(f, self.0, self.1)?;
+        Ok(())
+    }
+}
+
+impl Reproject for LngLat {
+    fn reproject<F:Fn(f64, f64) -> (f64, f64)>(&mut self, f:&F) {
+        let (lng, lat) = f(self.lng, self.lat);
+        self.lng = lng;
+        self.lat = lat;
+    }
+
+    fn try_reproject<F:Fn(f64, f64) -> Result<(f64, f64)>>(&mut self, f:&F) -> Result<()> {
+        let (lng, lat) = checked(f, self.lng, self.lat)?;
+        self.lng = lng;
+        self.lat = lat;
+        Ok(())
+    }
+}
+
+impl Reproject for Projected {
+    fn reproject<F:Fn(f64, f64) -> (f64, f64)>(&mut self, f:&F) {
+        let (x, y) = f(self.x, self.y);
+        self.x = x;
+        self.y = y;
+    }
+
+    fn try_reproject<F:Fn(f64, f64) -> Result<(f64, f64)>>(&mut self, f:&F) -> Result<()> {
+        let (x, y) = checked(f, self.x, self.y)?;
+        self.x = x;
+        self.y = y;
+        Ok(())
+    }
+}
+
+impl<T:Reproject> Reproject for Vec<T> {
+    fn reproject<F:Fn(f64, f64) -> (f64, f64)>(&mut self, f:&F) {
+        for item in self.iter_mut() {
+            item.reproject(f);
+        }
+    }
+
+    fn try_reproject<F:Fn(f64, f64) -> Result<(f64, f64)>>(&mut self, f:&F) -> Result<()> {
+        self.iter_mut().try_for_each(|item| item.try_reproject(f))
+    }
+}
+
+/// a stream yielding the items of another stream with `f` applied to their coordinates
+#[derive(Debug)]
+pub struct Transformed<S, F> {
+    inner:S,
+    f:F,
+}
+
+impl<S, F> Stream for Transformed<S, F>
+    where S: Stream + Unpin, S::Item: Reproject, F: Fn(f64, f64) -> (f64, f64) + Unpin
+{
+    type Item = S::Item;
+
+    fn poll_next(self:Pin<&mut Self>, cx:&mut Context) -> Poll<Option<S::Item>> {
+        let this = self.get_mut();
+        Pin::new(&mut this.inner).poll_next(cx).map(|item| item.map(|mut item| {
+            item.reproject(&this.f);
+            item
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// a stream yielding the items of another stream with a fallible `f` applied
+/// to their coordinates, an item that fails comes out as its error
+#[derive(Debug)]
+pub struct TryTransformed<S, F> {
+    inner:S,
+    f:F,
+}
+
+impl<S, F> Stream for TryTransformed<S, F>
+    where S: Stream + Unpin, S::Item: Reproject, F: Fn(f64, f64) -> Result<(f64, f64)> + Unpin
+{
+    type Item = Result<S::Item>;
+
+    fn poll_next(self:Pin<&mut Self>, cx:&mut Context) -> Poll<Option<Result<S::Item>>> {
+        let this = self.get_mut();
+        Pin::new(&mut this.inner).poll_next(cx).map(|item| item.map(|mut item| {
+            item.try_reproject(&this.f).map(|_| item)
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// coordinate transforms as stream combinators
+pub trait TransformStreamExt: Stream + Sized {
+    /// transform the coordinates of every item with `f`
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate futures;
+    /// # extern crate mercator;
+    /// use futures::executor::block_on;
+    /// use futures::stream::{self, StreamExt};
+    /// use mercator::stream::TransformStreamExt;
+    /// use mercator::wgs84_to_twd97;
+    /// let lines = stream::iter(vec![vec![(121.0, 24.0), (121.5, 24.5)], vec![(120.5, 23.0)]]);
+    /// let out:Vec<Vec<(f64, f64)>> = block_on(lines.transform_coords(wgs84_to_twd97).collect());
+    /// assert_eq!(out.len(), 2);
+    /// assert!((out[0][0].0 - 250000.0).abs() < 1e-6);
+    /// ```
+    fn transform_coords<F>(self, f:F) -> Transformed<Self, F>
+        where Self::Item: Reproject, F: Fn(f64, f64) -> (f64, f64)
+    {
+        Transformed { inner: self, f }
+    }
+
+    /// transform the coordinates of every item with a fallible `f`, such as
+    /// [`Transformer::try_transform`](::crs::Transformer::try_transform)
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate futures;
+    /// # extern crate mercator;
+    /// use futures::executor::block_on;
+    /// use futures::stream::{self, StreamExt};
+    /// use mercator::crs::{BoundsCheck, Transformer};
+    /// use mercator::stream::TransformStreamExt;
+    /// let t = Transformer::from_epsg(3826, 4326).unwrap().with_bounds_check(BoundsCheck::Error);
+    /// let points = stream::iter(vec![(250000.0, 2650000.0), (5000000.0, 2650000.0)]);
+    /// let out:Vec<_> = block_on(points.try_transform_coords(move |x, y| t.try_transform(x, y)).collect());
+    /// assert!(out[0].is_ok() && out[1].is_err());
+    /// ```
+    fn try_transform_coords<F>(self, f:F) -> TryTransformed<Self, F>
+        where Self::Item: Reproject, F: Fn(f64, f64) -> Result<(f64, f64)>
+    {
+        TryTransformed { inner: self, f }
+    }
+}
+
+impl<S:Stream> TransformStreamExt for S {}
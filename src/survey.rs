@@ -0,0 +1,181 @@
+//! grid distortion quantities surveyors need to reduce observations to a TM grid
+
+use ellipsoid::WGS84;
+use longitude::lng_diff;
+use zone::Zone;
+
+/// point scale factor k of a TM projection at a wgs84 point (Snyder 8-11)
+///
+/// grid distance = k × ellipsoid distance for an infinitesimal line at the point.
+/// # Examples
+///
+/// ```
+/// use mercator::survey::scale_factor;
+/// // on the central meridian k equals k0
+/// assert_eq!(scale_factor(121.0, 23.5, 121.0, 0.9999), 0.9999);
+/// assert!(scale_factor(122.0, 23.5, 121.0, 0.9999) > 1.0);
+/// ```
+pub fn scale_factor(lng:f64, lat:f64, center_lng:f64, k0:f64) -> f64 {
+    let ep2 = WGS84.ep2();
+    let lat = lat.to_radians();
+    let big_a = lat.cos() * lng_diff(lng, center_lng).to_radians();
+    let c = ep2 * lat.cos().powi(2);
+    let t = lat.tan().powi(2);
+    let a2 = big_a * big_a;
+    k0 * (1.0 + (1.0 + c) * a2 / 2.0
+        + (5.0 - 4.0 * t + 42.0 * c + 13.0 * c * c - 28.0 * ep2) * a2 * a2 / 24.0
+        + (61.0 - 148.0 * t + 16.0 * t * t) * a2 * a2 * a2 / 720.0)
+}
+
+/// meridian convergence γ of a TM projection at a wgs84 point, in degrees
+///
+/// γ is the clockwise angle from true north to grid north, positive east of
+/// the central meridian in the northern hemisphere.
+/// # Examples
+///
+/// ```
+/// use mercator::survey::convergence;
+/// assert_eq!(convergence(121.0, 23.5, 121.0), 0.0);
+/// let g = convergence(122.0, 23.5, 121.0);
+/// assert!(g > 0.39 && g < 0.40);
+/// ```
+pub fn convergence(lng:f64, lat:f64, center_lng:f64) -> f64 {
+    let ep2 = WGS84.ep2();
+    let p = lng_diff(lng, center_lng).to_radians();
+    let lat = lat.to_radians();
+    let (s, c) = lat.sin_cos();
+    let eta2 = ep2 * c * c;
+    let t2 = (s / c).powi(2);
+    let pc2 = (p * c).powi(2);
+    let gamma = p * s * (1.0 + pc2 / 3.0 * (1.0 + 3.0 * eta2 + 2.0 * eta2 * eta2)
+        + pc2 * pc2 / 15.0 * (2.0 - t2));
+    gamma.to_degrees()
+}
+
+/// arc-to-chord correction (t − T) at `from` for the line `from` → `to`, in degrees
+///
+/// t is the grid bearing of the straight chord between the two grid points and
+/// T the grid bearing of the projected geodesic at `from`, so an observed
+/// direction reduced to the grid is `T + (t − T)`.
+/// # Examples
+///
+/// ```
+/// use mercator::zone::Zone;
+/// use mercator::survey::arc_to_chord;
+/// let zone = Zone::twd97();
+/// let a = zone.forward(121.5, 24.0);
+/// let b = zone.forward(121.5, 24.2);
+/// // a north-going line east of the central meridian bows away from it
+/// let d = arc_to_chord(&zone, a, b);
+/// assert!(d < 0.0 && d * 3600.0 > -3.0);
+/// ```
+pub fn arc_to_chord(zone:&Zone, from:(f64, f64), to:(f64, f64)) -> f64 {
+    let dx = zone.width.dx();
+    let (e1, e2) = (from.0 - dx, to.0 - dx);
+    let mid = ((from.0 + to.0) / 2.0, (from.1 + to.1) / 2.0);
+    let (_, lat) = zone.inverse(mid.0, mid.1);
+    let (rho, nu) = WGS84.radii(lat);
+    let k0 = zone.width.k0();
+    let d = -(to.1 - from.1) * (2.0 * e1 + e2) / (6.0 * k0 * k0 * rho * nu);
+    d.to_degrees()
+}
+
+/// average scale factor of the line `from` → `to` given in grid coordinates (Simpson's rule)
+/// # Examples
+///
+/// ```
+/// use mercator::zone::Zone;
+/// use mercator::survey::line_scale_factor;
+/// let zone = Zone::twd97();
+/// let k = line_scale_factor(&zone, zone.forward(121.0, 24.0), zone.forward(121.0, 24.1));
+/// assert!((k - 0.9999).abs() < 1e-9);
+/// ```
+pub fn line_scale_factor(zone:&Zone, from:(f64, f64), to:(f64, f64)) -> f64 {
+    let k_at = |x:f64, y:f64| {
+        let (lng, lat) = zone.inverse(x, y);
+        zone.scale_factor(lng, lat)
+    };
+    let k1 = k_at(from.0, from.1);
+    let km = k_at((from.0 + to.0) / 2.0, (from.1 + to.1) / 2.0);
+    let k2 = k_at(to.0, to.1);
+    (k1 + 4.0 * km + k2) / 6.0
+}
+
+/// elevation (height) factor R / (R + h) reducing ellipsoidal distances at height `h` meters to the ellipsoid
+pub fn elevation_factor(lat:f64, h:f64) -> f64 {
+    let (rho, nu) = WGS84.radii(lat);
+    let r = (rho * nu).sqrt();
+    r / (r + h)
+}
+
+/// combined factor (line scale factor × elevation factor) for the line `from` → `to`
+///
+/// grid distance = ground distance × combined factor, `h` is the mean
+/// ellipsoidal height of the line in meters.
+/// # Examples
+///
+/// ```
+/// use mercator::zone::Zone;
+/// use mercator::survey::combined_factor;
+/// let zone = Zone::twd97();
+/// let (a, b) = (zone.forward(121.5, 25.0), zone.forward(121.52, 25.01));
+/// let cf = combined_factor(&zone, a, b, 500.0);
+/// let ground = 2000.0;
+/// assert!((ground * cf - ground).abs() < 0.5);
+/// ```
+pub fn combined_factor(zone:&Zone, from:(f64, f64), to:(f64, f64), h:f64) -> f64 {
+    let (_, lat) = zone.inverse((from.0 + to.0) / 2.0, (from.1 + to.1) / 2.0);
+    line_scale_factor(zone, from, to) * elevation_factor(lat, h)
+}
+
+/// reduce a horizontal distance measured on the ground at mean ellipsoidal
+/// height `h` to a grid distance, with the scale factor at the midpoint `mid`
+/// in wgs84 lng/lat
+///
+/// the point scale at the midpoint stands for the line scale factor, which is
+/// within 0.1 ppm of [`line_scale_factor`] for lines up to 10 km. `h` is
+/// ellipsoidal: add the geoid undulation to orthometric heights first.
+/// # Examples
+///
+/// ```
+/// use mercator::zone::Zone;
+/// use mercator::survey::{ground_to_grid_distance, grid_to_ground_distance};
+/// let zone = Zone::twd97();
+/// // 1 km measured at 1000 m on the central meridian shrinks by 100 ppm of scale and 157 ppm of height
+/// let grid = ground_to_grid_distance(&zone, 1000.0, (121.0, 24.0), 1000.0);
+/// assert!((grid - 999.743).abs() < 1e-3);
+/// assert!((grid_to_ground_distance(&zone, grid, (121.0, 24.0), 1000.0) - 1000.0).abs() < 1e-9);
+/// ```
+pub fn ground_to_grid_distance(zone:&Zone, d_ground:f64, mid:(f64, f64), h:f64) -> f64 {
+    d_ground * zone.scale_factor(mid.0, mid.1) * elevation_factor(mid.1, h)
+}
+
+/// the reverse of [`ground_to_grid_distance`], a grid distance back to a ground distance at height `h`
+pub fn grid_to_ground_distance(zone:&Zone, d_grid:f64, mid:(f64, f64), h:f64) -> f64 {
+    d_grid / (zone.scale_factor(mid.0, mid.1) * elevation_factor(mid.1, h))
+}
+
+fn normalize_azimuth(az:f64) -> f64 {
+    az.rem_euclid(360.0)
+}
+
+/// convert a grid bearing at a wgs84 point to a geodetic (true) azimuth, degrees in [0, 360)
+/// # Examples
+///
+/// ```
+/// use mercator::zone::Zone;
+/// use mercator::survey::{grid_to_geodetic_azimuth, geodetic_to_grid_azimuth};
+/// let zone = Zone::twd97();
+/// let true_az = grid_to_geodetic_azimuth(&zone, 122.0, 24.0, 359.9);
+/// assert!(true_az > 0.0 && true_az < 1.0);
+/// let back = geodetic_to_grid_azimuth(&zone, 122.0, 24.0, true_az);
+/// assert!((back - 359.9).abs() < 1e-9);
+/// ```
+pub fn grid_to_geodetic_azimuth(zone:&Zone, lng:f64, lat:f64, grid_bearing:f64) -> f64 {
+    normalize_azimuth(grid_bearing + zone.convergence(lng, lat))
+}
+
+/// convert a geodetic (true) azimuth at a wgs84 point to a grid bearing, degrees in [0, 360)
+pub fn geodetic_to_grid_azimuth(zone:&Zone, lng:f64, lat:f64, azimuth:f64) -> f64 {
+    normalize_azimuth(azimuth - zone.convergence(lng, lat))
+}
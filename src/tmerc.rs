@@ -0,0 +1,69 @@
+//! transverse mercator with arbitrary parameters
+//!
+//! [`Zone`](::zone::Zone) covers the 2°, 3° and 6° zone systems; national
+//! grids such as the British National Grid or the Japanese plane rectangular
+//! systems have their own origin latitude, scale and false origin. this is the
+//! exact (Krüger) projection with all five parameters free, usable anywhere a
+//! [`Projection`] is.
+
+use ellipsoid::Ellipsoid;
+use kruger::{tm_exact_forward, tm_exact_inverse};
+use projection::Projection;
+
+/// series order, the one of [`TmMethod::Exact`](::zone::TmMethod::Exact)
+const ORDER:usize = 6;
+
+/// transverse mercator on an ellipsoid with the EPSG 9807 parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransverseMercator {
+    pub ellipsoid:Ellipsoid,
+    /// latitude of natural origin in degrees
+    pub lat0:f64,
+    /// longitude of natural origin (central meridian) in degrees
+    pub lng0:f64,
+    /// scale factor at natural origin
+    pub k0:f64,
+    pub false_easting:f64,
+    pub false_northing:f64,
+    /// northing of the origin latitude before the false northing is added
+    origin_northing:f64,
+}
+
+impl TransverseMercator {
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::ellipsoid::Ellipsoid;
+    /// use mercator::projection::Projection;
+    /// use mercator::tmerc::TransverseMercator;
+    /// // British National Grid on Airy 1830, the worked example of the OS guide
+    /// let airy = Ellipsoid::from_inverse_flattening(6377563.396, 299.3249646);
+    /// let bng = TransverseMercator::new(airy, 49.0, -2.0, 0.9996012717, 400000.0, -100000.0);
+    /// let lat = 52.0 + 39.0 / 60.0 + 27.2531 / 3600.0;
+    /// let lng = 1.0 + 43.0 / 60.0 + 4.5177 / 3600.0;
+    /// let (e, n) = bng.forward(lng, lat);
+    /// assert!((e - 651409.903).abs() < 0.001 && (n - 313177.270).abs() < 0.001);
+    /// let (lng2, lat2) = bng.inverse(e, n);
+    /// assert!((lng2 - lng).abs() < 1e-10 && (lat2 - lat).abs() < 1e-10);
+    /// ```
+    pub fn new(ellipsoid:Ellipsoid, lat0:f64, lng0:f64, k0:f64, false_easting:f64, false_northing:f64) -> TransverseMercator {
+        let (_, origin_northing) = tm_exact_forward(&ellipsoid, ORDER, lng0, lat0, lng0, k0, 0.0);
+        TransverseMercator { ellipsoid, lat0, lng0, k0, false_easting, false_northing, origin_northing }
+    }
+}
+
+impl Projection for TransverseMercator {
+    fn name(&self) -> &str {
+        "transverse mercator"
+    }
+
+    fn forward(&self, lng:f64, lat:f64) -> (f64, f64) {
+        let (x, y) = tm_exact_forward(&self.ellipsoid, ORDER, lng, lat, self.lng0, self.k0, self.false_easting);
+        (x, y - self.origin_northing + self.false_northing)
+    }
+
+    fn inverse(&self, x:f64, y:f64) -> (f64, f64) {
+        let y = y - self.false_northing + self.origin_northing;
+        tm_exact_inverse(&self.ellipsoid, ORDER, x, y, self.lng0, self.k0, self.false_easting)
+    }
+}
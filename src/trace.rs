@@ -0,0 +1,117 @@
+//! tracing instrumentation of batch transforms, file IO and pipelines
+//!
+//! with the `tracing` feature long running operations open a span under the
+//! `mercator` target and close it with an event giving the points processed,
+//! failures, elapsed time and points per second; failing points and pipeline
+//! steps are reported at debug level. without the feature every hook here
+//! compiles to nothing.
+
+use std::path::Path;
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+#[cfg(feature = "tracing")]
+use tracing::span::EnteredSpan;
+
+use error::Error;
+use pipeline::{Pipeline, Step};
+
+/// a running operation, reported when finished or dropped on an error path
+pub(crate) struct Activity {
+    #[cfg(feature = "tracing")]
+    _span:EnteredSpan,
+    #[cfg(feature = "tracing")]
+    op:&'static str,
+    #[cfg(feature = "tracing")]
+    start:Instant,
+    #[cfg(feature = "tracing")]
+    finished:bool,
+}
+
+impl Activity {
+    /// an operation over `points` points in memory
+    #[inline]
+    pub(crate) fn start(op:&'static str, points:u64) -> Activity {
+        #[cfg(feature = "tracing")]
+        {
+            let span = ::tracing::info_span!(target: "mercator", "transform", op, points).entered();
+            Activity { _span: span, op, start: Instant::now(), finished: false }
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = (op, points);
+            Activity {}
+        }
+    }
+
+    /// an operation reading or writing the file at `path`
+    #[inline]
+    pub(crate) fn file(op:&'static str, path:&Path) -> Activity {
+        #[cfg(feature = "tracing")]
+        {
+            let span = ::tracing::info_span!(target: "mercator", "file", op, path = %path.display()).entered();
+            Activity { _span: span, op, start: Instant::now(), finished: false }
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = (op, path);
+            Activity {}
+        }
+    }
+
+    /// report a finished operation
+    #[inline]
+    pub(crate) fn finish(self, points:u64, failed:u64) {
+        #[cfg(feature = "tracing")]
+        {
+            let mut this = self;
+            this.finished = true;
+            let elapsed = this.start.elapsed().as_secs_f64();
+            let points_per_sec = if elapsed > 0.0 { points as f64 / elapsed } else { 0.0 };
+            let elapsed_ms = elapsed * 1e3;
+            if failed == 0 {
+                ::tracing::info!(target: "mercator", points, failed, elapsed_ms, points_per_sec, "{} finished", this.op);
+            } else {
+                ::tracing::warn!(target: "mercator", points, failed, elapsed_ms, points_per_sec, "{} finished with failures", this.op);
+            }
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = (self, points, failed);
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for Activity {
+    fn drop(&mut self) {
+        if !self.finished {
+            ::tracing::warn!(target: "mercator", elapsed_ms = self.start.elapsed().as_secs_f64() * 1e3, "{} stopped by an error", self.op);
+        }
+    }
+}
+
+/// a point a fallible batch could not transform
+#[inline]
+pub(crate) fn point_failed(index:usize, error:&Error) {
+    #[cfg(feature = "tracing")]
+    ::tracing::debug!(target: "mercator", index, %error, "point failed");
+    #[cfg(not(feature = "tracing"))]
+    let _ = (index, error);
+}
+
+/// a pipeline step that rejected a coordinate
+#[inline]
+pub(crate) fn step_failed(step:&Step, error:&Error) {
+    #[cfg(feature = "tracing")]
+    ::tracing::debug!(target: "mercator", %step, %error, "pipeline step failed");
+    #[cfg(not(feature = "tracing"))]
+    let _ = (step, error);
+}
+
+/// the steps chosen for a transformer
+#[inline]
+pub(crate) fn pipeline_built(pipeline:&Pipeline) {
+    #[cfg(feature = "tracing")]
+    ::tracing::debug!(target: "mercator", %pipeline, "pipeline built");
+    #[cfg(not(feature = "tracing"))]
+    let _ = pipeline;
+}
@@ -0,0 +1,104 @@
+//! units of CRS coordinates
+//!
+//! projections compute in meters; State Plane and older cadastral systems are
+//! defined in feet or links, so linear units convert at the edges with the
+//! exact factors from their legal definitions.
+
+use error::{Error, Result};
+
+/// meters in an international foot
+pub const FOOT:f64 = 0.3048;
+/// meters in a US survey foot, 1200/3937 by definition
+pub const US_SURVEY_FOOT:f64 = 1200.0 / 3937.0;
+/// meters in a Gunter's link, 66/100 US survey feet
+pub const LINK:f64 = 0.66 * US_SURVEY_FOOT;
+
+/// the unit of the horizontal coordinates of a CRS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Degree,
+    Meter,
+    /// international foot
+    Foot,
+    UsSurveyFoot,
+    /// Gunter's link
+    Link,
+}
+
+impl Unit {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Unit::Degree => "degree",
+            Unit::Meter => "metre",
+            Unit::Foot => "foot",
+            Unit::UsSurveyFoot => "US survey foot",
+            Unit::Link => "link",
+        }
+    }
+
+    /// parse a unit name or abbreviation such as `m`, `ft`, `us-ft` or `link`
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::units::Unit;
+    /// assert_eq!(Unit::parse("US survey foot").unwrap(), Unit::UsSurveyFoot);
+    /// assert_eq!(Unit::parse("ft").unwrap(), Unit::Foot);
+    /// assert!(Unit::parse("furlong").is_err());
+    /// ```
+    pub fn parse(text:&str) -> Result<Unit> {
+        let name = text.trim().to_lowercase().replace(['_', ' '], "-");
+        Ok(match name.as_str() {
+            "deg" | "degree" | "degrees" => Unit::Degree,
+            "m" | "metre" | "meter" | "metres" | "meters" => Unit::Meter,
+            "ft" | "foot" | "feet" | "international-foot" => Unit::Foot,
+            "us-ft" | "ftus" | "us-survey-foot" | "us-survey-feet" => Unit::UsSurveyFoot,
+            "link" | "links" | "gunter-link" | "ch/100" => Unit::Link,
+            _ => return Err(Error::InvalidInput(format!("unknown unit {:?}", text))),
+        })
+    }
+
+    /// meters per unit, `None` for angular units
+    pub fn meters_per_unit(&self) -> Option<f64> {
+        match *self {
+            Unit::Degree => None,
+            Unit::Meter => Some(1.0),
+            Unit::Foot => Some(FOOT),
+            Unit::UsSurveyFoot => Some(US_SURVEY_FOOT),
+            Unit::Link => Some(LINK),
+        }
+    }
+
+    pub fn is_linear(&self) -> bool {
+        self.meters_per_unit().is_some()
+    }
+
+    /// the factor that turns a value in this unit into `to`
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::units::Unit;
+    /// let k = Unit::UsSurveyFoot.factor_to(Unit::Foot).unwrap();
+    /// assert!((k - 1.000002).abs() < 1e-6);
+    /// assert_eq!(Unit::Link.factor_to(Unit::UsSurveyFoot).unwrap(), 0.66);
+    /// assert!(Unit::Degree.factor_to(Unit::Meter).is_err());
+    /// ```
+    pub fn factor_to(&self, to:Unit) -> Result<f64> {
+        if *self == to {
+            return Ok(1.0);
+        }
+        match (self.meters_per_unit(), to.meters_per_unit()) {
+            (Some(a), Some(b)) => Ok(a / b),
+            _ => Err(Error::InvalidInput(format!("cannot convert {} to {}", self.name(), to.name()))),
+        }
+    }
+
+    /// a value in this unit in meters, angular values pass through
+    pub fn to_meters(&self, v:f64) -> f64 {
+        v * self.meters_per_unit().unwrap_or(1.0)
+    }
+
+    /// a value in meters in this unit, angular values pass through
+    pub fn from_meters(&self, v:f64) -> f64 {
+        v / self.meters_per_unit().unwrap_or(1.0)
+    }
+}
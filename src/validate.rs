@@ -0,0 +1,118 @@
+//! cross-validation against PROJ
+//!
+//! runs the same points through this crate and through PROJ (via the `proj`
+//! crate, which needs libproj at build time) and reports how far apart the
+//! results are. meant to be run once for the CRSs of a deployment, not in the
+//! transform path: PROJ may pick a different datum transformation than the
+//! Helmert shifts used here, and the report makes that visible.
+//! - https://proj.org
+
+use std::fmt::Display;
+
+use proj::Proj;
+
+use crs::{Crs, Transformer};
+use error::{Error, Result};
+
+/// the result of one point that differs most between the two libraries
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delta {
+    /// index of the point in the input
+    pub index:usize,
+    /// output of this crate
+    pub ours:(f64, f64),
+    /// output of PROJ
+    pub proj:(f64, f64),
+    /// distance between the two outputs in meters
+    pub distance:f64,
+}
+
+/// summary of the differences over a set of points, in meters
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// number of points both libraries transformed
+    pub count:usize,
+    pub max:f64,
+    pub mean:f64,
+    pub rms:f64,
+    pub worst:Option<Delta>,
+    /// indices of points either library failed to transform
+    pub failed:Vec<usize>,
+}
+
+impl Comparison {
+    /// true when every point transformed and no difference exceeds `meters`
+    pub fn within(&self, meters:f64) -> bool {
+        self.failed.is_empty() && self.max <= meters
+    }
+}
+
+fn proj_err<E:Display>(e:E) -> Error {
+    Error::Transform(format!("proj: {}", e))
+}
+
+/// distance between two outputs of `target`; lng/lat differences are measured on the ellipsoid
+fn distance(target:&Crs, a:(f64, f64), b:(f64, f64)) -> f64 {
+    match *target {
+        Crs::Geographic(ref datum) => {
+            let p = datum.ellipsoid.geodetic_to_ecef(a.0, a.1, 0.0);
+            let q = datum.ellipsoid.geodetic_to_ecef(b.0, b.1, 0.0);
+            ((p.0 - q.0).powi(2) + (p.1 - q.1).powi(2) + (p.2 - q.2).powi(2)).sqrt()
+        }
+        _ => ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt(),
+    }
+}
+
+/// compare the transform between two CRSs with PROJ's transform between two definitions
+///
+/// the definitions are anything PROJ accepts as a CRS (`"EPSG:3826"`, WKT,
+/// a `+proj` string with `+type=crs`). PROJ is asked for lng/lat order, like
+/// this crate uses.
+pub fn compare_definitions(source:&Crs, source_def:&str, target:&Crs, target_def:&str, points:&[(f64, f64)]) -> Result<Comparison> {
+    let theirs = Proj::new_known_crs(source_def, target_def, None).map_err(proj_err)?;
+    let ours = Transformer::new(source.clone(), target.clone());
+    let mut cmp = Comparison { count: 0, max: 0.0, mean: 0.0, rms: 0.0, worst: None, failed: Vec::new() };
+    for (i, &(x, y)) in points.iter().enumerate() {
+        let a = ours.try_transform(x, y);
+        let b = theirs.convert((x, y));
+        let (a, b) = match (a, b) {
+            (Ok(a), Ok(b)) if a.0.is_finite() && a.1.is_finite() => (a, b),
+            _ => {
+                cmp.failed.push(i);
+                continue;
+            }
+        };
+        let d = distance(target, a, b);
+        if cmp.worst.is_none() || d > cmp.max {
+            cmp.max = d;
+            cmp.worst = Some(Delta { index: i, ours: a, proj: b, distance: d });
+        }
+        cmp.count += 1;
+        cmp.mean += d;
+        cmp.rms += d * d;
+    }
+    if cmp.count > 0 {
+        cmp.mean /= cmp.count as f64;
+        cmp.rms = (cmp.rms / cmp.count as f64).sqrt();
+    }
+    Ok(cmp)
+}
+
+/// compare two EPSG codes, with PROJ using its own database definitions
+/// # Examples
+///
+/// ```no_run
+/// use mercator::validate::compare_epsg;
+/// let grid:Vec<(f64, f64)> = (0..10).flat_map(|i| (0..10).map(move |j| (120.0 + 0.2 * i as f64, 22.0 + 0.3 * j as f64))).collect();
+/// let cmp = compare_epsg(4326, 3826, &grid).unwrap();
+/// assert!(cmp.within(0.01), "worst case {:?}", cmp.worst);
+/// ```
+pub fn compare_epsg(source:u32, target:u32, points:&[(f64, f64)]) -> Result<Comparison> {
+    let (s, t) = (Crs::from_epsg(source)?, Crs::from_epsg(target)?);
+    compare_definitions(&s, &format!("EPSG:{}", source), &t, &format!("EPSG:{}", target), points)
+}
+
+/// compare two CRSs, handing PROJ their WKT2 definitions
+pub fn compare(source:&Crs, target:&Crs, points:&[(f64, f64)]) -> Result<Comparison> {
+    compare_definitions(source, &source.to_wkt()?, target, &target.to_wkt()?, points)
+}
@@ -0,0 +1,226 @@
+//! vertical coordinate reference systems
+//!
+//! heights are either ellipsoidal (what GNSS gives) or orthometric (above a
+//! geoid / leveling datum). converting between the two needs a geoid model,
+//! which is configured on the orthometric system. a [`CompoundCrs`] pairs a
+//! horizontal CRS with a vertical one, e.g. TWD97 TM2 with TWVD2001 heights,
+//! and a [`CompoundTransformer`] carries 3D coordinates between two of them.
+
+use std::fmt;
+use std::sync::Arc;
+
+use coord::LngLat;
+use crs::{Crs, Transformer};
+use error::{Error, Result};
+
+/// a model giving the geoid undulation N (meters above the ellipsoid) at a point
+pub trait GeoidModel {
+    fn undulation(&self, lng:f64, lat:f64) -> f64;
+}
+
+/// a geoid that is a constant offset from the ellipsoid, for small project areas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConstantGeoid(pub f64);
+
+impl GeoidModel for ConstantGeoid {
+    fn undulation(&self, _lng:f64, _lat:f64) -> f64 {
+        self.0
+    }
+}
+
+#[cfg(feature = "geoid")]
+impl GeoidModel for ::geoid::GeoidGrid {
+    fn undulation(&self, lng:f64, lat:f64) -> f64 {
+        self.geoid_height(lng, lat)
+    }
+}
+
+/// a vertical coordinate reference system
+#[derive(Clone)]
+pub enum VerticalCrs {
+    /// heights above the ellipsoid of the horizontal datum
+    Ellipsoidal,
+    /// heights above a geoid, convertible to ellipsoidal heights when a geoid model is set
+    Orthometric {
+        name:String,
+        geoid:Option<Arc<dyn GeoidModel + Send + Sync>>,
+    },
+}
+
+impl fmt::Debug for VerticalCrs {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerticalCrs::Ellipsoidal => write!(f, "Ellipsoidal"),
+            VerticalCrs::Orthometric { ref name, ref geoid } =>
+                write!(f, "Orthometric {{ name: {:?}, geoid: {} }}", name, if geoid.is_some() { "Some(..)" } else { "None" }),
+        }
+    }
+}
+
+impl VerticalCrs {
+    /// an orthometric system without a geoid model
+    pub fn orthometric(name:&str) -> VerticalCrs {
+        VerticalCrs::Orthometric { name: name.to_string(), geoid: None }
+    }
+
+    /// Taiwan Vertical Datum 2001
+    pub fn twvd2001() -> VerticalCrs {
+        VerticalCrs::orthometric("TWVD2001")
+    }
+
+    /// the same system with `geoid` used for ellipsoidal ⇄ orthometric conversion
+    ///
+    /// ellipsoidal heights need no geoid, an ellipsoidal system is returned
+    /// as it is and the model dropped.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::vertical::{ConstantGeoid, VerticalCrs};
+    /// let h = VerticalCrs::Ellipsoidal.with_geoid(ConstantGeoid(20.5))
+    ///     .transform(&VerticalCrs::Ellipsoidal, 121.5, 25.0, 100.0).unwrap();
+    /// assert_eq!(h, 100.0);
+    /// ```
+    pub fn with_geoid<G: GeoidModel + Send + Sync + 'static>(self, geoid:G) -> VerticalCrs {
+        match self {
+            VerticalCrs::Ellipsoidal => VerticalCrs::Ellipsoidal,
+            VerticalCrs::Orthometric { name, .. } => VerticalCrs::Orthometric { name, geoid: Some(Arc::new(geoid)) },
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match *self {
+            VerticalCrs::Ellipsoidal => "ellipsoidal",
+            VerticalCrs::Orthometric { ref name, .. } => name,
+        }
+    }
+
+    fn undulation(&self, lng:f64, lat:f64) -> Result<f64> {
+        match *self {
+            VerticalCrs::Ellipsoidal => Ok(0.0),
+            VerticalCrs::Orthometric { geoid: Some(ref g), .. } => Ok(g.undulation(lng, lat)),
+            VerticalCrs::Orthometric { ref name, geoid: None } =>
+                Err(Error::Transform(format!("no geoid model configured for {}", name))),
+        }
+    }
+
+    /// convert a height in this system to an ellipsoidal height
+    pub fn to_ellipsoidal(&self, lng:f64, lat:f64, height:f64) -> Result<f64> {
+        Ok(height + self.undulation(lng, lat)?)
+    }
+
+    /// convert an ellipsoidal height to a height in this system
+    pub fn from_ellipsoidal(&self, lng:f64, lat:f64, h:f64) -> Result<f64> {
+        Ok(h - self.undulation(lng, lat)?)
+    }
+
+    /// convert a height from this system to `to`
+    ///
+    /// two orthometric systems with the same name are treated as the same
+    /// datum and need no geoid model.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::vertical::{ConstantGeoid, VerticalCrs};
+    /// let twvd = VerticalCrs::twvd2001().with_geoid(ConstantGeoid(20.5));
+    /// let h = twvd.transform(&VerticalCrs::Ellipsoidal, 121.5, 25.0, 100.0).unwrap();
+    /// assert_eq!(h, 120.5);
+    /// assert!(VerticalCrs::twvd2001().transform(&VerticalCrs::Ellipsoidal, 121.5, 25.0, 100.0).is_err());
+    /// ```
+    pub fn transform(&self, to:&VerticalCrs, lng:f64, lat:f64, height:f64) -> Result<f64> {
+        match (self, to) {
+            (VerticalCrs::Orthometric { name: a, .. }, VerticalCrs::Orthometric { name: b, .. }) if a == b => return Ok(height),
+            (VerticalCrs::Ellipsoidal, VerticalCrs::Ellipsoidal) => return Ok(height),
+            _ => {}
+        }
+        to.from_ellipsoidal(lng, lat, self.to_ellipsoidal(lng, lat, height)?)
+    }
+
+    /// convert the height of a coordinate from this system to `to`
+    pub fn transform_coord(&self, to:&VerticalCrs, p:LngLat) -> Result<LngLat> {
+        Ok(LngLat { h: self.transform(to, p.lng, p.lat, p.h)?, ..p })
+    }
+}
+
+/// a horizontal CRS with the vertical CRS its heights are in
+#[derive(Debug, Clone)]
+pub struct CompoundCrs {
+    pub horizontal:Crs,
+    pub vertical:VerticalCrs,
+}
+
+impl CompoundCrs {
+    pub fn new(horizontal:Crs, vertical:VerticalCrs) -> CompoundCrs {
+        CompoundCrs { horizontal, vertical }
+    }
+
+    /// a horizontal CRS with heights above its ellipsoid
+    pub fn ellipsoidal(horizontal:Crs) -> CompoundCrs {
+        CompoundCrs::new(horizontal, VerticalCrs::Ellipsoidal)
+    }
+}
+
+/// a prepared transform of 3D coordinates from one compound CRS to another
+///
+/// heights go through the ellipsoid of the source datum whenever the
+/// horizontal datums differ, since a datum shift changes ellipsoidal heights
+/// too; between systems on the same frame they are converted directly, and
+/// equal orthometric systems need no geoid model at all.
+#[derive(Debug, Clone)]
+pub struct CompoundTransformer {
+    pub source:CompoundCrs,
+    pub target:CompoundCrs,
+    horizontal:Transformer,
+}
+
+impl CompoundTransformer {
+    pub fn new(source:CompoundCrs, target:CompoundCrs) -> CompoundTransformer {
+        let horizontal = Transformer::new(source.horizontal.clone(), target.horizontal.clone());
+        CompoundTransformer { source, target, horizontal }
+    }
+
+    /// the transformer of the horizontal components
+    pub fn horizontal(&self) -> &Transformer {
+        &self.horizontal
+    }
+
+    /// transform one coordinate, its height in the source vertical CRS
+    ///
+    /// errors of the horizontal transform come back as they are, see
+    /// [`Transformer::try_transform_3d`].
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Crs;
+    /// use mercator::vertical::{CompoundCrs, CompoundTransformer, ConstantGeoid, VerticalCrs};
+    /// let twvd = VerticalCrs::twvd2001().with_geoid(ConstantGeoid(20.5));
+    /// let local = CompoundCrs::new(Crs::from_epsg(3826).unwrap(), twvd);
+    /// let (x, y) = Crs::from_epsg(3826).unwrap().from_lnglat(121.5, 25.0);
+    ///
+    /// // to GNSS heights
+    /// let t = CompoundTransformer::new(local.clone(), CompoundCrs::ellipsoidal(Crs::wgs84()));
+    /// let (lng, lat, h) = t.transform(x, y, 100.0).unwrap();
+    /// assert!((lng - 121.5).abs() < 1e-9 && (lat - 25.0).abs() < 1e-9);
+    /// assert!((h - 120.5).abs() < 1e-6);
+    /// assert!((t.inverse().transform(lng, lat, h).unwrap().2 - 100.0).abs() < 1e-6);
+    ///
+    /// // leveled heights stay as they are when only the grid changes
+    /// let t = CompoundTransformer::new(local, CompoundCrs::new(Crs::wgs84(), VerticalCrs::twvd2001()));
+    /// assert_eq!(t.transform(x, y, 100.0).unwrap().2, 100.0);
+    /// ```
+    pub fn transform(&self, x:f64, y:f64, height:f64) -> Result<(f64, f64, f64)> {
+        let (lng, lat) = self.source.horizontal.to_lnglat(x, y);
+        if self.source.horizontal.datum().same_frame(&self.target.horizontal.datum()) {
+            let (x2, y2) = self.horizontal.try_transform(x, y)?;
+            return Ok((x2, y2, self.source.vertical.transform(&self.target.vertical, lng, lat, height)?));
+        }
+        let h = self.source.vertical.to_ellipsoidal(lng, lat, height)?;
+        let (x2, y2, h2) = self.horizontal.try_transform_3d(x, y, h)?;
+        let (lng2, lat2) = self.target.horizontal.to_lnglat(x2, y2);
+        Ok((x2, y2, self.target.vertical.from_ellipsoidal(lng2, lat2, h2)?))
+    }
+
+    /// the transformer going the other way
+    pub fn inverse(&self) -> CompoundTransformer {
+        CompoundTransformer::new(self.target.clone(), self.source.clone())
+    }
+}
@@ -0,0 +1,156 @@
+//! Vincenty's direct and inverse solutions of the geodesic problem
+//!
+//! iterative formulas on any ellipsoid, accurate to well under a millimeter.
+//! the inverse iteration fails to converge for nearly antipodal points,
+//! which is reported as an error instead of a wrong answer.
+//! - T. Vincenty, Direct and inverse solutions of geodesics on the ellipsoid
+//!   with application of nested equations, Survey Review 23 (1975)
+
+use ellipsoid::Ellipsoid;
+use error::{Error, Result};
+use heading::normalize_heading;
+use longitude::normalize_lng;
+
+const MAX_ITERATIONS:usize = 200;
+
+/// distance and bearings between two points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Inverse {
+    /// length of the geodesic in meters
+    pub distance:f64,
+    /// bearing at the first point, degrees clockwise from north
+    pub initial_bearing:f64,
+    /// bearing at the second point, continuing the line
+    pub final_bearing:f64,
+}
+
+/// the end of a geodesic given its start, bearing and length
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Direct {
+    /// `(lng, lat)` in degrees
+    pub point:(f64, f64),
+    /// bearing at the end point
+    pub final_bearing:f64,
+}
+
+/// the series coefficients A and B of Vincenty for u²
+fn series(u2:f64) -> (f64, f64) {
+    let a = 1.0 + u2 / 16384.0 * (4096.0 + u2 * (-768.0 + u2 * (320.0 - 175.0 * u2)));
+    let b = u2 / 1024.0 * (256.0 + u2 * (-128.0 + u2 * (74.0 - 47.0 * u2)));
+    (a, b)
+}
+
+fn delta_sigma(b:f64, sin_s:f64, cos_s:f64, cos_2sm:f64) -> f64 {
+    let c2 = cos_2sm * cos_2sm;
+    b * sin_s * (cos_2sm + b / 4.0 * (cos_s * (-1.0 + 2.0 * c2)
+        - b / 6.0 * cos_2sm * (-3.0 + 4.0 * sin_s * sin_s) * (-3.0 + 4.0 * c2)))
+}
+
+/// distance and bearings between two `(lng, lat)` points
+///
+/// fails with [`Error::Transform`] when the iteration does not converge,
+/// which happens for points within a few tenths of a degree of antipodal.
+/// # Examples
+///
+/// ```
+/// use mercator::ellipsoid::WGS84;
+/// use mercator::vincenty::inverse;
+/// // Flinders Peak to Buninyong, on WGS84 rather than the ellipsoid of the paper
+/// let r = inverse(&WGS84, (144.424868, -37.951033), (143.926496, -37.652821)).unwrap();
+/// assert!((r.distance - 54972.227).abs() < 0.001);
+/// assert!((r.initial_bearing - 306.86816).abs() < 1e-4);
+/// assert!(inverse(&WGS84, (0.0, 0.0), (179.8, 0.3)).is_err());
+/// ```
+pub fn inverse(ellipsoid:&Ellipsoid, from:(f64, f64), to:(f64, f64)) -> Result<Inverse> {
+    let (a, b, f) = (ellipsoid.a, ellipsoid.b, ellipsoid.f());
+    let l = normalize_lng(to.0 - from.0).to_radians();
+    let u1 = ((1.0 - f) * from.1.to_radians().tan()).atan();
+    let u2 = ((1.0 - f) * to.1.to_radians().tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+    let mut lambda = l;
+    for _ in 0..MAX_ITERATIONS {
+        let (sin_l, cos_l) = lambda.sin_cos();
+        let t = cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_l;
+        let sin_s = (cos_u2 * sin_l).hypot(t);
+        if sin_s == 0.0 {
+            return Ok(Inverse { distance: 0.0, initial_bearing: 0.0, final_bearing: 0.0 });
+        }
+        let cos_s = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_l;
+        let sigma = sin_s.atan2(cos_s);
+        let sin_alpha = cos_u1 * cos_u2 * sin_l / sin_s;
+        let cos2_alpha = 1.0 - sin_alpha * sin_alpha;
+        // on the equator cos²α is 0 and the midpoint term drops out
+        let cos_2sm = if cos2_alpha == 0.0 { 0.0 } else { cos_s - 2.0 * sin_u1 * sin_u2 / cos2_alpha };
+        let c = f / 16.0 * cos2_alpha * (4.0 + f * (4.0 - 3.0 * cos2_alpha));
+        let next = l + (1.0 - c) * f * sin_alpha
+            * (sigma + c * sin_s * (cos_2sm + c * cos_s * (-1.0 + 2.0 * cos_2sm * cos_2sm)));
+        if next.abs() > ::std::f64::consts::PI {
+            break;
+        }
+        if (next - lambda).abs() < 1e-12 {
+            let (sin_l, cos_l) = next.sin_cos();
+            let u2 = cos2_alpha * (a * a - b * b) / (b * b);
+            let (big_a, big_b) = series(u2);
+            let distance = b * big_a * (sigma - delta_sigma(big_b, sin_s, cos_s, cos_2sm));
+            let a1 = (cos_u2 * sin_l).atan2(cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_l);
+            let a2 = (cos_u1 * sin_l).atan2(-sin_u1 * cos_u2 + cos_u1 * sin_u2 * cos_l);
+            return Ok(Inverse {
+                distance,
+                initial_bearing: normalize_heading(a1.to_degrees()),
+                final_bearing: normalize_heading(a2.to_degrees()),
+            });
+        }
+        lambda = next;
+    }
+    Err(Error::Transform(format!("vincenty: no convergence between {:?} and {:?}, nearly antipodal points", from, to)))
+}
+
+/// the point `distance` meters from a `(lng, lat)` point along the geodesic of initial `bearing`
+/// # Examples
+///
+/// ```
+/// use mercator::ellipsoid::WGS84;
+/// use mercator::vincenty::{direct, inverse};
+/// let start = (121.0, 24.0);
+/// let d = direct(&WGS84, start, 30.0, 150_000.0);
+/// let back = inverse(&WGS84, start, d.point).unwrap();
+/// assert!((back.distance - 150_000.0).abs() < 1e-4);
+/// assert!((back.final_bearing - d.final_bearing).abs() < 1e-8);
+/// ```
+pub fn direct(ellipsoid:&Ellipsoid, from:(f64, f64), bearing:f64, distance:f64) -> Direct {
+    let (a, b, f) = (ellipsoid.a, ellipsoid.b, ellipsoid.f());
+    let (sin_a1, cos_a1) = bearing.to_radians().sin_cos();
+    let tan_u1 = (1.0 - f) * from.1.to_radians().tan();
+    let cos_u1 = 1.0 / (1.0 + tan_u1 * tan_u1).sqrt();
+    let sin_u1 = tan_u1 * cos_u1;
+    let sigma1 = tan_u1.atan2(cos_a1);
+    let sin_alpha = cos_u1 * sin_a1;
+    let cos2_alpha = 1.0 - sin_alpha * sin_alpha;
+    let u2 = cos2_alpha * (a * a - b * b) / (b * b);
+    let (big_a, big_b) = series(u2);
+    let mut sigma = distance / (b * big_a);
+    let mut cos_2sm = (2.0 * sigma1 + sigma).cos();
+    for _ in 0..MAX_ITERATIONS {
+        cos_2sm = (2.0 * sigma1 + sigma).cos();
+        let (sin_s, cos_s) = sigma.sin_cos();
+        let next = distance / (b * big_a) + delta_sigma(big_b, sin_s, cos_s, cos_2sm);
+        let done = (next - sigma).abs() < 1e-12;
+        sigma = next;
+        if done {
+            break;
+        }
+    }
+    let (sin_s, cos_s) = sigma.sin_cos();
+    let t = sin_u1 * sin_s - cos_u1 * cos_s * cos_a1;
+    let lat = (sin_u1 * cos_s + cos_u1 * sin_s * cos_a1).atan2((1.0 - f) * sin_alpha.hypot(t));
+    let lambda = (sin_s * sin_a1).atan2(cos_u1 * cos_s - sin_u1 * sin_s * cos_a1);
+    let c = f / 16.0 * cos2_alpha * (4.0 + f * (4.0 - 3.0 * cos2_alpha));
+    let l = lambda - (1.0 - c) * f * sin_alpha
+        * (sigma + c * sin_s * (cos_2sm + c * cos_s * (-1.0 + 2.0 * cos_2sm * cos_2sm)));
+    let a2 = sin_alpha.atan2(-t);
+    Direct {
+        point: (normalize_lng(from.0 + l.to_degrees()), lat.to_degrees()),
+        final_bearing: normalize_heading(a2.to_degrees()),
+    }
+}
@@ -0,0 +1,151 @@
+//! in-place coordinate transforms of WKB / EWKB geometries
+//!
+//! a transform never changes the structure of a geometry, so the coordinates
+//! are rewritten inside the existing buffer without decoding it into objects.
+
+use error::{Error, Result};
+
+/// deepest nesting of geometry collections accepted, so crafted input cannot overflow the stack
+const MAX_DEPTH:usize = 32;
+
+fn wkb_err(msg:&str) -> Error {
+    Error::Format(format!("wkb: {}", msg))
+}
+
+struct Cursor<'a> {
+    buf:&'a mut [u8],
+    pos:usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n:usize) -> Result<usize> {
+        if self.pos + n > self.buf.len() {
+            return Err(wkb_err("truncated geometry"));
+        }
+        let at = self.pos;
+        self.pos += n;
+        Ok(at)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        let at = self.take(1)?;
+        Ok(self.buf[at])
+    }
+
+    fn u32(&mut self, le:bool) -> Result<u32> {
+        let at = self.take(4)?;
+        let b = [self.buf[at], self.buf[at + 1], self.buf[at + 2], self.buf[at + 3]];
+        Ok(if le { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    }
+
+    fn f64_at(&self, at:usize, le:bool) -> f64 {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(&self.buf[at..at + 8]);
+        if le { f64::from_le_bytes(b) } else { f64::from_be_bytes(b) }
+    }
+
+    fn put_f64(&mut self, at:usize, v:f64, le:bool) {
+        let b = if le { v.to_le_bytes() } else { v.to_be_bytes() };
+        self.buf[at..at + 8].copy_from_slice(&b);
+    }
+
+    fn coords<F>(&mut self, n:usize, dims:usize, le:bool, f:&mut F) -> Result<()>
+        where F: FnMut(f64, f64) -> (f64, f64)
+    {
+        for _ in 0..n {
+            let at = self.take(8 * dims)?;
+            let (x, y) = f(self.f64_at(at, le), self.f64_at(at + 8, le));
+            self.put_f64(at, x, le);
+            self.put_f64(at + 8, y, le);
+        }
+        Ok(())
+    }
+
+    fn geometry<F>(&mut self, f:&mut F, depth:usize) -> Result<()>
+        where F: FnMut(f64, f64) -> (f64, f64)
+    {
+        if depth > MAX_DEPTH {
+            return Err(wkb_err("geometry collections nested too deep"));
+        }
+        let le = match self.u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(wkb_err("bad byte order marker")),
+        };
+        let raw = self.u32(le)?;
+        // EWKB flags
+        let mut dims = 2;
+        if raw & 0x8000_0000 != 0 {
+            dims += 1;
+        }
+        if raw & 0x4000_0000 != 0 {
+            dims += 1;
+        }
+        if raw & 0x2000_0000 != 0 {
+            self.u32(le)?;
+        }
+        let code = raw & 0x0fff_ffff;
+        // ISO Z / M / ZM type codes
+        dims += match code / 1000 {
+            0 => 0,
+            1 | 2 => 1,
+            3 => 2,
+            _ => return Err(wkb_err("unknown geometry type")),
+        };
+        match code % 1000 {
+            1 => self.coords(1, dims, le, f),
+            2 => {
+                let n = self.u32(le)? as usize;
+                self.coords(n, dims, le, f)
+            }
+            3 => {
+                let rings = self.u32(le)?;
+                for _ in 0..rings {
+                    let n = self.u32(le)? as usize;
+                    self.coords(n, dims, le, f)?;
+                }
+                Ok(())
+            }
+            4..=7 => {
+                let parts = self.u32(le)?;
+                for _ in 0..parts {
+                    self.geometry(f, depth + 1)?;
+                }
+                Ok(())
+            }
+            _ => Err(wkb_err("unknown geometry type")),
+        }
+    }
+}
+
+/// rewrite every (x, y) of a WKB or EWKB geometry with `f`, z and m values are kept
+/// # Examples
+///
+/// ```
+/// use mercator::wkb::transform_wkb;
+/// // POINT(121 24), little endian
+/// let mut wkb = vec![1u8, 1, 0, 0, 0];
+/// wkb.extend_from_slice(&121f64.to_le_bytes());
+/// wkb.extend_from_slice(&24f64.to_le_bytes());
+/// transform_wkb(&mut wkb, |x, y| (x + 1.0, y - 1.0)).unwrap();
+/// assert_eq!(&wkb[5..13], &122f64.to_le_bytes());
+/// assert_eq!(&wkb[13..21], &23f64.to_le_bytes());
+///
+/// // collections nested 100 deep are refused
+/// let mut deep = Vec::new();
+/// for _ in 0..100 {
+///     deep.extend_from_slice(&[1u8, 7, 0, 0, 0, 1, 0, 0, 0]);
+/// }
+/// deep.extend_from_slice(&wkb);
+/// assert!(transform_wkb(&mut deep, |x, y| (x, y)).is_err());
+/// ```
+pub fn transform_wkb<F>(wkb:&mut [u8], mut f:F) -> Result<()>
+    where F: FnMut(f64, f64) -> (f64, f64)
+{
+    let mut cur = Cursor { buf: wkb, pos: 0 };
+    cur.geometry(&mut f, 0)?;
+    if cur.pos != cur.buf.len() {
+        return Err(wkb_err("trailing bytes after geometry"));
+    }
+    Ok(())
+}
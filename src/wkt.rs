@@ -0,0 +1,272 @@
+//! WKT export of CRS definitions
+//!
+//! WKT2 (ISO 19162:2019) is what GeoPackage and current GDAL / PROJ expect;
+//! the ESRI dialect is what `.prj` sidecar files of shapefiles hold. ESRI WKT
+//! has no place for a datum shift, so a non-WGS84 datum is written by name only.
+
+use std::fs;
+use std::path::Path;
+
+use crs::Crs;
+use datum::{self, Datum};
+use ellipsoid;
+use error::{Error, Result};
+use info::epsg_name;
+use vertical::{CompoundCrs, VerticalCrs};
+use zone::{Hemisphere, Zone};
+
+/// the WKT dialect to write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WktFlavor {
+    /// WKT2 2019
+    #[default]
+    Wkt2,
+    /// the WKT1 dialect of ESRI `.prj` files
+    Esri,
+}
+
+const DEGREE:&str = "ANGLEUNIT[\"degree\",0.0174532925199433]";
+const METRE:&str = "LENGTHUNIT[\"metre\",1]";
+
+fn id(code:Option<u32>) -> String {
+    code.map(|c| format!(",ID[\"EPSG\",{}]", c)).unwrap_or_default()
+}
+
+fn datum_wkt2(d:&Datum) -> String {
+    let e = &d.ellipsoid;
+    format!("DATUM[\"{}\",ELLIPSOID[\"{}\",{},{},{}]],PRIMEM[\"Greenwich\",0,{}]",
+        d.name, e.name().unwrap_or("unknown"), e.a, e.inverse_flattening(), METRE, DEGREE)
+}
+
+fn base_wkt2(keyword:&str, d:&Datum, name:&str) -> String {
+    format!("{}[\"{}\",{}]", keyword, name, datum_wkt2(d))
+}
+
+fn geographic_wkt2(d:&Datum, name:&str, code:Option<u32>) -> String {
+    format!("GEOGCRS[\"{}\",{},CS[ellipsoidal,2],\
+        AXIS[\"geodetic latitude (Lat)\",north,ORDER[1],{}],\
+        AXIS[\"geodetic longitude (Lon)\",east,ORDER[2],{}]{}]",
+        name, datum_wkt2(d), DEGREE, DEGREE, id(code))
+}
+
+fn param(name:&str, value:f64, unit:&str, code:u32) -> String {
+    format!(",PARAMETER[\"{}\",{},{},ID[\"EPSG\",{}]]", name, value, unit, code)
+}
+
+fn projected_wkt2(zone:&Zone, d:&Datum, base:&str, name:&str, code:Option<u32>) -> String {
+    format!("PROJCRS[\"{}\",{},CONVERSION[\"Transverse Mercator {}\",\
+        METHOD[\"Transverse Mercator\",ID[\"EPSG\",9807]]{}{}{}{}{}],\
+        CS[Cartesian,2],AXIS[\"easting (E)\",east,ORDER[1],{}],AXIS[\"northing (N)\",north,ORDER[2],{}]{}]",
+        name, base_wkt2("BASEGEOGCRS", d, base), zone.center_lng,
+        param("Latitude of natural origin", 0.0, DEGREE, 8801),
+        param("Longitude of natural origin", zone.center_lng, DEGREE, 8802),
+        param("Scale factor at natural origin", zone.width.k0(), "SCALEUNIT[\"unity\",1]", 8805),
+        param("False easting", zone.width.dx(), METRE, 8806),
+        param("False northing", zone.hemisphere.dy(), METRE, 8807),
+        METRE, METRE, id(code))
+}
+
+fn bound_wkt2(source:String, d:&Datum) -> String {
+    let h = &d.to_wgs84;
+    let arcsec = "ANGLEUNIT[\"arc-second\",4.84813681109536E-06]";
+    let ppm = "SCALEUNIT[\"parts per million\",1E-06]";
+    format!("BOUNDCRS[SOURCECRS[{}],TARGETCRS[{}],ABRIDGEDTRANSFORMATION[\"{} to WGS 84\",\
+        METHOD[\"Position Vector transformation (geog2D domain)\",ID[\"EPSG\",9606]]{}{}{}{}{}{}{}]]",
+        source, geographic_wkt2(&datum::WGS84, "WGS 84", Some(4326)), d.name,
+        param("X-axis translation", h.tx, METRE, 8605),
+        param("Y-axis translation", h.ty, METRE, 8606),
+        param("Z-axis translation", h.tz, METRE, 8607),
+        param("X-axis rotation", h.rx, arcsec, 8608),
+        param("Y-axis rotation", h.ry, arcsec, 8609),
+        param("Z-axis rotation", h.rz, arcsec, 8610),
+        param("Scale difference", h.s, ppm, 8611))
+}
+
+/// the ESRI geographic CRS and datum names of the datums the crate knows
+const ESRI_DATUMS:[(&str, &str, &str); 12] = [
+    ("WGS 84", "GCS_WGS_1984", "D_WGS_1984"),
+    ("Taiwan Datum 1997", "GCS_TWD_1997", "D_TWD_1997"),
+    ("TWD67", "GCS_TWD_1967", "D_TWD_1967"),
+    ("NAD83", "GCS_North_American_1983", "D_North_American_1983"),
+    ("ETRS89", "GCS_ETRS_1989", "D_ETRS_1989"),
+    ("GDA94", "GCS_GDA_1994", "D_GDA_1994"),
+    ("GDA2020", "GCS_GDA2020", "D_GDA2020"),
+    ("NZGD2000", "GCS_NZGD_2000", "D_NZGD_2000"),
+    ("JGD2011", "GCS_JGD_2011", "D_JGD_2011"),
+    ("CGCS2000", "GCS_China_Geodetic_Coordinate_System_2000", "D_China_2000"),
+    ("OSGB36", "GCS_OSGB_1936", "D_OSGB_1936"),
+    ("DHDN", "GCS_Deutsches_Hauptdreiecksnetz", "D_Deutsches_Hauptdreiecksnetz"),
+];
+
+/// the ESRI spheroid names by semi-major axis and inverse flattening
+const ESRI_SPHEROIDS:[(f64, f64, &str); 6] = [
+    (6378137.0, 298.257223563, "WGS_1984"),
+    (6378137.0, 298.257222101, "GRS_1980"),
+    (6378160.0, 298.247167427, "GRS_1967"),
+    (6378160.0, 298.25, "GRS_1967_Truncated"),
+    (6377563.396, 299.3249646, "Airy_1830"),
+    (6377397.155, 299.1528128, "Bessel_1841"),
+];
+
+/// the base geographic CRS and datum EPSG gives a code, where the crate
+/// computes on another datum of the same frame: TWD97 is taken as WGS84
+fn epsg_datum(code:u32) -> Option<(&'static str, Datum)> {
+    match code {
+        3824..=3826 => Some(("TWD97", Datum { name: "Taiwan Datum 1997", ellipsoid: ellipsoid::GRS80, ..datum::WGS84 })),
+        _ => None,
+    }
+}
+
+/// ESRI names use underscores for spaces and punctuation
+fn esri_name(name:&str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c);
+        } else if !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    out.trim_matches('_').to_string()
+}
+
+fn geographic_esri(d:&Datum) -> String {
+    let e = &d.ellipsoid;
+    let (gcs, datum) = match ESRI_DATUMS.iter().find(|n| n.0 == d.name) {
+        Some(&(_, gcs, datum)) => (gcs.to_string(), datum.to_string()),
+        None => (format!("GCS_{}", esri_name(d.name)), format!("D_{}", esri_name(d.name))),
+    };
+    let spheroid = match ESRI_SPHEROIDS.iter().find(|s| s.0 == e.a && (s.1 - e.inverse_flattening()).abs() < 1e-6) {
+        Some(&(_, _, name)) => name.to_string(),
+        None => esri_name(e.name().unwrap_or("unknown")),
+    };
+    format!("GEOGCS[\"{}\",DATUM[\"{}\",SPHEROID[\"{}\",{:?},{:?}]],PRIMEM[\"Greenwich\",0.0],UNIT[\"Degree\",0.0174532925199433]]",
+        gcs, datum, spheroid, e.a, e.inverse_flattening())
+}
+
+fn projected_esri(zone:&Zone, d:&Datum, name:&str) -> String {
+    format!("PROJCS[\"{}\",{},PROJECTION[\"Transverse_Mercator\"],PARAMETER[\"False_Easting\",{:?}],\
+        PARAMETER[\"False_Northing\",{:?}],PARAMETER[\"Central_Meridian\",{:?}],PARAMETER[\"Scale_Factor\",{:?}],\
+        PARAMETER[\"Latitude_Of_Origin\",0.0],UNIT[\"Meter\",1.0]]",
+        esri_name(name), geographic_esri(d), zone.width.dx(), zone.hemisphere.dy(), zone.center_lng, zone.width.k0())
+}
+
+fn default_name(crs:&Crs) -> String {
+    match *crs {
+        Crs::Projected(ref zone, ref d) => {
+            let ns = if zone.hemisphere == Hemisphere::South { "S" } else { "N" };
+            format!("{} / TM {}{}", d.name, zone.center_lng, ns)
+        }
+        _ => crs.datum().name.to_string(),
+    }
+}
+
+fn export(crs:&Crs, flavor:WktFlavor, name:Option<String>, code:Option<u32>) -> Result<String> {
+    let name = name.unwrap_or_else(|| default_name(crs));
+    // the datum the crate shifts with decides on a BOUNDCRS, the EPSG one is written
+    let shift = crs.datum();
+    let (base, d) = code.and_then(epsg_datum).unwrap_or((shift.name, shift));
+    match (crs, flavor) {
+        (Crs::Custom(p, _), _) => Err(Error::InvalidInput(format!("custom projection {} has no WKT form", p.name()))),
+        (Crs::Geographic(_), WktFlavor::Esri) => Ok(geographic_esri(&d)),
+        (Crs::Projected(zone, _), WktFlavor::Esri) => Ok(projected_esri(zone, &d, &name)),
+        (_, WktFlavor::Wkt2) => {
+            let wkt = horizontal_wkt2(crs, &d, base, &name, code);
+            Ok(if shift.is_wgs84() { wkt } else { bound_wkt2(wkt, &shift) })
+        }
+    }
+}
+
+fn horizontal_wkt2(crs:&Crs, d:&Datum, base:&str, name:&str, code:Option<u32>) -> String {
+    match *crs {
+        Crs::Projected(ref zone, _) => projected_wkt2(zone, d, base, name, code),
+        _ => geographic_wkt2(d, name, code),
+    }
+}
+
+impl CompoundCrs {
+    /// the WKT2 definition, a `COMPOUNDCRS` of the horizontal CRS and a `VERTCRS`
+    ///
+    /// WKT2 has no vertical CRS for ellipsoidal heights, they belong to a 3D
+    /// geographic CRS instead, so an ellipsoidal vertical part is an error.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Crs;
+    /// use mercator::vertical::{CompoundCrs, VerticalCrs};
+    /// let crs = CompoundCrs::new(Crs::from_epsg(3826).unwrap(), VerticalCrs::twvd2001());
+    /// let wkt = crs.to_wkt().unwrap();
+    /// // EPSG:3826 is kept on the WGS 84 datum
+    /// assert!(wkt.starts_with("COMPOUNDCRS[\"WGS 84 / TM 121N + TWVD2001\",PROJCRS["));
+    /// assert!(wkt.ends_with("VERTCRS[\"TWVD2001\",VDATUM[\"TWVD2001\"],CS[vertical,1],\
+    ///     AXIS[\"gravity-related height (H)\",up,ORDER[1],LENGTHUNIT[\"metre\",1]]]]"));
+    /// assert!(CompoundCrs::ellipsoidal(Crs::wgs84()).to_wkt().is_err());
+    /// ```
+    pub fn to_wkt(&self) -> Result<String> {
+        let vertical = match self.vertical {
+            VerticalCrs::Ellipsoidal => return Err(Error::InvalidInput("ellipsoidal heights have no WKT2 vertical CRS".to_string())),
+            VerticalCrs::Orthometric { ref name, .. } => name,
+        };
+        if let Crs::Custom(ref p, _) = self.horizontal {
+            return Err(Error::InvalidInput(format!("custom projection {} has no WKT form", p.name())));
+        }
+        let d = self.horizontal.datum();
+        let name = default_name(&self.horizontal);
+        let wkt = format!("COMPOUNDCRS[\"{} + {}\",{},VERTCRS[\"{}\",VDATUM[\"{}\"],CS[vertical,1],\
+            AXIS[\"gravity-related height (H)\",up,ORDER[1],{}]]]",
+            name, vertical, horizontal_wkt2(&self.horizontal, &d, d.name, &name, None), vertical, vertical, METRE);
+        Ok(if d.is_wgs84() { wkt } else { bound_wkt2(wkt, &d) })
+    }
+}
+
+impl Crs {
+    /// the WKT2 definition of this CRS
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Crs;
+    /// use mercator::zone::Zone;
+    /// let wkt = Crs::projected(Zone::utm(51)).to_wkt().unwrap();
+    /// assert!(wkt.starts_with("PROJCRS[\"WGS 84 / TM 123N\",BASEGEOGCRS[\"WGS 84\""));
+    /// assert!(wkt.contains("PARAMETER[\"Scale factor at natural origin\",0.9996,"));
+    /// assert!(Crs::custom(Zone::utm(51)).to_wkt().is_err());
+    /// ```
+    pub fn to_wkt(&self) -> Result<String> {
+        export(self, WktFlavor::Wkt2, None, None)
+    }
+
+    /// the definition of this CRS in the given dialect
+    pub fn to_wkt_with(&self, flavor:WktFlavor) -> Result<String> {
+        export(self, flavor, None, None)
+    }
+
+    /// the definition of a built-in EPSG code, with its EPSG name and, in WKT2, its id
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::crs::Crs;
+    /// use mercator::wkt::WktFlavor;
+    /// let wkt = Crs::epsg_wkt(3826, WktFlavor::Wkt2).unwrap();
+    /// assert!(wkt.starts_with("PROJCRS[\"TWD97 / TM2 zone 121\""));
+    /// assert!(wkt.ends_with("ID[\"EPSG\",3826]]"));
+    /// // on its own datum, which the crate takes as WGS 84
+    /// assert!(wkt.contains("BASEGEOGCRS[\"TWD97\",DATUM[\"Taiwan Datum 1997\",ELLIPSOID[\"GRS 1980\",6378137,298.257222101,"));
+    /// assert!(Crs::epsg_wkt(3828, WktFlavor::Wkt2).unwrap().starts_with("BOUNDCRS[SOURCECRS[PROJCRS[\"TWD67 / TM2 zone 121\""));
+    /// assert_eq!(Crs::epsg_wkt(4326, WktFlavor::Esri).unwrap(),
+    ///     "GEOGCS[\"GCS_WGS_1984\",DATUM[\"D_WGS_1984\",SPHEROID[\"WGS_1984\",6378137.0,298.257223563]],\
+    ///      PRIMEM[\"Greenwich\",0.0],UNIT[\"Degree\",0.0174532925199433]]");
+    /// let prj = Crs::epsg_wkt(3826, WktFlavor::Esri).unwrap();
+    /// assert!(prj.contains("GEOGCS[\"GCS_TWD_1997\",DATUM[\"D_TWD_1997\",SPHEROID[\"GRS_1980\",6378137.0,298.257222101]]"));
+    /// assert!(Crs::epsg_wkt(3828, WktFlavor::Esri).unwrap().contains("SPHEROID[\"GRS_1967_Truncated\",6378160.0,298.25]"));
+    /// ```
+    pub fn epsg_wkt(code:u32, flavor:WktFlavor) -> Result<String> {
+        let crs = Crs::from_epsg(code)?;
+        export(&crs, flavor, epsg_name(code), Some(code))
+    }
+
+    /// write an ESRI `.prj` file for this CRS
+    pub fn write_prj<P: AsRef<Path>>(&self, path:P) -> Result<()> {
+        fs::write(path, self.to_wkt_with(WktFlavor::Esri)?)?;
+        Ok(())
+    }
+}
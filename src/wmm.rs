@@ -0,0 +1,239 @@
+//! World Magnetic Model declination (feature `wmm`)
+//!
+//! the WMM2025 coefficients are compiled in, valid from 2025.0 to 2030.0;
+//! a newer release or a high resolution variant in the same COF format can
+//! be parsed at runtime. the field is synthesized to degree 12 on WGS84 as in
+//! the WMM technical report, declination is good to about half a degree away
+//! from the magnetic poles.
+//! - https://www.ncei.noaa.gov/products/world-magnetic-model
+
+use std::sync::OnceLock;
+
+use error::{Error, Result};
+use heading::Norths;
+use zone::Zone;
+
+const WMM2025:&str = include_str!("../data/WMM2025.COF");
+
+/// geomagnetic reference radius in km
+const RADIUS:f64 = 6371.2;
+
+/// years a model is valid after its epoch
+const VALIDITY:f64 = 5.0;
+
+fn format_err(msg:String) -> Error {
+    Error::Format(format!("wmm: {}", msg))
+}
+
+/// the magnetic field at a point, in nT and degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MagneticField {
+    /// northward component
+    pub x:f64,
+    /// eastward component
+    pub y:f64,
+    /// downward component
+    pub z:f64,
+    /// horizontal intensity
+    pub h:f64,
+    /// total intensity
+    pub f:f64,
+    /// angle from true north to the horizontal field, positive east
+    pub declination:f64,
+    /// angle from the horizontal plane to the field, positive down
+    pub inclination:f64,
+}
+
+/// a spherical harmonic model of the main field with its secular variation
+#[derive(Debug, Clone, PartialEq)]
+pub struct MagneticModel {
+    pub name:String,
+    /// decimal year of the coefficients
+    pub epoch:f64,
+    degree:usize,
+    /// `(g, h, g dot, h dot)` indexed by `n (n + 1) / 2 + m`
+    coefficients:Vec<(f64, f64, f64, f64)>,
+}
+
+fn index(n:usize, m:usize) -> usize {
+    n * (n + 1) / 2 + m
+}
+
+impl MagneticModel {
+    /// the compiled-in WMM2025
+    pub fn wmm2025() -> &'static MagneticModel {
+        static MODEL:OnceLock<MagneticModel> = OnceLock::new();
+        MODEL.get_or_init(|| MagneticModel::parse(WMM2025).expect("data/WMM2025.COF"))
+    }
+
+    /// read a model in the WMM COF format: a header line with the epoch and
+    /// the name, then `n m g h g_dot h_dot` lines up to a line of 9s
+    pub fn parse(text:&str) -> Result<MagneticModel> {
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+        let header = lines.next().ok_or_else(|| format_err("empty model".to_string()))?;
+        let mut fields = header.split_whitespace();
+        let epoch = fields.next().and_then(|e| e.parse::<f64>().ok())
+            .ok_or_else(|| format_err(format!("bad header {:?}", header)))?;
+        let name = fields.next().unwrap_or("").to_string();
+        let mut rows = Vec::new();
+        for line in lines {
+            if line.trim_start().starts_with("9999") {
+                break;
+            }
+            let v:Vec<f64> = line.split_whitespace().map(|f| f.parse::<f64>())
+                .collect::<::std::result::Result<_, _>>()
+                .map_err(|_| format_err(format!("bad line {:?}", line)))?;
+            if v.len() < 6 || v[0] < 1.0 || v[1] > v[0] {
+                return Err(format_err(format!("bad line {:?}", line)));
+            }
+            rows.push((v[0] as usize, v[1] as usize, (v[2], v[3], v[4], v[5])));
+        }
+        let degree = rows.iter().map(|r| r.0).max().ok_or_else(|| format_err("no coefficients".to_string()))?;
+        let mut coefficients = vec![(0.0, 0.0, 0.0, 0.0); index(degree + 1, 0)];
+        for (n, m, c) in rows {
+            coefficients[index(n, m)] = c;
+        }
+        Ok(MagneticModel { name, epoch, degree, coefficients })
+    }
+
+    /// true if `year` lies in the five years the model is made for
+    pub fn is_valid_at(&self, year:f64) -> bool {
+        year >= self.epoch && year <= self.epoch + VALIDITY
+    }
+
+    /// the field at a wgs84 point `height` meters above the ellipsoid at a decimal year
+    ///
+    /// fails outside the validity of the model, where its secular variation
+    /// would be extrapolated.
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::wmm::MagneticModel;
+    /// let model = MagneticModel::wmm2025();
+    /// let taipei = model.field(121.5, 25.0, 0.0, 2026.0).unwrap();
+    /// assert!(taipei.declination < -4.0 && taipei.declination > -5.5);
+    /// assert!(taipei.inclination > 35.0 && taipei.inclination < 40.0);
+    /// assert!(model.field(121.5, 25.0, 0.0, 2035.0).is_err());
+    /// ```
+    pub fn field(&self, lng:f64, lat:f64, height:f64, year:f64) -> Result<MagneticField> {
+        if !self.is_valid_at(year) {
+            return Err(Error::InvalidInput(format!("{} is valid from {} to {}, not at {}", self.name, self.epoch, self.epoch + VALIDITY, year)));
+        }
+        if !(-90.0..=90.0).contains(&lat) || !lng.is_finite() || !height.is_finite() {
+            return Err(Error::InvalidInput(format!("({}, {}, {}) is not a position", lng, lat, height)));
+        }
+        Ok(self.synthesize(lng, lat, height, year - self.epoch))
+    }
+
+    fn synthesize(&self, lng:f64, lat:f64, height:f64, dt:f64) -> MagneticField {
+        // geodetic to geocentric spherical, in km
+        let a = 6378.137;
+        let fl = 1.0 / 298.257223563;
+        let e2 = fl * (2.0 - fl);
+        let h = height / 1000.0;
+        let (sphi, cphi) = lat.to_radians().sin_cos();
+        let rc = a / (1.0 - e2 * sphi * sphi).sqrt();
+        let p = (rc + h) * cphi;
+        let z = (rc * (1.0 - e2) + h) * sphi;
+        let r = p.hypot(z);
+        let psi = (z / r).asin();
+        // colatitude; keep off the poles, where the east component is undefined
+        let (sin_t, cos_t) = {
+            let (s, c) = psi.sin_cos();
+            (c.max(1e-12), s)
+        };
+        let lam = lng.to_radians();
+        let n_max = self.degree;
+        let len = index(n_max + 1, 0);
+        // Schmidt semi-normalized associated Legendre functions and their θ derivatives
+        let mut pnm = vec![0.0; len];
+        let mut dpnm = vec![0.0; len];
+        pnm[0] = 1.0;
+        for n in 1..=n_max {
+            for m in 0..=n {
+                let k = index(n, m);
+                if n == m {
+                    let (pp, dp) = (pnm[index(n - 1, n - 1)], dpnm[index(n - 1, n - 1)]);
+                    let f = if n == 1 { 1.0 } else { ((2 * n - 1) as f64 / (2 * n) as f64).sqrt() };
+                    pnm[k] = f * sin_t * pp;
+                    dpnm[k] = f * (cos_t * pp + sin_t * dp);
+                } else {
+                    let (p1, d1) = (pnm[index(n - 1, m)], dpnm[index(n - 1, m)]);
+                    let (p2, d2) = if n >= m + 2 { (pnm[index(n - 2, m)], dpnm[index(n - 2, m)]) } else { (0.0, 0.0) };
+                    let k1 = (2 * n - 1) as f64;
+                    let k2 = (((n - 1) * (n - 1) - m * m) as f64).sqrt();
+                    let k3 = ((n * n - m * m) as f64).sqrt();
+                    pnm[k] = (k1 * cos_t * p1 - k2 * p2) / k3;
+                    dpnm[k] = (k1 * (cos_t * d1 - sin_t * p1) - k2 * d2) / k3;
+                }
+            }
+        }
+        let (mut bx, mut by, mut bz) = (0.0, 0.0, 0.0);
+        let ratio = RADIUS / r;
+        let mut rn = ratio * ratio;
+        for n in 1..=n_max {
+            rn *= ratio;
+            for m in 0..=n {
+                let k = index(n, m);
+                let (g, hh, gd, hd) = self.coefficients[k];
+                let (g, hh) = (g + dt * gd, hh + dt * hd);
+                let (sm, cm) = (m as f64 * lam).sin_cos();
+                let gc = g * cm + hh * sm;
+                // dP/dψ = -dP/dθ
+                bx += rn * gc * dpnm[k];
+                by += rn * m as f64 * (g * sm - hh * cm) * pnm[k];
+                bz -= rn * (n + 1) as f64 * gc * pnm[k];
+            }
+        }
+        by /= sin_t;
+        // rotate from the geocentric to the geodetic frame
+        let (sd, cd) = (psi - lat.to_radians()).sin_cos();
+        let x = bx * cd - bz * sd;
+        let zz = bx * sd + bz * cd;
+        let hor = x.hypot(by);
+        MagneticField {
+            x, y: by, z: zz, h: hor,
+            f: hor.hypot(zz),
+            declination: by.atan2(x).to_degrees(),
+            inclination: zz.atan2(hor).to_degrees(),
+        }
+    }
+}
+
+/// decimal year of a calendar date, as the models take it
+/// # Examples
+///
+/// ```
+/// use mercator::wmm::decimal_year;
+/// assert_eq!(decimal_year(2025, 1, 1), 2025.0);
+/// assert!((decimal_year(2026, 7, 2) - 2026.5).abs() < 0.002);
+/// ```
+pub fn decimal_year(year:i32, month:u32, day:u32) -> f64 {
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days = [31, if leap { 29 } else { 28 }, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    let month = month.clamp(1, 12) as usize;
+    let before:u32 = days[..month - 1].iter().sum();
+    let total = if leap { 366.0 } else { 365.0 };
+    year as f64 + (before + day.max(1) - 1) as f64 / total
+}
+
+/// WMM2025 declination in degrees at a wgs84 point and decimal year, positive east
+pub fn declination(lng:f64, lat:f64, height:f64, year:f64) -> Result<f64> {
+    MagneticModel::wmm2025().field(lng, lat, height, year).map(|f| f.declination)
+}
+
+impl Norths {
+    /// the norths of a zone grid at a wgs84 point, with the WMM2025 declination at a decimal year
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::heading::{North, Norths};
+    /// use mercator::zone::Zone;
+    /// let n = Norths::at_year(&Zone::twd97(), 121.5, 25.0, 2026.0).unwrap();
+    /// let compass = n.convert(0.0, North::True, North::Magnetic);
+    /// assert!(compass > 4.0 && compass < 5.5);
+    /// ```
+    pub fn at_year(zone:&Zone, lng:f64, lat:f64, year:f64) -> Result<Norths> {
+        Ok(Norths::at(zone, lng, lat).with_declination(declination(lng, lat, 0.0, year)?))
+    }
+}
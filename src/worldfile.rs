@@ -0,0 +1,146 @@
+//! ESRI world files (.tfw, .jgw, .pgw, ...) and pixel ⇄ CRS placement
+//!
+//! a world file holds six lines A, D, B, E, C, F mapping the center of pixel
+//! (col, row) to X = A col + B row + C, Y = D col + E row + F.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use affine::Affine2D;
+use error::{Error, Result};
+use fit::fit_affine;
+use precision::Precision;
+use trace::Activity;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldFile {
+    pub transform:Affine2D,
+}
+
+impl WorldFile {
+    /// a north-up world file from the upper-left pixel center and the pixel size
+    pub fn north_up(x:f64, y:f64, pixel_width:f64, pixel_height:f64) -> WorldFile {
+        WorldFile { transform: Affine2D::new(pixel_width, 0.0, x, 0.0, -pixel_height, y) }
+    }
+
+    /// parse the six-line text of a world file
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::worldfile::WorldFile;
+    /// let wf = WorldFile::parse("0.5\n0.0\n0.0\n-0.5\n250000.25\n2650000.75\n").unwrap();
+    /// assert_eq!(wf.pixel_to_world(0.0, 0.0), (250000.25, 2650000.75));
+    /// assert_eq!(wf.world_to_pixel(250005.25, 2649990.75), Some((10.0, 20.0)));
+    /// assert_eq!(WorldFile::parse(&wf.to_string()).unwrap(), wf);
+    /// ```
+    pub fn parse(text:&str) -> Result<WorldFile> {
+        let values = text.split_whitespace()
+            .map(|v| v.parse::<f64>().map_err(|_| Error::Format(format!("world file: bad value {:?}", v))))
+            .collect::<Result<Vec<f64>>>()?;
+        if values.len() != 6 {
+            return Err(Error::Format(format!("world file: expected 6 values, got {}", values.len())));
+        }
+        let (a, d, b, e, c, f) = (values[0], values[1], values[2], values[3], values[4], values[5]);
+        Ok(WorldFile { transform: Affine2D::new(a, b, c, d, e, f) })
+    }
+
+    pub fn open<P: AsRef<Path>>(path:P) -> Result<WorldFile> {
+        let activity = Activity::file("read_world_file", path.as_ref());
+        let world = WorldFile::parse(&fs::read_to_string(path)?)?;
+        activity.finish(0, 0);
+        Ok(world)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path:P) -> Result<()> {
+        self.save_with(path, Precision::Full)
+    }
+
+    /// save with the six values rounded to `precision`
+    pub fn save_with<P: AsRef<Path>>(&self, path:P, precision:Precision) -> Result<()> {
+        let activity = Activity::file("write_world_file", path.as_ref());
+        fs::write(path, self.to_string_with(precision))?;
+        activity.finish(0, 0);
+        Ok(())
+    }
+
+    /// the six-line text with values rounded to `precision`
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::precision::Precision;
+    /// use mercator::worldfile::WorldFile;
+    /// let wf = WorldFile::north_up(250000.123456, 2650000.5, 0.5, 0.5);
+    /// assert_eq!(wf.to_string_with(Precision::Decimals(3)),
+    ///     "0.500\n0.000\n0.000\n-0.500\n250000.123\n2650000.500\n");
+    /// ```
+    pub fn to_string_with(&self, precision:Precision) -> String {
+        let t = &self.transform;
+        [t.a, t.d, t.b, t.e, t.c, t.f].iter().map(|&v| precision.format(v) + "\n").collect()
+    }
+
+    /// CRS coordinate of a pixel center (fractional pixels allowed)
+    pub fn pixel_to_world(&self, col:f64, row:f64) -> (f64, f64) {
+        self.transform.apply(col, row)
+    }
+
+    /// pixel position of a CRS coordinate, `None` for a degenerate world file
+    pub fn world_to_pixel(&self, x:f64, y:f64) -> Option<(f64, f64)> {
+        self.transform.inverse().map(|t| t.apply(x, y))
+    }
+
+    /// position of a pixel center in another CRS, `reproject` maps this file's CRS to the target
+    pub fn pixel_to<F>(&self, col:f64, row:f64, reproject:F) -> (f64, f64)
+        where F: Fn(f64, f64) -> (f64, f64)
+    {
+        let (x, y) = self.pixel_to_world(col, row);
+        reproject(x, y)
+    }
+
+    /// approximate placement of a `width` × `height` image in another CRS
+    ///
+    /// the reprojection is sampled on a 9 × 9 lattice of pixels and an affine
+    /// world file is fitted to it; the returned rms (target units) tells how
+    /// well the image can actually be placed without warping. an image less
+    /// than 2 pixels wide or high cannot be fitted and is an [`Error::InvalidInput`].
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::worldfile::WorldFile;
+    /// use mercator::{wgs84_to_twd97, f2degree_zone_to_wgs84};
+    /// let twd97 = WorldFile::north_up(300000.0, 2770000.0, 1.0, 1.0);
+    /// let (wgs84, rms) = twd97.reproject(1000, 1000, |x, y| f2degree_zone_to_wgs84(x, y, 121.0)).unwrap();
+    /// assert!(rms < 1e-7);
+    /// let (lng, lat) = wgs84.pixel_to_world(500.0, 500.0);
+    /// let (x, y) = wgs84_to_twd97(lng, lat);
+    /// assert!((x - 300500.0).abs() < 0.1 && (y - 2769500.0).abs() < 0.1);
+    /// assert!(twd97.reproject(1, 1000, |x, y| f2degree_zone_to_wgs84(x, y, 121.0)).is_err());
+    /// ```
+    pub fn reproject<F>(&self, width:usize, height:usize, reproject:F) -> Result<(WorldFile, f64)>
+        where F: Fn(f64, f64) -> (f64, f64)
+    {
+        if width < 2 || height < 2 {
+            return Err(Error::InvalidInput(format!("a {} x {} image is too small to fit a world file to", width, height)));
+        }
+        let n = 9;
+        let mut src = Vec::with_capacity(n * n);
+        let mut dst = Vec::with_capacity(n * n);
+        for i in 0..n {
+            for j in 0..n {
+                let col = (width - 1) as f64 * i as f64 / (n - 1) as f64;
+                let row = (height - 1) as f64 * j as f64 / (n - 1) as f64;
+                src.push((col, row));
+                dst.push(self.pixel_to(col, row, &reproject));
+            }
+        }
+        let fit = fit_affine(&src, &dst)?;
+        Ok((WorldFile { transform: fit.transform }, fit.rms))
+    }
+}
+
+impl fmt::Display for WorldFile {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        let t = &self.transform;
+        writeln!(f, "{}\n{}\n{}\n{}\n{}\n{}", t.a, t.d, t.b, t.e, t.c, t.f)
+    }
+}
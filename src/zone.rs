@@ -0,0 +1,419 @@
+//! transverse mercator zone definitions and zone-bounds validation
+
+use std::collections::BTreeMap;
+use std::fmt;
+use longitude::{lng_diff, normalize_lng};
+use {tm_forward, tm_inverse};
+use ellipsoid::{Ellipsoid, WGS84};
+use kruger::{tm_exact_forward, tm_exact_inverse};
+use survey::{convergence, ground_to_grid_distance, scale_factor};
+
+/// the zone systems supported by the `wgs84_to_*degree_zone` functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ZoneWidth {
+    /// 2° zones, k0 = 0.9999, false easting 250 km (TWD97 / TWD67)
+    Degree2,
+    /// 3° zones, k0 = 1.0, false easting 350 km
+    Degree3,
+    /// 6° zones, k0 = 0.9996, false easting 500 km (UTM)
+    Degree6,
+}
+
+impl ZoneWidth {
+    /// full width of a zone in degrees of longitude
+    pub fn degrees(&self) -> f64 {
+        match *self {
+            ZoneWidth::Degree2 => 2.0,
+            ZoneWidth::Degree3 => 3.0,
+            ZoneWidth::Degree6 => 6.0,
+        }
+    }
+
+    /// scale factor on the central meridian
+    pub fn k0(&self) -> f64 {
+        match *self {
+            ZoneWidth::Degree2 => 0.9999,
+            ZoneWidth::Degree3 => 1.0,
+            ZoneWidth::Degree6 => 0.9996,
+        }
+    }
+
+    /// false easting in meters
+    pub fn dx(&self) -> f64 {
+        match *self {
+            ZoneWidth::Degree2 => 250000.0,
+            ZoneWidth::Degree3 => 350000.0,
+            ZoneWidth::Degree6 => 500000.0,
+        }
+    }
+}
+
+/// which side of the equator grid northings are measured for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hemisphere {
+    North,
+    /// northings get a 10 000 km false northing so they stay positive
+    South,
+}
+
+impl Hemisphere {
+    /// the hemisphere a latitude lies in, the equator counts as north
+    pub fn from_lat(lat:f64) -> Hemisphere {
+        if lat < 0.0 { Hemisphere::South } else { Hemisphere::North }
+    }
+
+    /// false northing in meters
+    pub fn dy(&self) -> f64 {
+        match *self {
+            Hemisphere::North => 0.0,
+            Hemisphere::South => 10000000.0,
+        }
+    }
+}
+
+/// how a zone evaluates the transverse mercator projection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TmMethod {
+    /// the classic series of the crate root functions, millimeters near the central meridian
+    #[default]
+    Series,
+    /// the Krüger series to 4th order, for games and visualization: cheapest, within a few micrometers
+    Fast,
+    /// the extended Krüger series to 6th order, nanometers anywhere inside 4000 km of the central meridian
+    Exact,
+    /// the full Krüger series to 8th order, for surveying: what is left below 6th order is under f64 resolution
+    Accurate,
+}
+
+impl TmMethod {
+    /// order of the Krüger series, `None` for the classic series
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::zone::{TmMethod, Zone};
+    /// let fast = Zone::utm(51).with_method(TmMethod::Fast).forward(126.0, 45.0);
+    /// let accurate = Zone::utm(51).with_method(TmMethod::Accurate).forward(126.0, 45.0);
+    /// assert!((fast.0 - accurate.0).abs() < 1e-5 && (fast.1 - accurate.1).abs() < 1e-5);
+    /// assert_eq!(TmMethod::Accurate.order(), Some(8));
+    /// ```
+    pub fn order(&self) -> Option<usize> {
+        match *self {
+            TmMethod::Series => None,
+            TmMethod::Fast => Some(4),
+            TmMethod::Exact => Some(6),
+            TmMethod::Accurate => Some(8),
+        }
+    }
+}
+
+/// one zone of a zone system: its width (and so k0 / false easting), central meridian and hemisphere
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zone {
+    pub width:ZoneWidth,
+    pub center_lng:f64,
+    pub hemisphere:Hemisphere,
+    pub method:TmMethod,
+}
+
+impl Zone {
+    /// a northern hemisphere zone
+    pub fn new(width:ZoneWidth, center_lng:f64) -> Zone {
+        Zone { width, center_lng, hemisphere: Hemisphere::North, method: TmMethod::Series }
+    }
+
+    /// the same zone with northings referred to `hemisphere`
+    pub fn with_hemisphere(self, hemisphere:Hemisphere) -> Zone {
+        Zone { hemisphere, ..self }
+    }
+
+    /// the same zone evaluated with another TM implementation
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::zone::{TmMethod, Zone};
+    /// let zone = Zone::utm(51).with_method(TmMethod::Exact);
+    /// // 1000 km off the central meridian the exact mode still round-trips
+    /// let (x, y) = zone.forward(132.0, 30.0);
+    /// let (lng, lat) = zone.inverse(x, y);
+    /// assert!((lng - 132.0).abs() < 1e-11 && (lat - 30.0).abs() < 1e-11);
+    /// // and agrees with the classic series near the central meridian
+    /// let (xs, ys) = Zone::utm(51).forward(123.5, 30.0);
+    /// let (xe, ye) = zone.forward(123.5, 30.0);
+    /// assert!((xs - xe).abs() < 0.01 && (ys - ye).abs() < 0.01);
+    /// ```
+    pub fn with_method(self, method:TmMethod) -> Zone {
+        Zone { method, ..self }
+    }
+
+    /// the 121° 2° zone used by TWD97
+    pub fn twd97() -> Zone {
+        Zone::new(ZoneWidth::Degree2, 121.0)
+    }
+
+    /// UTM zone 1-60, northern hemisphere
+    pub fn utm(number:u8) -> Zone {
+        Zone::new(ZoneWidth::Degree6, utm_central_meridian(number))
+    }
+
+    /// UTM zone 1-60, southern hemisphere
+    pub fn utm_south(number:u8) -> Zone {
+        Zone::utm(number).with_hemisphere(Hemisphere::South)
+    }
+
+    /// the zone used by a projected EPSG code: TWD97 TM2 (3825, 3826) and WGS84 UTM (326xx, 327xx)
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::zone::Zone;
+    /// assert_eq!(Zone::from_epsg(3826), Some(Zone::twd97()));
+    /// assert_eq!(Zone::from_epsg(32756), Some(Zone::utm_south(56)));
+    /// assert_eq!(Zone::from_epsg(4326), None);
+    /// ```
+    pub fn from_epsg(code:u32) -> Option<Zone> {
+        match code {
+            3825 => Some(Zone::new(ZoneWidth::Degree2, 119.0)),
+            3826 => Some(Zone::twd97()),
+            32601..=32660 => Some(Zone::utm((code - 32600) as u8)),
+            32701..=32760 => Some(Zone::utm_south((code - 32700) as u8)),
+            _ => None,
+        }
+    }
+
+    /// the zone of this width containing `lng`, northern hemisphere
+    pub fn containing(lng:f64, width:ZoneWidth) -> Zone {
+        Zone::new(width, best_tm_zone(lng, width))
+    }
+
+    /// the zone of this width containing a point, hemisphere detected from `lat`
+    /// # Examples
+    ///
+    /// ```
+    /// use mercator::zone::{Zone, ZoneWidth};
+    /// // Sydney
+    /// let zone = Zone::for_point(151.2093, -33.8688, ZoneWidth::Degree6);
+    /// let (_, n) = zone.forward(151.2093, -33.8688);
+    /// assert!(n > 6000000.0 && n < 7000000.0);
+    /// ```
+    pub fn for_point(lng:f64, lat:f64, width:ZoneWidth) -> Zone {
+        let center_lng = match width {
+            ZoneWidth::Degree6 => utm_central_meridian(best_utm_zone(lng, lat)),
+            _ => best_tm_zone(lng, width),
+        };
+        Zone::new(width, center_lng).with_hemisphere(Hemisphere::from_lat(lat))
+    }
+
+    /// project a wgs84 point into this zone's grid
+    pub fn forward(&self, lng:f64, lat:f64) -> (f64, f64) {
+        self.forward_on(&WGS84, lng, lat)
+    }
+
+    /// convert grid coordinates of this zone back to wgs84
+    pub fn inverse(&self, x:f64, y:f64) -> (f64, f64) {
+        self.inverse_on(&WGS84, x, y)
+    }
+
+    /// project a point into this zone's grid on another ellipsoid, e.g. GRS67 modified for TWD67
+    pub fn forward_on(&self, ellipsoid:&Ellipsoid, lng:f64, lat:f64) -> (f64, f64) {
+        let (k0, dx) = (self.width.k0(), self.width.dx());
+        let (x, y) = match self.method.order() {
+            None => tm_forward(ellipsoid, lng, lat, self.center_lng, k0, dx),
+            Some(order) => tm_exact_forward(ellipsoid, order, lng, lat, self.center_lng, k0, dx),
+        };
+        (x, y + self.hemisphere.dy())
+    }
+
+    /// convert grid coordinates of this zone on another ellipsoid back to lng/lat
+    pub fn inverse_on(&self, ellipsoid:&Ellipsoid, x:f64, y:f64) -> (f64, f64) {
+        let (k0, dx, y) = (self.width.k0(), self.width.dx(), y - self.hemisphere.dy());
+        match self.method.order() {
+            None => tm_inverse(ellipsoid, x, y, self.center_lng, k0, dx),
+            Some(order) => tm_exact_inverse(ellipsoid, order, x, y, self.center_lng, k0, dx),
+        }
+    }
+
+    /// point scale factor k of this zone at a wgs84 point
+    pub fn scale_factor(&self, lng:f64, lat:f64) -> f64 {
+        scale_factor(lng, lat, self.center_lng, self.width.k0())
+    }
+
+    /// meridian convergence γ of this zone at a wgs84 point, in degrees
+    pub fn convergence(&self, lng:f64, lat:f64) -> f64 {
+        convergence(lng, lat, self.center_lng)
+    }
+
+    /// a ground distance at ellipsoidal height `h` reduced to this grid, see [`ground_to_grid_distance`](::survey::ground_to_grid_distance)
+    pub fn ground_to_grid_distance(&self, d_ground:f64, mid:(f64, f64), h:f64) -> f64 {
+        ground_to_grid_distance(self, d_ground, mid, h)
+    }
+}
+
+/// convert grid coordinates from one zone into another zone's grid
+///
+/// typically used for data straddling a zone boundary, but the zones may be
+/// of different widths as well.
+/// # Examples
+///
+/// ```
+/// use mercator::zone::{rezone, Zone, ZoneWidth};
+/// let from = Zone::new(ZoneWidth::Degree2, 119.0);
+/// let to = Zone::twd97();
+/// let (e, n) = from.forward(120.1, 23.5);
+/// let (e2, n2) = rezone(e, n, from, to);
+/// let (e3, n3) = to.forward(120.1, 23.5);
+/// assert!((e2 - e3).abs() < 0.01 && (n2 - n3).abs() < 0.01);
+/// ```
+pub fn rezone(e:f64, n:f64, from:Zone, to:Zone) -> (f64, f64) {
+    let (lng, lat) = from.inverse(e, n);
+    to.forward(lng, lat)
+}
+
+/// result of checking a point against the nominal extent of a zone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoneCheck {
+    /// signed longitude offset from the central meridian in degrees
+    pub offset:f64,
+    /// how many degrees the point lies beyond the zone edge, 0 when inside
+    pub excess:f64,
+    /// scale error (k - 1) at the point
+    pub scale_error:f64,
+    /// scale error (k - 1) at the zone edge on the same latitude
+    pub edge_scale_error:f64,
+}
+
+impl ZoneCheck {
+    /// true when the point is outside the zone
+    pub fn is_outside(&self) -> bool {
+        self.excess > 0.0
+    }
+
+    /// growth of the scale error beyond what the zone edge allows, 0 when inside
+    pub fn scale_error_growth(&self) -> f64 {
+        if self.is_outside() {
+            self.scale_error.abs() - self.edge_scale_error.abs()
+        } else {
+            0.0
+        }
+    }
+}
+
+impl fmt::Display for ZoneCheck {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        if self.is_outside() {
+            write!(f, "point is {:.3}° outside the zone ({:.3}° from the central meridian), \
+                scale error {:.1} ppm vs {:.1} ppm at the zone edge",
+                self.excess, self.offset, self.scale_error * 1e6, self.edge_scale_error * 1e6)
+        } else {
+            write!(f, "point is inside the zone, scale error {:.1} ppm", self.scale_error * 1e6)
+        }
+    }
+}
+
+/// check a wgs84 point against the zone centered on `center_lng`
+/// # Examples
+///
+/// ```
+/// use mercator::zone::{check_zone, ZoneWidth};
+/// // Taipei projected with the 119° zone instead of 121°
+/// let check = check_zone(121.5654, 25.0330, 119.0, ZoneWidth::Degree2);
+/// assert!(check.is_outside());
+/// assert!(check.scale_error_growth() > 0.0);
+/// assert!(!check_zone(121.5654, 25.0330, 121.0, ZoneWidth::Degree2).is_outside());
+/// ```
+pub fn check_zone(lng:f64, lat:f64, center_lng:f64, width:ZoneWidth) -> ZoneCheck {
+    let offset = lng_diff(lng, center_lng);
+    let half = width.degrees() / 2.0;
+    let k = scale_factor(lng, lat, center_lng, width.k0());
+    let k_edge = scale_factor(center_lng + half, lat, center_lng, width.k0());
+    ZoneCheck {
+        offset,
+        excess: (offset.abs() - half).max(0.0),
+        scale_error: k - 1.0,
+        edge_scale_error: k_edge - 1.0,
+    }
+}
+
+/// central meridian of the `width` zone containing `lng`
+///
+/// 2° zones are centered on odd meridians (119°, 121°, ...), 3° zones on
+/// multiples of 3° and 6° zones on the UTM central meridians.
+/// # Examples
+///
+/// ```
+/// use mercator::zone::{best_tm_zone, ZoneWidth};
+/// assert_eq!(best_tm_zone(121.5654, ZoneWidth::Degree2), 121.0);
+/// assert_eq!(best_tm_zone(118.3, ZoneWidth::Degree2), 119.0);
+/// assert_eq!(best_tm_zone(120.6736, ZoneWidth::Degree3), 120.0);
+/// assert_eq!(best_tm_zone(121.5654, ZoneWidth::Degree6), 123.0);
+/// ```
+pub fn best_tm_zone(lng:f64, width:ZoneWidth) -> f64 {
+    let lng = normalize_lng(lng);
+    match width {
+        ZoneWidth::Degree2 => 2.0 * (lng / 2.0).floor() + 1.0,
+        ZoneWidth::Degree3 => 3.0 * (lng / 3.0).round(),
+        ZoneWidth::Degree6 => utm_central_meridian(utm_zone_number(lng)),
+    }
+}
+
+fn utm_zone_number(lng:f64) -> u8 {
+    ((normalize_lng(lng) + 180.0) / 6.0).floor() as u8 + 1
+}
+
+/// UTM zone number (1-60) for a point, including the Norway and Svalbard exceptions
+/// # Examples
+///
+/// ```
+/// use mercator::zone::best_utm_zone;
+/// assert_eq!(best_utm_zone(121.5654, 25.0330), 51);
+/// assert_eq!(best_utm_zone(5.3, 60.4), 32);
+/// assert_eq!(best_utm_zone(15.6, 78.2), 33);
+/// ```
+pub fn best_utm_zone(lng:f64, lat:f64) -> u8 {
+    let lng = normalize_lng(lng);
+    if (56.0..64.0).contains(&lat) && (3.0..12.0).contains(&lng) {
+        return 32;
+    }
+    if (72.0..=84.0).contains(&lat) && (0.0..42.0).contains(&lng) {
+        return if lng < 9.0 {
+            31
+        } else if lng < 21.0 {
+            33
+        } else if lng < 33.0 {
+            35
+        } else {
+            37
+        };
+    }
+    utm_zone_number(lng)
+}
+
+/// central meridian of a UTM zone
+pub fn utm_central_meridian(zone:u8) -> f64 {
+    6.0 * zone as f64 - 183.0
+}
+
+/// group point indexes by the central meridian of their `width` zone, sorted west to east
+/// # Examples
+///
+/// ```
+/// use mercator::zone::{group_by_tm_zone, ZoneWidth};
+/// let pts = [(121.5, 25.0), (119.6, 23.6), (120.7, 24.1)];
+/// let groups = group_by_tm_zone(&pts, ZoneWidth::Degree2);
+/// assert_eq!(groups, vec![(119.0, vec![1]), (121.0, vec![0, 2])]);
+/// ```
+pub fn group_by_tm_zone(points:&[(f64, f64)], width:ZoneWidth) -> Vec<(f64, Vec<usize>)> {
+    let mut groups:BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+    for (i, &(lng, _)) in points.iter().enumerate() {
+        // central meridians are whole degrees, so they make exact keys
+        groups.entry(best_tm_zone(lng, width) as i64).or_default().push(i);
+    }
+    groups.into_iter().map(|(k, v)| (k as f64, v)).collect()
+}
+
+/// group point indexes by UTM zone number, sorted by zone
+pub fn group_by_utm_zone(points:&[(f64, f64)]) -> Vec<(u8, Vec<usize>)> {
+    let mut groups:BTreeMap<u8, Vec<usize>> = BTreeMap::new();
+    for (i, &(lng, lat)) in points.iter().enumerate() {
+        groups.entry(best_utm_zone(lng, lat)).or_default().push(i);
+    }
+    groups.into_iter().collect()
+}